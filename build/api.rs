@@ -0,0 +1,347 @@
+//! The exported C API, read back from the header generated by cbindgen so that every language
+//! binding is generated from exactly the declarations in `crossterm.h`.
+//!
+//! Only the subset of C that cbindgen emits for this crate is understood: `typedef`'d enums,
+//! structs and opaque structs, bitflags constants and function prototypes.
+
+/// A C type as used in function signatures.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Void,
+    Bool,
+    Int {
+        bits: u8,
+        signed: bool,
+    },
+    Double,
+    /// `const char *`, a null-terminated UTF-8 string. Returned strings are allocated by the
+    /// library and freed with `crossterm_free_c_char`.
+    Str,
+    /// `char *`, a string allocated by the library that is handed back to it.
+    OwnedStr,
+    /// An enum declared in the header.
+    Enum(String),
+    /// A struct declared in the header, passed by value.
+    Struct(String),
+    /// A pointer to an opaque struct declared in the header.
+    Opaque(String),
+    /// A pointer to a value, e.g. an out parameter.
+    Pointer(Box<Type>),
+    VoidPointer,
+    FunctionPointer {
+        ret: Box<Type>,
+        params: Vec<Type>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub name: String,
+    pub ty: Type,
+}
+
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub name: String,
+    pub ret: Type,
+    pub params: Vec<Param>,
+    /// The first line of the documentation.
+    pub summary: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Enum {
+    /// The type name without the `crossterm_` prefix, e.g. `Attribute`.
+    pub name: String,
+    /// Variants with their values, e.g. `CROSSTERM_ATTRIBUTE_RESET`.
+    pub variants: Vec<(String, i64)>,
+}
+
+impl Enum {
+    /// A variant's name without the `CROSSTERM_` and enum name prefixes, e.g. `RESET`.
+    pub fn short_name<'a>(&self, variant: &'a str) -> &'a str {
+        let variant = variant.strip_prefix("CROSSTERM_").unwrap_or(variant);
+        let base = self.name.strip_suffix("_Tag").unwrap_or(&self.name);
+        variant
+            .strip_prefix(&format!("{}_", screaming(base)))
+            .unwrap_or(variant)
+    }
+}
+
+/// A bitflags constant, e.g. `crossterm_KeyModifiers_SHIFT`.
+#[derive(Debug, Clone)]
+pub struct Flag {
+    /// The flags type without the `crossterm_` prefix, e.g. `KeyModifiers`.
+    pub ty: String,
+    /// The flag name, e.g. `SHIFT`.
+    pub name: String,
+    /// The width of the flags type in bits.
+    pub bits: u8,
+    pub value: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct Api {
+    pub enums: Vec<Enum>,
+    pub structs: Vec<String>,
+    pub opaques: Vec<String>,
+    pub flags: Vec<Flag>,
+    pub functions: Vec<Function>,
+    /// Declarations that could not be understood.
+    pub unknown: Vec<String>,
+}
+
+/// `BidiDirection` -> `BIDI_DIRECTION`.
+pub fn screaming(name: &str) -> String {
+    let mut s = String::new();
+    let mut previous_lower = false;
+    for c in name.chars() {
+        if c.is_uppercase() && previous_lower {
+            s.push('_');
+        }
+        previous_lower = c.is_lowercase() || c.is_ascii_digit();
+        s.extend(c.to_uppercase());
+    }
+    s
+}
+
+impl Api {
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.functions.iter().find(|f| f.name == name)
+    }
+
+    /// Parses a header generated by cbindgen.
+    pub fn parse(header: &str) -> Api {
+        let mut api = Api::default();
+        let mut statement = String::new();
+        let mut doc = String::new();
+        let mut depth = 0;
+        let mut rest = header;
+        while let Some(c) = rest.chars().next() {
+            if let Some(comment) = rest.strip_prefix("/*") {
+                let end = comment.find("*/").map_or(comment.len(), |i| i + 2);
+                if depth == 0 && comment.starts_with('*') {
+                    doc = comment[..end.saturating_sub(2)].to_string();
+                }
+                rest = &comment[end..];
+                continue;
+            }
+            if rest.starts_with("//") || (c == '#' && statement.trim().is_empty()) {
+                let end = rest.find('\n').unwrap_or(rest.len());
+                if let Some(flag) = parse_flag(&rest[..end]) {
+                    api.flags.push(flag);
+                }
+                rest = &rest[end..];
+                continue;
+            }
+            rest = &rest[c.len_utf8()..];
+            match c {
+                // `extern "C" { ... }` around the functions
+                '{' if statement.trim() == "extern \"C\"" => statement.clear(),
+                '}' if depth == 0 => statement.clear(),
+                '{' => {
+                    depth += 1;
+                    statement.push(c);
+                }
+                '}' => {
+                    depth -= 1;
+                    statement.push(c);
+                }
+                ';' if depth == 0 => {
+                    let text = statement.split_whitespace().collect::<Vec<_>>().join(" ");
+                    api.add_statement(&text, summary(&doc));
+                    statement.clear();
+                    doc.clear();
+                }
+                c => statement.push(c),
+            }
+        }
+        api
+    }
+
+    fn add_statement(&mut self, text: &str, summary: String) {
+        if let Some(body) = text.strip_prefix("typedef enum {") {
+            let Some((variants, name)) = body.rsplit_once('}') else {
+                return;
+            };
+            let mut next = 0;
+            let variants = variants
+                .split(',')
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(|v| {
+                    let (name, value) = match v.split_once('=') {
+                        Some((name, value)) => (name.trim(), value.trim().parse().unwrap_or(next)),
+                        None => (v, next),
+                    };
+                    next = value + 1;
+                    (name.to_string(), value)
+                })
+                .collect();
+            self.enums.push(Enum {
+                name: strip_prefix(name.trim()).to_string(),
+                variants,
+            });
+        } else if let Some(body) = text.strip_prefix("typedef struct") {
+            match body.rsplit_once('}') {
+                Some((_, name)) => self.structs.push(strip_prefix(name.trim()).to_string()),
+                None => {
+                    if let Some(name) = body.split_whitespace().last() {
+                        self.opaques.push(strip_prefix(name).to_string());
+                    }
+                }
+            }
+        } else if !text.starts_with("typedef") && text.ends_with(')') {
+            match self.parse_function(text, summary) {
+                Some(function) => self.functions.push(function),
+                None => self.unknown.push(text.to_string()),
+            }
+        }
+    }
+
+    fn parse_function(&self, text: &str, summary: String) -> Option<Function> {
+        let open = text.find('(')?;
+        let (ret, name) = split_name(&text[..open]);
+        let params = split_params(&text[open + 1..text.len() - 1])
+            .into_iter()
+            .map(|param| self.parse_param(param))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Function {
+            name: name.to_string(),
+            ret: self.parse_type(ret)?,
+            params,
+            summary,
+        })
+    }
+
+    fn parse_param(&self, param: &str) -> Option<Param> {
+        if let Some((ret, rest)) = param.split_once("(*") {
+            // a function pointer, `int (*callback)(void *user_data)`
+            let (name, params) = rest.split_once(")(")?;
+            let params = split_params(params.strip_suffix(')')?)
+                .into_iter()
+                .map(|param| Some(self.parse_param(param)?.ty))
+                .collect::<Option<Vec<_>>>()?;
+            return Some(Param {
+                name: name.trim().to_string(),
+                ty: Type::FunctionPointer {
+                    ret: Box::new(self.parse_type(ret)?),
+                    params,
+                },
+            });
+        }
+        let (ty, name) = split_name(param);
+        Some(Param {
+            name: name.to_string(),
+            ty: self.parse_type(ty)?,
+        })
+    }
+
+    fn parse_type(&self, ty: &str) -> Option<Type> {
+        let base = ty.trim_end_matches([' ', '*']);
+        let pointers = ty[base.len()..].matches('*').count();
+        let base = base.strip_prefix("const ").unwrap_or(base);
+        let ty = match (base, pointers) {
+            ("char", 1) if ty.starts_with("const") => Type::Str,
+            ("char", 1) => Type::OwnedStr,
+            ("void", 0) => Type::Void,
+            ("void", 1) => Type::VoidPointer,
+            (name, 1) if self.opaques.iter().any(|o| strip_prefix(name) == o) => {
+                Type::Opaque(strip_prefix(name).to_string())
+            }
+            (base, 0) => self.parse_value_type(base)?,
+            (base, 1) => Type::Pointer(Box::new(self.parse_value_type(base)?)),
+            _ => return None,
+        };
+        Some(ty)
+    }
+
+    fn parse_value_type(&self, base: &str) -> Option<Type> {
+        let int = |bits, signed| Some(Type::Int { bits, signed });
+        match base {
+            "bool" => Some(Type::Bool),
+            "double" => Some(Type::Double),
+            "int" | "int32_t" => int(32, true),
+            "unsigned int" | "uint32_t" => int(32, false),
+            "short" | "int16_t" => int(16, true),
+            "unsigned short" | "uint16_t" => int(16, false),
+            "int8_t" => int(8, true),
+            "uint8_t" => int(8, false),
+            "int64_t" | "intptr_t" => int(64, true),
+            "uint64_t" | "size_t" | "uintptr_t" => int(64, false),
+            name => {
+                let name = strip_prefix(name);
+                if self.enums.iter().any(|e| e.name == name) {
+                    Some(Type::Enum(name.to_string()))
+                } else if self.structs.iter().any(|s| s == name) {
+                    Some(Type::Struct(name.to_string()))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+fn strip_prefix(name: &str) -> &str {
+    name.strip_prefix("crossterm_").unwrap_or(name)
+}
+
+/// Splits `const char *text` into the type and the name.
+fn split_name(declaration: &str) -> (&str, &str) {
+    let declaration = declaration.trim();
+    let start = declaration
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .map_or(0, |i| i + 1);
+    (declaration[..start].trim(), &declaration[start..])
+}
+
+/// Splits a parameter list at the commas outside of nested parentheses.
+fn split_params(params: &str) -> Vec<&str> {
+    let params = params.trim();
+    if params.is_empty() || params == "void" {
+        return Vec::new();
+    }
+    let mut split = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in params.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                split.push(params[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    split.push(params[start..].trim());
+    split
+}
+
+/// Parses `#define crossterm_KeyModifiers_SHIFT (crossterm_KeyModifiers){ .bits = (uint8_t)1 }`.
+fn parse_flag(line: &str) -> Option<Flag> {
+    let rest = line.strip_prefix("#define crossterm_")?;
+    let (constant, value) = rest.split_once(' ')?;
+    let ty = strip_prefix(value.trim().strip_prefix('(')?.split(')').next()?);
+    let name = constant.strip_prefix(ty)?.strip_prefix('_')?;
+    let (cast, value) = value.rsplit_once('(')?.1.split_once(')')?;
+    let bits = cast.trim_start_matches("uint").trim_end_matches("_t");
+    let value = value.trim().trim_end_matches('}').trim();
+    Some(Flag {
+        ty: ty.to_string(),
+        name: name.to_string(),
+        bits: bits.parse().ok()?,
+        value: value.parse().ok()?,
+    })
+}
+
+/// The first line of a `/** ... */` comment.
+fn summary(doc: &str) -> String {
+    doc.lines()
+        .map(|line| line.trim().trim_start_matches('*').trim())
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+        .to_string()
+}
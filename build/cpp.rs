@@ -0,0 +1,253 @@
+//! A C++ header wrapping the C API: errors become exceptions, returned strings become
+//! `std::string`s, and modes that are enabled and disabled in pairs get RAII guards.
+
+use std::fmt::Write;
+
+use crate::api::{Api, Function, Type};
+
+const PRELUDE: &str = r#"// C++ wrapper of the libcrossterm C API.
+//
+// Generated by the libcrossterm build script from crossterm.h. Don't modify this manually.
+//
+// Functions are in the `crossterm` namespace without the `crossterm_` prefix. Errors are thrown as
+// `crossterm::Error`, strings are taken and returned as `std::string`, and guard classes enable a
+// mode for their lifetime.
+
+#pragma once
+
+#include <stdexcept>
+#include <string>
+
+#include "crossterm.h"
+
+namespace crossterm {
+
+class Error : public std::runtime_error {
+public:
+  using std::runtime_error::runtime_error;
+};
+
+/// Takes a string returned by the library.
+inline std::string take_string(const char *s) {
+  std::string string(s);
+  crossterm_free_c_char(const_cast<char *>(s));
+  return string;
+}
+
+[[noreturn]] inline void throw_last_error() {
+  throw Error(take_string(crossterm_last_error_message()));
+}
+
+/// Throws the last error if `rc` is negative.
+inline int check(int rc) {
+  if (rc < 0) {
+    throw_last_error();
+  }
+  return rc;
+}
+
+/// Throws the last error if `s` is null, and takes the string otherwise.
+inline std::string check(const char *s) {
+  if (s == nullptr) {
+    throw_last_error();
+  }
+  return take_string(s);
+}
+"#;
+
+/// Guards as the class name, the function that enables the mode and the function that disables
+/// it. A value returned by the first function (other than `int`) is passed to the second.
+const GUARDS: &[(&str, &str, &str)] = &[
+    (
+        "RawMode",
+        "crossterm_terminal_raw_mode_acquire",
+        "crossterm_terminal_raw_mode_release",
+    ),
+    (
+        "AlternateScreen",
+        "crossterm_terminal_enter_alternate_screen",
+        "crossterm_terminal_leave_alternate_screen",
+    ),
+    (
+        "MouseCapture",
+        "crossterm_event_enable_mouse_capture",
+        "crossterm_event_disable_mouse_capture",
+    ),
+    (
+        "BracketedPaste",
+        "crossterm_event_enable_bracketed_paste",
+        "crossterm_event_disable_bracketed_paste",
+    ),
+    (
+        "FocusChange",
+        "crossterm_event_enable_focus_change",
+        "crossterm_event_disable_focus_change",
+    ),
+    (
+        "KeyboardEnhancement",
+        "crossterm_event_push_keyboard_enhancement_flags",
+        "crossterm_event_pop_keyboard_enhancement_flags",
+    ),
+    (
+        "LineWrapDisabled",
+        "crossterm_terminal_disable_line_wrap",
+        "crossterm_terminal_enable_line_wrap",
+    ),
+    (
+        "SynchronizedUpdate",
+        "crossterm_terminal_begin_synchronized_update",
+        "crossterm_terminal_end_synchronized_update",
+    ),
+    (
+        "HiddenCursor",
+        "crossterm_cursor_hide_guarded",
+        "crossterm_cursor_show_guarded",
+    ),
+    ("Tui", "crossterm_tui_enter", "crossterm_tui_exit"),
+];
+
+const INT: Type = Type::Int {
+    bits: 32,
+    signed: true,
+};
+
+/// The C spelling of a type, or `None` for function pointers.
+fn ctype(ty: &Type) -> Option<String> {
+    Some(match ty {
+        Type::Void => "void".to_string(),
+        Type::Bool => "bool".to_string(),
+        Type::Int {
+            bits: 32,
+            signed: true,
+        } => "int".to_string(),
+        Type::Int { bits, signed } => format!("{}int{}_t", if *signed { "" } else { "u" }, bits),
+        Type::Double => "double".to_string(),
+        Type::Str => "const char *".to_string(),
+        Type::OwnedStr => "char *".to_string(),
+        Type::Enum(name) | Type::Struct(name) => format!("crossterm_{}", name),
+        Type::Opaque(name) => format!("crossterm_{} *", name),
+        Type::Pointer(ty) => format!("{} *", ctype(ty)?),
+        Type::VoidPointer => "void *".to_string(),
+        Type::FunctionPointer { .. } => return None,
+    })
+}
+
+/// The wrapper's parameter list and the arguments it passes on.
+fn params(function: &Function) -> Option<(String, String)> {
+    let mut params = Vec::new();
+    let mut args = Vec::new();
+    for param in &function.params {
+        match &param.ty {
+            Type::Str => {
+                params.push(format!("const std::string &{}", param.name));
+                args.push(format!("{}.c_str()", param.name));
+            }
+            Type::OwnedStr => return None,
+            ty => {
+                params.push(format!("{}{}", spaced(ctype(ty)?), param.name));
+                args.push(param.name.clone());
+            }
+        }
+    }
+    Some((params.join(", "), args.join(", ")))
+}
+
+/// `int` -> `int `, `char *` -> `char *`, for putting a name after a type.
+fn spaced(ty: String) -> String {
+    if ty.ends_with('*') {
+        ty
+    } else {
+        ty + " "
+    }
+}
+
+fn write_guard(out: &mut String, api: &Api, class: &str, enter: &str, exit: &str) {
+    let (Some(enter), Some(exit)) = (api.function(enter), api.function(exit)) else {
+        return;
+    };
+    let Some((params, args)) = params(enter) else {
+        return;
+    };
+    // the value to hand back, like the token of a hidden cursor
+    let token = match (&enter.ret, exit.params.as_slice()) {
+        (ret, []) if *ret == Type::Void || *ret == INT => None,
+        (ret, [param]) if *ret == param.ty => ctype(ret),
+        _ => return,
+    };
+    let enter_call = if enter.ret == INT {
+        format!("check({}({}));", enter.name, args)
+    } else if token.is_some() {
+        format!("token_ = {}({});", enter.name, args)
+    } else {
+        format!("{}({});", enter.name, args)
+    };
+    writeln!(out, "\n/// {}", enter.summary).unwrap();
+    writeln!(out, "class {} {{\npublic:", class).unwrap();
+    let explicit = if enter.params.len() == 1 {
+        "explicit "
+    } else {
+        ""
+    };
+    writeln!(
+        out,
+        "  {}{}({}) {{ {} }}",
+        explicit, class, params, enter_call
+    )
+    .unwrap();
+    match &token {
+        Some(_) => writeln!(out, "  ~{}() {{ {}(token_); }}", class, exit.name).unwrap(),
+        None => writeln!(out, "  ~{}() {{ {}(); }}", class, exit.name).unwrap(),
+    }
+    writeln!(out, "  {0}(const {0} &) = delete;", class).unwrap();
+    writeln!(out, "  {0} &operator=(const {0} &) = delete;", class).unwrap();
+    if let Some(token) = token {
+        writeln!(out, "\nprivate:\n  {}token_;", spaced(token)).unwrap();
+    }
+    writeln!(out, "}};").unwrap();
+}
+
+pub fn generate(api: &Api) -> String {
+    let mut out = PRELUDE.to_string();
+    for function in &api.functions {
+        if function.name == "crossterm_free_c_char" {
+            continue;
+        }
+        let Some(name) = function.name.strip_prefix("crossterm_") else {
+            continue;
+        };
+        let Some((params, args)) = params(function) else {
+            continue;
+        };
+        let call = format!("{}({})", function.name, args);
+        let (ret, body) = match &function.ret {
+            ty if *ty == INT => ("int".to_string(), format!("return check({});", call)),
+            Type::Str => (
+                "std::string".to_string(),
+                format!("return check({});", call),
+            ),
+            Type::Void => ("void".to_string(), format!("{};", call)),
+            ty => match ctype(ty) {
+                Some(ty) => (ty, format!("return {};", call)),
+                None => continue,
+            },
+        };
+        writeln!(out).unwrap();
+        if !function.summary.is_empty() {
+            writeln!(out, "/// {}", function.summary).unwrap();
+        }
+        writeln!(
+            out,
+            "inline {}{}({}) {{ {} }}",
+            spaced(ret),
+            name,
+            params,
+            body
+        )
+        .unwrap();
+    }
+    for (class, enter, exit) in GUARDS {
+        write_guard(&mut out, api, class, enter, exit);
+    }
+    writeln!(out, "\n}} // namespace crossterm").unwrap();
+    out
+}
@@ -0,0 +1,101 @@
+//! A Julia file with the C API's constants and a `ccall` wrapper per function.
+
+use std::fmt::Write;
+
+use crate::api::{Api, Type};
+
+const PRELUDE: &str = r#"# ccall declarations of the libcrossterm C API.
+#
+# Generated by the libcrossterm build script from crossterm.h. Don't modify this manually.
+#
+# The library is loaded from the path in the LIBCROSSTERM_PATH environment variable, or else found
+# by name. Constants and functions have their C names. Strings returned by the library are
+# `Ptr{Cchar}`: read them with `unsafe_string` and free them with `crossterm_free_c_char`.
+
+const libcrossterm = get(ENV, "LIBCROSSTERM_PATH", "libcrossterm")
+"#;
+
+const KEYWORDS: &[&str] = &[
+    "begin", "end", "function", "global", "local", "let", "type", "module", "quote", "macro",
+];
+
+fn jltype(ty: &Type, ret: bool) -> Option<String> {
+    Some(match ty {
+        Type::Void => "Cvoid".to_string(),
+        Type::Bool => "Bool".to_string(),
+        Type::Int { bits, signed } => format!("{}Int{}", if *signed { "" } else { "U" }, bits),
+        Type::Double => "Cdouble".to_string(),
+        Type::Str if !ret => "Cstring".to_string(),
+        Type::Str | Type::OwnedStr => "Ptr{Cchar}".to_string(),
+        Type::Enum(_) => "Cint".to_string(),
+        Type::Opaque(_) | Type::VoidPointer | Type::FunctionPointer { .. } => {
+            "Ptr{Cvoid}".to_string()
+        }
+        Type::Pointer(ty) if matches!(**ty, Type::Struct(_)) => "Ptr{Cvoid}".to_string(),
+        Type::Pointer(ty) => format!("Ptr{{{}}}", jltype(ty, false)?),
+        Type::Struct(_) => return None,
+    })
+}
+
+pub fn generate(api: &Api) -> String {
+    let mut out = PRELUDE.to_string();
+    for e in &api.enums {
+        writeln!(out, "\n# {}", e.name).unwrap();
+        for (variant, value) in &e.variants {
+            writeln!(out, "const {} = Cint({})", variant, value).unwrap();
+        }
+    }
+    let mut flag_types: Vec<&str> = api.flags.iter().map(|f| f.ty.as_str()).collect();
+    flag_types.dedup();
+    for ty in flag_types {
+        writeln!(out, "\n# {}", ty).unwrap();
+        for flag in api.flags.iter().filter(|f| f.ty == ty) {
+            writeln!(
+                out,
+                "const crossterm_{}_{} = UInt{}({})",
+                flag.ty, flag.name, flag.bits, flag.value
+            )
+            .unwrap();
+        }
+    }
+    for function in &api.functions {
+        let names: Vec<String> = function
+            .params
+            .iter()
+            .map(|p| {
+                if KEYWORDS.contains(&p.name.as_str()) {
+                    format!("{}_", p.name)
+                } else {
+                    p.name.clone()
+                }
+            })
+            .collect();
+        let types = function
+            .params
+            .iter()
+            .map(|p| jltype(&p.ty, false))
+            .collect::<Option<Vec<_>>>();
+        writeln!(out).unwrap();
+        if !function.summary.is_empty() {
+            writeln!(out, "# {}", function.summary).unwrap();
+        }
+        let (Some(types), Some(ret)) = (types, jltype(&function.ret, true)) else {
+            writeln!(out, "# {} passes a struct by value", function.name).unwrap();
+            continue;
+        };
+        // a one element tuple needs a trailing comma
+        let types = match types.len() {
+            1 => format!("({},)", types[0]),
+            _ => format!("({})", types.join(", ")),
+        };
+        let args: String = names.iter().map(|name| format!(", {}", name)).collect();
+        writeln!(
+            out,
+            "{name}({params}) = ccall((:{name}, libcrossterm), {ret}, {types}{args})",
+            name = function.name,
+            params = names.join(", "),
+        )
+        .unwrap();
+    }
+    out
+}
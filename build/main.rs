@@ -0,0 +1,73 @@
+use std::{env, fs::File, io::Read, path::Path};
+
+mod api;
+mod cpp;
+mod julia;
+mod python;
+
+#[allow(dead_code)]
+fn create_colors() {
+    let out_dir = env::var("OUT_DIR").unwrap();
+    let dest_path = Path::new(&out_dir).join("colors.rs");
+    let mut file = File::open("./data/colors.json").expect("Could not open colors.json");
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)
+        .expect("Could not read colors.json");
+    let data: serde_json::Value = serde_json::from_str(&contents).expect("Could not parse JSON");
+    std::fs::write(
+        dest_path,
+        format!("pub static COLORS: &str = r##\"{}\"##;", data),
+    )
+    .unwrap();
+}
+
+/// Writes `contents` to `path` unless it already has them, so that unchanged files keep their
+/// modification time (as cbindgen does for the header).
+#[allow(dead_code)]
+fn write_if_changed(path: &str, contents: &str) {
+    if std::fs::read_to_string(path).is_ok_and(|existing| existing == contents) {
+        return;
+    }
+    if let Err(err) = std::fs::write(path, contents) {
+        println!("cargo:warning=Could not write {}: {}", path, err);
+    }
+}
+
+/// Generates `crossterm.h` with cbindgen, and the bindings for other languages from the API it
+/// declares: a Python `ctypes` module, a Julia file of constants and `ccall` wrappers, and a C++
+/// wrapper header with RAII guards.
+#[allow(dead_code)]
+fn create_bindings() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or(".".to_string());
+    let Ok(bindings) = cbindgen::generate(&crate_dir) else {
+        return;
+    };
+    bindings.write_to_file(format!("{}/include/crossterm.h", crate_dir));
+
+    let mut header = Vec::new();
+    bindings.write(&mut header);
+    let api = api::Api::parse(&String::from_utf8_lossy(&header));
+    for declaration in &api.unknown {
+        println!("cargo:warning=No bindings generated for `{}`", declaration);
+    }
+    write_if_changed(
+        &format!("{}/include/crossterm.py", crate_dir),
+        &python::generate(&api),
+    );
+    write_if_changed(
+        &format!("{}/include/crossterm.jl", crate_dir),
+        &julia::generate(&api),
+    );
+    write_if_changed(
+        &format!("{}/include/crossterm.hpp", crate_dir),
+        &cpp::generate(&api),
+    );
+}
+
+fn main() {
+    #[cfg(not(feature = "docsrs"))]
+    create_colors();
+
+    #[cfg(not(feature = "docsrs"))]
+    create_bindings();
+}
@@ -0,0 +1,128 @@
+//! A Python module declaring the C API with `ctypes`.
+
+use std::fmt::Write;
+
+use crate::api::{Api, Type};
+
+const PRELUDE: &str = r#""""ctypes declarations of the libcrossterm C API.
+
+Generated by the libcrossterm build script from crossterm.h. Don't modify this manually.
+
+The library is loaded from the path in the LIBCROSSTERM_PATH environment variable, or else found
+by name. Enums are `IntEnum` and bitflags `IntFlag` classes, named as in the C API without the
+`crossterm_` prefix. Functions are declared on `lib` with their C names. Strings returned by the
+library are declared as `c_void_p`: pass them to `string` to decode and free them.
+"""
+
+import ctypes
+import ctypes.util
+import enum
+import os
+
+
+class CrosstermError(Exception):
+    pass
+
+
+def _load():
+    path = os.environ.get("LIBCROSSTERM_PATH") or ctypes.util.find_library("crossterm")
+    if path is None:
+        raise OSError("libcrossterm not found, set LIBCROSSTERM_PATH to its path")
+    return ctypes.CDLL(path)
+
+
+lib = _load()
+
+
+def _take_string(pointer):
+    try:
+        return ctypes.string_at(pointer).decode("utf-8")
+    finally:
+        lib.crossterm_free_c_char(pointer)
+
+
+def string(pointer):
+    """Decodes and frees a string returned by the library, raising `CrosstermError` for null."""
+    if not pointer:
+        raise CrosstermError(_take_string(lib.crossterm_last_error_message()))
+    return _take_string(pointer)
+"#;
+
+fn ctype(ty: &Type) -> Option<String> {
+    Some(match ty {
+        Type::Void => "None".to_string(),
+        Type::Bool => "ctypes.c_bool".to_string(),
+        Type::Int { bits, signed } => {
+            format!("ctypes.c_{}int{}", if *signed { "" } else { "u" }, bits)
+        }
+        Type::Double => "ctypes.c_double".to_string(),
+        Type::Str => "ctypes.c_char_p".to_string(),
+        Type::OwnedStr | Type::Opaque(_) | Type::VoidPointer => "ctypes.c_void_p".to_string(),
+        Type::Enum(_) => "ctypes.c_int".to_string(),
+        Type::Struct(_) => return None,
+        // structs aren't declared, but a pointer to one can still be passed around
+        Type::Pointer(ty) if matches!(**ty, Type::Struct(_)) => "ctypes.c_void_p".to_string(),
+        Type::Pointer(ty) => format!("ctypes.POINTER({})", ctype(ty)?),
+        Type::FunctionPointer { ret, params } => {
+            let types = std::iter::once(ret.as_ref())
+                .chain(params)
+                .map(ctype)
+                .collect::<Option<Vec<_>>>()?;
+            format!("ctypes.CFUNCTYPE({})", types.join(", "))
+        }
+    })
+}
+
+pub fn generate(api: &Api) -> String {
+    let mut out = PRELUDE.to_string();
+    for e in &api.enums {
+        writeln!(out, "\n\nclass {}(enum.IntEnum):", e.name.replace('_', "")).unwrap();
+        for (variant, value) in &e.variants {
+            writeln!(out, "    {} = {}", e.short_name(variant), value).unwrap();
+        }
+    }
+    let mut flag_types: Vec<&str> = api.flags.iter().map(|f| f.ty.as_str()).collect();
+    flag_types.dedup();
+    for ty in flag_types {
+        writeln!(out, "\n\nclass {}(enum.IntFlag):", ty).unwrap();
+        for flag in api.flags.iter().filter(|f| f.ty == ty) {
+            writeln!(out, "    {} = {}", flag.name, flag.value).unwrap();
+        }
+    }
+    writeln!(out).unwrap();
+    for function in &api.functions {
+        let params = function
+            .params
+            .iter()
+            .map(|p| ctype(&p.ty))
+            .collect::<Option<Vec<_>>>();
+        // returned strings are freed by the caller, so the pointer must not be converted
+        let ret = match &function.ret {
+            Type::Str => Some("ctypes.c_void_p".to_string()),
+            ty => ctype(ty),
+        };
+        writeln!(out).unwrap();
+        if !function.summary.is_empty() {
+            writeln!(out, "# {}", function.summary).unwrap();
+        }
+        match (params, ret) {
+            (Some(params), Some(ret)) => {
+                writeln!(
+                    out,
+                    "lib.{}.argtypes = [{}]",
+                    function.name,
+                    params.join(", ")
+                )
+                .unwrap();
+                writeln!(out, "lib.{}.restype = {}", function.name, ret).unwrap();
+            }
+            _ => writeln!(
+                out,
+                "# {} passes a struct by value, which ctypes can't declare here",
+                function.name
+            )
+            .unwrap(),
+        }
+    }
+    out
+}
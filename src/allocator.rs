@@ -0,0 +1,38 @@
+//! The allocator of memory handed to the caller, such as returned strings: `malloc` and `free`,
+//! unless the caller sets its own, e.g. to use the allocator of its runtime.
+
+use std::sync::RwLock;
+
+type Malloc = unsafe extern "C" fn(usize) -> *mut libc::c_void;
+type Free = unsafe extern "C" fn(*mut libc::c_void);
+
+static HOOKS: RwLock<Option<(Malloc, Free)>> = RwLock::new(None);
+
+/// Sets the functions to allocate and free with, or restores `malloc` and `free` for `None`.
+pub fn set(hooks: Option<(Malloc, Free)>) {
+    *HOOKS.write().unwrap_or_else(|e| e.into_inner()) = hooks;
+}
+
+fn hooks() -> Option<(Malloc, Free)> {
+    *HOOKS.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Allocates `size` bytes for the caller, returning null if that fails.
+pub fn malloc(size: usize) -> *mut libc::c_void {
+    match hooks() {
+        Some((malloc, _)) => unsafe { malloc(size) },
+        None => unsafe { libc::malloc(size) },
+    }
+}
+
+/// Frees memory allocated with [`malloc`].
+///
+/// # Safety
+///
+/// `ptr` must have been returned by [`malloc`] with the same allocator set, and not freed yet.
+pub unsafe fn free(ptr: *mut libc::c_void) {
+    match hooks() {
+        Some((_, free)) => free(ptr),
+        None => libc::free(ptr),
+    }
+}
@@ -0,0 +1,102 @@
+//! Display ordering of bidirectional text.
+//!
+//! Most terminals lay text out in the order it is written, left to right, so right-to-left
+//! scripts (Hebrew, Arabic) come out backwards. [`visual`] applies the Unicode Bidirectional
+//! Algorithm to put each line in display order before printing, mirroring brackets in
+//! right-to-left runs. Terminals that implement bidi themselves (e.g. mlterm, or Konsole with bidi
+//! rendering enabled) should be given the logical text instead.
+
+use unicode_bidi::{BidiInfo, Level};
+use unicode_width::UnicodeWidthChar;
+
+/// The base direction of a paragraph.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash)]
+pub enum BidiDirection {
+    /// Taken from the first strong character of each line, left to right if there is none.
+    Auto,
+    LeftToRight,
+    RightToLeft,
+}
+
+impl BidiDirection {
+    fn level(self) -> Option<Level> {
+        match self {
+            BidiDirection::Auto => None,
+            BidiDirection::LeftToRight => Some(Level::ltr()),
+            BidiDirection::RightToLeft => Some(Level::rtl()),
+        }
+    }
+}
+
+/// Returns `text` with each line in display order.
+pub fn visual(text: &str, direction: BidiDirection) -> String {
+    text.split('\n')
+        .map(|line| visual_line(line, direction))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn visual_line(line: &str, direction: BidiDirection) -> String {
+    let info = BidiInfo::new(line, direction.level());
+    let mut visual = String::with_capacity(line.len());
+    for paragraph in &info.paragraphs {
+        let (levels, runs) = info.visual_runs(paragraph, paragraph.range.clone());
+        for run in runs {
+            let text = &line[run.clone()];
+            if levels[run.start].is_rtl() {
+                for cluster in clusters(text).into_iter().rev() {
+                    let mut chars = cluster.chars();
+                    visual.extend(chars.next().map(mirror));
+                    visual.push_str(chars.as_str());
+                }
+            } else {
+                visual.push_str(text);
+            }
+        }
+    }
+    visual
+}
+
+/// Splits text into characters with their combining marks, which stay together when reversed.
+fn clusters(text: &str) -> Vec<&str> {
+    let mut clusters = Vec::new();
+    let mut start = 0;
+    for (i, c) in text.char_indices().skip(1) {
+        if c.width() != Some(0) {
+            clusters.push(&text[start..i]);
+            start = i;
+        }
+    }
+    if !text.is_empty() {
+        clusters.push(&text[start..]);
+    }
+    clusters
+}
+
+/// The mirrored glyph of a bracket, as shown in right-to-left text.
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '{' => '}',
+        '}' => '{',
+        '<' => '>',
+        '>' => '<',
+        '«' => '»',
+        '»' => '«',
+        '‹' => '›',
+        '›' => '‹',
+        c => c,
+    }
+}
+
+/// The number of columns `text` takes up on the widest line.
+pub fn width(text: &str) -> usize {
+    text.split('\n')
+        .map(unicode_width::UnicodeWidthStr::width)
+        .max()
+        .unwrap_or(0)
+}
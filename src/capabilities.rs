@@ -0,0 +1,249 @@
+//! Heuristics for what the terminal supports.
+
+use std::path::PathBuf;
+
+use crate::colors::{self, ColorDepth};
+use crate::query;
+
+fn env(name: &str) -> String {
+    std::env::var(name).unwrap_or_default()
+}
+
+/// Parses a dotted version such as `3.4.19` into a single number that orders like the version.
+fn version_number(version: &str) -> u64 {
+    let mut parts = version
+        .split(|c: char| !c.is_ascii_digit())
+        .map(|p| p.parse::<u64>().unwrap_or(0));
+    let major = parts.next().unwrap_or(0);
+    let minor = parts.next().unwrap_or(0);
+    let patch = parts.next().unwrap_or(0);
+    major * 1_000_000 + minor * 1_000 + patch
+}
+
+/// Decides hyperlink support from the environment alone, or `None` if it can't tell.
+fn hyperlinks_from_env() -> Option<bool> {
+    let force = env("FORCE_HYPERLINK");
+    if !force.is_empty() {
+        return Some(force != "0");
+    }
+    if !env("WT_SESSION").is_empty() || !env("DOMTERM").is_empty() {
+        return Some(true);
+    }
+    let vte = env("VTE_VERSION");
+    if !vte.is_empty() {
+        // e.g. `5402` for 0.54.2; hyperlinks landed in 0.50.1
+        return Some(vte.parse::<u64>().map(|v| v >= 5001).unwrap_or(false));
+    }
+    let version = version_number(&env("TERM_PROGRAM_VERSION"));
+    match env("TERM_PROGRAM").as_str() {
+        "iTerm.app" => return Some(version >= version_number("3.1")),
+        "WezTerm" | "ghostty" | "Hyper" | "rio" => return Some(true),
+        "vscode" => return Some(version >= version_number("1.72")),
+        "Apple_Terminal" => return Some(false),
+        _ => {}
+    }
+    let term = env("TERM");
+    if [
+        "kitty",
+        "alacritty",
+        "foot",
+        "wezterm",
+        "ghostty",
+        "contour",
+    ]
+    .iter()
+    .any(|name| term.contains(name))
+    {
+        return Some(true);
+    }
+    if term == "dumb" || term == "linux" {
+        return Some(false);
+    }
+    None
+}
+
+/// Whether the terminal is known to render OSC 8 hyperlinks.
+///
+/// Falls back to asking the terminal for its name with XTVERSION when the environment is not
+/// conclusive.
+pub fn supports_hyperlinks() -> bool {
+    if let Some(supported) = hyperlinks_from_env() {
+        return supported;
+    }
+    match query::terminal_version(query::DEFAULT_TIMEOUT) {
+        Ok(version) => {
+            let version = version.to_lowercase();
+            [
+                "kitty", "wezterm", "foot", "iterm2", "ghostty", "contour", "konsole", "vte",
+            ]
+            .iter()
+            .any(|name| version.contains(name))
+        }
+        Err(_) => false,
+    }
+}
+
+/// Directories searched for compiled terminfo entries, in the order ncurses searches them.
+fn terminfo_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = std::env::var_os("TERMINFO") {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(home) = std::env::var_os("HOME") {
+        dirs.push(PathBuf::from(home).join(".terminfo"));
+    }
+    let defaults = [
+        "/etc/terminfo",
+        "/lib/terminfo",
+        "/usr/share/terminfo",
+        "/usr/lib/terminfo",
+    ];
+    let listed = env("TERMINFO_DIRS");
+    dirs.extend(
+        listed
+            .split(':')
+            .filter(|d| !d.is_empty())
+            .map(PathBuf::from),
+    );
+    dirs.extend(defaults.iter().map(PathBuf::from));
+    dirs
+}
+
+/// The `colors` capability of the compiled terminfo entry for `term`.
+fn terminfo_colors(term: &str) -> Option<u32> {
+    /// Index of `colors` among the numeric capabilities.
+    const COLORS: usize = 13;
+    let first = term.chars().next()?;
+    // entries are filed under their first letter, or its hex code on case-insensitive filesystems
+    let data = terminfo_dirs().iter().find_map(|dir| {
+        [first.to_string(), format!("{:x}", first as u32)]
+            .iter()
+            .find_map(|sub| std::fs::read(dir.join(sub).join(term)).ok())
+    })?;
+    let short = |i: usize| {
+        data.get(i * 2..i * 2 + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+    };
+    // the legacy format has 16-bit numbers, the extended one 32-bit numbers
+    let width = match short(0)? {
+        0o432 => 2,
+        0o1036 => 4,
+        _ => return None,
+    };
+    let (names, booleans, numbers) = (short(1)? as usize, short(2)? as usize, short(3)? as usize);
+    if numbers <= COLORS {
+        return None;
+    }
+    // numbers start on an even offset
+    let start = (12 + names + booleans).next_multiple_of(2) + COLORS * width;
+    let bytes = data.get(start..start + width)?;
+    let colors = match width {
+        2 => i16::from_le_bytes([bytes[0], bytes[1]]) as i32,
+        _ => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+    };
+    u32::try_from(colors).ok()
+}
+
+fn depth_from_count(colors: u32) -> ColorDepth {
+    match colors {
+        0x100_0000.. => ColorDepth::TrueColor,
+        256.. => ColorDepth::Ansi256,
+        _ => ColorDepth::Ansi16,
+    }
+}
+
+/// Decodes a hex encoded XTGETTCAP name or value.
+fn unhex(hex: &str) -> Option<String> {
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    String::from_utf8(bytes).ok()
+}
+
+/// Asks the terminal for its `RGB` and `colors` capabilities with XTGETTCAP.
+fn color_depth_from_terminal() -> Option<ColorDepth> {
+    // one request per name, since terminals stop at the first unknown name in a request
+    let reply = query::query(
+        b"\x1bP+q524742\x1b\\\x1bP+q636f6c6f7273\x1b\\",
+        query::DEFAULT_TIMEOUT,
+    )
+    .ok()?;
+    query::dcs_payloads(&reply)
+        .iter()
+        .filter_map(|payload| {
+            let reply = payload.strip_prefix("1+r")?;
+            let (name, value) = reply.split_once('=').unwrap_or((reply, ""));
+            match unhex(name)?.as_str() {
+                "RGB" => Some(ColorDepth::TrueColor),
+                "colors" => Some(depth_from_count(unhex(value)?.parse().ok()?)),
+                _ => None,
+            }
+        })
+        .max()
+}
+
+/// The number of colors the terminal can display.
+///
+/// Decided from `COLORTERM`, terminals known for true color, and the `TERM` name and its terminfo
+/// entry. With `query`, the terminal is also asked for its capabilities, which can only raise the
+/// depth.
+pub fn color_support(query: bool) -> ColorDepth {
+    let colorterm = env("COLORTERM").to_lowercase();
+    let term = env("TERM").to_lowercase();
+    let mut depth = if colorterm == "truecolor"
+        || colorterm == "24bit"
+        || term.contains("direct")
+        || !env("WT_SESSION").is_empty()
+        || matches!(
+            env("TERM_PROGRAM").as_str(),
+            "iTerm.app" | "WezTerm" | "ghostty" | "vscode" | "Hyper" | "rio"
+        ) {
+        ColorDepth::TrueColor
+    } else if term.contains("256") {
+        ColorDepth::Ansi256
+    } else {
+        ColorDepth::Ansi16
+    };
+    if depth < ColorDepth::TrueColor {
+        if let Some(colors) = terminfo_colors(&env("TERM")) {
+            depth = depth.max(depth_from_count(colors));
+        }
+    }
+    if query && depth < ColorDepth::TrueColor {
+        if let Some(reported) = color_depth_from_terminal() {
+            depth = depth.max(reported);
+        }
+    }
+    depth
+}
+
+/// Whether the terminal has a dark background, from its background color reported to an OSC 11
+/// query, or from `COLORFGBG` (e.g. `15;0`, set by rxvt and Konsole) if it doesn't report it.
+pub fn is_dark() -> anyhow::Result<bool> {
+    let err = match query::dynamic_color(11, query::DEFAULT_TIMEOUT) {
+        Ok(background) => return Ok(colors::is_dark(background)),
+        Err(err) => err,
+    };
+    // the background is the last field, after the foreground and an optional default
+    let background = env("COLORFGBG")
+        .rsplit(';')
+        .next()
+        .and_then(|bg| bg.parse::<u8>().ok());
+    match background {
+        Some(bg) => Ok(matches!(bg, 0..=6 | 8)),
+        None => Err(err),
+    }
+}
+
+/// Whether the terminal is expected to repeat a character with REP (`CSI n b`).
+///
+/// Most terminals emulating xterm do, so this is only false for terminals known not to: Apple
+/// Terminal, the Linux console, and the Windows console outside of Windows Terminal.
+pub fn supports_repeat() -> bool {
+    let term = env("TERM");
+    if cfg!(windows) && term.is_empty() {
+        return !env("WT_SESSION").is_empty();
+    }
+    env("TERM_PROGRAM") != "Apple_Terminal" && term != "linux" && term != "dumb"
+}
@@ -0,0 +1,44 @@
+//! The DEC special graphics charset, which draws lines and boxes with ASCII bytes for terminals
+//! whose fonts lack the Unicode box-drawing chars.
+
+/// The DEC special graphics char that draws `c`, e.g. `q` for `─`.
+///
+/// Heavy, double and rounded box-drawing chars map to the light lines, the only ones in the
+/// charset.
+pub fn dec_special_graphics(c: char) -> Option<char> {
+    let dec = match c {
+        '◆' => '`',
+        '▒' => 'a',
+        '␉' => 'b',
+        '␌' => 'c',
+        '␍' => 'd',
+        '␊' => 'e',
+        '°' => 'f',
+        '±' => 'g',
+        '␤' => 'h',
+        '␋' => 'i',
+        '┘' | '┛' | '╝' | '╯' | '┙' | '┚' | '╛' | '╜' => 'j',
+        '┐' | '┓' | '╗' | '╮' | '┑' | '┒' | '╕' | '╖' => 'k',
+        '┌' | '┏' | '╔' | '╭' | '┍' | '┎' | '╒' | '╓' => 'l',
+        '└' | '┗' | '╚' | '╰' | '┕' | '┖' | '╘' | '╙' => 'm',
+        '┼' | '╋' | '╬' | '┿' | '╂' | '╪' | '╫' => 'n',
+        '⎺' => 'o',
+        '⎻' => 'p',
+        '─' | '━' | '═' | '╌' | '╍' | '┄' | '┅' | '┈' | '┉' => 'q',
+        '⎼' => 'r',
+        '⎽' => 's',
+        '├' | '┣' | '╠' | '┝' | '┠' | '╞' | '╟' => 't',
+        '┤' | '┫' | '╣' | '┥' | '┨' | '╡' | '╢' => 'u',
+        '┴' | '┻' | '╩' | '┷' | '┸' | '╧' | '╨' => 'v',
+        '┬' | '┳' | '╦' | '┯' | '┰' | '╤' | '╥' => 'w',
+        '│' | '┃' | '║' | '╎' | '╏' | '┆' | '┇' | '┊' | '┋' => 'x',
+        '≤' => 'y',
+        '≥' => 'z',
+        'π' => '{',
+        '≠' => '|',
+        '£' => '}',
+        '·' => '~',
+        _ => return None,
+    };
+    Some(dec)
+}
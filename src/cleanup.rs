@@ -0,0 +1,199 @@
+//! Best-effort restoring of the terminal when the process exits without tearing down, e.g. when a
+//! host language exits on an uncaught exception.
+//!
+//! The restoring runs from an `atexit` handler, and on Unix from handlers of terminating signals
+//! that had their default action. It must be async-signal-safe there, so it only writes to the
+//! terminal and resets its attributes, and skips the modes if their state is locked.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::crossterm::{cursor, event, terminal, Command};
+use crate::state;
+
+static REGISTERED: AtomicBool = AtomicBool::new(false);
+
+/// Registers the handlers, once.
+pub fn register() -> anyhow::Result<()> {
+    if REGISTERED.load(Ordering::SeqCst) {
+        return Ok(());
+    }
+    native::init()?;
+    // initialized now, since the handlers can't allocate
+    drop(state::modes());
+    if unsafe { libc::atexit(on_exit) } != 0 {
+        anyhow::bail!("Unable to register exit handler");
+    }
+    native::register_signals()?;
+    REGISTERED.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+extern "C" fn on_exit() {
+    restore();
+}
+
+/// The escape sequence of a command, without allocating.
+fn ansi(command: impl Command, buffer: &mut [u8]) -> &[u8] {
+    struct Cursor<'a> {
+        buffer: &'a mut [u8],
+        len: usize,
+    }
+    impl std::fmt::Write for Cursor<'_> {
+        fn write_str(&mut self, s: &str) -> std::fmt::Result {
+            let end = self.len + s.len();
+            let target = self.buffer.get_mut(self.len..end).ok_or(std::fmt::Error)?;
+            target.copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+    let mut cursor = Cursor { buffer, len: 0 };
+    let len = match command.write_ansi(&mut cursor) {
+        Ok(()) => cursor.len,
+        Err(_) => 0,
+    };
+    &buffer[..len]
+}
+
+/// Leaves the modes set through this library, and restores cooked mode.
+fn restore() {
+    if let Some(modes) = state::try_modes() {
+        let mut buffer = [0u8; 64];
+        if modes.line_drawing {
+            native::write(crate::END_LINE_DRAWING.as_bytes());
+        }
+        for _ in &modes.keyboard_enhancement_flags {
+            native::write(ansi(event::PopKeyboardEnhancementFlags, &mut buffer));
+        }
+        if modes.focus_change {
+            native::write(ansi(event::DisableFocusChange, &mut buffer));
+        }
+        if modes.bracketed_paste {
+            native::write(ansi(event::DisableBracketedPaste, &mut buffer));
+        }
+        if modes.mouse_pixels.is_some() {
+            native::write(crate::RESET_MOUSE_PIXELS.as_bytes());
+        }
+        if modes.mouse_capture {
+            native::write(ansi(event::DisableMouseCapture, &mut buffer));
+        }
+        if modes.cursor_hidden {
+            native::write(ansi(cursor::Show, &mut buffer));
+        }
+        if modes.line_wrap_disabled {
+            native::write(ansi(terminal::EnableLineWrap, &mut buffer));
+        }
+        if modes.alternate_screen {
+            native::write(ansi(terminal::LeaveAlternateScreen, &mut buffer));
+        }
+        if modes.default_colors_changed {
+            native::write(crate::RESET_DEFAULT_COLORS.as_bytes());
+        }
+        if modes.palette_changed {
+            native::write(crate::RESET_PALETTE.as_bytes());
+        }
+    }
+    native::restore_cooked_mode();
+}
+
+#[cfg(unix)]
+mod native {
+    use std::os::fd::IntoRawFd;
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::sync::atomic::{AtomicI32, Ordering};
+    use std::sync::OnceLock;
+
+    /// The terminal, opened for the handlers.
+    static TTY: AtomicI32 = AtomicI32::new(-1);
+    /// Terminal attributes in cooked mode.
+    static COOKED: OnceLock<libc::termios> = OnceLock::new();
+
+    pub fn init() -> anyhow::Result<()> {
+        let fd = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NOCTTY)
+            .open("/dev/tty")?
+            .into_raw_fd();
+        let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(fd, &mut termios) } != 0 {
+            let err = std::io::Error::last_os_error();
+            unsafe { libc::close(fd) };
+            return Err(err.into());
+        }
+        if termios.c_lflag & libc::ICANON == 0 {
+            // in raw mode already: undo what `cfmakeraw` turns off
+            termios.c_iflag |= libc::BRKINT | libc::ICRNL | libc::IXON;
+            termios.c_oflag |= libc::OPOST;
+            termios.c_lflag |= libc::ECHO | libc::ECHOE | libc::ICANON | libc::ISIG | libc::IEXTEN;
+        }
+        let _ = COOKED.set(termios);
+        TTY.store(fd, Ordering::SeqCst);
+        Ok(())
+    }
+
+    extern "C" fn on_signal(signal: libc::c_int) {
+        super::restore();
+        // the handler was reset to the default action, which runs now
+        unsafe { libc::raise(signal) };
+    }
+
+    /// Handles terminating signals that have their default action.
+    pub fn register_signals() -> anyhow::Result<()> {
+        for signal in [libc::SIGHUP, libc::SIGINT, libc::SIGQUIT, libc::SIGTERM] {
+            let mut previous: libc::sigaction = unsafe { std::mem::zeroed() };
+            if unsafe { libc::sigaction(signal, std::ptr::null(), &mut previous) } != 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            if previous.sa_sigaction != libc::SIG_DFL {
+                continue;
+            }
+            let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+            action.sa_sigaction = on_signal as extern "C" fn(libc::c_int) as usize;
+            action.sa_flags = libc::SA_RESETHAND;
+            if unsafe { libc::sigaction(signal, &action, std::ptr::null_mut()) } != 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn write(bytes: &[u8]) {
+        let fd = TTY.load(Ordering::SeqCst);
+        unsafe { libc::write(fd, bytes.as_ptr() as *const libc::c_void, bytes.len()) };
+    }
+
+    pub fn restore_cooked_mode() {
+        let fd = TTY.load(Ordering::SeqCst);
+        let Some(cooked) = COOKED.get() else {
+            return;
+        };
+        let mut current: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(fd, &mut current) } == 0 && current.c_lflag & libc::ICANON == 0
+        {
+            unsafe { libc::tcsetattr(fd, libc::TCSANOW, cooked) };
+        }
+    }
+}
+
+#[cfg(windows)]
+mod native {
+    use std::io::Write;
+
+    pub fn init() -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Windows has no terminating signals to handle.
+    pub fn register_signals() -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    pub fn write(bytes: &[u8]) {
+        let _ = std::io::stderr().write_all(bytes);
+    }
+
+    pub fn restore_cooked_mode() {
+        let _ = crate::crossterm::terminal::disable_raw_mode();
+    }
+}
@@ -0,0 +1,293 @@
+//! System clipboard access.
+//!
+//! Locally, the platform's clipboard is used: the Win32 clipboard on Windows, `pbcopy`/`pbpaste`
+//! on macOS, and `wl-copy`/`wl-paste`, `xclip` or `xsel` elsewhere. Over SSH, or when no native
+//! clipboard is available, the terminal's clipboard is used through OSC 52, which most terminals
+//! support for copying but only some allow for pasting.
+
+use std::io::Write;
+use std::time::Duration;
+
+use crate::query;
+
+fn env(name: &str) -> String {
+    std::env::var(name).unwrap_or_default()
+}
+
+/// Whether the process runs in an SSH session, where the local clipboard is on the remote host.
+fn is_remote() -> bool {
+    ["SSH_CONNECTION", "SSH_CLIENT", "SSH_TTY"]
+        .iter()
+        .any(|name| !env(name).is_empty())
+}
+
+/// Copies `text` to the clipboard. OSC 52 sequences are written (and flushed) to `terminal`.
+pub fn copy(text: &str, terminal: &mut dyn Write) -> anyhow::Result<()> {
+    if !is_remote() {
+        match native::copy(text) {
+            Ok(()) => return Ok(()),
+            Err(err) => log::trace!("Native clipboard unavailable, using OSC 52: {:#}", err),
+        }
+    }
+    let sequence = format!("\x1b]52;c;{}\x07", base64_encode(text.as_bytes()));
+    terminal.write_all(passthrough(&sequence).as_bytes())?;
+    terminal.flush()?;
+    Ok(())
+}
+
+/// Returns the text on the clipboard.
+pub fn paste() -> anyhow::Result<String> {
+    if !is_remote() {
+        match native::paste() {
+            Ok(text) => return Ok(text),
+            Err(err) => log::trace!("Native clipboard unavailable, using OSC 52: {:#}", err),
+        }
+    }
+    let bytes = terminal_paste(query::DEFAULT_TIMEOUT)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Returns the bytes on the terminal's clipboard, asking for them with OSC 52 and waiting up to
+/// `timeout` for the reply.
+///
+/// Terminals may ask the user before replying, so the wait isn't cut short by a device attributes
+/// reply like other queries, and a terminal that doesn't reply costs the full timeout.
+pub fn terminal_paste(timeout: Duration) -> anyhow::Result<Vec<u8>> {
+    let request = passthrough("\x1b]52;c;?\x07");
+    let replies = query::exchange(request.as_bytes(), timeout, false, |reply| {
+        reply.starts_with(b"\x1b]52;")
+    })
+    .map_err(|e| {
+        if e.to_string().starts_with("Timed out") {
+            anyhow::anyhow!("Unsupported: terminal did not return the clipboard")
+        } else {
+            e
+        }
+    })?;
+    let data = query::osc_payloads(&replies.concat())
+        .into_iter()
+        .find_map(|payload| {
+            let mut parts = payload.splitn(3, ';');
+            if parts.next()? != "52" {
+                return None;
+            }
+            parts.next()?;
+            Some(parts.next()?.to_string())
+        })
+        .ok_or_else(|| anyhow::anyhow!("Unsupported: terminal did not return the clipboard"))?;
+    base64_decode(&data)
+        .ok_or_else(|| anyhow::anyhow!("Received invalid base64 clipboard data from terminal"))
+}
+
+/// Wraps a sequence for tmux, which otherwise swallows it.
+fn passthrough(sequence: &str) -> String {
+    if env("TMUX").is_empty() {
+        sequence.to_string()
+    } else {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    }
+}
+
+/// Runs `program` with `args`, writing `input` to its standard input if given, and returns its
+/// standard output.
+#[cfg(not(windows))]
+pub fn run(program: &str, args: &[&str], input: Option<&str>) -> anyhow::Result<String> {
+    use std::process::{Command, Stdio};
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Unable to run {}: {}", program, e))?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        anyhow::bail!("{} exited with {}", program, output.status);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(target_os = "macos")]
+mod native {
+    use super::run;
+
+    pub fn copy(text: &str) -> anyhow::Result<()> {
+        run("pbcopy", &[], Some(text)).map(|_| ())
+    }
+
+    pub fn paste() -> anyhow::Result<String> {
+        run("pbpaste", &[], None)
+    }
+}
+
+#[cfg(all(not(windows), not(target_os = "macos")))]
+mod native {
+    use super::{env, run};
+
+    struct Tool {
+        copy: (&'static str, &'static [&'static str]),
+        paste: (&'static str, &'static [&'static str]),
+    }
+
+    /// Clipboard tools for the current display, in order of preference.
+    fn tools() -> Vec<Tool> {
+        let mut tools = Vec::new();
+        if !env("WAYLAND_DISPLAY").is_empty() {
+            tools.push(Tool {
+                copy: ("wl-copy", &[]),
+                paste: ("wl-paste", &["--no-newline"]),
+            });
+        }
+        if !env("DISPLAY").is_empty() {
+            tools.push(Tool {
+                copy: ("xclip", &["-selection", "clipboard"]),
+                paste: ("xclip", &["-selection", "clipboard", "-o"]),
+            });
+            tools.push(Tool {
+                copy: ("xsel", &["--clipboard", "--input"]),
+                paste: ("xsel", &["--clipboard", "--output"]),
+            });
+        }
+        tools
+    }
+
+    /// Returns the result of the first tool `f` succeeds with.
+    fn first_working<T>(mut f: impl FnMut(&Tool) -> anyhow::Result<T>) -> anyhow::Result<T> {
+        let mut last_error = anyhow::anyhow!("No clipboard tool for this display");
+        for tool in tools() {
+            match f(&tool) {
+                Ok(t) => return Ok(t),
+                Err(err) => last_error = err,
+            }
+        }
+        Err(last_error)
+    }
+
+    pub fn copy(text: &str) -> anyhow::Result<()> {
+        first_working(|tool| run(tool.copy.0, tool.copy.1, Some(text)).map(|_| ()))
+    }
+
+    pub fn paste() -> anyhow::Result<String> {
+        first_working(|tool| run(tool.paste.0, tool.paste.1, None))
+    }
+}
+
+#[cfg(windows)]
+mod native {
+    use winapi::um::winbase::{GlobalAlloc, GlobalFree, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
+    use winapi::um::winuser::{
+        CloseClipboard, EmptyClipboard, GetClipboardData, OpenClipboard, SetClipboardData,
+        CF_UNICODETEXT,
+    };
+
+    /// Keeps the clipboard open until dropped.
+    struct Open;
+
+    impl Open {
+        fn new() -> anyhow::Result<Self> {
+            if unsafe { OpenClipboard(std::ptr::null_mut()) } == 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            Ok(Open)
+        }
+    }
+
+    impl Drop for Open {
+        fn drop(&mut self) {
+            unsafe { CloseClipboard() };
+        }
+    }
+
+    pub fn copy(text: &str) -> anyhow::Result<()> {
+        let wide: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+        let _open = Open::new()?;
+        unsafe {
+            if EmptyClipboard() == 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            let memory = GlobalAlloc(GMEM_MOVEABLE, wide.len() * 2);
+            if memory.is_null() {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            let locked = GlobalLock(memory) as *mut u16;
+            if locked.is_null() {
+                GlobalFree(memory);
+                return Err(std::io::Error::last_os_error().into());
+            }
+            std::ptr::copy_nonoverlapping(wide.as_ptr(), locked, wide.len());
+            GlobalUnlock(memory);
+            // the clipboard owns the memory once this succeeds
+            if SetClipboardData(CF_UNICODETEXT, memory).is_null() {
+                GlobalFree(memory);
+                return Err(std::io::Error::last_os_error().into());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn paste() -> anyhow::Result<String> {
+        let _open = Open::new()?;
+        unsafe {
+            let memory = GetClipboardData(CF_UNICODETEXT);
+            if memory.is_null() {
+                // no text on the clipboard
+                return Ok(String::new());
+            }
+            let locked = GlobalLock(memory) as *const u16;
+            if locked.is_null() {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            let mut len = 0;
+            while *locked.add(len) != 0 {
+                len += 1;
+            }
+            let text = String::from_utf16_lossy(std::slice::from_raw_parts(locked, len));
+            GlobalUnlock(memory);
+            Ok(text)
+        }
+    }
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+fn base64_decode(s: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(s.len() / 4 * 3);
+    let mut n = 0u32;
+    let mut bits = 0;
+    for c in s.bytes().filter(|c| !c.is_ascii_whitespace() && *c != b'=') {
+        let value = BASE64.iter().position(|b| *b == c)? as u32;
+        n = (n << 6 | value) & 0xffff;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((n >> bits) as u8);
+        }
+    }
+    Some(decoded)
+}
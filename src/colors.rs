@@ -0,0 +1,476 @@
+//! Lookups over the bundled 256 color database ([`crate::COLORS`]).
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use serde_json::Value;
+
+use crate::crossterm::style as ct;
+use crate::crossterm::Command;
+
+/// An RGB color.
+pub type Rgb = (u8, u8, u8);
+
+/// A single entry of the color database.
+pub struct ColorEntry {
+    /// ANSI 256 color index.
+    pub id: u8,
+    /// Name, e.g. `DodgerBlue1`. Names are not unique.
+    pub name: String,
+    /// Hex string, e.g. `#0087ff`.
+    pub hex: String,
+    pub rgb: Rgb,
+    /// The entry exactly as stored in the database.
+    pub json: Value,
+}
+
+fn parse_entry(value: &Value) -> Option<ColorEntry> {
+    let channel = |c: &str| value["rgb"][c].as_u64().map(|v| v as u8);
+    Some(ColorEntry {
+        id: value["colorId"].as_u64()? as u8,
+        name: value["name"].as_str()?.to_string(),
+        hex: value["hexString"].as_str()?.to_string(),
+        rgb: (channel("r")?, channel("g")?, channel("b")?),
+        json: value.clone(),
+    })
+}
+
+fn parse_database() -> Result<Vec<ColorEntry>, String> {
+    let values: Vec<Value> = serde_json::from_str(crate::COLORS)
+        .map_err(|e| format!("Unable to parse color database: {}", e))?;
+    values
+        .iter()
+        .map(|v| parse_entry(v).ok_or_else(|| format!("Invalid color database entry: {}", v)))
+        .collect()
+}
+
+/// Returns the parsed color database, parsing it on first use.
+pub fn database() -> anyhow::Result<&'static [ColorEntry]> {
+    static DATABASE: OnceLock<Result<Vec<ColorEntry>, String>> = OnceLock::new();
+    match DATABASE.get_or_init(parse_database) {
+        Ok(entries) => Ok(entries),
+        Err(e) => Err(anyhow::anyhow!("{}", e)),
+    }
+}
+
+/// Returns the entries matching `query`:
+///
+/// * an empty query matches every entry,
+/// * a number matches the entry with that ANSI index,
+/// * a query starting with `#` matches hex strings by prefix,
+/// * anything else matches names by case-insensitive substring.
+pub fn find(query: &str) -> anyhow::Result<Vec<&'static ColorEntry>> {
+    let query = query.trim();
+    let entries = database()?;
+    let matches = if query.is_empty() {
+        entries.iter().collect()
+    } else if let Ok(index) = query.parse::<u8>() {
+        entries.iter().filter(|e| e.id == index).collect()
+    } else if query.starts_with('#') {
+        let query = query.to_lowercase();
+        entries
+            .iter()
+            .filter(|e| e.hex.to_lowercase().starts_with(&query))
+            .collect()
+    } else {
+        let query = query.to_lowercase();
+        entries
+            .iter()
+            .filter(|e| e.name.to_lowercase().contains(&query))
+            .collect()
+    };
+    Ok(matches)
+}
+
+/// Returns the first entry named `name`, ignoring case.
+pub fn by_name(name: &str) -> anyhow::Result<&'static ColorEntry> {
+    database()?
+        .iter()
+        .find(|e| e.name.eq_ignore_ascii_case(name.trim()))
+        .ok_or_else(|| anyhow::anyhow!("No color named {}", name))
+}
+
+/// Returns the entry with the ANSI index `index`.
+pub fn by_index(index: u8) -> anyhow::Result<&'static ColorEntry> {
+    database()?
+        .iter()
+        .find(|e| e.id == index)
+        .ok_or_else(|| anyhow::anyhow!("No color with index {}", index))
+}
+
+/// The hue in degrees `[0, 360)` of a color with channels in `[0, 1]`, given their maximum and the
+/// difference between their maximum and minimum.
+fn hue(r: f64, g: f64, b: f64, max: f64, delta: f64) -> f64 {
+    if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    }
+}
+
+/// The RGB color with hue `h` in degrees `[0, 360)`, chroma `c` and the amount `m` added to all
+/// channels.
+fn from_chroma(h: f64, c: f64, m: f64) -> Rgb {
+    let x = c * (1.0 - ((h / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let (r, g, b) = match (h / 60.0) as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    (to_channel(r + m), to_channel(g + m), to_channel(b + m))
+}
+
+/// Converts an RGB color to HSL, with hue in degrees `[0, 360)` and saturation and lightness in
+/// `[0, 1]`.
+pub fn rgb_to_hsl(r: u8, g: u8, b: u8) -> (f64, f64, f64) {
+    let (r, g, b) = (r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let delta = max - min;
+    if delta == 0.0 {
+        return (0.0, 0.0, l);
+    }
+    let s = delta / (1.0 - (2.0 * l - 1.0).abs());
+    (hue(r, g, b, max, delta), s, l)
+}
+
+/// Converts an HSL color to RGB. Hue is taken modulo 360 degrees, saturation and lightness are
+/// clamped to `[0, 1]`.
+pub fn hsl_to_rgb(h: f64, s: f64, l: f64) -> Rgb {
+    let h = h.rem_euclid(360.0);
+    let s = s.clamp(0.0, 1.0);
+    let l = l.clamp(0.0, 1.0);
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    from_chroma(h, c, l - c / 2.0)
+}
+
+/// Converts an RGB color to HSV, with hue in degrees `[0, 360)` and saturation and value in
+/// `[0, 1]`.
+pub fn rgb_to_hsv(r: u8, g: u8, b: u8) -> (f64, f64, f64) {
+    let (r, g, b) = (r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0);
+    let max = r.max(g).max(b);
+    let delta = max - r.min(g).min(b);
+    let s = if max == 0.0 { 0.0 } else { delta / max };
+    (hue(r, g, b, max, delta), s, max)
+}
+
+/// Converts an HSV color to RGB. Hue is taken modulo 360 degrees, saturation and value are
+/// clamped to `[0, 1]`.
+pub fn hsv_to_rgb(h: f64, s: f64, v: f64) -> Rgb {
+    let h = h.rem_euclid(360.0);
+    let s = s.clamp(0.0, 1.0);
+    let v = v.clamp(0.0, 1.0);
+    let c = v * s;
+    from_chroma(h, c, v - c)
+}
+
+/// The RGB value of a color, taking named and ANSI colors from the default palette.
+pub fn to_rgb(color: ct::Color) -> anyhow::Result<Rgb> {
+    let index = match color {
+        ct::Color::Rgb { r, g, b } => return Ok((r, g, b)),
+        ct::Color::Reset => anyhow::bail!("Received Reset, which has no RGB value"),
+        ct::Color::AnsiValue(index) => index,
+        ct::Color::Black => 0,
+        ct::Color::DarkRed => 1,
+        ct::Color::DarkGreen => 2,
+        ct::Color::DarkYellow => 3,
+        ct::Color::DarkBlue => 4,
+        ct::Color::DarkMagenta => 5,
+        ct::Color::DarkCyan => 6,
+        ct::Color::Grey => 7,
+        ct::Color::DarkGrey => 8,
+        ct::Color::Red => 9,
+        ct::Color::Green => 10,
+        ct::Color::Yellow => 11,
+        ct::Color::Blue => 12,
+        ct::Color::Magenta => 13,
+        ct::Color::Cyan => 14,
+        ct::Color::White => 15,
+    };
+    Ok(by_index(index)?.rgb)
+}
+
+fn to_channel(v: f64) -> u8 {
+    (v.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Adds `amount` (in `[-1, 1]`) to the HSL lightness of a color.
+pub fn lighten(r: u8, g: u8, b: u8, amount: f64) -> Rgb {
+    let (h, s, l) = rgb_to_hsl(r, g, b);
+    hsl_to_rgb(h, s, l + amount)
+}
+
+/// The relative luminance of a color as defined by WCAG, from 0 for black to 1 for white.
+pub fn luminance(rgb: Rgb) -> f64 {
+    let linear = |c: u8| {
+        let c = c as f64 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * linear(rgb.0) + 0.7152 * linear(rgb.1) + 0.0722 * linear(rgb.2)
+}
+
+/// Whether white text has more contrast on a color than black text.
+pub fn is_dark(rgb: Rgb) -> bool {
+    // the luminance where the contrast ratios with black and white are equal
+    luminance(rgb) < 0.179
+}
+
+/// Linearly interpolates between two colors, `t = 0` giving `a` and `t = 1` giving `b`.
+pub fn mix(a: Rgb, b: Rgb, t: f64) -> Rgb {
+    let t = t.clamp(0.0, 1.0);
+    let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+    (channel(a.0, b.0), channel(a.1, b.1), channel(a.2, b.2))
+}
+
+/// Rejects NaN and infinite arguments, which have no meaningful color.
+pub fn finite(v: f64, what: &str) -> anyhow::Result<f64> {
+    if v.is_finite() {
+        Ok(v)
+    } else {
+        Err(anyhow::anyhow!(
+            "Received non-finite value {} for {}",
+            v,
+            what
+        ))
+    }
+}
+
+/// Number of colors a terminal can display.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+    /// The 16 ANSI colors.
+    Ansi16,
+    /// The 256 indexed colors.
+    Ansi256,
+    /// 24-bit RGB colors.
+    TrueColor,
+}
+
+/// The depth that colors are downgraded to when they are set.
+static DEPTH: Mutex<ColorDepth> = Mutex::new(ColorDepth::TrueColor);
+
+pub fn set_depth(depth: ColorDepth) {
+    *DEPTH.lock().unwrap_or_else(|e| e.into_inner()) = depth;
+}
+
+/// Downgrades a color that is about to be set to the depth set with [`set_depth`]: RGB colors,
+/// and 256 colors in 16 color mode, are replaced by the nearest ANSI color.
+pub fn downgrade(color: ct::Color) -> ct::Color {
+    let depth = *DEPTH.lock().unwrap_or_else(|e| e.into_inner());
+    let rgb = match (color, depth) {
+        (_, ColorDepth::TrueColor) => return color,
+        (ct::Color::Rgb { r, g, b }, _) => (r, g, b),
+        (ct::Color::AnsiValue(index), ColorDepth::Ansi16) if index >= 16 => match by_index(index) {
+            Ok(entry) => entry.rgb,
+            Err(_) => return color,
+        },
+        _ => return color,
+    };
+    match quantize(rgb, depth) {
+        Ok((_, Some(index))) => ct::Color::AnsiValue(index),
+        _ => color,
+    }
+}
+
+/// Whether SGR sequences are written, as decided from the environment.
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Decides whether colors are used from the `NO_COLOR`, `CLICOLOR` and `CLICOLOR_FORCE`
+/// conventions and whether the output of the calling thread goes to a terminal, or enables them
+/// again if `respect` is false.
+pub fn respect_color_env(respect: bool) {
+    let var = |name| std::env::var_os(name).filter(|v| !v.is_empty());
+    let enabled = if !respect || var("CLICOLOR_FORCE").is_some_and(|v| v != "0") {
+        true
+    } else if var("NO_COLOR").is_some() || var("CLICOLOR").is_some_and(|v| v == "0") {
+        false
+    } else {
+        crate::output::is_terminal()
+    };
+    ENABLED.store(enabled, Ordering::SeqCst);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+/// A command that sets colors or attributes, written only while colors are enabled.
+pub struct Sgr<C>(pub C);
+
+impl<C: Command> Command for Sgr<C> {
+    fn write_ansi(&self, f: &mut impl std::fmt::Write) -> std::fmt::Result {
+        if enabled() {
+            self.0.write_ansi(f)
+        } else {
+            Ok(())
+        }
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        if enabled() {
+            self.0.execute_winapi()
+        } else {
+            Ok(())
+        }
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        self.0.is_ansi_code_supported()
+    }
+}
+
+/// Perceptual distance between two colors ("redmean" weighted euclidean distance).
+pub fn distance(a: Rgb, b: Rgb) -> f64 {
+    let rmean = (a.0 as f64 + b.0 as f64) / 2.0;
+    let dr = a.0 as f64 - b.0 as f64;
+    let dg = a.1 as f64 - b.1 as f64;
+    let db = a.2 as f64 - b.2 as f64;
+    ((2.0 + rmean / 256.0) * dr * dr + 4.0 * dg * dg + (2.0 + (255.0 - rmean) / 256.0) * db * db)
+        .sqrt()
+}
+
+/// Returns the entry of the first `limit` ANSI colors that is closest to `rgb`.
+pub fn nearest(rgb: Rgb, limit: usize) -> anyhow::Result<&'static ColorEntry> {
+    database()?
+        .iter()
+        .take(limit)
+        .min_by(|a, b| distance(a.rgb, rgb).total_cmp(&distance(b.rgb, rgb)))
+        .ok_or_else(|| anyhow::anyhow!("Color database is empty"))
+}
+
+/// Kind of palette generated by [`palette`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub enum PaletteScheme {
+    /// Hues spread within 30 degrees on either side of the base color.
+    Analogous,
+    /// The hue of the base color at lightness levels from dark to light.
+    Monochrome,
+    /// Alternates between the base hue and its complement, at varying lightness.
+    Complementary,
+}
+
+/// Generates `count` colors derived from `base`.
+pub fn palette(base: Rgb, count: usize, scheme: PaletteScheme) -> Vec<Rgb> {
+    let (h, s, l) = rgb_to_hsl(base.0, base.1, base.2);
+    // position of color `i` in `[0, 1]`
+    let at = |i: usize| {
+        if count > 1 {
+            i as f64 / (count - 1) as f64
+        } else {
+            0.5
+        }
+    };
+    (0..count)
+        .map(|i| match scheme {
+            PaletteScheme::Analogous => hsl_to_rgb(h - 30.0 + 60.0 * at(i), s, l),
+            PaletteScheme::Monochrome => hsl_to_rgb(h, s, 0.15 + 0.7 * at(i)),
+            PaletteScheme::Complementary => {
+                // each pair shares a lightness, spread within 0.2 of the base lightness
+                let pairs = count.div_ceil(2);
+                let lightness = if pairs > 1 {
+                    l - 0.2 + 0.4 * (i / 2) as f64 / (pairs - 1) as f64
+                } else {
+                    l
+                };
+                hsl_to_rgb(h + 180.0 * (i % 2) as f64, s, lightness)
+            }
+        })
+        .collect()
+}
+
+/// Quantizes `rgb` to `depth`, returning the displayed color and its ANSI index if quantized.
+pub fn quantize(rgb: Rgb, depth: ColorDepth) -> anyhow::Result<(Rgb, Option<u8>)> {
+    let limit = match depth {
+        ColorDepth::TrueColor => return Ok((rgb, None)),
+        ColorDepth::Ansi256 => 256,
+        ColorDepth::Ansi16 => 16,
+    };
+    let entry = nearest(rgb, limit)?;
+    Ok((entry.rgb, Some(entry.id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: (f64, f64, f64), b: (f64, f64, f64)) -> bool {
+        (a.0 - b.0).abs() < 0.5 && (a.1 - b.1).abs() < 0.005 && (a.2 - b.2).abs() < 0.005
+    }
+
+    #[test]
+    fn finds_colors_by_index_hex_and_name() {
+        assert_eq!(find("#0087ff").unwrap().len(), 1);
+        assert_eq!(find("196").unwrap()[0].hex, "#ff0000");
+        assert!(find("dodgerblue").unwrap().len() > 1);
+        assert_eq!(find("").unwrap().len(), 256);
+    }
+
+    #[test]
+    fn converts_known_colors_to_hsl() {
+        assert!(close(rgb_to_hsl(255, 0, 0), (0.0, 1.0, 0.5)));
+        assert!(close(rgb_to_hsl(0, 128, 255), (210.0, 1.0, 0.5)));
+        assert!(close(rgb_to_hsl(255, 0, 128), (330.0, 1.0, 0.5)));
+        assert!(close(rgb_to_hsl(128, 128, 128), (0.0, 0.0, 0.502)));
+    }
+
+    #[test]
+    fn converts_hsl_back_to_rgb() {
+        assert_eq!(hsl_to_rgb(240.0, 1.0, 0.5), (0, 0, 255));
+        assert_eq!(hsl_to_rgb(-120.0, 1.0, 0.5), (0, 0, 255));
+        assert_eq!(hsl_to_rgb(0.0, 2.0, 1.5), (255, 255, 255));
+        for r in (0..=255).step_by(51) {
+            for g in (0..=255).step_by(17) {
+                for b in (0..=255).step_by(85) {
+                    let (h, s, l) = rgb_to_hsl(r, g, b);
+                    assert_eq!(hsl_to_rgb(h, s, l), (r, g, b));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lightens_within_bounds() {
+        assert_eq!(lighten(255, 0, 0, 0.5), (255, 255, 255));
+        assert_eq!(lighten(255, 0, 0, -0.5), (0, 0, 0));
+        assert_eq!(lighten(0, 0, 255, 0.25), (128, 128, 255));
+    }
+
+    #[test]
+    fn looks_up_colors_by_name_and_index() {
+        assert_eq!(by_index(196).unwrap().rgb, (255, 0, 0));
+        assert_eq!(by_name("dodgerblue1").unwrap().hex, "#0087ff");
+        assert_eq!(by_name("DodgerBlue1").unwrap().id, 33);
+        assert!(by_name("nosuchcolor").is_err());
+    }
+
+    #[test]
+    fn converts_hsv_to_and_from_rgb() {
+        assert!(close(rgb_to_hsv(0, 255, 0), (120.0, 1.0, 1.0)));
+        assert!(close(rgb_to_hsv(0, 0, 0), (0.0, 0.0, 0.0)));
+        assert_eq!(hsv_to_rgb(60.0, 1.0, 1.0), (255, 255, 0));
+        for r in (0..=255).step_by(51) {
+            for g in (0..=255).step_by(17) {
+                for b in (0..=255).step_by(85) {
+                    let (h, s, v) = rgb_to_hsv(r, g, b);
+                    assert_eq!(hsv_to_rgb(h, s, v), (r, g, b));
+                }
+            }
+        }
+    }
+}
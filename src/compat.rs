@@ -0,0 +1,17 @@
+//! Adapter over the supported `crossterm` releases.
+//!
+//! The rest of the crate refers to `crossterm` only through this module, so that the exported C
+//! symbols and the JSON formats stay the same no matter which release was selected with the
+//! `crossterm-0-27` / `crossterm-0-28` features. Anything that differs between releases gets a
+//! wrapper here instead of a `cfg` at the call site.
+
+#[cfg(all(feature = "crossterm-0-27", feature = "crossterm-0-28"))]
+compile_error!("features `crossterm-0-27` and `crossterm-0-28` are mutually exclusive");
+
+#[cfg(not(any(feature = "crossterm-0-27", feature = "crossterm-0-28")))]
+compile_error!("one of the features `crossterm-0-27` or `crossterm-0-28` must be enabled");
+
+#[cfg(feature = "crossterm-0-27")]
+pub use ::crossterm;
+#[cfg(all(feature = "crossterm-0-28", not(feature = "crossterm-0-27")))]
+pub use ::crossterm_0_28 as crossterm;
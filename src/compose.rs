@@ -0,0 +1,207 @@
+//! Best-effort text composition from combining sequences.
+//!
+//! Terminals handle input methods and dead keys themselves and only send the composed result, so
+//! there is no composition state to pass on. What does arrive is decomposed text: a base
+//! character followed by combining marks (e.g. `e` + U+0301), each read as a separate key. When
+//! enabled, such a sequence is reported as a composition instead of separate keys: a
+//! `CompositionStart` with the base character, a `CompositionUpdate` per combining mark, and a
+//! `CompositionCommit` with the full text once a key that doesn't combine (or any other event)
+//! arrives.
+
+use std::time::{Duration, Instant};
+
+use crate::crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crate::events::KeyInput;
+
+/// How long a character key is held back waiting for a combining mark. Marks of a decomposed
+/// sequence are sent together, so they are read right after the base character.
+const COMBINE_TIMEOUT: Duration = Duration::from_millis(20);
+
+/// Result of feeding a key event to a [`Composer`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Output {
+    /// A key that is not part of a composition, to be passed on unchanged.
+    Key(KeyInput),
+    Start(String),
+    Update(String),
+    Commit(String),
+}
+
+#[derive(Default)]
+pub struct Composer {
+    pub enabled: bool,
+    /// A character key that may start a composition, and when it arrived.
+    held: Option<(KeyInput, Instant)>,
+    /// Text of the composition in progress, and when it was last updated.
+    text: Option<(String, Instant)>,
+}
+
+/// Whether `c` combines with the preceding character.
+fn is_combining(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036F}' // combining diacritical marks
+        | '\u{0483}'..='\u{0489}' // cyrillic
+        | '\u{0591}'..='\u{05BD}' | '\u{05BF}' | '\u{05C1}'..='\u{05C2}' | '\u{05C4}'..='\u{05C5}' | '\u{05C7}' // hebrew
+        | '\u{0610}'..='\u{061A}' | '\u{064B}'..='\u{065F}' | '\u{0670}' // arabic
+        | '\u{0E31}' | '\u{0E34}'..='\u{0E3A}' | '\u{0E47}'..='\u{0E4E}' // thai
+        | '\u{1160}'..='\u{11FF}' // hangul medial vowels and final consonants
+        | '\u{1AB0}'..='\u{1AFF}' | '\u{1DC0}'..='\u{1DFF}' | '\u{20D0}'..='\u{20FF}'
+        | '\u{200D}' // zero width joiner
+        | '\u{3099}'..='\u{309A}' // kana voiced sound marks
+        | '\u{FE00}'..='\u{FE0F}' // variation selectors
+        | '\u{FE20}'..='\u{FE2F}'
+    )
+}
+
+/// The character a key press would insert, if any.
+fn typed_char(key: &KeyEvent) -> Option<char> {
+    match key.code {
+        KeyCode::Char(c)
+            if key.kind != KeyEventKind::Release
+                && (key.modifiers - KeyModifiers::SHIFT).is_empty() =>
+        {
+            Some(c)
+        }
+        _ => None,
+    }
+}
+
+impl Composer {
+    /// Feeds a key event and returns what should be passed on in its place.
+    pub fn feed(&mut self, key: KeyInput, now: Instant) -> Vec<Output> {
+        if !self.enabled {
+            return vec![Output::Key(key)];
+        }
+        match typed_char(&key.event) {
+            Some(c) if is_combining(c) => {
+                if let Some((base, _)) = self.held.take() {
+                    let base: String = typed_char(&base.event).into_iter().collect();
+                    let text = format!("{}{}", base, c);
+                    self.text = Some((text.clone(), now));
+                    vec![Output::Start(base), Output::Update(text)]
+                } else if let Some((text, last)) = self.text.as_mut() {
+                    text.push(c);
+                    *last = now;
+                    vec![Output::Update(text.clone())]
+                } else {
+                    // nothing to combine with
+                    vec![Output::Key(key)]
+                }
+            }
+            Some(_) => {
+                let output = self.flush();
+                self.held = Some((key, now));
+                output
+            }
+            None => {
+                let mut output = self.flush();
+                output.push(Output::Key(key));
+                output
+            }
+        }
+    }
+
+    /// Commits a composition in progress or releases a held key.
+    pub fn flush(&mut self) -> Vec<Output> {
+        let mut output = Vec::new();
+        if let Some((text, _)) = self.text.take() {
+            output.push(Output::Commit(text));
+        }
+        if let Some((key, _)) = self.held.take() {
+            output.push(Output::Key(key));
+        }
+        output
+    }
+
+    /// Flushes once no combining mark followed in time.
+    pub fn expire(&mut self, now: Instant) -> Vec<Output> {
+        match self.deadline() {
+            Some(deadline) if now >= deadline => self.flush(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn deadline(&self) -> Option<Instant> {
+        let since = match (&self.held, &self.text) {
+            (Some((_, at)), _) | (None, Some((_, at))) => *at,
+            (None, None) => return None,
+        };
+        since.checked_add(COMBINE_TIMEOUT)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(c: char) -> KeyInput {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE).into()
+    }
+
+    fn composer() -> Composer {
+        Composer {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn composes_combining_marks() {
+        let mut composer = composer();
+        let now = Instant::now();
+        assert_eq!(composer.feed(key('e'), now), vec![]);
+        assert_eq!(
+            composer.feed(key('\u{0301}'), now),
+            vec![
+                Output::Start("e".to_string()),
+                Output::Update("e\u{0301}".to_string())
+            ]
+        );
+        assert_eq!(
+            composer.feed(key('\u{0323}'), now),
+            vec![Output::Update("e\u{0301}\u{0323}".to_string())]
+        );
+        assert_eq!(
+            composer.feed(key('x'), now),
+            vec![Output::Commit("e\u{0301}\u{0323}".to_string())]
+        );
+        assert_eq!(composer.flush(), vec![Output::Key(key('x'))]);
+    }
+
+    #[test]
+    fn releases_keys_that_nothing_combines_with() {
+        let mut composer = composer();
+        let now = Instant::now();
+        composer.feed(key('a'), now);
+        assert_eq!(composer.expire(now), vec![]);
+        assert_eq!(
+            composer.expire(now + COMBINE_TIMEOUT),
+            vec![Output::Key(key('a'))]
+        );
+        assert_eq!(
+            composer.feed(key('\u{0301}'), now),
+            vec![Output::Key(key('\u{0301}'))]
+        );
+    }
+
+    #[test]
+    fn passes_on_other_keys_after_flushing() {
+        let mut composer = composer();
+        let now = Instant::now();
+        composer.feed(key('a'), now);
+        let ctrl_a: KeyInput = KeyEvent::new(KeyCode::Char('a'), KeyModifiers::CONTROL).into();
+        assert_eq!(
+            composer.feed(ctrl_a.clone(), now),
+            vec![Output::Key(key('a')), Output::Key(ctrl_a)]
+        );
+    }
+
+    #[test]
+    fn passes_on_everything_while_disabled() {
+        let mut composer = Composer::default();
+        assert_eq!(
+            composer.feed(key('e'), Instant::now()),
+            vec![Output::Key(key('e'))]
+        );
+    }
+}
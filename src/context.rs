@@ -0,0 +1,95 @@
+//! Contexts: handles that hold their own output buffer and error state, for callers whose calls
+//! don't stay on one thread (Go, Java and Node worker pools, ...).
+//!
+//! A `_ctx` function runs its regular counterpart with the context made current on the calling
+//! thread. Output is queued to the context's buffer and written to the terminal when the context
+//! is flushed, or to the frame in progress on the flushing thread, so that the frame still holds
+//! back all output until it ends. While the context is current, the error state is the context's own, so the call
+//! never touches the error state of the thread or the one shared by all threads.
+
+use std::cell::{Cell, RefCell};
+use std::io::Write;
+
+#[derive(Default)]
+pub struct Context {
+    /// Output queued since the last flush.
+    buffer: RefCell<Vec<u8>>,
+    /// Error of the last call in this context.
+    pub error: RefCell<Option<anyhow::Error>>,
+    /// Result of the last call in this context.
+    pub result: RefCell<libc::c_int>,
+}
+
+thread_local! {
+    static CURRENT: Cell<*const Context> = const { Cell::new(std::ptr::null()) };
+}
+
+pub fn is_active() -> bool {
+    CURRENT.with(|current| !current.get().is_null())
+}
+
+/// The context current on this thread, if any.
+pub fn current() -> Option<&'static Context> {
+    let ctx = CURRENT.with(|current| current.get());
+    // SAFETY: the context is current only while `with` runs, which the caller made sure is valid
+    // and not used elsewhere
+    unsafe { ctx.as_ref() }
+}
+
+/// Runs the function `f` with `ctx` current on this thread and returns its result, or `-1` for a
+/// null `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be null or a pointer returned by `crossterm_context_new` that was not freed, and not
+/// in use on another thread.
+pub unsafe fn with(ctx: *mut Context, f: impl FnOnce() -> libc::c_int) -> libc::c_int {
+    if ctx.is_null() {
+        crate::set_last_error(anyhow::anyhow!("Received null pointer for context"));
+        crate::RESULT.with(|r| *r.borrow_mut() = -1);
+        return -1;
+    }
+    // like a call on a thread, the call starts without an error of its own
+    *(*ctx).error.borrow_mut() = None;
+    *(*ctx).result.borrow_mut() = 0;
+    let previous = CURRENT.with(|current| current.replace(ctx));
+    let value = f();
+    CURRENT.with(|current| current.set(previous));
+    value
+}
+
+/// Appends to the buffer of the current context, and writes it to the terminal on flush, or to the
+/// frame in progress.
+pub struct Writer;
+
+impl Writer {
+    fn with_context<T>(f: impl FnOnce(&Context) -> std::io::Result<T>) -> std::io::Result<T> {
+        match current() {
+            Some(ctx) => f(ctx),
+            None => Err(std::io::Error::other("No context in use")),
+        }
+    }
+}
+
+impl Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Self::with_context(|ctx| {
+            ctx.buffer.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        })
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Self::with_context(|ctx| {
+            let mut buffer = ctx.buffer.borrow_mut();
+            let mut out: Box<dyn Write> = if crate::frame::is_active() {
+                Box::new(crate::frame::Writer)
+            } else {
+                crate::output::writer()
+            };
+            out.write_all(&buffer)?;
+            buffer.clear();
+            out.flush()
+        })
+    }
+}
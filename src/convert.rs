@@ -0,0 +1,302 @@
+//! Conversions between `crossterm`'s event types and the `#[repr(C)]` types of the C API.
+//!
+//! Like the JSON format, the C types are owned by this crate, so each variant is mapped by hand.
+
+use crate::compat::crossterm::event as ct;
+use crate::{events, gestures, signals};
+use crate::{
+    ActionEvent, DragEvent, Event, KeyCode, KeyDetails, KeyEvent, KeyEventKind, KeyEventState,
+    KeyModifiers, MediaKeyCode, ModifierKeyCode, MouseButton, MouseEvent, MouseEventKind,
+    SignalFlags,
+};
+
+/// Converts a terminal event. The text of a paste or key is copied into a string allocated with
+/// the allocator of the caller, which frees it with `crossterm_event_free`.
+pub fn event(event: &ct::Event, details: &events::Details) -> Event {
+    match event {
+        ct::Event::FocusGained => Event::FocusGained,
+        ct::Event::FocusLost => Event::FocusLost,
+        ct::Event::Key(key) => Event::Key(key_event(key), key_details(details)),
+        ct::Event::Mouse(mouse) => Event::Mouse(mouse_event(mouse, details)),
+        ct::Event::Paste(s) => Event::Paste(crate::convert_string_to_c_char(s.clone())),
+        ct::Event::Resize(cols, rows) => Event::Resize(*cols, *rows),
+    }
+}
+
+/// Converts an event read through the library. Like [`event`], its text is copied into a string
+/// allocated with the allocator of the caller.
+///
+/// Returns `None` for [`events::Event::Wakeup`], which ends a read rather than being returned.
+pub fn library_event(event: &events::Event) -> Option<Event> {
+    let event = match event {
+        events::Event::Terminal(terminal, details) => self::event(terminal, details),
+        events::Event::Action { id, sequence } => Event::Action(ActionEvent {
+            id: *id,
+            sequence: crate::convert_string_to_c_char(sequence.clone()),
+        }),
+        events::Event::Idle => Event::Idle,
+        events::Event::Resume => Event::Resume,
+        events::Event::Wakeup => return None,
+        events::Event::Signal(signal) => Event::Signal(signal_flag(*signal)),
+        events::Event::Resumed => Event::Resumed,
+        events::Event::DragStart {
+            button,
+            column,
+            row,
+        } => Event::DragStart(DragEvent {
+            button: mouse_button(*button),
+            start_col: *column,
+            start_row: *row,
+            col: *column,
+            row: *row,
+            duration_ms: 0,
+        }),
+        events::Event::DragEnd {
+            button,
+            start_column,
+            start_row,
+            column,
+            row,
+            duration,
+        } => Event::DragEnd(DragEvent {
+            button: mouse_button(*button),
+            start_col: *start_column,
+            start_row: *start_row,
+            col: *column,
+            row: *row,
+            duration_ms: gestures::millis(*duration),
+        }),
+        events::Event::CompositionStart(text) => {
+            Event::CompositionStart(crate::convert_string_to_c_char(text.clone()))
+        }
+        events::Event::CompositionUpdate(text) => {
+            Event::CompositionUpdate(crate::convert_string_to_c_char(text.clone()))
+        }
+        events::Event::CompositionCommit(text) => {
+            Event::CompositionCommit(crate::convert_string_to_c_char(text.clone()))
+        }
+    };
+    Some(event)
+}
+
+fn signal_flag(signal: signals::Signal) -> SignalFlags {
+    match signal {
+        signals::Signal::Winch => SignalFlags::Winch,
+        signals::Signal::Int => SignalFlags::Int,
+        signals::Signal::Term => SignalFlags::Term,
+        signals::Signal::Tstp => SignalFlags::Tstp,
+    }
+}
+
+pub fn key_event(key: &ct::KeyEvent) -> KeyEvent {
+    KeyEvent {
+        code: key_code(&key.code),
+        modifiers: key_modifiers(key.modifiers),
+        kind: match key.kind {
+            ct::KeyEventKind::Press => KeyEventKind::Press,
+            ct::KeyEventKind::Repeat => KeyEventKind::Repeat,
+            ct::KeyEventKind::Release => KeyEventKind::Release,
+        },
+        state: KeyEventState::from_bits_retain(key.state.bits()),
+    }
+}
+
+fn key_details(details: &events::Details) -> KeyDetails {
+    KeyDetails {
+        text: match &details.text {
+            Some(text) => crate::convert_string_to_c_char(text.clone()),
+            None => std::ptr::null(),
+        },
+    }
+}
+
+pub fn key_modifiers(modifiers: ct::KeyModifiers) -> KeyModifiers {
+    KeyModifiers::from_bits_retain(modifiers.bits())
+}
+
+pub fn key_code(code: &ct::KeyCode) -> KeyCode {
+    match code {
+        ct::KeyCode::Backspace => KeyCode::Backspace,
+        ct::KeyCode::Enter => KeyCode::Enter,
+        ct::KeyCode::Left => KeyCode::Left,
+        ct::KeyCode::Right => KeyCode::Right,
+        ct::KeyCode::Up => KeyCode::Up,
+        ct::KeyCode::Down => KeyCode::Down,
+        ct::KeyCode::Home => KeyCode::Home,
+        ct::KeyCode::End => KeyCode::End,
+        ct::KeyCode::PageUp => KeyCode::PageUp,
+        ct::KeyCode::PageDown => KeyCode::PageDown,
+        ct::KeyCode::Tab => KeyCode::Tab,
+        ct::KeyCode::BackTab => KeyCode::BackTab,
+        ct::KeyCode::Delete => KeyCode::Delete,
+        ct::KeyCode::Insert => KeyCode::Insert,
+        ct::KeyCode::F(n) => KeyCode::F(*n),
+        ct::KeyCode::Char(c) => KeyCode::Char(*c),
+        ct::KeyCode::Null => KeyCode::Null,
+        ct::KeyCode::Esc => KeyCode::Esc,
+        ct::KeyCode::CapsLock => KeyCode::CapsLock,
+        ct::KeyCode::ScrollLock => KeyCode::ScrollLock,
+        ct::KeyCode::NumLock => KeyCode::NumLock,
+        ct::KeyCode::PrintScreen => KeyCode::PrintScreen,
+        ct::KeyCode::Pause => KeyCode::Pause,
+        ct::KeyCode::Menu => KeyCode::Menu,
+        ct::KeyCode::KeypadBegin => KeyCode::KeypadBegin,
+        ct::KeyCode::Media(m) => KeyCode::Media(media_key_code(*m)),
+        ct::KeyCode::Modifier(m) => KeyCode::Modifier(modifier_key_code(*m)),
+    }
+}
+
+fn media_key_code(code: ct::MediaKeyCode) -> MediaKeyCode {
+    match code {
+        ct::MediaKeyCode::Play => MediaKeyCode::Play,
+        ct::MediaKeyCode::Pause => MediaKeyCode::Pause,
+        ct::MediaKeyCode::PlayPause => MediaKeyCode::PlayPause,
+        ct::MediaKeyCode::Reverse => MediaKeyCode::Reverse,
+        ct::MediaKeyCode::Stop => MediaKeyCode::Stop,
+        ct::MediaKeyCode::FastForward => MediaKeyCode::FastForward,
+        ct::MediaKeyCode::Rewind => MediaKeyCode::Rewind,
+        ct::MediaKeyCode::TrackNext => MediaKeyCode::TrackNext,
+        ct::MediaKeyCode::TrackPrevious => MediaKeyCode::TrackPrevious,
+        ct::MediaKeyCode::Record => MediaKeyCode::Record,
+        ct::MediaKeyCode::LowerVolume => MediaKeyCode::LowerVolume,
+        ct::MediaKeyCode::RaiseVolume => MediaKeyCode::RaiseVolume,
+        ct::MediaKeyCode::MuteVolume => MediaKeyCode::MuteVolume,
+    }
+}
+
+fn modifier_key_code(code: ct::ModifierKeyCode) -> ModifierKeyCode {
+    match code {
+        ct::ModifierKeyCode::LeftShift => ModifierKeyCode::LeftShift,
+        ct::ModifierKeyCode::LeftControl => ModifierKeyCode::LeftControl,
+        ct::ModifierKeyCode::LeftAlt => ModifierKeyCode::LeftAlt,
+        ct::ModifierKeyCode::LeftSuper => ModifierKeyCode::LeftSuper,
+        ct::ModifierKeyCode::LeftHyper => ModifierKeyCode::LeftHyper,
+        ct::ModifierKeyCode::LeftMeta => ModifierKeyCode::LeftMeta,
+        ct::ModifierKeyCode::RightShift => ModifierKeyCode::RightShift,
+        ct::ModifierKeyCode::RightControl => ModifierKeyCode::RightControl,
+        ct::ModifierKeyCode::RightAlt => ModifierKeyCode::RightAlt,
+        ct::ModifierKeyCode::RightSuper => ModifierKeyCode::RightSuper,
+        ct::ModifierKeyCode::RightHyper => ModifierKeyCode::RightHyper,
+        ct::ModifierKeyCode::RightMeta => ModifierKeyCode::RightMeta,
+        ct::ModifierKeyCode::IsoLevel3Shift => ModifierKeyCode::IsoLevel3Shift,
+        ct::ModifierKeyCode::IsoLevel5Shift => ModifierKeyCode::IsoLevel5Shift,
+    }
+}
+
+pub fn mouse_event(mouse: &ct::MouseEvent, details: &events::Details) -> MouseEvent {
+    let (pixel_x, pixel_y) = details.pixels.unwrap_or_default();
+    MouseEvent {
+        kind: match mouse.kind {
+            ct::MouseEventKind::Down(b) => MouseEventKind::Down(mouse_button(b)),
+            ct::MouseEventKind::Up(b) => MouseEventKind::Up(mouse_button(b)),
+            ct::MouseEventKind::Drag(b) => MouseEventKind::Drag(mouse_button(b)),
+            ct::MouseEventKind::Moved => MouseEventKind::Moved,
+            ct::MouseEventKind::ScrollDown => MouseEventKind::ScrollDown,
+            ct::MouseEventKind::ScrollUp => MouseEventKind::ScrollUp,
+            ct::MouseEventKind::ScrollLeft => MouseEventKind::ScrollLeft,
+            ct::MouseEventKind::ScrollRight => MouseEventKind::ScrollRight,
+        },
+        col: mouse.column,
+        row: mouse.row,
+        modifiers: key_modifiers(mouse.modifiers),
+        pixel_x,
+        pixel_y,
+    }
+}
+
+fn mouse_button(button: ct::MouseButton) -> MouseButton {
+    match button {
+        ct::MouseButton::Left => MouseButton::Left,
+        ct::MouseButton::Right => MouseButton::Right,
+        ct::MouseButton::Middle => MouseButton::Middle,
+    }
+}
+
+/// Converts a key code of the C API back to `crossterm`'s.
+pub fn from_key_code(code: &KeyCode) -> ct::KeyCode {
+    match code {
+        KeyCode::Backspace => ct::KeyCode::Backspace,
+        KeyCode::Enter => ct::KeyCode::Enter,
+        KeyCode::Left => ct::KeyCode::Left,
+        KeyCode::Right => ct::KeyCode::Right,
+        KeyCode::Up => ct::KeyCode::Up,
+        KeyCode::Down => ct::KeyCode::Down,
+        KeyCode::Home => ct::KeyCode::Home,
+        KeyCode::End => ct::KeyCode::End,
+        KeyCode::PageUp => ct::KeyCode::PageUp,
+        KeyCode::PageDown => ct::KeyCode::PageDown,
+        KeyCode::Tab => ct::KeyCode::Tab,
+        KeyCode::BackTab => ct::KeyCode::BackTab,
+        KeyCode::Delete => ct::KeyCode::Delete,
+        KeyCode::Insert => ct::KeyCode::Insert,
+        KeyCode::F(n) => ct::KeyCode::F(*n),
+        KeyCode::Char(c) => ct::KeyCode::Char(*c),
+        KeyCode::Null => ct::KeyCode::Null,
+        KeyCode::Esc => ct::KeyCode::Esc,
+        KeyCode::CapsLock => ct::KeyCode::CapsLock,
+        KeyCode::ScrollLock => ct::KeyCode::ScrollLock,
+        KeyCode::NumLock => ct::KeyCode::NumLock,
+        KeyCode::PrintScreen => ct::KeyCode::PrintScreen,
+        KeyCode::Pause => ct::KeyCode::Pause,
+        KeyCode::Menu => ct::KeyCode::Menu,
+        KeyCode::KeypadBegin => ct::KeyCode::KeypadBegin,
+        KeyCode::Media(m) => ct::KeyCode::Media(from_media_key_code(*m)),
+        KeyCode::Modifier(m) => ct::KeyCode::Modifier(from_modifier_key_code(*m)),
+    }
+}
+
+fn from_media_key_code(code: MediaKeyCode) -> ct::MediaKeyCode {
+    match code {
+        MediaKeyCode::Play => ct::MediaKeyCode::Play,
+        MediaKeyCode::Pause => ct::MediaKeyCode::Pause,
+        MediaKeyCode::PlayPause => ct::MediaKeyCode::PlayPause,
+        MediaKeyCode::Reverse => ct::MediaKeyCode::Reverse,
+        MediaKeyCode::Stop => ct::MediaKeyCode::Stop,
+        MediaKeyCode::FastForward => ct::MediaKeyCode::FastForward,
+        MediaKeyCode::Rewind => ct::MediaKeyCode::Rewind,
+        MediaKeyCode::TrackNext => ct::MediaKeyCode::TrackNext,
+        MediaKeyCode::TrackPrevious => ct::MediaKeyCode::TrackPrevious,
+        MediaKeyCode::Record => ct::MediaKeyCode::Record,
+        MediaKeyCode::LowerVolume => ct::MediaKeyCode::LowerVolume,
+        MediaKeyCode::RaiseVolume => ct::MediaKeyCode::RaiseVolume,
+        MediaKeyCode::MuteVolume => ct::MediaKeyCode::MuteVolume,
+    }
+}
+
+fn from_modifier_key_code(code: ModifierKeyCode) -> ct::ModifierKeyCode {
+    match code {
+        ModifierKeyCode::LeftShift => ct::ModifierKeyCode::LeftShift,
+        ModifierKeyCode::LeftControl => ct::ModifierKeyCode::LeftControl,
+        ModifierKeyCode::LeftAlt => ct::ModifierKeyCode::LeftAlt,
+        ModifierKeyCode::LeftSuper => ct::ModifierKeyCode::LeftSuper,
+        ModifierKeyCode::LeftHyper => ct::ModifierKeyCode::LeftHyper,
+        ModifierKeyCode::LeftMeta => ct::ModifierKeyCode::LeftMeta,
+        ModifierKeyCode::RightShift => ct::ModifierKeyCode::RightShift,
+        ModifierKeyCode::RightControl => ct::ModifierKeyCode::RightControl,
+        ModifierKeyCode::RightAlt => ct::ModifierKeyCode::RightAlt,
+        ModifierKeyCode::RightSuper => ct::ModifierKeyCode::RightSuper,
+        ModifierKeyCode::RightHyper => ct::ModifierKeyCode::RightHyper,
+        ModifierKeyCode::RightMeta => ct::ModifierKeyCode::RightMeta,
+        ModifierKeyCode::IsoLevel3Shift => ct::ModifierKeyCode::IsoLevel3Shift,
+        ModifierKeyCode::IsoLevel5Shift => ct::ModifierKeyCode::IsoLevel5Shift,
+    }
+}
+
+pub fn from_key_modifiers(modifiers: KeyModifiers) -> ct::KeyModifiers {
+    ct::KeyModifiers::from_bits_retain(modifiers.bits())
+}
+
+/// Converts a key event of the C API back to `crossterm`'s.
+pub fn from_key_event(key: &KeyEvent) -> ct::KeyEvent {
+    let kind = match key.kind {
+        KeyEventKind::Press => ct::KeyEventKind::Press,
+        KeyEventKind::Repeat => ct::KeyEventKind::Repeat,
+        KeyEventKind::Release => ct::KeyEventKind::Release,
+    };
+    ct::KeyEvent::new_with_kind_and_state(
+        from_key_code(&key.code),
+        from_key_modifiers(key.modifiers),
+        kind,
+        ct::KeyEventState::from_bits_retain(key.state.bits()),
+    )
+}
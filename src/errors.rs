@@ -0,0 +1,54 @@
+//! Where the error state lives: with the calling thread (the default), or shared by all threads
+//! for runtimes that make consecutive calls from different threads (Go, .NET, Node, ...). While a
+//! context is current, its own error state takes the place of either.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread::LocalKey;
+
+use crate::context::{self, Context};
+
+static GLOBAL: AtomicBool = AtomicBool::new(false);
+
+/// Shares the error state between all threads, or gives each thread its own.
+///
+/// The state of the scope that is left stays as it was, and is seen again when switching back.
+pub fn set_global(global: bool) {
+    GLOBAL.store(global, Ordering::SeqCst);
+}
+
+/// A value of the error state, held by the thread or shared as the scope says, or by the current
+/// context.
+pub struct Slot<T: 'static> {
+    local: &'static LocalKey<RefCell<T>>,
+    global: Mutex<RefCell<T>>,
+    in_context: fn(&Context) -> &RefCell<T>,
+}
+
+impl<T> Slot<T> {
+    pub const fn new(
+        local: &'static LocalKey<RefCell<T>>,
+        init: T,
+        in_context: fn(&Context) -> &RefCell<T>,
+    ) -> Self {
+        Slot {
+            local,
+            global: Mutex::new(RefCell::new(init)),
+            in_context,
+        }
+    }
+
+    /// Runs `f` with the value of the current context or scope, which must not be used again
+    /// within `f`.
+    pub fn with<R>(&'static self, f: impl FnOnce(&RefCell<T>) -> R) -> R {
+        if let Some(ctx) = context::current() {
+            f((self.in_context)(ctx))
+        } else if GLOBAL.load(Ordering::SeqCst) {
+            let value = self.global.lock().unwrap_or_else(|e| e.into_inner());
+            f(&value)
+        } else {
+            self.local.with(f)
+        }
+    }
+}
@@ -0,0 +1,888 @@
+//! The event pipeline between `crossterm`'s reader and [`crate::crossterm_event_read`].
+//!
+//! Terminal events are read from `crossterm`, waiting for them with [`crate::input`], and passed
+//! through the library's processing stages (currently synthesized key repeat, text composition,
+//! key sequence bindings and drag gestures), which may hold events back, replace them or add new
+//! ones.
+//! Finished events wait in a queue until they are read. Stages may also produce events on their
+//! own after a while, like the idle timer.
+//!
+//! Keyboard macros record the key events as they are read, and play them back by pushing them
+//! through the stages again.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, LazyLock, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::compose::{self, Composer};
+use crate::crossterm;
+use crate::crossterm::event::{
+    KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEvent, MouseEventKind,
+};
+use crate::focus;
+use crate::gestures::Gestures;
+use crate::input;
+use crate::keymap::{self, Keymap};
+use crate::repeat::KeyRepeat;
+use crate::screen;
+use crate::signals;
+
+/// What is known about an event beyond `crossterm`'s event types, taken when it is received.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Details {
+    /// The text a key inserts, if the terminal reported it. See [`key_text`].
+    pub text: Option<String>,
+    /// The position of a mouse event in pixels, while mouse events report pixels (SGR-Pixels).
+    pub pixels: Option<(u16, u16)>,
+    /// The hitmap region a mouse event is over.
+    pub region: Option<u32>,
+}
+
+/// A key event with its [`Details`], as passed through the stages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyInput {
+    pub event: KeyEvent,
+    pub details: Details,
+}
+
+impl From<KeyEvent> for KeyInput {
+    fn from(event: KeyEvent) -> Self {
+        KeyInput {
+            event,
+            details: Details::default(),
+        }
+    }
+}
+
+/// An event as delivered to the caller.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// An event read from the terminal.
+    Terminal(crossterm::event::Event, Details),
+    /// A bound key sequence was completed. See [`Keymap`].
+    Action { id: u32, sequence: String },
+    /// No input arrived for the idle timeout.
+    Idle,
+    /// Input arrived after an [`Event::Idle`].
+    Resume,
+    /// The pointer started moving with `button` down at the given cell. See [`Gestures`].
+    DragStart {
+        button: crossterm::event::MouseButton,
+        column: u16,
+        row: u16,
+    },
+    /// The button of a drag was released at the given cell.
+    DragEnd {
+        button: crossterm::event::MouseButton,
+        start_column: u16,
+        start_row: u16,
+        column: u16,
+        row: u16,
+        duration: Duration,
+    },
+    /// A combining sequence started with the given text. See [`Composer`].
+    CompositionStart(String),
+    /// The text of the composition in progress changed.
+    CompositionUpdate(String),
+    /// The composition ended with the given text.
+    CompositionCommit(String),
+    /// Another thread asked for the reader to be woken up. See [`wakeup`].
+    Wakeup,
+    /// An OS signal was received. See [`signals`].
+    Signal(signals::Signal),
+    /// The process continued after being suspended. See [`crate::suspend`].
+    Resumed,
+}
+
+impl Event {
+    /// Whether the event tells the reader about something other than input: a wakeup, a signal or
+    /// a resume. Control events are never dropped, see [`crate::reader`].
+    pub fn is_control(&self) -> bool {
+        matches!(self, Event::Wakeup | Event::Signal(_) | Event::Resumed)
+    }
+}
+
+impl From<keymap::Output> for Event {
+    fn from(output: keymap::Output) -> Self {
+        match output {
+            keymap::Output::Key(key) => {
+                Event::Terminal(crossterm::event::Event::Key(key.event), key.details)
+            }
+            keymap::Output::Action { id, sequence } => Event::Action { id, sequence },
+        }
+    }
+}
+
+/// Tracks input inactivity for [`Event::Idle`].
+#[derive(Default)]
+pub struct IdleTimer {
+    timeout: Option<Duration>,
+    last_input: Option<Instant>,
+    idle: bool,
+}
+
+impl IdleTimer {
+    /// Sets the idle timeout (disabled if `None`) and restarts the timer.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+        self.last_input = Some(Instant::now());
+        self.idle = false;
+    }
+
+    fn deadline(&self) -> Option<Instant> {
+        if self.idle {
+            return None;
+        }
+        self.last_input?.checked_add(self.timeout?)
+    }
+}
+
+/// Which runs of events are merged into their last event, set with
+/// `crossterm_event_set_coalescing`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Coalescing {
+    pub resize: bool,
+    pub mouse_move: bool,
+    pub mouse_drag: bool,
+}
+
+impl Coalescing {
+    /// Whether `next` replaces `last` when it directly follows it.
+    fn merges(&self, last: &crossterm::event::Event, next: &crossterm::event::Event) -> bool {
+        use crossterm::event::Event::{Mouse, Resize};
+        match (last, next) {
+            (Resize(..), Resize(..)) => self.resize,
+            (Mouse(last), Mouse(next)) if last.modifiers == next.modifiers => {
+                match (last.kind, next.kind) {
+                    (MouseEventKind::Moved, MouseEventKind::Moved) => self.mouse_move,
+                    (MouseEventKind::Drag(a), MouseEventKind::Drag(b)) => self.mouse_drag && a == b,
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The kinds of terminal events that are read, as a mask of [`crate::EventFilter`] values set
+/// with `crossterm_event_set_filter`.
+#[derive(Debug, Clone, Copy)]
+pub struct Filter(pub u8);
+
+impl Default for Filter {
+    fn default() -> Self {
+        Filter(u8::MAX)
+    }
+}
+
+impl Filter {
+    fn accepts(&self, event: &crossterm::event::Event) -> bool {
+        use crate::EventFilter;
+        use crossterm::event::Event::*;
+        let kind = match event {
+            Key(_) => EventFilter::Key,
+            Mouse(mouse) => match mouse.kind {
+                MouseEventKind::Moved | MouseEventKind::Drag(_) => EventFilter::MouseMove,
+                _ => EventFilter::Mouse,
+            },
+            Resize(..) => EventFilter::Resize,
+            FocusGained | FocusLost => EventFilter::Focus,
+            Paste(_) => EventFilter::Paste,
+        };
+        self.0 & kind as u8 != 0
+    }
+}
+
+#[derive(Default)]
+pub struct Pipeline {
+    ready: VecDeque<Event>,
+    /// Synthesized key repeat, enabled with `crossterm_event_enable_key_repeat`.
+    pub key_repeat: KeyRepeat,
+    /// Key sequences bound with `crossterm_keybinding_add`.
+    pub keybindings: Keymap,
+    /// Keys recorded since `crossterm_macro_record_start`, if recording.
+    recording: Option<Vec<KeyInput>>,
+    /// The last key read while recording. It is only recorded once the next event is read, so that
+    /// the key that made the caller stop recording is not part of the macro.
+    last_read: Option<KeyInput>,
+    /// The last recorded macro.
+    recorded: Vec<KeyInput>,
+    pub idle: IdleTimer,
+    /// Drag gestures, enabled with `crossterm_event_enable_drag_gestures`.
+    pub gestures: Gestures,
+    /// Whether Shift + vertical scrolling is read as horizontal scrolling.
+    pub shift_scroll_horizontal: bool,
+    /// Text composition, enabled with `crossterm_event_enable_composition`.
+    pub composer: Composer,
+    pub coalescing: Coalescing,
+    pub filter: Filter,
+}
+
+impl Pipeline {
+    /// Takes an event read from the terminal.
+    fn input(&mut self, event: crossterm::event::Event, details: Details, now: Instant) {
+        self.idle.last_input = Some(now);
+        if self.idle.idle {
+            self.idle.idle = false;
+            self.ready.push_back(Event::Resume);
+        }
+        match event {
+            crossterm::event::Event::Key(event) => {
+                let details = Details {
+                    text: key_text(&event),
+                    ..details
+                };
+                for key in self.key_repeat.feed(KeyInput { event, details }, now) {
+                    let output = self.composer.feed(key, now);
+                    self.compose(output, now);
+                }
+            }
+            crossterm::event::Event::Mouse(mouse) => {
+                self.key_repeat.reset();
+                let output = self.composer.flush();
+                self.compose(output, now);
+                let (mut mouse, pixels) = mouse_cells(&mouse);
+                if self.shift_scroll_horizontal {
+                    mouse = shift_scroll_horizontal(mouse);
+                }
+                let details = Details {
+                    pixels,
+                    region: crate::hitmap::hitmap().region_at(mouse.column, mouse.row),
+                    ..details
+                };
+                let (before, after) = self.gestures.feed(&mouse, now);
+                if let Some(before) = before {
+                    self.emit(before);
+                }
+                self.push(crossterm::event::Event::Mouse(mouse), details, now);
+                if let Some(after) = after {
+                    self.emit(after);
+                }
+            }
+            event => {
+                self.key_repeat.reset();
+                let output = self.composer.flush();
+                self.compose(output, now);
+                focus::focus().observe(&event);
+                if let crossterm::event::Event::Resize(cols, rows) = event {
+                    screen::resize(cols, rows);
+                    update_pixel_grid(cols, rows);
+                }
+                self.push(event, details, now);
+            }
+        }
+    }
+
+    /// Passes on the output of the composition stage.
+    pub fn compose(&mut self, output: Vec<compose::Output>, now: Instant) {
+        for output in output {
+            match output {
+                compose::Output::Key(key) => {
+                    self.push(crossterm::event::Event::Key(key.event), key.details, now)
+                }
+                compose::Output::Start(text) => self.emit(Event::CompositionStart(text)),
+                compose::Output::Update(text) => self.emit(Event::CompositionUpdate(text)),
+                compose::Output::Commit(text) => self.emit(Event::CompositionCommit(text)),
+            }
+        }
+    }
+
+    /// Queues the signals received since the last call, as events.
+    fn take_signals(&mut self) {
+        self.ready
+            .extend(signals::take_pending().map(Event::Signal));
+    }
+
+    /// Queues an event produced by a stage, after any pending keys.
+    fn emit(&mut self, event: Event) {
+        let output = self.keybindings.flush();
+        self.ready.extend(output.into_iter().map(Event::from));
+        if let (Some(Event::Terminal(last, _)), Event::Terminal(next, _)) =
+            (self.ready.back(), &event)
+        {
+            if self.coalescing.merges(last, next) {
+                self.ready.pop_back();
+            }
+        }
+        self.ready.push_back(event);
+    }
+
+    fn push(&mut self, event: crossterm::event::Event, details: Details, now: Instant) {
+        if !self.filter.accepts(&event) {
+            return;
+        }
+        match event {
+            crossterm::event::Event::Key(event)
+                if event.kind != KeyEventKind::Release && !self.keybindings.is_empty() =>
+            {
+                let output = self.keybindings.feed(KeyInput { event, details }, now);
+                self.ready.extend(output.into_iter().map(Event::from));
+            }
+            // anything else ends a pending key sequence
+            event => self.emit(Event::Terminal(event, details)),
+        }
+    }
+
+    fn expire(&mut self, now: Instant) {
+        for key in self.key_repeat.expire(now) {
+            let output = self.composer.feed(key, now);
+            self.compose(output, now);
+        }
+        let output = self.composer.expire(now);
+        self.compose(output, now);
+        let output = self.keybindings.expire(now);
+        self.ready.extend(output.into_iter().map(Event::from));
+        if self.idle.deadline().is_some_and(|deadline| now >= deadline) {
+            self.idle.idle = true;
+            self.ready.push_back(Event::Idle);
+        }
+    }
+
+    fn pop(&mut self) -> Option<Event> {
+        let event = self.ready.pop_front()?;
+        self.record(&event);
+        Some(event)
+    }
+
+    /// Records a key read while recording a macro.
+    fn record(&mut self, event: &Event) {
+        if let Some(recording) = self.recording.as_mut() {
+            recording.extend(self.last_read.take());
+            if let Event::Terminal(crossterm::event::Event::Key(event), details) = &event {
+                self.last_read = Some(KeyInput {
+                    event: *event,
+                    details: details.clone(),
+                });
+            }
+        }
+    }
+
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Vec::new());
+        self.last_read = None;
+        RECORDING.store(true, Ordering::SeqCst);
+    }
+
+    /// Stops recording and keeps the recorded keys for [`Pipeline::play`], returning how many
+    /// keys were recorded.
+    pub fn stop_recording(&mut self) -> anyhow::Result<usize> {
+        let recording = self
+            .recording
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Not recording a macro"))?;
+        RECORDING.store(false, Ordering::SeqCst);
+        self.last_read = None;
+        self.recorded = recording;
+        Ok(self.recorded.len())
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Pushes the recorded keys `times` times, returning the number of keys pushed.
+    pub fn play(&mut self, times: usize) -> usize {
+        let now = Instant::now();
+        let keys = self.recorded.clone();
+        for _ in 0..times {
+            for key in &keys {
+                let event = crossterm::event::Event::Key(key.event);
+                self.push(event, key.details.clone(), now);
+            }
+        }
+        keys.len() * times
+    }
+
+    /// Whether an event is waiting to be read, in the pipeline or in the queue of
+    /// [`crate::reader`].
+    pub fn has_ready(&self) -> bool {
+        !self.ready.is_empty() || crate::reader::has_queued()
+    }
+
+    /// Passes the events waiting to be read to `f` in order, and keeps waiting the ones it
+    /// returns. Used by [`crate::reader`] to move events to its queue.
+    pub fn hand_over(&mut self, mut f: impl FnMut(Event) -> Option<Event>) {
+        let ready = std::mem::take(&mut self.ready);
+        self.ready = ready.into_iter().filter_map(&mut f).collect();
+    }
+
+    /// Puts `events` back in front of the events waiting to be read.
+    pub fn restore(&mut self, events: Vec<Event>) {
+        for event in events.into_iter().rev() {
+            self.ready.push_front(event);
+        }
+    }
+
+    /// The earliest time a stage will release held back events on its own.
+    fn deadline(&self) -> Option<Instant> {
+        [
+            self.key_repeat.deadline(),
+            self.composer.deadline(),
+            self.keybindings.deadline(),
+            self.idle.deadline(),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+}
+
+fn shift_scroll_horizontal(mouse: MouseEvent) -> MouseEvent {
+    if !mouse.modifiers.contains(KeyModifiers::SHIFT) {
+        return mouse;
+    }
+    let kind = match mouse.kind {
+        MouseEventKind::ScrollUp => MouseEventKind::ScrollLeft,
+        MouseEventKind::ScrollDown => MouseEventKind::ScrollRight,
+        _ => return mouse,
+    };
+    MouseEvent {
+        kind,
+        modifiers: mouse.modifiers - KeyModifiers::SHIFT,
+        ..mouse
+    }
+}
+
+/// The size of the window in pixels and in cells, to work out the cell of a position in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelGrid {
+    pub width: u16,
+    pub height: u16,
+    pub columns: u16,
+    pub rows: u16,
+}
+
+impl PixelGrid {
+    /// The cell containing the pixel at `(x, y)`. Cells needn't be a whole number of pixels wide,
+    /// and positions past the edge of the grid are in the last column or row.
+    pub fn cell(&self, x: u16, y: u16) -> (u16, u16) {
+        fn scale(pixel: u16, pixels: u16, cells: u16) -> u16 {
+            let cell = pixel as u32 * cells as u32 / pixels.max(1) as u32;
+            cell.min(cells.saturating_sub(1) as u32) as u16
+        }
+        (
+            scale(x, self.width, self.columns),
+            scale(y, self.height, self.rows),
+        )
+    }
+
+    /// The grid after the terminal was resized to `columns` and `rows`, with the same cell size.
+    pub fn resized(&self, columns: u16, rows: u16) -> Self {
+        PixelGrid {
+            width: (self.width as u32 * columns as u32 / self.columns.max(1) as u32) as u16,
+            height: (self.height as u32 * rows as u32 / self.rows.max(1) as u32) as u16,
+            columns,
+            rows,
+        }
+    }
+}
+
+/// The text a key inserts, as far as the terminal reported it. Without keyboard enhancement flags,
+/// the terminal sends a printable key as the text it inserts, which `crossterm` reads as the
+/// character of the key. With the kitty keyboard protocol, it sends key codes instead, and
+/// `crossterm` doesn't pass on the associated text, so there is none.
+fn key_text(key: &KeyEvent) -> Option<String> {
+    let KeyCode::Char(c) = key.code else {
+        return None;
+    };
+    if key.kind == KeyEventKind::Release || !(key.modifiers - KeyModifiers::SHIFT).is_empty() {
+        return None;
+    }
+    let enhanced = crate::state::modes()
+        .keyboard_enhancement_flags
+        .last()
+        .is_some_and(|&flags| flags != 0);
+    (!enhanced).then(|| c.to_string())
+}
+
+/// The mouse event with its position in cells, and its position in pixels if mouse events report
+/// pixels (SGR-Pixels), in which case `crossterm` read the pixels as the cell.
+fn mouse_cells(mouse: &MouseEvent) -> (MouseEvent, Option<(u16, u16)>) {
+    let Some(grid) = crate::state::modes().mouse_pixels else {
+        return (*mouse, None);
+    };
+    let (column, row) = grid.cell(mouse.column, mouse.row);
+    let cells = MouseEvent {
+        column,
+        row,
+        ..*mouse
+    };
+    (cells, Some((mouse.column, mouse.row)))
+}
+
+/// Takes the new window size after a resize if mouse events report pixels. If the terminal
+/// doesn't report its size in pixels, the cell size is kept.
+fn update_pixel_grid(columns: u16, rows: u16) {
+    let mut modes = crate::state::modes();
+    if let Some(grid) = &mut modes.mouse_pixels {
+        *grid = crate::window_pixel_grid().unwrap_or_else(|| grid.resized(columns, rows));
+    }
+}
+
+static PIPELINE: LazyLock<Mutex<Pipeline>> = LazyLock::new(Default::default);
+/// Notified when events become ready and when the input is released, for threads that wait for
+/// events while another thread waits for the input.
+static READY: Condvar = Condvar::new();
+
+/// Wakes the threads waiting for events if events are ready, and signals or clears the handle of
+/// [`crate::notify`]. Called with the pipeline locked.
+fn announce(pipeline: &Pipeline) {
+    let ready = pipeline.has_ready();
+    if ready {
+        READY.notify_all();
+    }
+    crate::notify::update(ready);
+}
+
+/// The locked pipeline. Threads waiting for events are woken when it is unlocked with events
+/// ready, and the handle of [`crate::notify`] follows whether events are ready.
+pub struct PipelineGuard(MutexGuard<'static, Pipeline>);
+
+impl std::ops::Deref for PipelineGuard {
+    type Target = Pipeline;
+
+    fn deref(&self) -> &Pipeline {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for PipelineGuard {
+    fn deref_mut(&mut self) -> &mut Pipeline {
+        &mut self.0
+    }
+}
+
+impl Drop for PipelineGuard {
+    fn drop(&mut self) {
+        announce(self);
+    }
+}
+
+/// Locks the pipeline. The lock is never held while waiting for terminal input.
+pub fn pipeline() -> PipelineGuard {
+    PipelineGuard(lock_pipeline())
+}
+
+fn lock_pipeline() -> MutexGuard<'static, Pipeline> {
+    PIPELINE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Held while waiting for or reading terminal input, so that a terminal query can read its reply
+/// without another thread taking the reply for key events.
+static INPUT: Mutex<()> = Mutex::new(());
+/// Taken on the way to [`INPUT`]. A query holds it until it has the input, so that the threads it
+/// woke up can't take the input back first.
+static TURN: Mutex<()> = Mutex::new(());
+
+/// The input, locked. Releasing it wakes the threads waiting for events, so that one of them takes
+/// over the input. Must not be released with the pipeline locked.
+pub struct InputGuard(Option<MutexGuard<'static, ()>>);
+
+impl Drop for InputGuard {
+    fn drop(&mut self) {
+        drop(self.0.take());
+        // taking the pipeline lock orders this after a waiter's check of the input
+        drop(lock_pipeline());
+        READY.notify_all();
+    }
+}
+
+/// Keeps other threads from reading terminal input until the guard is dropped, ending a wait for
+/// input in progress.
+#[cfg(unix)]
+pub fn lock_input() -> InputGuard {
+    let _turn = TURN.lock().unwrap_or_else(|e| e.into_inner());
+    loop {
+        input::wake();
+        match INPUT.try_lock() {
+            Ok(input) => return InputGuard(Some(input)),
+            Err(std::sync::TryLockError::Poisoned(e)) => return InputGuard(Some(e.into_inner())),
+            // the waiting thread may not have started its wait when it was woken
+            Err(std::sync::TryLockError::WouldBlock) => {
+                std::thread::sleep(Duration::from_millis(1))
+            }
+        }
+    }
+}
+
+/// Takes the input to wait for it.
+fn lock_input_to_wait() -> InputGuard {
+    drop(TURN.lock().unwrap_or_else(|e| e.into_inner()));
+    InputGuard(Some(INPUT.lock().unwrap_or_else(|e| e.into_inner())))
+}
+
+/// Takes the input to wait for it, unless another thread has it or a query is about to take it.
+fn try_lock_input() -> Option<InputGuard> {
+    let _turn = TURN.try_lock().ok()?;
+    match INPUT.try_lock() {
+        Ok(input) => Some(InputGuard(Some(input))),
+        Err(std::sync::TryLockError::Poisoned(e)) => Some(InputGuard(Some(e.into_inner()))),
+        Err(std::sync::TryLockError::WouldBlock) => None,
+    }
+}
+
+/// Waits up to `wait` for terminal input and passes up to `limit` events read from it through the
+/// stages, all at once, so that runs of events can be coalesced before any of them is read. Must
+/// be called with the input locked.
+fn read_input(wait: Option<Duration>, limit: usize) -> anyhow::Result<()> {
+    keep_input(input::read(wait, limit)?);
+    Ok(())
+}
+
+/// Passes terminal events through the stages, e.g. input that arrived while a query waited for
+/// its reply.
+pub fn keep_input(events: Vec<(crossterm::event::Event, Details)>) {
+    let now = Instant::now();
+    let mut pipeline = pipeline();
+    for (event, details) in events {
+        pipeline.input(event, details, now);
+    }
+}
+
+/// Waits up to `timeout` (forever if `None`) for an event to be ready.
+///
+/// If another thread is waiting for terminal input, this thread waits for that thread to pass
+/// the input through the stages instead, or to release the input.
+pub fn poll(timeout: Option<Duration>) -> anyhow::Result<bool> {
+    poll_while(timeout, || true)
+}
+
+/// Like [`poll`], but gives up once `keep_waiting` returns false. It is checked with the pipeline
+/// locked whenever the wait ends, so the condition is to be changed with the pipeline locked and
+/// followed by [`wake_pollers`].
+fn poll_while(timeout: Option<Duration>, keep_waiting: impl Fn() -> bool) -> anyhow::Result<bool> {
+    crate::reader::take_error()?;
+    let deadline = timeout.and_then(|t| Instant::now().checked_add(t));
+    loop {
+        // declared first so that it is released after the pipeline
+        let input;
+        let mut locked = lock_pipeline();
+        // checked with the pipeline locked, so that the input can't be released in between
+        input = try_lock_input();
+        let now = Instant::now();
+        locked.take_signals();
+        locked.expire(now);
+        if locked.has_ready() {
+            announce(&locked);
+            return Ok(true);
+        }
+        if !keep_waiting() {
+            return Ok(false);
+        }
+        let wake = match (deadline, locked.deadline()) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        let wait = wake.map(|wake| wake.saturating_duration_since(now));
+        match input {
+            Some(input) => {
+                drop(locked);
+                read_input(wait, usize::MAX)?;
+                drop(input);
+            }
+            None => {
+                // the pipeline is unlocked while waiting
+                match wait {
+                    Some(wait) => drop(READY.wait_timeout(locked, wait)),
+                    None => drop(READY.wait(locked)),
+                }
+            }
+        }
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            let mut pipeline = pipeline();
+            pipeline.take_signals();
+            pipeline.expire(Instant::now());
+            return Ok(pipeline.has_ready());
+        }
+    }
+}
+
+/// Waits up to `timeout` (forever if `None`) for terminal input and passes it through the stages,
+/// whether or not events are already waiting to be read. The wait ends early when a stage has
+/// events due. If `capacity` is given, no more input is read than fits in a queue of that many
+/// events, and none while it is full. Nothing is read if `keep_waiting` returns false once the
+/// input is locked, so the condition is to be changed before calling [`input::wake`].
+pub fn pump(
+    timeout: Option<Duration>,
+    capacity: Option<usize>,
+    keep_waiting: impl Fn() -> bool,
+) -> anyhow::Result<()> {
+    let _input = lock_input_to_wait();
+    if !keep_waiting() {
+        return Ok(());
+    }
+    let now = Instant::now();
+    let (wait, limit) = {
+        let mut pipeline = pipeline();
+        pipeline.take_signals();
+        pipeline.expire(now);
+        let due = pipeline
+            .deadline()
+            .map(|deadline| deadline.saturating_duration_since(now));
+        let wait = match (timeout, due) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        let limit = capacity.map_or(usize::MAX, |c| c.saturating_sub(pipeline.ready.len()));
+        (wait, limit)
+    };
+    if limit == 0 {
+        return Ok(());
+    }
+    read_input(wait, limit)
+}
+
+/// Whether a macro is being recorded, so that events taken from the queue of [`crate::reader`]
+/// are passed to the pipeline to be recorded.
+static RECORDING: AtomicBool = AtomicBool::new(false);
+
+/// Takes the next ready event. The events in the queue of [`crate::reader`] come first, as they
+/// are older than the ones waiting in the pipeline.
+fn pop() -> Option<Event> {
+    let Some(event) = crate::reader::pop() else {
+        return pipeline().pop();
+    };
+    // the pipeline is only locked to record the event, and to clear the handle of
+    // [`crate::notify`] once no event is ready
+    if RECORDING.load(Ordering::SeqCst) || !crate::reader::has_queued() {
+        pipeline().record(&event);
+    }
+    Some(event)
+}
+
+/// Blocks until an event is ready and returns it.
+pub fn read() -> anyhow::Result<Event> {
+    crate::reader::take_error()?;
+    loop {
+        if let Some(event) = pop() {
+            return Ok(event);
+        }
+        poll(None)?;
+    }
+}
+
+/// Returns the next event if one is ready, without waiting for input.
+pub fn try_read() -> anyhow::Result<Option<Event>> {
+    if poll(Some(Duration::ZERO))? {
+        Ok(pop())
+    } else {
+        Ok(None)
+    }
+}
+
+/// Set to end the loop in [`run`].
+static STOP: AtomicBool = AtomicBool::new(false);
+
+/// Passes each event to `f` as it becomes ready, until [`stop`] is called.
+pub fn run(mut f: impl FnMut(Event)) -> anyhow::Result<()> {
+    STOP.store(false, Ordering::SeqCst);
+    while !STOP.load(Ordering::SeqCst) {
+        if poll_while(None, || !STOP.load(Ordering::SeqCst))? {
+            if let Some(event) = pop() {
+                f(event);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Ends the loop in [`run`] once the event being handled, if any, has been handled.
+pub fn stop() {
+    {
+        let _pipeline = lock_pipeline();
+        STOP.store(true, Ordering::SeqCst);
+    }
+    wake_pollers();
+}
+
+/// Ends the waits of threads in [`poll`], so that they check their conditions again.
+fn wake_pollers() {
+    READY.notify_all();
+    input::wake();
+}
+
+/// Queues an [`Event::Wakeup`], which ends a blocking read on any thread.
+pub fn wakeup() {
+    pipeline().ready.push_back(Event::Wakeup);
+    input::wake();
+}
+
+/// Queues an [`Event::Resumed`].
+pub fn resumed() {
+    pipeline().ready.push_back(Event::Resumed);
+    input::wake();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polls_while_another_thread_waits_for_input() {
+        let (locked_tx, locked_rx) = std::sync::mpsc::channel();
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+        let holder = std::thread::spawn(move || {
+            let _input = lock_input_to_wait();
+            locked_tx.send(()).unwrap();
+            release_rx.recv().unwrap();
+        });
+        locked_rx.recv().unwrap();
+
+        let start = Instant::now();
+        assert!(!poll(Some(Duration::ZERO)).unwrap());
+        assert!(start.elapsed() < Duration::from_millis(500));
+
+        let waiter = std::thread::spawn(|| poll(Some(Duration::from_secs(10))));
+        std::thread::sleep(Duration::from_millis(50));
+        pipeline().ready.push_back(Event::Wakeup);
+        assert!(waiter.join().unwrap().unwrap());
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(matches!(pipeline().pop(), Some(Event::Wakeup)));
+
+        release_tx.send(()).unwrap();
+        holder.join().unwrap();
+    }
+
+    #[test]
+    fn works_out_cells_of_fractional_size() {
+        // cells are 8.0625 pixels wide and 16.25 pixels high
+        let grid = PixelGrid {
+            width: 645,
+            height: 390,
+            columns: 80,
+            rows: 24,
+        };
+        assert_eq!(grid.cell(0, 0), (0, 0));
+        assert_eq!(grid.cell(8, 16), (0, 0));
+        assert_eq!(grid.cell(9, 17), (1, 1));
+        assert_eq!(grid.cell(644, 389), (79, 23));
+        // positions past the edge, e.g. in the window's padding
+        assert_eq!(grid.cell(645, 390), (79, 23));
+        assert_eq!(grid.cell(u16::MAX, u16::MAX), (79, 23));
+    }
+
+    #[test]
+    fn handles_empty_grids() {
+        let grid = PixelGrid {
+            width: 0,
+            height: 0,
+            columns: 0,
+            rows: 0,
+        };
+        assert_eq!(grid.cell(10, 10), (0, 0));
+    }
+
+    #[test]
+    fn keeps_the_cell_size_when_resized() {
+        let grid = PixelGrid {
+            width: 800,
+            height: 480,
+            columns: 80,
+            rows: 24,
+        };
+        let resized = grid.resized(100, 30);
+        assert_eq!((resized.width, resized.height), (1000, 600));
+        assert_eq!(resized.cell(999, 599), (99, 29));
+    }
+}
@@ -0,0 +1,42 @@
+//! Whether the terminal has focus, so that rendering can pause while it is in the background.
+
+use std::sync::{LazyLock, Mutex, MutexGuard};
+
+use crate::crossterm::event::Event;
+
+#[derive(Default)]
+pub struct Focus {
+    /// Whether rendering pauses on focus loss, enabled with
+    /// `crossterm_event_enable_focus_tracking`.
+    pub tracking: bool,
+    /// Whether flushing is held back while rendering is paused.
+    pub hold_flush: bool,
+    /// Whether the terminal has focus, as of the last focus event or report, or `None` before
+    /// the first. Terminals mostly report focus changes only, so rendering assumes focus until
+    /// told otherwise.
+    pub focused: Option<bool>,
+}
+
+impl Focus {
+    pub fn observe(&mut self, event: &Event) {
+        match event {
+            Event::FocusGained => self.focused = Some(true),
+            Event::FocusLost => self.focused = Some(false),
+            _ => {}
+        }
+    }
+
+    pub fn should_render(&self) -> bool {
+        !self.tracking || self.focused != Some(false)
+    }
+
+    pub fn holds_flush(&self) -> bool {
+        self.hold_flush && !self.should_render()
+    }
+}
+
+static FOCUS: LazyLock<Mutex<Focus>> = LazyLock::new(Default::default);
+
+pub fn focus() -> MutexGuard<'static, Focus> {
+    FOCUS.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,86 @@
+//! Frame pacing: output between `crossterm_frame_begin` and `crossterm_frame_end` is buffered and
+//! written at once, then the calling thread sleeps until the next frame is due.
+
+use std::cell::RefCell;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+pub struct Frame {
+    start: Instant,
+    /// Time allotted to a frame, or `None` to not pace frames.
+    interval: Option<Duration>,
+    /// Output queued during the frame.
+    pub buffer: Vec<u8>,
+}
+
+impl Frame {
+    /// Time since the frame began.
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    /// Sleeps for the rest of the frame's interval.
+    pub fn sleep_remaining(&self) {
+        if let Some(interval) = self.interval {
+            let remaining = interval.saturating_sub(self.elapsed());
+            if !remaining.is_zero() {
+                std::thread::sleep(remaining);
+            }
+        }
+    }
+}
+
+thread_local! {
+    static FRAME: RefCell<Option<Frame>> = const { RefCell::new(None) };
+}
+
+/// Begins a frame on the current thread, paced to `target_fps` frames per second (not paced if
+/// `target_fps` is `0`).
+pub fn begin(target_fps: f64) -> anyhow::Result<()> {
+    if !target_fps.is_finite() || target_fps < 0.0 {
+        anyhow::bail!("Received invalid target frame rate {}", target_fps);
+    }
+    FRAME.with(|frame| {
+        let mut frame = frame.borrow_mut();
+        if frame.is_some() {
+            anyhow::bail!("A frame is already in progress");
+        }
+        *frame = Some(Frame {
+            start: Instant::now(),
+            interval: (target_fps > 0.0).then(|| Duration::from_secs_f64(1.0 / target_fps)),
+            buffer: Vec::new(),
+        });
+        Ok(())
+    })
+}
+
+/// Ends the frame on the current thread and returns it.
+pub fn end() -> anyhow::Result<Frame> {
+    FRAME
+        .with(|frame| frame.borrow_mut().take())
+        .ok_or_else(|| anyhow::anyhow!("No frame in progress"))
+}
+
+pub fn is_active() -> bool {
+    FRAME.with(|frame| frame.borrow().is_some())
+}
+
+/// Appends to the buffer of the current frame.
+pub struct Writer;
+
+impl Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        FRAME.with(|frame| match frame.borrow_mut().as_mut() {
+            Some(frame) => {
+                frame.buffer.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            None => Err(std::io::Error::other("No frame in progress")),
+        })
+    }
+
+    /// Output is only flushed at the end of the frame.
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+//! Drag gestures aggregated from raw mouse events.
+//!
+//! A drag starts with the first `Drag` event after a button went down and ends when the button is
+//! released. `DragStart` is produced just before that first `Drag` event and `DragEnd` just after
+//! the `Up` event, so the raw events are still passed on in between.
+
+use std::time::{Duration, Instant};
+
+use crate::crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use crate::events::Event;
+
+struct Press {
+    button: MouseButton,
+    column: u16,
+    row: u16,
+    time: Instant,
+    /// Whether the pointer moved with the button down.
+    dragging: bool,
+}
+
+#[derive(Default)]
+pub struct Gestures {
+    pub enabled: bool,
+    press: Option<Press>,
+}
+
+impl Gestures {
+    /// Feeds a mouse event and returns the gesture events to pass on before and after it.
+    pub fn feed(&mut self, mouse: &MouseEvent, now: Instant) -> (Option<Event>, Option<Event>) {
+        if !self.enabled {
+            return (None, None);
+        }
+        match mouse.kind {
+            MouseEventKind::Down(button) => {
+                self.press = Some(Press {
+                    button,
+                    column: mouse.column,
+                    row: mouse.row,
+                    time: now,
+                    dragging: false,
+                });
+                (None, None)
+            }
+            MouseEventKind::Drag(button) => {
+                let press = match self.press.as_mut() {
+                    Some(press) if press.button == button => press,
+                    // the press happened before gestures were enabled or wasn't reported
+                    _ => self.press.insert(Press {
+                        button,
+                        column: mouse.column,
+                        row: mouse.row,
+                        time: now,
+                        dragging: false,
+                    }),
+                };
+                if press.dragging {
+                    return (None, None);
+                }
+                press.dragging = true;
+                let start = Event::DragStart {
+                    button,
+                    column: press.column,
+                    row: press.row,
+                };
+                (Some(start), None)
+            }
+            MouseEventKind::Up(button) => match self.press.take() {
+                Some(press) if press.button == button && press.dragging => {
+                    let end = Event::DragEnd {
+                        button,
+                        start_column: press.column,
+                        start_row: press.row,
+                        column: mouse.column,
+                        row: mouse.row,
+                        duration: now.saturating_duration_since(press.time),
+                    };
+                    (None, Some(end))
+                }
+                _ => (None, None),
+            },
+            _ => (None, None),
+        }
+    }
+}
+
+/// Displacement of a drag, in cells.
+pub fn delta(from: u16, to: u16) -> i32 {
+    to as i32 - from as i32
+}
+
+/// Duration in whole milliseconds, for the JSON payload.
+pub fn millis(duration: Duration) -> u64 {
+    duration.as_millis().min(u64::MAX as u128) as u64
+}
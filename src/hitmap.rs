@@ -0,0 +1,104 @@
+//! Screen regions registered by the caller, so mouse events can say which region they are over.
+
+use std::sync::{LazyLock, Mutex, MutexGuard};
+
+struct Region {
+    id: u32,
+    col: u16,
+    row: u16,
+    width: u16,
+    height: u16,
+}
+
+impl Region {
+    fn contains(&self, col: u16, row: u16) -> bool {
+        (self.col as u32..self.col as u32 + self.width as u32).contains(&(col as u32))
+            && (self.row as u32..self.row as u32 + self.height as u32).contains(&(row as u32))
+    }
+}
+
+#[derive(Default)]
+pub struct Hitmap {
+    regions: Vec<Region>,
+}
+
+impl Hitmap {
+    /// Registers a region on top of the existing ones, replacing an existing region with the same
+    /// `id`.
+    pub fn add(&mut self, id: u32, col: u16, row: u16, width: u16, height: u16) {
+        self.remove(id);
+        self.regions.push(Region {
+            id,
+            col,
+            row,
+            width,
+            height,
+        });
+    }
+
+    /// Removes the region `id`. Returns whether there was one.
+    pub fn remove(&mut self, id: u32) -> bool {
+        let before = self.regions.len();
+        self.regions.retain(|r| r.id != id);
+        self.regions.len() != before
+    }
+
+    pub fn clear(&mut self) {
+        self.regions.clear();
+    }
+
+    /// The topmost (most recently added) region containing the cell, if any.
+    pub fn region_at(&self, col: u16, row: u16) -> Option<u32> {
+        self.regions
+            .iter()
+            .rev()
+            .find(|r| r.contains(col, row))
+            .map(|r| r.id)
+    }
+}
+
+static HITMAP: LazyLock<Mutex<Hitmap>> = LazyLock::new(Default::default);
+
+pub fn hitmap() -> MutexGuard<'static, Hitmap> {
+    HITMAP.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_topmost_region() {
+        let mut hitmap = Hitmap::default();
+        hitmap.add(1, 0, 0, 10, 5);
+        hitmap.add(2, 5, 2, 10, 5);
+        assert_eq!(hitmap.region_at(0, 0), Some(1));
+        assert_eq!(hitmap.region_at(9, 4), Some(2));
+        assert_eq!(hitmap.region_at(14, 6), Some(2));
+        assert_eq!(hitmap.region_at(15, 6), None);
+        assert_eq!(hitmap.region_at(4, 5), None);
+    }
+
+    #[test]
+    fn replaces_and_removes_regions() {
+        let mut hitmap = Hitmap::default();
+        hitmap.add(1, 0, 0, 10, 5);
+        hitmap.add(2, 0, 0, 10, 5);
+        hitmap.add(1, 0, 0, 10, 5);
+        assert_eq!(hitmap.region_at(3, 3), Some(1));
+        assert!(hitmap.remove(1));
+        assert!(!hitmap.remove(1));
+        assert_eq!(hitmap.region_at(3, 3), Some(2));
+        hitmap.clear();
+        assert_eq!(hitmap.region_at(3, 3), None);
+    }
+
+    #[test]
+    fn handles_regions_at_the_edge_of_the_coordinates() {
+        let mut hitmap = Hitmap::default();
+        hitmap.add(1, u16::MAX - 1, u16::MAX - 1, u16::MAX, u16::MAX);
+        hitmap.add(2, 0, 0, 0, 0);
+        assert_eq!(hitmap.region_at(u16::MAX, u16::MAX), Some(1));
+        assert_eq!(hitmap.region_at(0, 0), None);
+    }
+}
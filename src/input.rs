@@ -0,0 +1,195 @@
+//! Reading terminal input for the event pipeline.
+//!
+//! Events are read with `crossterm`, but waits for them are done here. On Unix, the terminal
+//! `crossterm` reads is waited on together with a pipe, so that [`wake`] ends the wait right away,
+//! e.g. when another thread queued an event or a signal arrived. Resizes write to the pipe too.
+//!
+//! On Windows, `crossterm` reads the console input, and a wait for it is waited on together with an
+//! event object set by [`wake`].
+
+#[cfg(unix)]
+pub use native::{init, wake_from_signal};
+pub use native::{read, wake};
+
+#[cfg(unix)]
+mod native {
+    use std::sync::{Mutex, OnceLock};
+    use std::time::Duration;
+
+    use crate::crossterm;
+    use crate::crossterm::event::Event;
+    use crate::events::Details;
+    use crate::notify;
+
+    /// Has a byte written to it to end a wait for input.
+    static PIPE: OnceLock<notify::native::Signal> = OnceLock::new();
+
+    /// Creates the pipe [`wake`] writes to and registers a `SIGWINCH` handler that writes to it,
+    /// unless done already.
+    pub fn init() -> anyhow::Result<notify::native::Signal> {
+        static INIT: Mutex<()> = Mutex::new(());
+        let _init = INIT.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(pipe) = PIPE.get() {
+            return Ok(*pipe);
+        }
+        let pipe = notify::native::Signal::new()?;
+        // creates `crossterm`'s reader, which registers its own `SIGWINCH` handler; handlers run in
+        // the order they were registered, so the resize is queued by the time the wait ends
+        crossterm::event::poll(Duration::ZERO)?;
+        // SAFETY: the action only uses `write`, which is async-signal-safe
+        unsafe {
+            signal_hook::low_level::register(libc::SIGWINCH, wake_from_signal)?;
+        }
+        Ok(*PIPE.get_or_init(|| pipe))
+    }
+
+    /// Ends a wait for input in progress on another thread, or the next one if none is.
+    pub fn wake() {
+        if let Ok(pipe) = init() {
+            pipe.set();
+        }
+    }
+
+    /// Like [`wake`], for signal handlers, which must not create the pipe. Handlers are installed
+    /// after calling [`init`].
+    pub fn wake_from_signal() {
+        if let Some(pipe) = PIPE.get() {
+            pipe.set();
+        }
+    }
+
+    /// Waits up to `timeout` (forever if `None`) for input, a resize or [`wake`], and returns the
+    /// events `crossterm` read, which are none if the wait was woken, timed out or interrupted. No
+    /// more than `limit` events are read, and input beyond them is left for the next read.
+    pub fn read(timeout: Option<Duration>, limit: usize) -> anyhow::Result<Vec<(Event, Details)>> {
+        let pipe = init()?;
+        let events = read_available(limit)?;
+        if !events.is_empty() {
+            return Ok(events);
+        }
+        let mut fds = [
+            libc::pollfd {
+                fd: crate::raw::event_fd()?,
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: pipe.fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+        // rounded up, so that a wait for a deadline doesn't end just before it
+        let timeout = timeout.map_or(-1, |t| {
+            t.as_micros().div_ceil(1000).min(libc::c_int::MAX as u128) as libc::c_int
+        });
+        if crate::raw::poll(&mut fds, timeout) < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                return Ok(Vec::new());
+            }
+            return Err(err.into());
+        }
+        if fds[1].revents != 0 {
+            pipe.clear();
+        }
+        read_available(limit)
+    }
+
+    /// Reads the events `crossterm` has ready without waiting, up to `limit`.
+    fn read_available(limit: usize) -> anyhow::Result<Vec<(Event, Details)>> {
+        let mut events = Vec::new();
+        while events.len() < limit && crossterm::event::poll(Duration::ZERO)? {
+            events.push((crossterm::event::read()?, Details::default()));
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(windows)]
+mod native {
+    use std::sync::{Mutex, OnceLock};
+    use std::time::Duration;
+
+    use winapi::um::fileapi::{CreateFileW, OPEN_EXISTING};
+    use winapi::um::handleapi::INVALID_HANDLE_VALUE;
+    use winapi::um::synchapi::WaitForMultipleObjects;
+    use winapi::um::winbase::{INFINITE, WAIT_FAILED};
+    use winapi::um::winnt::{
+        FILE_SHARE_READ, FILE_SHARE_WRITE, GENERIC_READ, GENERIC_WRITE, HANDLE,
+    };
+
+    use crate::crossterm;
+    use crate::crossterm::event::Event;
+    use crate::events::Details;
+    use crate::notify;
+
+    /// Set to end a wait for input.
+    static EVENT: OnceLock<notify::native::Signal> = OnceLock::new();
+    /// The console input handle, kept as an integer so it can be shared between threads.
+    static CONSOLE: OnceLock<usize> = OnceLock::new();
+
+    /// Creates the event [`wake`] sets, unless done already.
+    pub fn init() -> anyhow::Result<notify::native::Signal> {
+        static INIT: Mutex<()> = Mutex::new(());
+        let _init = INIT.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(event) = EVENT.get() {
+            return Ok(*event);
+        }
+        let event = notify::native::Signal::new()?;
+        Ok(*EVENT.get_or_init(|| event))
+    }
+
+    /// Ends a wait for input in progress on another thread, or the next one if none is.
+    pub fn wake() {
+        if let Ok(event) = init() {
+            event.set();
+        }
+    }
+
+    /// The console input, which `crossterm` reads even if stdin is redirected.
+    fn console() -> anyhow::Result<usize> {
+        if let Some(console) = CONSOLE.get() {
+            return Ok(*console);
+        }
+        let name: Vec<u16> = "CONIN$\0".encode_utf16().collect();
+        let handle = unsafe {
+            CreateFileW(
+                name.as_ptr(),
+                GENERIC_READ | GENERIC_WRITE,
+                FILE_SHARE_READ | FILE_SHARE_WRITE,
+                std::ptr::null_mut(),
+                OPEN_EXISTING,
+                0,
+                std::ptr::null_mut(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(*CONSOLE.get_or_init(|| handle as usize))
+    }
+
+    /// Waits up to `timeout` (forever if `None`) for input or [`wake`], and returns the events
+    /// read, which are none if the wait was woken or timed out. No more than `limit` events are
+    /// read, and input beyond them is left for the next read.
+    pub fn read(timeout: Option<Duration>, limit: usize) -> anyhow::Result<Vec<(Event, Details)>> {
+        let event = init()?;
+        let handles: [HANDLE; 2] = [console()? as _, event.handle() as _];
+        let timeout = timeout.map_or(INFINITE, |t| {
+            t.as_micros().div_ceil(1000).min(INFINITE as u128 - 1) as u32
+        });
+        let rc =
+            unsafe { WaitForMultipleObjects(handles.len() as u32, handles.as_ptr(), 0, timeout) };
+        if rc == WAIT_FAILED {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        event.clear();
+        let mut events = Vec::new();
+        // console input that isn't an event, like key releases, signals the console too
+        while events.len() < limit && crossterm::event::poll(Duration::ZERO)? {
+            events.push((crossterm::event::read()?, Details::default()));
+        }
+        Ok(events)
+    }
+}
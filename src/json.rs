@@ -0,0 +1,372 @@
+//! Serializers for the event JSON wire format.
+//!
+//! The format is owned by this crate rather than derived from `crossterm`'s serde support, so a
+//! change in `crossterm` can never silently change what bindings parse.
+//!
+//! # Schema versions
+//!
+//! * `1` (default): the original layout, e.g. `{"Resize":[80,24]}` or `"FocusGained"`.
+//! * `2`: every event is an object `{"v":2,"type":"Resize","data":[80,24]}`; `data` is omitted
+//!   for events without a payload.
+//!
+//! The payloads themselves are identical in both versions. Mouse events over a region registered
+//! with `crossterm_hitmap_add` have an extra `"region"` field with the region id. While mouse
+//! events report pixels, they have `"pixel_x"` and `"pixel_y"` fields with the position in pixels.
+//! Key events have a `"text"` field with the text they insert if the terminal reported it.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use serde_json::{json, Value};
+
+use crate::compat::crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers, MediaKeyCode,
+    ModifierKeyCode, MouseButton, MouseEvent, MouseEventKind,
+};
+use crate::events;
+use crate::gestures;
+
+/// Oldest schema version still produced.
+pub(crate) const MIN_SCHEMA_VERSION: u32 = 1;
+/// Newest schema version.
+pub(crate) const MAX_SCHEMA_VERSION: u32 = 2;
+
+static SCHEMA_VERSION: AtomicU32 = AtomicU32::new(MIN_SCHEMA_VERSION);
+
+pub fn schema_version() -> u32 {
+    SCHEMA_VERSION.load(Ordering::Relaxed)
+}
+
+pub fn set_schema_version(version: u32) -> anyhow::Result<()> {
+    if !(MIN_SCHEMA_VERSION..=MAX_SCHEMA_VERSION).contains(&version) {
+        anyhow::bail!(
+            "Unsupported event schema version {} (supported: {}..={})",
+            version,
+            MIN_SCHEMA_VERSION,
+            MAX_SCHEMA_VERSION
+        );
+    }
+    SCHEMA_VERSION.store(version, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Wraps an event `type` and its optional payload in the envelope of the current schema version.
+pub fn envelope(kind: &str, data: Option<Value>) -> Value {
+    match schema_version() {
+        1 => match data {
+            Some(data) => json!({ kind: data }),
+            None => Value::String(kind.to_string()),
+        },
+        v => {
+            let mut object = serde_json::Map::new();
+            object.insert("v".to_string(), json!(v));
+            object.insert("type".to_string(), json!(kind));
+            if let Some(data) = data {
+                object.insert("data".to_string(), data);
+            }
+            Value::Object(object)
+        }
+    }
+}
+
+/// Error object in the current schema version.
+pub fn error(message: String) -> Value {
+    match schema_version() {
+        1 => json!({ "error": message }),
+        v => json!({ "v": v, "type": "Error", "error": message }),
+    }
+}
+
+pub fn event(evt: &events::Event) -> Value {
+    match evt {
+        events::Event::Terminal(evt, details) => terminal_event(evt, details),
+        events::Event::Action { id, sequence } => {
+            envelope("Action", Some(json!({ "id": id, "sequence": sequence })))
+        }
+        events::Event::Idle => envelope("Idle", None),
+        events::Event::Resume => envelope("Resume", None),
+        events::Event::Wakeup => envelope("Wakeup", None),
+        events::Event::Resumed => envelope("Resumed", None),
+        events::Event::Signal(signal) => {
+            envelope("Signal", Some(json!({ "signal": signal.name() })))
+        }
+        events::Event::CompositionStart(text) => {
+            envelope("CompositionStart", Some(json!({ "text": text })))
+        }
+        events::Event::CompositionUpdate(text) => {
+            envelope("CompositionUpdate", Some(json!({ "text": text })))
+        }
+        events::Event::CompositionCommit(text) => {
+            envelope("CompositionCommit", Some(json!({ "text": text })))
+        }
+        events::Event::DragStart {
+            button,
+            column,
+            row,
+        } => envelope(
+            "DragStart",
+            Some(json!({ "button": mouse_button(*button), "column": column, "row": row })),
+        ),
+        events::Event::DragEnd {
+            button,
+            start_column,
+            start_row,
+            column,
+            row,
+            duration,
+        } => envelope(
+            "DragEnd",
+            Some(json!({
+                "button": mouse_button(*button),
+                "start_column": start_column,
+                "start_row": start_row,
+                "column": column,
+                "row": row,
+                "delta_column": gestures::delta(*start_column, *column),
+                "delta_row": gestures::delta(*start_row, *row),
+                "duration_ms": gestures::millis(*duration),
+            })),
+        ),
+    }
+}
+
+pub fn terminal_event(evt: &Event, details: &events::Details) -> Value {
+    match evt {
+        Event::FocusGained => envelope("FocusGained", None),
+        Event::FocusLost => envelope("FocusLost", None),
+        Event::Key(key) => envelope("Key", Some(key_event(key, details))),
+        Event::Mouse(mouse) => envelope("Mouse", Some(mouse_event(mouse, details))),
+        Event::Paste(s) => envelope("Paste", Some(json!(s))),
+        Event::Resize(cols, rows) => envelope("Resize", Some(json!([cols, rows]))),
+    }
+}
+
+pub fn key_event(key: &KeyEvent, details: &events::Details) -> Value {
+    let mut value = json!({
+        "code": key_code(&key.code),
+        "modifiers": key_modifiers(key.modifiers),
+        "kind": key_event_kind(key.kind),
+        "state": key_event_state(key.state),
+    });
+    if let Some(text) = &details.text {
+        value["text"] = json!(text);
+    }
+    value
+}
+
+pub fn mouse_event(mouse: &MouseEvent, details: &events::Details) -> Value {
+    let mut value = json!({
+        "kind": mouse_event_kind(mouse.kind),
+        "column": mouse.column,
+        "row": mouse.row,
+        "modifiers": key_modifiers(mouse.modifiers),
+    });
+    if let Some((x, y)) = details.pixels {
+        value["pixel_x"] = json!(x);
+        value["pixel_y"] = json!(y);
+    }
+    if let Some(region) = details.region {
+        value["region"] = json!(region);
+    }
+    value
+}
+
+/// Formats flag bits as `"A | B"`, naming each bit once even if several names share it.
+fn flags(bits: u8, names: &[(&str, u8)]) -> Value {
+    let mut remaining = bits;
+    let mut set = Vec::new();
+    for (name, flag) in names {
+        if *flag != 0 && remaining & flag == *flag {
+            set.push(*name);
+            remaining &= !flag;
+        }
+    }
+    Value::String(set.join(" | "))
+}
+
+pub fn key_modifiers(m: KeyModifiers) -> Value {
+    flags(
+        m.bits(),
+        &[
+            ("SHIFT", KeyModifiers::SHIFT.bits()),
+            ("CONTROL", KeyModifiers::CONTROL.bits()),
+            ("ALT", KeyModifiers::ALT.bits()),
+            ("SUPER", KeyModifiers::SUPER.bits()),
+            ("HYPER", KeyModifiers::HYPER.bits()),
+            ("META", KeyModifiers::META.bits()),
+        ],
+    )
+}
+
+fn key_event_state(s: KeyEventState) -> Value {
+    flags(
+        s.bits(),
+        &[
+            ("KEYPAD", KeyEventState::KEYPAD.bits()),
+            ("CAPS_LOCK", KeyEventState::CAPS_LOCK.bits()),
+            ("NUM_LOCK", KeyEventState::NUM_LOCK.bits()),
+        ],
+    )
+}
+
+fn key_event_kind(kind: KeyEventKind) -> Value {
+    json!(match kind {
+        KeyEventKind::Press => "Press",
+        KeyEventKind::Repeat => "Repeat",
+        KeyEventKind::Release => "Release",
+    })
+}
+
+pub fn key_code(code: &KeyCode) -> Value {
+    let name = match code {
+        KeyCode::Backspace => "Backspace",
+        KeyCode::Enter => "Enter",
+        KeyCode::Left => "Left",
+        KeyCode::Right => "Right",
+        KeyCode::Up => "Up",
+        KeyCode::Down => "Down",
+        KeyCode::Home => "Home",
+        KeyCode::End => "End",
+        KeyCode::PageUp => "PageUp",
+        KeyCode::PageDown => "PageDown",
+        KeyCode::Tab => "Tab",
+        KeyCode::BackTab => "BackTab",
+        KeyCode::Delete => "Delete",
+        KeyCode::Insert => "Insert",
+        KeyCode::Null => "Null",
+        KeyCode::Esc => "Esc",
+        KeyCode::CapsLock => "CapsLock",
+        KeyCode::ScrollLock => "ScrollLock",
+        KeyCode::NumLock => "NumLock",
+        KeyCode::PrintScreen => "PrintScreen",
+        KeyCode::Pause => "Pause",
+        KeyCode::Menu => "Menu",
+        KeyCode::KeypadBegin => "KeypadBegin",
+        KeyCode::F(n) => return json!({ "F": n }),
+        KeyCode::Char(c) => return json!({ "Char": c.to_string() }),
+        KeyCode::Media(m) => return json!({ "Media": media_key_code(*m) }),
+        KeyCode::Modifier(m) => return json!({ "Modifier": modifier_key_code(*m) }),
+    };
+    json!(name)
+}
+
+fn media_key_code(code: MediaKeyCode) -> &'static str {
+    match code {
+        MediaKeyCode::Play => "Play",
+        MediaKeyCode::Pause => "Pause",
+        MediaKeyCode::PlayPause => "PlayPause",
+        MediaKeyCode::Reverse => "Reverse",
+        MediaKeyCode::Stop => "Stop",
+        MediaKeyCode::FastForward => "FastForward",
+        MediaKeyCode::Rewind => "Rewind",
+        MediaKeyCode::TrackNext => "TrackNext",
+        MediaKeyCode::TrackPrevious => "TrackPrevious",
+        MediaKeyCode::Record => "Record",
+        MediaKeyCode::LowerVolume => "LowerVolume",
+        MediaKeyCode::RaiseVolume => "RaiseVolume",
+        MediaKeyCode::MuteVolume => "MuteVolume",
+    }
+}
+
+fn modifier_key_code(code: ModifierKeyCode) -> &'static str {
+    match code {
+        ModifierKeyCode::LeftShift => "LeftShift",
+        ModifierKeyCode::LeftControl => "LeftControl",
+        ModifierKeyCode::LeftAlt => "LeftAlt",
+        ModifierKeyCode::LeftSuper => "LeftSuper",
+        ModifierKeyCode::LeftHyper => "LeftHyper",
+        ModifierKeyCode::LeftMeta => "LeftMeta",
+        ModifierKeyCode::RightShift => "RightShift",
+        ModifierKeyCode::RightControl => "RightControl",
+        ModifierKeyCode::RightAlt => "RightAlt",
+        ModifierKeyCode::RightSuper => "RightSuper",
+        ModifierKeyCode::RightHyper => "RightHyper",
+        ModifierKeyCode::RightMeta => "RightMeta",
+        ModifierKeyCode::IsoLevel3Shift => "IsoLevel3Shift",
+        ModifierKeyCode::IsoLevel5Shift => "IsoLevel5Shift",
+    }
+}
+
+fn mouse_button(button: MouseButton) -> &'static str {
+    match button {
+        MouseButton::Left => "Left",
+        MouseButton::Right => "Right",
+        MouseButton::Middle => "Middle",
+    }
+}
+
+fn mouse_event_kind(kind: MouseEventKind) -> Value {
+    match kind {
+        MouseEventKind::Down(b) => json!({ "Down": mouse_button(b) }),
+        MouseEventKind::Up(b) => json!({ "Up": mouse_button(b) }),
+        MouseEventKind::Drag(b) => json!({ "Drag": mouse_button(b) }),
+        MouseEventKind::Moved => json!("Moved"),
+        MouseEventKind::ScrollDown => json!("ScrollDown"),
+        MouseEventKind::ScrollUp => json!("ScrollUp"),
+        MouseEventKind::ScrollLeft => json!("ScrollLeft"),
+        MouseEventKind::ScrollRight => json!("ScrollRight"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_the_region_of_mouse_events() {
+        let mouse = MouseEvent {
+            kind: MouseEventKind::Moved,
+            column: 3,
+            row: 1,
+            modifiers: KeyModifiers::NONE,
+        };
+        assert!(mouse_event(&mouse, &events::Details::default())
+            .get("region")
+            .is_none());
+        let details = events::Details {
+            region: Some(7),
+            ..Default::default()
+        };
+        assert_eq!(
+            mouse_event(&mouse, &details),
+            json!({
+                "kind": "Moved",
+                "column": 3,
+                "row": 1,
+                "modifiers": "",
+                "region": 7,
+            })
+        );
+    }
+
+    #[test]
+    fn adds_the_text_of_key_events() {
+        let key = KeyEvent::new(KeyCode::Char('!'), KeyModifiers::NONE);
+        assert!(key_event(&key, &events::Details::default())
+            .get("text")
+            .is_none());
+        let details = events::Details {
+            text: Some("!".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(key_event(&key, &details)["text"], "!");
+    }
+
+    #[test]
+    fn adds_the_pixel_position_of_mouse_events() {
+        let mouse = MouseEvent {
+            kind: MouseEventKind::Moved,
+            column: 3,
+            row: 1,
+            modifiers: KeyModifiers::NONE,
+        };
+        let details = events::Details {
+            pixels: Some((30, 20)),
+            ..Default::default()
+        };
+        let value = mouse_event(&mouse, &details);
+        assert_eq!(
+            (&value["pixel_x"], &value["pixel_y"]),
+            (&json!(30), &json!(20))
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! Matching key events against bound key sequences (chords) such as `"ctrl+x ctrl+s"`.
+//!
+//! Keys that could still complete a sequence are held back. When a sequence completes, a single
+//! action is produced in their place; when it can't complete anymore (a different key arrives or
+//! the inter-key timeout expires), the held back keys are released unchanged.
+
+use std::time::{Duration, Instant};
+
+use crate::events::KeyInput;
+use crate::keys::{self, Key};
+
+/// Default time allowed between two keys of a sequence.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// Result of feeding a key event to a [`Keymap`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Output {
+    /// A key that is not part of a bound sequence, to be passed on unchanged.
+    Key(KeyInput),
+    /// A completed sequence.
+    Action { id: u32, sequence: String },
+}
+
+struct Binding {
+    keys: Vec<Key>,
+    sequence: String,
+    id: u32,
+}
+
+pub struct Keymap {
+    bindings: Vec<Binding>,
+    pending: Vec<KeyInput>,
+    last_key: Option<Instant>,
+    pub timeout: Duration,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap {
+            bindings: Vec::new(),
+            pending: Vec::new(),
+            last_key: None,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+impl Keymap {
+    /// Binds `sequence` to the action `id`, replacing an existing binding of the same keys.
+    pub fn add(&mut self, sequence: &str, id: u32) -> anyhow::Result<()> {
+        let keys = keys::parse_sequence(sequence)?;
+        self.bindings.retain(|b| b.keys != keys);
+        self.bindings.push(Binding {
+            keys,
+            sequence: sequence.split_whitespace().collect::<Vec<_>>().join(" "),
+            id,
+        });
+        Ok(())
+    }
+
+    /// Removes the binding of `sequence`. Returns whether there was one.
+    pub fn remove(&mut self, sequence: &str) -> anyhow::Result<bool> {
+        let keys = keys::parse_sequence(sequence)?;
+        let before = self.bindings.len();
+        self.bindings.retain(|b| b.keys != keys);
+        Ok(self.bindings.len() != before)
+    }
+
+    pub fn clear(&mut self) {
+        self.bindings.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bindings.is_empty()
+    }
+
+    /// Whether keys are held back as the start of a sequence.
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// When the held back keys will be released if no other key arrives.
+    pub fn deadline(&self) -> Option<Instant> {
+        if self.pending.is_empty() {
+            None
+        } else {
+            self.last_key.map(|t| t + self.timeout)
+        }
+    }
+
+    fn exact(&self, keys: &[Key]) -> Option<&Binding> {
+        self.bindings.iter().find(|b| b.keys == keys)
+    }
+
+    fn extendable(&self, keys: &[Key]) -> bool {
+        self.bindings
+            .iter()
+            .any(|b| b.keys.len() > keys.len() && b.keys.starts_with(keys))
+    }
+
+    /// Feeds a key press (or repeat) and returns what should be passed on in its place.
+    pub fn feed(&mut self, event: KeyInput, now: Instant) -> Vec<Output> {
+        let mut output = Vec::new();
+        if self.deadline().is_some_and(|deadline| now >= deadline) {
+            output.extend(self.flush());
+        }
+        let mut keys: Vec<Key> = self.pending.iter().map(|k| Key::from(&k.event)).collect();
+        keys.push(Key::from(&event.event));
+        let extendable = self.extendable(&keys);
+        match self.exact(&keys) {
+            Some(binding) if !extendable => {
+                output.push(Output::Action {
+                    id: binding.id,
+                    sequence: binding.sequence.clone(),
+                });
+                self.pending.clear();
+            }
+            _ if extendable => {
+                self.pending.push(event);
+                self.last_key = Some(now);
+            }
+            _ if self.pending.is_empty() => output.push(Output::Key(event)),
+            _ => {
+                // the held back keys can't be extended by this key, so resolve them on their own
+                // and start over with this key
+                output.extend(self.flush());
+                output.extend(self.feed(event, now));
+            }
+        }
+        output
+    }
+
+    /// Releases the held back keys if the timeout expired.
+    pub fn expire(&mut self, now: Instant) -> Vec<Output> {
+        match self.deadline() {
+            Some(deadline) if now >= deadline => self.flush(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Resolves the held back keys without waiting for more: each longest run that forms a
+    /// complete sequence becomes its action, every other key is passed on.
+    pub fn flush(&mut self) -> Vec<Output> {
+        let mut pending = std::mem::take(&mut self.pending);
+        let mut output = Vec::new();
+        while !pending.is_empty() {
+            let keys: Vec<Key> = pending.iter().map(|k| Key::from(&k.event)).collect();
+            match (1..=keys.len())
+                .rev()
+                .find_map(|n| self.exact(&keys[..n]).map(|b| (n, b)))
+            {
+                Some((n, binding)) => {
+                    output.push(Output::Action {
+                        id: binding.id,
+                        sequence: binding.sequence.clone(),
+                    });
+                    pending.drain(..n);
+                }
+                None => output.push(Output::Key(pending.remove(0))),
+            }
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    fn key(s: &str) -> KeyInput {
+        let key = keys::parse_key(s).unwrap();
+        KeyEvent::new(key.code, key.modifiers).into()
+    }
+
+    fn keymap() -> Keymap {
+        let mut keymap = Keymap::default();
+        keymap.add("ctrl+x ctrl+s", 1).unwrap();
+        keymap.add("ctrl+x", 2).unwrap();
+        keymap.add("g g", 3).unwrap();
+        keymap
+    }
+
+    fn action(id: u32, sequence: &str) -> Output {
+        Output::Action {
+            id,
+            sequence: sequence.to_string(),
+        }
+    }
+
+    #[test]
+    fn completes_sequences() {
+        let mut keymap = keymap();
+        let now = Instant::now();
+        assert_eq!(keymap.feed(key("ctrl+x"), now), vec![]);
+        assert_eq!(
+            keymap.feed(key("ctrl+s"), now),
+            vec![action(1, "ctrl+x ctrl+s")]
+        );
+    }
+
+    #[test]
+    fn passes_on_unbound_keys() {
+        let mut keymap = keymap();
+        let now = Instant::now();
+        assert_eq!(keymap.feed(key("x"), now), vec![Output::Key(key("x"))]);
+    }
+
+    #[test]
+    fn resolves_held_keys_on_a_mismatch() {
+        let mut keymap = keymap();
+        let now = Instant::now();
+        keymap.feed(key("ctrl+x"), now);
+        assert_eq!(
+            keymap.feed(key("g"), now),
+            vec![action(2, "ctrl+x")],
+            "the held prefix is its own binding, and g starts a new sequence"
+        );
+        assert_eq!(
+            keymap.feed(key("x"), now),
+            vec![Output::Key(key("g")), Output::Key(key("x"))]
+        );
+    }
+
+    #[test]
+    fn releases_held_keys_after_the_timeout() {
+        let mut keymap = keymap();
+        let now = Instant::now();
+        keymap.feed(key("g"), now);
+        assert_eq!(keymap.deadline(), Some(now + DEFAULT_TIMEOUT));
+        assert_eq!(keymap.expire(now), vec![]);
+        assert_eq!(
+            keymap.expire(now + DEFAULT_TIMEOUT),
+            vec![Output::Key(key("g"))]
+        );
+        assert_eq!(keymap.deadline(), None);
+    }
+
+    #[test]
+    fn matches_uppercase_as_shift() {
+        let mut keymap = Keymap::default();
+        keymap.add("shift+a", 4).unwrap();
+        let event = KeyEvent::new(KeyCode::Char('A'), KeyModifiers::SHIFT).into();
+        assert_eq!(
+            keymap.feed(event, Instant::now()),
+            vec![action(4, "shift+a")]
+        );
+    }
+
+    #[test]
+    fn replaces_and_removes_bindings() {
+        let mut keymap = keymap();
+        keymap.add("ctrl+x", 5).unwrap();
+        keymap.feed(key("ctrl+x"), Instant::now());
+        assert_eq!(keymap.flush(), vec![action(5, "ctrl+x")]);
+        assert!(keymap.remove("g g").unwrap());
+        assert!(!keymap.remove("g g").unwrap());
+    }
+
+    #[test]
+    fn reports_held_keys_as_pending() {
+        let mut keymap = keymap();
+        let now = Instant::now();
+        keymap.feed(key("ctrl+x"), now);
+        assert!(keymap.is_pending());
+        keymap.feed(key("ctrl+s"), now);
+        assert!(!keymap.is_pending());
+        keymap.feed(key("g"), now);
+        assert!(keymap.is_pending());
+        keymap.flush();
+        assert!(!keymap.is_pending());
+    }
+}
@@ -0,0 +1,285 @@
+//! Key strings such as `"ctrl+x"`, `"alt+enter"` or `"shift+f5"`.
+//!
+//! A key string is a `+` separated list of modifiers followed by a key name. Modifiers are `ctrl`
+//! (or `control`), `shift`, `alt`, `super` (or `cmd`), `hyper` and `meta`. Key names are single
+//! characters or the names listed in [`NAMED_KEYS`], matched case-insensitively. A sequence of keys
+//! is written as key strings separated by spaces, e.g. `"ctrl+x ctrl+s"` or `"g g"`.
+
+use std::fmt;
+
+use crate::crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MediaKeyCode, ModifierKeyCode};
+
+/// A key with its modifiers, normalized so that equal keys compare equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl Key {
+    /// Normalizes a key: uppercase letters become lowercase letters with `SHIFT`, and `BackTab`
+    /// becomes `shift+tab`.
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        match code {
+            KeyCode::Char(c) if c.is_uppercase() && c.to_lowercase().count() == 1 => Key {
+                code: KeyCode::Char(c.to_lowercase().next().unwrap_or(c)),
+                modifiers: modifiers | KeyModifiers::SHIFT,
+            },
+            KeyCode::BackTab => Key {
+                code: KeyCode::Tab,
+                modifiers: modifiers | KeyModifiers::SHIFT,
+            },
+            code => Key { code, modifiers },
+        }
+    }
+}
+
+/// Formats the key as a key string that parses back to it, e.g. `"ctrl+shift+f5"`, with the
+/// modifiers in the order `ctrl`, `alt`, `shift`, `super`, `hyper`, `meta`.
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, modifier) in MODIFIERS {
+            if self.modifiers.contains(*modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match self.code {
+            KeyCode::F(n) => write!(f, "f{}", n),
+            // spaces separate the keys of a sequence
+            KeyCode::Char(' ') => f.write_str("space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            code => match NAMED_KEYS.iter().find(|(_, c)| *c == code) {
+                Some((name, _)) => f.write_str(name),
+                None => write!(f, "{:?}", code),
+            },
+        }
+    }
+}
+
+impl From<&KeyEvent> for Key {
+    fn from(event: &KeyEvent) -> Self {
+        Key::new(event.code, event.modifiers)
+    }
+}
+
+/// Names of keys that are not a single character. The first name of a key is the one it is
+/// formatted with.
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("backspace", KeyCode::Backspace),
+    ("enter", KeyCode::Enter),
+    ("return", KeyCode::Enter),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("home", KeyCode::Home),
+    ("end", KeyCode::End),
+    ("pageup", KeyCode::PageUp),
+    ("pgup", KeyCode::PageUp),
+    ("pagedown", KeyCode::PageDown),
+    ("pgdn", KeyCode::PageDown),
+    ("tab", KeyCode::Tab),
+    ("backtab", KeyCode::BackTab),
+    ("delete", KeyCode::Delete),
+    ("del", KeyCode::Delete),
+    ("insert", KeyCode::Insert),
+    ("ins", KeyCode::Insert),
+    ("null", KeyCode::Null),
+    ("esc", KeyCode::Esc),
+    ("escape", KeyCode::Esc),
+    ("space", KeyCode::Char(' ')),
+    ("plus", KeyCode::Char('+')),
+    ("capslock", KeyCode::CapsLock),
+    ("scrolllock", KeyCode::ScrollLock),
+    ("numlock", KeyCode::NumLock),
+    ("printscreen", KeyCode::PrintScreen),
+    ("pause", KeyCode::Pause),
+    ("menu", KeyCode::Menu),
+    ("keypadbegin", KeyCode::KeypadBegin),
+    ("mediaplay", KeyCode::Media(MediaKeyCode::Play)),
+    ("mediapause", KeyCode::Media(MediaKeyCode::Pause)),
+    ("mediaplaypause", KeyCode::Media(MediaKeyCode::PlayPause)),
+    ("mediareverse", KeyCode::Media(MediaKeyCode::Reverse)),
+    ("mediastop", KeyCode::Media(MediaKeyCode::Stop)),
+    (
+        "mediafastforward",
+        KeyCode::Media(MediaKeyCode::FastForward),
+    ),
+    ("mediarewind", KeyCode::Media(MediaKeyCode::Rewind)),
+    ("mediatracknext", KeyCode::Media(MediaKeyCode::TrackNext)),
+    (
+        "mediatrackprevious",
+        KeyCode::Media(MediaKeyCode::TrackPrevious),
+    ),
+    ("mediarecord", KeyCode::Media(MediaKeyCode::Record)),
+    (
+        "medialowervolume",
+        KeyCode::Media(MediaKeyCode::LowerVolume),
+    ),
+    (
+        "mediaraisevolume",
+        KeyCode::Media(MediaKeyCode::RaiseVolume),
+    ),
+    ("mediamutevolume", KeyCode::Media(MediaKeyCode::MuteVolume)),
+    ("leftshift", KeyCode::Modifier(ModifierKeyCode::LeftShift)),
+    (
+        "leftcontrol",
+        KeyCode::Modifier(ModifierKeyCode::LeftControl),
+    ),
+    ("leftalt", KeyCode::Modifier(ModifierKeyCode::LeftAlt)),
+    ("leftsuper", KeyCode::Modifier(ModifierKeyCode::LeftSuper)),
+    ("lefthyper", KeyCode::Modifier(ModifierKeyCode::LeftHyper)),
+    ("leftmeta", KeyCode::Modifier(ModifierKeyCode::LeftMeta)),
+    ("rightshift", KeyCode::Modifier(ModifierKeyCode::RightShift)),
+    (
+        "rightcontrol",
+        KeyCode::Modifier(ModifierKeyCode::RightControl),
+    ),
+    ("rightalt", KeyCode::Modifier(ModifierKeyCode::RightAlt)),
+    ("rightsuper", KeyCode::Modifier(ModifierKeyCode::RightSuper)),
+    ("righthyper", KeyCode::Modifier(ModifierKeyCode::RightHyper)),
+    ("rightmeta", KeyCode::Modifier(ModifierKeyCode::RightMeta)),
+    (
+        "isolevel3shift",
+        KeyCode::Modifier(ModifierKeyCode::IsoLevel3Shift),
+    ),
+    (
+        "isolevel5shift",
+        KeyCode::Modifier(ModifierKeyCode::IsoLevel5Shift),
+    ),
+];
+
+fn named_key(name: &str) -> Option<KeyCode> {
+    if let Some((_, code)) = NAMED_KEYS.iter().find(|(n, _)| *n == name) {
+        return Some(*code);
+    }
+    let n = name.strip_prefix('f')?.parse::<u8>().ok()?;
+    if n == 0 {
+        return None;
+    }
+    Some(KeyCode::F(n))
+}
+
+/// Modifiers in the order they are formatted in, with their names.
+const MODIFIERS: &[(&str, KeyModifiers)] = &[
+    ("ctrl", KeyModifiers::CONTROL),
+    ("alt", KeyModifiers::ALT),
+    ("shift", KeyModifiers::SHIFT),
+    ("super", KeyModifiers::SUPER),
+    ("hyper", KeyModifiers::HYPER),
+    ("meta", KeyModifiers::META),
+];
+
+fn modifier(name: &str) -> Option<KeyModifiers> {
+    Some(match name {
+        "ctrl" | "control" => KeyModifiers::CONTROL,
+        "shift" => KeyModifiers::SHIFT,
+        "alt" => KeyModifiers::ALT,
+        "super" | "cmd" => KeyModifiers::SUPER,
+        "hyper" => KeyModifiers::HYPER,
+        "meta" => KeyModifiers::META,
+        _ => return None,
+    })
+}
+
+/// Parses a single key string such as `"ctrl+shift+f5"`.
+pub fn parse_key(s: &str) -> anyhow::Result<Key> {
+    // a trailing `+` is the plus key itself, e.g. `ctrl++`
+    let (mods, key) = match s.strip_suffix("++") {
+        Some(mods) => (mods, "+"),
+        None if s == "+" => ("", "+"),
+        None => match s.rsplit_once('+') {
+            Some((mods, key)) => (mods, key),
+            None => ("", s),
+        },
+    };
+    if key.is_empty() {
+        anyhow::bail!("Missing key in key string {:?}", s);
+    }
+    let mut modifiers = KeyModifiers::NONE;
+    if !mods.is_empty() {
+        for name in mods.split('+') {
+            modifiers |= modifier(&name.to_lowercase()).ok_or_else(|| {
+                anyhow::anyhow!("Unknown modifier {:?} in key string {:?}", name, s)
+            })?;
+        }
+    }
+    let mut chars = key.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => named_key(&key.to_lowercase())
+            .ok_or_else(|| anyhow::anyhow!("Unknown key {:?} in key string {:?}", key, s))?,
+    };
+    Ok(Key::new(code, modifiers))
+}
+
+/// Parses a space separated sequence of key strings such as `"ctrl+x ctrl+s"`.
+pub fn parse_sequence(s: &str) -> anyhow::Result<Vec<Key>> {
+    let keys = s
+        .split_whitespace()
+        .map(parse_key)
+        .collect::<anyhow::Result<Vec<Key>>>()?;
+    if keys.is_empty() {
+        anyhow::bail!("Empty key sequence");
+    }
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_modifiers_and_names() {
+        let key = parse_key("Ctrl+Shift+F5").unwrap();
+        assert_eq!(key.code, KeyCode::F(5));
+        assert_eq!(key.modifiers, KeyModifiers::CONTROL | KeyModifiers::SHIFT);
+        assert_eq!(parse_key("cmd+pgup").unwrap().code, KeyCode::PageUp);
+        assert_eq!(
+            parse_key("ctrl++").unwrap(),
+            Key::new(KeyCode::Char('+'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(parse_key("+").unwrap().code, KeyCode::Char('+'));
+    }
+
+    #[test]
+    fn normalizes_uppercase_and_backtab() {
+        assert_eq!(parse_key("A").unwrap(), parse_key("shift+a").unwrap());
+        assert_eq!(
+            Key::new(KeyCode::BackTab, KeyModifiers::NONE),
+            parse_key("shift+tab").unwrap(),
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_keys() {
+        assert!(parse_key("ctrl+").is_err());
+        assert!(parse_key("hyperctrl+x").is_err());
+        assert!(parse_key("f0").is_err());
+        assert!(parse_key("nosuchkey").is_err());
+        assert!(parse_sequence("  ").is_err());
+    }
+
+    #[test]
+    fn parses_sequences() {
+        let keys = parse_sequence("ctrl+x  ctrl+s").unwrap();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[1], Key::new(KeyCode::Char('s'), KeyModifiers::CONTROL));
+    }
+
+    #[test]
+    fn formats_keys_that_parse_back() {
+        for s in [
+            "ctrl+alt+x",
+            "shift+f12",
+            "space",
+            "ctrl++",
+            "mediaplay",
+            "g",
+        ] {
+            let key = parse_key(s).unwrap();
+            assert_eq!(key.to_string(), s);
+            assert_eq!(parse_key(&key.to_string()).unwrap(), key);
+        }
+    }
+}
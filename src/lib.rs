@@ -1,8 +1,56 @@
 use std::io::Write;
 
 use bitflags::bitflags;
-use crossterm::{self, queue};
+
+mod allocator;
+mod bidi;
+mod capabilities;
+mod charset;
+mod cleanup;
+mod clipboard;
+mod colors;
+mod compat;
+mod compose;
+mod context;
+mod convert;
+mod errors;
+mod events;
+mod focus;
+mod frame;
+mod gestures;
+mod hitmap;
+mod input;
+mod json;
+mod keymap;
+mod keys;
+mod links;
+mod notify;
+mod output;
+mod pad;
+mod parse;
+mod query;
+mod raw;
+mod reader;
+mod repeat;
+mod screen;
+mod signals;
+mod spans;
+mod state;
+mod suspend;
+mod synchronized;
+mod theme;
+mod wrap;
+
+pub use bidi::BidiDirection;
+pub use colors::{ColorDepth, PaletteScheme};
+pub use compat::crossterm;
+pub use context::Context;
+use crossterm::queue;
+pub use keymap::Keymap;
 use log::trace;
+pub use reader::OverflowPolicy;
+pub use synchronized::SynchronizedUpdate;
+use unicode_segmentation::UnicodeSegmentation;
 
 #[cfg(not(feature = "docsrs"))]
 include!(concat!(env!("OUT_DIR"), "/colors.rs"));
@@ -22,14 +70,11 @@ fn convert_string_to_c_char(string: String) -> *mut libc::c_char {
 
     // Allocate space for the string
     let string_len = c_string.as_bytes_with_nul().len();
-    let addr = unsafe {
-        let addr = libc::malloc(string_len) as *mut libc::c_char;
-        if addr.is_null() {
-            set_last_error(anyhow::anyhow!("Unable to malloc for {}", &string));
-            return std::ptr::null_mut();
-        }
-        addr
-    };
+    let addr = allocator::malloc(string_len) as *mut libc::c_char;
+    if addr.is_null() {
+        set_last_error(anyhow::anyhow!("Unable to malloc for {}", &string));
+        return std::ptr::null_mut();
+    }
 
     // Copy the string into the allocated space
     unsafe {
@@ -38,6 +83,90 @@ fn convert_string_to_c_char(string: String) -> *mut libc::c_char {
     addr
 }
 
+/// A UTF-8 string of `len` bytes handed to the caller, which may contain null characters.
+///
+/// The bytes are followed by a null character that is not counted in `len`, so a string without
+/// null characters can also be used as a null-terminated string.
+/// Use [`crossterm_str_free`] to free data.
+#[repr(C)]
+pub struct Str {
+    pub ptr: *mut u8,
+    pub len: usize,
+}
+
+fn convert_string_to_str(string: String) -> Str {
+    let addr = allocator::malloc(string.len() + 1) as *mut u8;
+    if addr.is_null() {
+        set_last_error(anyhow::anyhow!("Unable to malloc for {}", &string));
+        return Str {
+            ptr: std::ptr::null_mut(),
+            len: 0,
+        };
+    }
+    unsafe {
+        std::ptr::copy_nonoverlapping(string.as_ptr(), addr, string.len());
+        *addr.add(string.len()) = 0;
+    }
+    Str {
+        ptr: addr,
+        len: string.len(),
+    }
+}
+
+/// Copies `bytes` into a buffer handed to the caller, followed by a null character that is not
+/// counted in `len`.
+fn convert_bytes_to_c(bytes: &[u8], len: &mut usize) -> *mut u8 {
+    let addr = allocator::malloc(bytes.len() + 1) as *mut u8;
+    if addr.is_null() {
+        set_last_error(anyhow::anyhow!(
+            "Unable to malloc for {} bytes",
+            bytes.len()
+        ));
+        return std::ptr::null_mut();
+    }
+    unsafe {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), addr, bytes.len());
+        *addr.add(bytes.len()) = 0;
+    }
+    *len = bytes.len();
+    addr
+}
+
+/// Borrows a null-terminated UTF-8 string received from C.
+///
+/// `what` names the argument in error messages.
+///
+/// # Safety
+///
+/// `s` must be null or point to a valid null-terminated string that is not deallocated or modified
+/// while the returned slice is in use.
+unsafe fn convert_c_char_to_str<'a>(s: *const libc::c_char, what: &str) -> anyhow::Result<&'a str> {
+    if s.is_null() {
+        anyhow::bail!("Received null pointer for {}", what);
+    }
+    std::ffi::CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| anyhow::anyhow!("Received invalid UTF-8 string for {}", what))
+}
+
+/// Borrows `len` values received from C.
+///
+/// `what` names the argument in error messages.
+///
+/// # Safety
+///
+/// `ptr` must point to `len` initialized values that are not deallocated or modified while the
+/// returned slice is in use, or be null if `len` is `0`.
+unsafe fn convert_slice<'a, T>(ptr: *const T, len: usize, what: &str) -> anyhow::Result<&'a [T]> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if ptr.is_null() {
+        anyhow::bail!("Received null pointer for {}", what);
+    }
+    Ok(std::slice::from_raw_parts(ptr, len))
+}
+
 // ensure that we always set a C exception instead of `panic`ing
 pub trait CUnwrapper<T> {
     fn c_unwrap(self) -> T;
@@ -91,24 +220,131 @@ where
     }
 }
 
+/// The value an exported function returns when it panicked.
+trait PanicValue {
+    fn panic_value() -> Self;
+}
+
+impl PanicValue for () {
+    fn panic_value() -> Self {}
+}
+
+impl PanicValue for libc::c_int {
+    fn panic_value() -> Self {
+        -1
+    }
+}
+
+impl PanicValue for bool {
+    fn panic_value() -> Self {
+        false
+    }
+}
+
+impl PanicValue for u32 {
+    fn panic_value() -> Self {
+        0
+    }
+}
+
+impl PanicValue for u64 {
+    fn panic_value() -> Self {
+        0
+    }
+}
+
+impl PanicValue for f64 {
+    fn panic_value() -> Self {
+        -1.0
+    }
+}
+
+impl<T> PanicValue for *const T {
+    fn panic_value() -> Self {
+        std::ptr::null()
+    }
+}
+
+impl<T> PanicValue for *mut T {
+    fn panic_value() -> Self {
+        std::ptr::null_mut()
+    }
+}
+
+impl PanicValue for ColorDepth {
+    fn panic_value() -> Self {
+        ColorDepth::Ansi16
+    }
+}
+
+impl PanicValue for Str {
+    fn panic_value() -> Self {
+        Str {
+            ptr: std::ptr::null_mut(),
+            len: 0,
+        }
+    }
+}
+
+/// A panic caught by [`catch_panic`].
+#[derive(Debug)]
+struct Panicked(String);
+
+impl std::fmt::Display for Panicked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Panicked: {}", self.0)
+    }
+}
+
+impl std::error::Error for Panicked {}
+
+/// Runs the body of an exported function, catching a panic instead of letting it unwind across
+/// the FFI boundary, which is undefined behavior. A panic is reported as the last error, and the
+/// function returns its [`PanicValue`].
+fn catch_panic<T: PanicValue>(f: impl FnOnce() -> T) -> T {
+    match std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)) {
+        Ok(t) => t,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown cause".to_string());
+            RESULT.with(|r| {
+                *r.borrow_mut() = -1;
+            });
+            set_last_error(Panicked(message).into());
+            T::panic_value()
+        }
+    }
+}
+
 thread_local! {
-  static LAST_ERROR: std::cell::RefCell<Option<anyhow::Error>> = std::cell::RefCell::new(None);
-  static RESULT: std::cell::RefCell<libc::c_int> = std::cell::RefCell::new(0);
-  static USE_STDOUT: std::cell::RefCell<bool> = std::cell::RefCell::new(std::env::var("LIBCROSSTERM_OUTPUT").unwrap_or_default() == "stdout");
+  static THREAD_LAST_ERROR: std::cell::RefCell<Option<anyhow::Error>> = const { std::cell::RefCell::new(None) };
+  static THREAD_RESULT: std::cell::RefCell<libc::c_int> = const { std::cell::RefCell::new(0) };
 }
 
+static LAST_ERROR: errors::Slot<Option<anyhow::Error>> =
+    errors::Slot::new(&THREAD_LAST_ERROR, None, |ctx| &ctx.error);
+static RESULT: errors::Slot<libc::c_int> = errors::Slot::new(&THREAD_RESULT, 0, |ctx| &ctx.result);
+
 macro_rules! r {
     () => {
         RESULT.with(|r| r.borrow().clone())
     };
 }
 
+/// The writer for a call's output. A context's buffer comes first, as the context collects the
+/// output of its calls until it is flushed, which adds it to the frame in progress, if any (see
+/// [`context::Writer`]). Otherwise output goes to the frame, and then to the output target.
 macro_rules! io {
     () => {
-        if USE_STDOUT.with(|r| *r.borrow()) {
-            Box::new(std::io::stdout()) as Box<dyn Write>
+        if context::is_active() {
+            Box::new(context::Writer) as Box<dyn Write>
+        } else if frame::is_active() {
+            Box::new(frame::Writer) as Box<dyn Write>
         } else {
-            Box::new(std::io::stderr()) as Box<dyn Write>
+            output::writer()
         }
     };
 }
@@ -128,12 +364,24 @@ pub fn take_last_error() -> Option<anyhow::Error> {
 /// Check whether error has been set.
 #[no_mangle]
 pub extern "C" fn crossterm_has_error() -> bool {
-    LAST_ERROR.with(|prev| prev.borrow().is_some())
+    catch_panic(|| LAST_ERROR.with(|prev| prev.borrow().is_some()))
+}
+
+/// Returns the result of the last call that reported one: `0` if it succeeded, or `-1` if it
+/// failed.
+///
+/// This doesn't change the result, so it can be checked again after the return value of the call
+/// was consumed, e.g. by a wrapper layer that handles errors in one place.
+#[no_mangle]
+pub extern "C" fn crossterm_last_result() -> libc::c_int {
+    catch_panic(|| r!())
 }
 
 #[no_mangle]
 pub extern "C" fn crossterm_clear_last_error() {
-    let _ = take_last_error();
+    catch_panic(|| {
+        let _ = take_last_error();
+    })
 }
 
 /// Peek at the most recent error and get its error message as a Rust `String`.
@@ -146,9 +394,50 @@ pub fn error_message() -> Option<String> {
 /// `0`.
 #[no_mangle]
 pub extern "C" fn crossterm_last_error_length() -> libc::c_int {
-    LAST_ERROR.with(|prev| match *prev.borrow() {
-        Some(ref err) => format!("{:#}", err).len() as libc::c_int + 1,
-        None => 0,
+    catch_panic(|| {
+        LAST_ERROR.with(|prev| match *prev.borrow() {
+            Some(ref err) => format!("{:#}", err).len() as libc::c_int + 1,
+            None => 0,
+        })
+    })
+}
+
+/// Copies the most recent error message into the caller's buffer `buf` of `len` bytes, as a
+/// null-terminated UTF-8 string, without clearing the error.
+///
+/// Returns the number of bytes the message needs including the trailing `null` character, as
+/// [`crossterm_last_error_length`] does, or `0` if there is no error. If that is more than `len`,
+/// the message is cut off at a character boundary to fit, and can be copied again with a larger
+/// buffer. `buf` may be null if `len` is `0`, to only get the length.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `buf` pointer points to memory that is valid for writing `len` bytes.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_last_error_message_into(
+    buf: *mut libc::c_char,
+    len: usize,
+) -> libc::c_int {
+    catch_panic(|| {
+        let Some(message) = error_message() else {
+            return 0;
+        };
+        if len > 0 {
+            if buf.is_null() {
+                set_last_error(anyhow::anyhow!("Received null pointer for buffer"));
+                return -1;
+            }
+            let mut end = message.len().min(len - 1);
+            while !message.is_char_boundary(end) {
+                end -= 1;
+            }
+            std::ptr::copy_nonoverlapping(message.as_ptr() as *const libc::c_char, buf, end);
+            *buf.add(end) = 0;
+        }
+        message.len() as libc::c_int + 1
     })
 }
 
@@ -159,11 +448,125 @@ pub extern "C" fn crossterm_last_error_length() -> libc::c_int {
 /// Use [`crossterm_free_c_char`] to free data.
 #[no_mangle]
 pub extern "C" fn crossterm_last_error_message() -> *const libc::c_char {
+    catch_panic(|| convert_string_to_c_char(take_error_message()))
+}
+
+/// Return most recent error message as a [`Str`], as [`crossterm_last_error_message`] does.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_str_free`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_last_error_message_s() -> Str {
+    catch_panic(|| convert_string_to_str(take_error_message()))
+}
+
+/// Takes the most recent error and returns its message.
+fn take_error_message() -> String {
     let last_error = take_last_error().unwrap_or(anyhow::anyhow!(
         "No error message found. Check library documentation for more information."
     ));
-    let string = format!("{:#}", last_error);
-    convert_string_to_c_char(string)
+    format!("{:#}", last_error)
+}
+
+/// Where the error state, i.e. the last error and result, is kept.
+#[repr(C)]
+pub enum ErrorScope {
+    /// Each thread has its own error state. This is the default.
+    Thread,
+    /// All threads share one error state, for runtimes that don't make consecutive calls from the
+    /// same thread. An error can then be overwritten by a call on another thread before it is
+    /// read, so calls should be serialized by the caller.
+    Global,
+}
+
+/// Selects where the error state is kept, for all threads.
+///
+/// Errors set in the previous scope are kept there, and seen again when switching back to it.
+/// Calls with a [`Context`] keep their error in the context in either scope.
+#[no_mangle]
+pub extern "C" fn crossterm_set_error_scope(scope: ErrorScope) {
+    catch_panic(|| errors::set_global(matches!(scope, ErrorScope::Global)))
+}
+
+/// Kinds of errors, as the `code` of [`crossterm_last_error_json`].
+#[repr(C)]
+pub enum ErrorKind {
+    /// Any other error, e.g. an invalid argument.
+    Other = 0,
+    /// An I/O error, e.g. from reading the terminal.
+    Io = 1,
+    /// A panic inside the library, which is a bug.
+    Panic = 2,
+}
+
+/// The most recent error as JSON, with its [`ErrorKind`] and details.
+fn error_json(err: &anyhow::Error) -> serde_json::Value {
+    let io = err.chain().find_map(|e| e.downcast_ref::<std::io::Error>());
+    let kind = if err.downcast_ref::<Panicked>().is_some() {
+        ErrorKind::Panic
+    } else if io.is_some() {
+        ErrorKind::Io
+    } else {
+        ErrorKind::Other
+    };
+    let name = match kind {
+        ErrorKind::Other => "Other",
+        ErrorKind::Io => "Io",
+        ErrorKind::Panic => "Panic",
+    };
+    serde_json::json!({
+        "code": kind as i32,
+        "kind": name,
+        "message": format!("{:#}", err),
+        "io_kind": io.map(|e| format!("{:?}", e.kind())),
+        "os_errno": io.and_then(|e| e.raw_os_error()),
+    })
+}
+
+/// Return the most recent error as a UTF-8 JSON string and clear it, e.g.
+/// `{"code":1,"io_kind":"Uncategorized","kind":"Io","message":"No such device or address (os error 6)","os_errno":6}`.
+///
+/// `code` is an [`ErrorKind`] value and `kind` its name. For I/O errors, `io_kind` is the name of
+/// the Rust `std::io::ErrorKind` and `os_errno` the OS error number, if there is one; they are
+/// `null` otherwise. Returns `null` as JSON if there is no error.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_last_error_json() -> *const libc::c_char {
+    catch_panic(|| {
+        let json = take_last_error().map_or(serde_json::Value::Null, |err| error_json(&err));
+        convert_string_to_c_char(json.to_string())
+    })
+}
+
+/// Sets the functions that allocate the memory handed to the caller, such as returned strings,
+/// and free it in [`crossterm_free_c_char`] and [`crossterm_event_free`], e.g. to use the
+/// allocator of the host runtime. `malloc_fn` allocates `size` bytes as `malloc` does, and
+/// `free_fn` frees them as `free` does. Both null restores `malloc` and `free`, the default.
+///
+/// Memory must be freed with the allocator it was allocated with, so set the allocator before
+/// anything is allocated, or free what was allocated before changing it.
+///
+/// Returns `-1` if only one of the functions is null.
+#[no_mangle]
+pub extern "C" fn crossterm_set_allocator(
+    malloc_fn: Option<unsafe extern "C" fn(size: usize) -> *mut libc::c_void>,
+    free_fn: Option<unsafe extern "C" fn(ptr: *mut libc::c_void)>,
+) -> libc::c_int {
+    catch_panic(|| {
+        match (malloc_fn, free_fn) {
+            (Some(malloc_fn), Some(free_fn)) => allocator::set(Some((malloc_fn, free_fn))),
+            (None, None) => allocator::set(None),
+            _ => {
+                set_last_error(anyhow::anyhow!(
+                    "Received null pointer for only one of the allocator functions"
+                ));
+                return -1;
+            }
+        }
+        0
+    })
 }
 
 /// Frees data behind pointer to UTF-8 string allocated by this crate
@@ -171,19 +574,35 @@ pub extern "C" fn crossterm_last_error_message() -> *const libc::c_char {
 /// Null character is stored in the last location of buffer.
 #[no_mangle]
 pub extern "C" fn crossterm_free_c_char(s: *mut libc::c_char) -> libc::c_int {
-    if !s.is_null() {
-        unsafe {
-            libc::free(s as *mut libc::c_void);
+    catch_panic(|| {
+        if !s.is_null() {
+            unsafe {
+                allocator::free(s as *mut libc::c_void);
+            }
+            0
+        } else {
+            set_last_error(anyhow::anyhow!("Received null pointer to free"));
+            -1
+        }
+    })
+}
+
+/// Frees data of a [`Str`] returned by this crate.
+#[no_mangle]
+pub extern "C" fn crossterm_str_free(s: Str) -> libc::c_int {
+    catch_panic(|| {
+        if s.ptr.is_null() {
+            set_last_error(anyhow::anyhow!("Received null pointer to free"));
+            return -1;
         }
+        unsafe { allocator::free(s.ptr as *mut libc::c_void) };
         0
-    } else {
-        set_last_error(anyhow::anyhow!("Received null pointer to free"));
-        -1
-    }
+    })
 }
 
 /// Represents a media key (as part of [`KeyCode::Media`]).
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub enum MediaKeyCode {
     /// Play media key.
     Play,
@@ -215,6 +634,7 @@ pub enum MediaKeyCode {
 
 /// Represents a modifier key (as part of [`KeyCode::Modifier`]).
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub enum ModifierKeyCode {
     /// Left Shift key.
     LeftShift,
@@ -248,6 +668,7 @@ pub enum ModifierKeyCode {
 
 /// Represents a key.
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub enum KeyCode {
     /// Backspace key.
     Backspace,
@@ -353,6 +774,7 @@ bitflags! {
     /// [`KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES`] has been enabled with
     /// [`PushKeyboardEnhancementFlags`].
     #[repr(C)]
+    #[derive(Clone, Copy)]
     pub struct KeyModifiers: u8 {
         const SHIFT = 0b0000_0001;
         const CONTROL = 0b0000_0010;
@@ -366,6 +788,7 @@ bitflags! {
 
 /// Represents a keyboard event kind.
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub enum KeyEventKind {
     Press,
     Repeat,
@@ -379,6 +802,7 @@ bitflags! {
     /// [`KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES`] has been enabled with
     /// [`crossterm_event_push_keyboard_enhancement_flags`].
     #[repr(C)]
+    #[derive(Clone, Copy)]
     pub struct KeyEventState: u8 {
         /// The key event origins from the keypad.
         const KEYPAD = 0b0000_0001;
@@ -396,6 +820,7 @@ bitflags! {
 
 /// Represents a key event.
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub struct KeyEvent {
     /// The key itself.
     pub code: KeyCode,
@@ -424,6 +849,7 @@ pub struct KeyEvent {
 /// `MouseEventKind::Up` and `MouseEventKind::Drag` events. `MouseButton::Left`
 /// is returned if we don't know which button was used.
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub enum MouseEventKind {
     /// Pressed mouse button. Contains the button that was pressed.
     Down(MouseButton),
@@ -437,10 +863,15 @@ pub enum MouseEventKind {
     ScrollDown,
     /// Scrolled mouse wheel upwards (away from the user).
     ScrollUp,
+    /// Scrolled mouse wheel left (mostly on a laptop touchpad).
+    ScrollLeft,
+    /// Scrolled mouse wheel right (mostly on a laptop touchpad).
+    ScrollRight,
 }
 
 /// Represents a mouse button.
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub enum MouseButton {
     /// Left mouse button.
     Left,
@@ -466,6 +897,7 @@ pub enum MouseButton {
 /// combinations for all mouse event types. For example - macOS reports
 /// `Ctrl` + left mouse button click as a right mouse button click.
 #[repr(C)]
+#[derive(Clone, Copy)]
 pub struct MouseEvent {
     /// The kind of mouse event that was caused.
     pub kind: MouseEventKind,
@@ -475,6 +907,53 @@ pub struct MouseEvent {
     pub row: u16,
     /// The key modifiers active when the event occurred.
     pub modifiers: KeyModifiers,
+    /// The horizontal position in pixels, from the left of the window, if mouse events report
+    /// pixels (see [`crossterm_event_enable_mouse_pixels`]), and 0 otherwise.
+    pub pixel_x: u16,
+    /// The vertical position in pixels, from the top of the window, if mouse events report
+    /// pixels, and 0 otherwise.
+    pub pixel_y: u16,
+}
+
+/// What the terminal reported about a key event beyond the [`KeyEvent`], as returned by
+/// [`crossterm_event_get_key_details`].
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct KeyDetails {
+    /// The text the key inserts, if the terminal reported it, and null otherwise. It is only
+    /// reported while no [`KeyboardEnhancementFlags`] are pushed, since `crossterm` doesn't pass
+    /// on the associated text of the kitty keyboard protocol. It is owned by the [`Event`] and
+    /// freed by [`crossterm_event_free`].
+    pub text: *const libc::c_char,
+}
+
+/// A bound key sequence that was completed, as returned by [`crossterm_event_get_action`].
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct ActionEvent {
+    /// The id the sequence was bound with.
+    pub id: u32,
+    /// The keys of the sequence, in the notation it was bound with. It is owned by the [`Event`]
+    /// and freed by [`crossterm_event_free`].
+    pub sequence: *const libc::c_char,
+}
+
+/// A drag gesture, as returned by [`crossterm_event_get_drag`].
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct DragEvent {
+    /// The button held down during the drag.
+    pub button: MouseButton,
+    /// The col the drag started on.
+    pub start_col: u16,
+    /// The row the drag started on.
+    pub start_row: u16,
+    /// The col the drag is at. Equal to `start_col` for [`Event::DragStart`].
+    pub col: u16,
+    /// The row the drag is at. Equal to `start_row` for [`Event::DragStart`].
+    pub row: u16,
+    /// How long the drag took in milliseconds, and 0 for [`Event::DragStart`].
+    pub duration_ms: u64,
 }
 
 /// Represents an event.
@@ -484,8 +963,9 @@ pub enum Event {
     FocusGained,
     /// The terminal lost focus
     FocusLost,
-    /// A single key event with additional pressed modifiers.
-    Key(KeyEvent),
+    /// A single key event with additional pressed modifiers, and what else the terminal reported
+    /// about it.
+    Key(KeyEvent, KeyDetails),
     /// A single mouse event with additional pressed modifiers.
     Mouse(MouseEvent),
     /// A string that was pasted into the terminal. Only emitted if bracketed paste has been
@@ -494,1479 +974,7730 @@ pub enum Event {
     /// An resize event with new dimensions after resize (cols, rows).
     /// **Note** that resize events can occur in batches.
     Resize(u16, u16),
-}
-
-/// Checks if there is an [`Event`] available.
-///
-/// Returns `1` if an [`Event`] is available, it returns `0` if no [`Event`] is available, returns -1 if error has occurred.
+    /// No input arrived for the timeout set with [`crossterm_event_set_idle_timeout_ms`].
+    Idle,
+    /// Input arrived after an [`Event::Idle`].
+    Resume,
+    /// An OS signal enabled with [`crossterm_event_enable_signals`] was received.
+    Signal(SignalFlags),
+    /// The process continued after [`crossterm_terminal_suspend`] stopped it.
+    Resumed,
+    /// A key sequence bound with the keymap was completed.
+    Action(ActionEvent),
+    /// The pointer started moving with a button down. Only emitted if drag gestures have been
+    /// enabled with [`crossterm_event_enable_drag_gestures`].
+    DragStart(DragEvent),
+    /// The button of a drag was released.
+    DragEnd(DragEvent),
+    /// A combining sequence started with the given text. Only emitted if composition has been
+    /// enabled with [`crossterm_event_enable_composition`].
+    CompositionStart(*const libc::c_char),
+    /// The text of the composition in progress changed.
+    CompositionUpdate(*const libc::c_char),
+    /// The composition ended with the given text.
+    CompositionCommit(*const libc::c_char),
+}
+
+/// The variant of an [`Event`], as returned by [`crossterm_event_type`].
+#[repr(C)]
+pub enum EventType {
+    FocusGained,
+    FocusLost,
+    Key,
+    Mouse,
+    Paste,
+    Resize,
+    Idle,
+    Resume,
+    Signal,
+    Resumed,
+    Action,
+    DragStart,
+    DragEnd,
+    CompositionStart,
+    CompositionUpdate,
+    CompositionCommit,
+}
+
+/// Returns the [`EventType`] of `event`, or `-1` if `event` is null.
 ///
-/// When it returns `1`, that guarantees that subsequent call to the [`crossterm_event_read`] function
-/// won't block.
+/// # Safety
 ///
-/// # Arguments
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `event` pointer points to an [`Event`] filled by [`crossterm_event_read_struct`].
 ///
-/// * `timeout_secs` - maximum waiting time for event availability
-/// * `timeout_nanos` - maximum waiting time for event availability
+/// If these conditions are not met, the behavior is undefined.
 #[no_mangle]
-pub extern "C" fn crossterm_event_poll(secs: u64, nanos: u32) -> libc::c_int {
-    let r = crossterm::event::poll(std::time::Duration::new(secs, nanos)).c_unwrap();
-    if crossterm_has_error() {
-        r!()
-    } else {
-        r.into()
-    }
+pub unsafe extern "C" fn crossterm_event_type(event: *const Event) -> libc::c_int {
+    catch_panic(|| {
+        let Some(event) = event.as_ref() else {
+            set_last_error(anyhow::anyhow!("Received null pointer for event"));
+            return -1;
+        };
+        let event_type = match event {
+            Event::FocusGained => EventType::FocusGained,
+            Event::FocusLost => EventType::FocusLost,
+            Event::Key(..) => EventType::Key,
+            Event::Mouse(_) => EventType::Mouse,
+            Event::Paste(_) => EventType::Paste,
+            Event::Resize(..) => EventType::Resize,
+            Event::Idle => EventType::Idle,
+            Event::Resume => EventType::Resume,
+            Event::Signal(_) => EventType::Signal,
+            Event::Resumed => EventType::Resumed,
+            Event::Action(_) => EventType::Action,
+            Event::DragStart(_) => EventType::DragStart,
+            Event::DragEnd(_) => EventType::DragEnd,
+            Event::CompositionStart(_) => EventType::CompositionStart,
+            Event::CompositionUpdate(_) => EventType::CompositionUpdate,
+            Event::CompositionCommit(_) => EventType::CompositionCommit,
+        };
+        event_type as libc::c_int
+    })
 }
 
-/// Reads a single [`Event`] as a UTF-8 JSON string.
-/// If error occurs during read, error will be returned as a UTF-8 JSON string.
+/// Copies the [`KeyEvent`] of a key `event` into `out`.
 ///
-/// This function blocks until an [`Event`] is available.
-/// Combine it with the [`crossterm_event_poll`] function to get non-blocking reads.
+/// Returns `0` if successful, or `-1` if `event` is not a key event.
 ///
-/// Caller is responsible for memory associated with string buffer.
-/// Use [`crossterm_free_c_char`] to free data.
+/// # Safety
+///
+/// This function takes raw pointers as arguments. As such, the caller must ensure that:
+/// - The `event` pointer points to an [`Event`] filled by [`crossterm_event_read_struct`].
+/// - The `out` pointer points to memory that is valid for writing a [`KeyEvent`].
+///
+/// If these conditions are not met, the behavior is undefined.
 #[no_mangle]
-pub extern "C" fn crossterm_event_read() -> *const libc::c_char {
-    let json_value = match crossterm::event::read() {
-        Ok(evt) => serde_json::to_value(&evt).unwrap_or_else(|e| {
-            serde_json::json!({
-                "error": format!("Unable to convert event {:?} to JSON: {:?}", evt, e),
-            })
-        }),
-        Err(e) => serde_json::json!({
-            "error": format!("Something went wrong with crossterm_event_read(): {:?}", e),
-        }),
-    };
-
-    let json_string = serde_json::to_string(&json_value).unwrap_or_else(|e| {
-        serde_json::json!({
-            "error": format!("Unable to convert JSON value to string: {:?}", e),
-        })
-        .to_string()
-    });
-    convert_string_to_c_char(json_string)
+pub unsafe extern "C" fn crossterm_event_get_key(
+    event: *const Event,
+    out: *mut KeyEvent,
+) -> libc::c_int {
+    catch_panic(|| match (event.as_ref(), out.is_null()) {
+        (Some(Event::Key(key, _)), false) => {
+            out.write(*key);
+            0
+        }
+        (Some(_), false) => {
+            set_last_error(anyhow::anyhow!("Event is not a key event"));
+            -1
+        }
+        _ => {
+            set_last_error(anyhow::anyhow!("Received null pointer for event"));
+            -1
+        }
+    })
 }
 
-/// Use `std::io::stdout()` for all commands
+/// Copies the [`KeyDetails`] of a key `event` into `out`: the text the key inserts, as far as the
+/// terminal reported it.
+///
+/// The text stays owned by `event` and is freed with it by [`crossterm_event_free`].
+///
+/// Returns `0` if successful, or `-1` if `event` is not a key event.
+///
+/// # Safety
+///
+/// This function takes raw pointers as arguments. As such, the caller must ensure that:
+/// - The `event` pointer points to an [`Event`] filled by [`crossterm_event_read_struct`].
+/// - The `out` pointer points to memory that is valid for writing a [`KeyDetails`].
+///
+/// If these conditions are not met, the behavior is undefined.
 #[no_mangle]
-pub extern "C" fn crossterm_use_stdout() {
-    USE_STDOUT.with(|io| {
-        *io.borrow_mut() = true;
-    });
+pub unsafe extern "C" fn crossterm_event_get_key_details(
+    event: *const Event,
+    out: *mut KeyDetails,
+) -> libc::c_int {
+    catch_panic(|| match (event.as_ref(), out.is_null()) {
+        (Some(Event::Key(_, details)), false) => {
+            out.write(*details);
+            0
+        }
+        (Some(_), false) => {
+            set_last_error(anyhow::anyhow!("Event is not a key event"));
+            -1
+        }
+        _ => {
+            set_last_error(anyhow::anyhow!("Received null pointer for event"));
+            -1
+        }
+    })
 }
 
-/// Use `std::io::stderr()` for all commands
+/// Copies the [`MouseEvent`] of a mouse `event` into `out`.
+///
+/// Returns `0` if successful, or `-1` if `event` is not a mouse event.
+///
+/// # Safety
+///
+/// This function takes raw pointers as arguments. As such, the caller must ensure that:
+/// - The `event` pointer points to an [`Event`] filled by [`crossterm_event_read_struct`].
+/// - The `out` pointer points to memory that is valid for writing a [`MouseEvent`].
+///
+/// If these conditions are not met, the behavior is undefined.
 #[no_mangle]
-pub extern "C" fn crossterm_use_stderr() {
-    USE_STDOUT.with(|io| {
-        *io.borrow_mut() = false;
-    });
+pub unsafe extern "C" fn crossterm_event_get_mouse(
+    event: *const Event,
+    out: *mut MouseEvent,
+) -> libc::c_int {
+    catch_panic(|| match (event.as_ref(), out.is_null()) {
+        (Some(Event::Mouse(mouse)), false) => {
+            out.write(*mouse);
+            0
+        }
+        (Some(_), false) => {
+            set_last_error(anyhow::anyhow!("Event is not a mouse event"));
+            -1
+        }
+        _ => {
+            set_last_error(anyhow::anyhow!("Received null pointer for event"));
+            -1
+        }
+    })
 }
 
-/// Sleeps for n seconds where n is the argument to this function
+/// Stores the new size of a resize `event` in `cols` and `rows`.
+///
+/// Returns `0` if successful, or `-1` if `event` is not a resize event.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `event` pointer points to an [`Event`] filled by [`crossterm_event_read_struct`].
+///
+/// If these conditions are not met, the behavior is undefined.
 #[no_mangle]
-pub extern "C" fn crossterm_sleep(seconds: f64) {
-    let duration = std::time::Duration::from_nanos((seconds * 1e9).round() as u64);
-    std::thread::sleep(duration);
+pub unsafe extern "C" fn crossterm_event_get_resize(
+    event: *const Event,
+    cols: &mut u16,
+    rows: &mut u16,
+) -> libc::c_int {
+    catch_panic(|| match event.as_ref() {
+        Some(Event::Resize(c, r)) => {
+            *cols = *c;
+            *rows = *r;
+            0
+        }
+        Some(_) => {
+            set_last_error(anyhow::anyhow!("Event is not a resize event"));
+            -1
+        }
+        None => {
+            set_last_error(anyhow::anyhow!("Received null pointer for event"));
+            -1
+        }
+    })
 }
 
-/// Set cursor position (col, row)
+/// Stores the signal of a signal `event` in `signal`.
 ///
-/// # Notes
-/// * Top left cell is represented as `0,0`.
-#[no_mangle]
+/// Returns `0` if successful, or `-1` if `event` is not a signal event.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `event` pointer points to an [`Event`] filled by [`crossterm_event_read_struct`].
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_event_get_signal(
+    event: *const Event,
+    signal: &mut SignalFlags,
+) -> libc::c_int {
+    catch_panic(|| match event.as_ref() {
+        Some(Event::Signal(s)) => {
+            *signal = *s;
+            0
+        }
+        Some(_) => {
+            set_last_error(anyhow::anyhow!("Event is not a signal event"));
+            -1
+        }
+        None => {
+            set_last_error(anyhow::anyhow!("Received null pointer for event"));
+            -1
+        }
+    })
+}
+
+/// Copies the [`ActionEvent`] of an action `event` into `out`. The sequence stays owned by the
+/// event.
+///
+/// Returns `0` if successful, or `-1` if `event` is not an action event.
+///
+/// # Safety
+///
+/// This function takes raw pointers as arguments. As such, the caller must ensure that:
+/// - The `event` pointer points to an [`Event`] filled by [`crossterm_event_read_struct`].
+/// - The `out` pointer points to memory that is valid for writing an [`ActionEvent`].
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_event_get_action(
+    event: *const Event,
+    out: *mut ActionEvent,
+) -> libc::c_int {
+    catch_panic(|| match (event.as_ref(), out.is_null()) {
+        (Some(Event::Action(action)), false) => {
+            out.write(*action);
+            0
+        }
+        (Some(_), false) => {
+            set_last_error(anyhow::anyhow!("Event is not an action event"));
+            -1
+        }
+        _ => {
+            set_last_error(anyhow::anyhow!("Received null pointer for event"));
+            -1
+        }
+    })
+}
+
+/// Copies the [`DragEvent`] of a drag start or drag end `event` into `out`.
+///
+/// Returns `0` if successful, or `-1` if `event` is not a drag event.
+///
+/// # Safety
+///
+/// This function takes raw pointers as arguments. As such, the caller must ensure that:
+/// - The `event` pointer points to an [`Event`] filled by [`crossterm_event_read_struct`].
+/// - The `out` pointer points to memory that is valid for writing a [`DragEvent`].
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_event_get_drag(
+    event: *const Event,
+    out: *mut DragEvent,
+) -> libc::c_int {
+    catch_panic(|| match (event.as_ref(), out.is_null()) {
+        (Some(Event::DragStart(drag) | Event::DragEnd(drag)), false) => {
+            out.write(*drag);
+            0
+        }
+        (Some(_), false) => {
+            set_last_error(anyhow::anyhow!("Event is not a drag event"));
+            -1
+        }
+        _ => {
+            set_last_error(anyhow::anyhow!("Received null pointer for event"));
+            -1
+        }
+    })
+}
+
+/// Checks if there is an [`Event`] available.
+///
+/// Returns `1` if an [`Event`] is available, it returns `0` if no [`Event`] is available, returns -1 if error has occurred.
+///
+/// When it returns `1`, that guarantees that subsequent call to the [`crossterm_event_read`] function
+/// won't block.
+///
+/// # Arguments
+///
+/// * `timeout_secs` - maximum waiting time for event availability
+/// * `timeout_nanos` - maximum waiting time for event availability
+#[no_mangle]
+pub extern "C" fn crossterm_event_poll(secs: u64, nanos: u32) -> libc::c_int {
+    catch_panic(|| {
+        let r = events::poll(Some(std::time::Duration::new(secs, nanos))).c_unwrap();
+        if crossterm_has_error() {
+            r!()
+        } else {
+            r.into()
+        }
+    })
+}
+
+/// Reads a single [`Event`] as a UTF-8 JSON string.
+/// If error occurs during read, error will be returned as a UTF-8 JSON string.
+///
+/// This function blocks until an [`Event`] is available.
+/// Combine it with the [`crossterm_event_poll`] function to get non-blocking reads.
+///
+/// The layout of the JSON string is selected with [`crossterm_event_set_schema_version`].
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_event_read() -> *const libc::c_char {
+    catch_panic(|| convert_string_to_c_char(read_event_json()))
+}
+
+/// Reads a single [`Event`] as a UTF-8 JSON string in a [`Str`], as [`crossterm_event_read`] does.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_str_free`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_event_read_s() -> Str {
+    catch_panic(|| convert_string_to_str(read_event_json()))
+}
+
+/// Reads a single event as a JSON string, or the error that occurred instead.
+fn read_event_json() -> String {
+    let json_value = match events::read() {
+        Ok(evt) => json::event(&evt),
+        Err(e) => json::error(format!(
+            "Something went wrong with crossterm_event_read(): {:?}",
+            e
+        )),
+    };
+
+    serde_json::to_string(&json_value).unwrap_or_else(|e| {
+        json::error(format!("Unable to convert JSON value to string: {:?}", e)).to_string()
+    })
+}
+
+/// Reads a single [`Event`] as a UTF-8 JSON string if one is available, without blocking.
+///
+/// Returns a null pointer if no [`Event`] is available. As with [`crossterm_event_read`], an error
+/// is returned as a UTF-8 JSON string, so a null pointer always means that there was no event.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_event_try_read() -> *const libc::c_char {
+    catch_panic(|| {
+        let json_value = match events::try_read() {
+            Ok(Some(evt)) => json::event(&evt),
+            Ok(None) => return std::ptr::null(),
+            Err(e) => json::error(format!(
+                "Something went wrong with crossterm_event_try_read(): {:?}",
+                e
+            )),
+        };
+
+        let json_string = serde_json::to_string(&json_value).unwrap_or_else(|e| {
+            json::error(format!("Unable to convert JSON value to string: {:?}", e)).to_string()
+        });
+        convert_string_to_c_char(json_string)
+    })
+}
+
+/// Reads every [`Event`] that is currently available as a UTF-8 JSON array string, without
+/// blocking.
+///
+/// Each element has the layout of the events returned by [`crossterm_event_read`]. The array is
+/// empty if no [`Event`] is available. If an error occurs while reading, the events read so far
+/// are followed by the error as the last element.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_event_read_all() -> *const libc::c_char {
+    catch_panic(|| {
+        let mut json_values = Vec::new();
+        loop {
+            match events::try_read() {
+                Ok(Some(evt)) => json_values.push(json::event(&evt)),
+                Ok(None) => break,
+                Err(e) => {
+                    json_values.push(json::error(format!(
+                        "Something went wrong with crossterm_event_read_all(): {:?}",
+                        e
+                    )));
+                    break;
+                }
+            }
+        }
+
+        let json_string = serde_json::to_string(&json_values).unwrap_or_else(|e| {
+            json::error(format!("Unable to convert JSON value to string: {:?}", e)).to_string()
+        });
+        convert_string_to_c_char(json_string)
+    })
+}
+
+/// Reads events until [`crossterm_stop_event_loop`] is called, passing each one to
+/// `callback(event, user_data)`.
+///
+/// `event` is a UTF-8 JSON string with the layout of the events returned by
+/// [`crossterm_event_read`]. It is owned by the library and only valid until the callback returns.
+///
+/// The loop can be stopped from the callback or from another thread. Returns `0` once stopped, or
+/// `-1` if an error occurred while reading.
+///
+/// # Safety
+///
+/// `callback` must be a valid function pointer (or null, which is reported as an error), and
+/// `user_data` must be valid for whatever the callback does with it.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_run_event_loop(
+    callback: Option<
+        unsafe extern "C" fn(event: *const libc::c_char, user_data: *mut libc::c_void),
+    >,
+    user_data: *mut libc::c_void,
+) -> libc::c_int {
+    catch_panic(|| {
+        let Some(callback) = callback else {
+            set_last_error(anyhow::anyhow!("Received null pointer for callback"));
+            return -1;
+        };
+        events::run(|evt| {
+            let json_string = serde_json::to_string(&json::event(&evt)).unwrap_or_else(|e| {
+                json::error(format!("Unable to convert JSON value to string: {:?}", e)).to_string()
+            });
+            // serialized JSON escapes NUL characters, so this can't fail
+            let json_string = std::ffi::CString::new(json_string).unwrap_or_default();
+            callback(json_string.as_ptr(), user_data);
+        })
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Stops the loop run by [`crossterm_run_event_loop`] after the event being handled, if any.
+#[no_mangle]
+pub extern "C" fn crossterm_stop_event_loop() {
+    catch_panic(|| {
+        events::stop();
+    })
+}
+
+/// Wakes up a thread blocked in [`crossterm_event_read`] or [`crossterm_event_poll`], e.g. to
+/// shut it down.
+///
+/// A `Wakeup` event is queued, which is returned like any other event, e.g. `"Wakeup"` in schema
+/// version 1. [`crossterm_event_read_struct`] returns `1` for it instead. If no thread is reading,
+/// the next read returns it immediately.
+///
+/// This function may be called from any thread.
+#[no_mangle]
+pub extern "C" fn crossterm_event_wakeup() {
+    catch_panic(|| {
+        events::wakeup();
+    })
+}
+
+/// Returns a file descriptor that is readable while an [`Event`] is available, for waiting on
+/// input in an external event loop (`select`, `poll`, epoll, kqueue, libuv, ...).
+///
+/// The descriptor becomes readable when an [`Event`] is available, and stops being readable once
+/// the available events have been read with [`crossterm_event_read`],
+/// [`crossterm_event_try_read`] or [`crossterm_event_read_all`]. Don't read from the descriptor;
+/// read events until none is available instead. The same descriptor is returned on every call,
+/// and stays open for the lifetime of the process.
+///
+/// The first call starts a thread that watches for input.
+///
+/// Returns `-1` if an error occurred, and on Windows, where [`crossterm_event_poll_handle`] is
+/// used instead.
+#[no_mangle]
+pub extern "C" fn crossterm_event_poll_fd() -> libc::c_int {
+    catch_panic(|| {
+        #[cfg(unix)]
+        {
+            let fd = notify::signal().map(|signal| signal.fd()).c_unwrap();
+            if crossterm_has_error() {
+                r!()
+            } else {
+                fd
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            set_last_error(anyhow::anyhow!(
+            "Unsupported: crossterm_event_poll_fd is only available on Unix, use crossterm_event_poll_handle"
+        ));
+            -1
+        }
+    })
+}
+
+/// Returns a Windows event `HANDLE` that is signaled while an [`Event`] is available, for waiting
+/// on input with `WaitForMultipleObjects` in an external event loop.
+///
+/// The handle behaves like the descriptor of [`crossterm_event_poll_fd`]: it is signaled when an
+/// [`Event`] is available, and reset once the available events have been read. Don't
+/// reset or close the handle.
+///
+/// Returns null if an error occurred, and on other platforms, where [`crossterm_event_poll_fd`] is
+/// used instead.
+#[no_mangle]
+pub extern "C" fn crossterm_event_poll_handle() -> *mut libc::c_void {
+    catch_panic(|| {
+        #[cfg(windows)]
+        {
+            match notify::signal() {
+                Ok(signal) => signal.handle(),
+                Err(err) => {
+                    set_last_error(err);
+                    std::ptr::null_mut()
+                }
+            }
+        }
+        #[cfg(not(windows))]
+        {
+            set_last_error(anyhow::anyhow!(
+            "Unsupported: crossterm_event_poll_handle is only available on Windows, use crossterm_event_poll_fd"
+        ));
+            std::ptr::null_mut()
+        }
+    })
+}
+
+/// Kinds of events that are merged by [`crossterm_event_set_coalescing`].
+#[repr(C)]
+pub enum EventCoalescing {
+    /// Merge consecutive resize events into the latest size.
+    Resize = 0b0000_0001,
+    /// Merge consecutive mouse moves into the latest one.
+    MouseMove = 0b0000_0010,
+    /// Merge consecutive drags with the same button into the latest one.
+    MouseDrag = 0b0000_0100,
+}
+
+/// Merges runs of events that wait to be read into their latest event, so a slow reader isn't
+/// flooded by resize or mouse move events.
+///
+/// `flags` is a combination of [`EventCoalescing`] values, or `0` to disable coalescing (the
+/// default). Events are only merged with the event directly before them, and only while neither
+/// has been read, so an event of another kind in between keeps both. Mouse events are only
+/// merged if their modifiers are the same.
+///
+/// Returns `-1` if `flags` contains unknown bits.
+#[no_mangle]
+pub extern "C" fn crossterm_event_set_coalescing(flags: u8) -> libc::c_int {
+    catch_panic(|| {
+        let known = EventCoalescing::Resize as u8
+            | EventCoalescing::MouseMove as u8
+            | EventCoalescing::MouseDrag as u8;
+        if flags & !known != 0 {
+            set_last_error(anyhow::anyhow!(
+                "Received unknown coalescing flags {:#010b}",
+                flags
+            ));
+            return -1;
+        }
+        events::pipeline().coalescing = events::Coalescing {
+            resize: flags & EventCoalescing::Resize as u8 != 0,
+            mouse_move: flags & EventCoalescing::MouseMove as u8 != 0,
+            mouse_drag: flags & EventCoalescing::MouseDrag as u8 != 0,
+        };
+        0
+    })
+}
+
+/// Kinds of terminal events that are read, selected with [`crossterm_event_set_filter`].
+#[repr(C)]
+pub enum EventFilter {
+    /// Key events.
+    Key = 0b0000_0001,
+    /// Mouse button presses and releases, and scrolling.
+    Mouse = 0b0000_0010,
+    /// Mouse moves, with or without a button pressed.
+    MouseMove = 0b0000_0100,
+    /// Resize events.
+    Resize = 0b0000_1000,
+    /// Focus gained and lost events.
+    Focus = 0b0001_0000,
+    /// Pasted text.
+    Paste = 0b0010_0000,
+}
+
+/// Selects the kinds of terminal events that are read, e.g.
+/// `CROSSTERM_EVENT_FILTER_KEY | CROSSTERM_EVENT_FILTER_RESIZE` to only read key and resize
+/// events.
+///
+/// `mask` is a combination of [`EventFilter`] values. Terminal events of other kinds are dropped
+/// as soon as they are read, before key sequence bindings see them and before they are
+/// serialized. The library still keeps track of the terminal size and focus. Events produced by
+/// the library itself, like key sequence actions or idle events, are not filtered.
+///
+/// All kinds of events are read by default. Returns `-1` if `mask` contains unknown bits.
+#[no_mangle]
+pub extern "C" fn crossterm_event_set_filter(mask: u8) -> libc::c_int {
+    catch_panic(|| {
+        let known = EventFilter::Key as u8
+            | EventFilter::Mouse as u8
+            | EventFilter::MouseMove as u8
+            | EventFilter::Resize as u8
+            | EventFilter::Focus as u8
+            | EventFilter::Paste as u8;
+        if mask & !known != 0 {
+            set_last_error(anyhow::anyhow!(
+                "Received unknown event filter bits {:#010b}",
+                mask
+            ));
+            return -1;
+        }
+        events::pipeline().filter = events::Filter(mask);
+        0
+    })
+}
+
+/// OS signals that can be delivered as events, selected with [`crossterm_event_enable_signals`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalFlags {
+    /// `SIGWINCH`, the terminal window changed size.
+    Winch = 0b0000_0001,
+    /// `SIGINT`, usually sent by Ctrl-C when raw mode is disabled.
+    Int = 0b0000_0010,
+    /// `SIGTERM`, a request to terminate.
+    Term = 0b0000_0100,
+    /// `SIGTSTP`, usually sent by Ctrl-Z when raw mode is disabled.
+    Tstp = 0b0000_1000,
+}
+
+/// Delivers the OS signals in `mask`, a combination of [`SignalFlags`] values, as events instead
+/// of handling them as the process otherwise would, e.g.
+/// `CROSSTERM_SIGNAL_FLAGS_INT | CROSSTERM_SIGNAL_FLAGS_WINCH`. Signals that are not in `mask`
+/// are handled as they were before, so `0` stops delivering signals.
+///
+/// A received signal is read as a `Signal` event, e.g. `{"Signal":{"signal":"SIGINT"}}` in schema
+/// version 1, as soon as it arrives. Signals received more than once before being read are
+/// delivered once.
+///
+/// Returns `-1` if `mask` contains unknown bits, or on Windows unless `mask` is `0`.
+#[no_mangle]
+pub extern "C" fn crossterm_event_enable_signals(mask: u8) -> libc::c_int {
+    catch_panic(|| {
+        signals::enable(mask).c_unwrap();
+        r!()
+    })
+}
+
+/// Starts a background thread that reads terminal input as it arrives.
+///
+/// Input is parsed into events by the thread instead of when the caller reads, so a busy caller
+/// never holds up input. Events are read as usual, e.g. with [`crossterm_event_read`] or
+/// [`crossterm_event_try_read`], from a queue that holds up to `capacity` events while the reader
+/// runs. When the queue is full, `policy` decides whether the oldest or the newest events are
+/// dropped, or whether the reader pauses until events have been read. With
+/// [`OverflowPolicy::Block`], the reader reads no more input than fits in the queue, and the rest
+/// waits in the terminal's input buffer.
+///
+/// The queue is lock-free, so reading events never waits for the reader. Wakeups, signals and
+/// resumes don't count towards `capacity` and are never dropped; they are read after the events
+/// already in the queue. If reading input fails, the error is returned by the next read, e.g.
+/// [`crossterm_event_read`] returns an error once, and the reader keeps trying.
+///
+/// Returns `-1` if `capacity` is zero or the reader is already running.
+#[no_mangle]
+pub extern "C" fn crossterm_event_reader_start(
+    capacity: usize,
+    policy: OverflowPolicy,
+) -> libc::c_int {
+    catch_panic(|| {
+        reader::start(capacity, policy).c_unwrap();
+        r!()
+    })
+}
+
+/// Stops the background thread started with [`crossterm_event_reader_start`].
+///
+/// Events that were already read wait to be read as usual, and the queue is no longer bounded.
+///
+/// Returns `-1` if the reader is not running.
+#[no_mangle]
+pub extern "C" fn crossterm_event_reader_stop() -> libc::c_int {
+    catch_panic(|| {
+        reader::stop().c_unwrap();
+        r!()
+    })
+}
+
+/// Returns the number of events dropped because the queue was full since the background reader
+/// was last started.
+#[no_mangle]
+pub extern "C" fn crossterm_event_reader_dropped() -> u64 {
+    catch_panic(reader::dropped)
+}
+
+/// Reads a single terminal [`Event`] into `out`.
+///
+/// Like [`crossterm_event_read`], this function blocks until an [`Event`] is available, but fills
+/// the [`Event`] struct instead of returning a JSON string. The events produced by the library
+/// itself, such as idle, signal, action, drag and composition events, are read as the [`Event`]
+/// variants of the same name.
+///
+/// The text of an [`Event::Paste`], of a composition, of the [`KeyDetails`] of an [`Event::Key`]
+/// and the sequence of an [`ActionEvent`] are allocated by this crate. Use
+/// [`crossterm_event_free`] to free them once the event has been handled.
+///
+/// Returns `0` if successful, `1` if the read was ended by [`crossterm_event_wakeup`], or `-1` if
+/// an error occurred. `out` is only written when `0` is returned.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `out` pointer points to memory that is valid for writing an [`Event`].
+/// - The `out` pointer is correctly aligned.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_event_read_struct(out: *mut Event) -> libc::c_int {
+    catch_panic(|| {
+        if out.is_null() {
+            set_last_error(anyhow::anyhow!("Received null pointer for event"));
+            return -1;
+        }
+        match events::read() {
+            // only a wakeup has no `Event`
+            Ok(event) => match convert::library_event(&event) {
+                Some(event) => {
+                    out.write(event);
+                    0
+                }
+                None => 1,
+            },
+            Err(e) => {
+                set_last_error(e);
+                -1
+            }
+        }
+    })
+}
+
+/// Frees data owned by an [`Event`] filled by [`crossterm_event_read_struct`], such as the text
+/// of an [`Event::Paste`] or of the [`KeyDetails`] of an [`Event::Key`].
+///
+/// The event itself is not freed, and may be reused. Events without owned data are left as they
+/// are, so it is safe to call this for every event read.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `event` pointer points to an [`Event`] filled by [`crossterm_event_read_struct`].
+/// - The data of the event has not been freed already.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_event_free(event: *mut Event) -> libc::c_int {
+    catch_panic(|| {
+        if event.is_null() {
+            set_last_error(anyhow::anyhow!("Received null pointer to free"));
+            return -1;
+        }
+        let text = match &mut *event {
+            Event::Paste(text)
+            | Event::CompositionStart(text)
+            | Event::CompositionUpdate(text)
+            | Event::CompositionCommit(text) => text,
+            Event::Key(_, details) => &mut details.text,
+            Event::Action(action) => &mut action.sequence,
+            _ => return 0,
+        };
+        if !text.is_null() {
+            allocator::free(*text as *mut libc::c_void);
+            *text = std::ptr::null();
+        }
+        0
+    })
+}
+
+/// Reads keyboard and mouse input from the controlling terminal (`/dev/tty`) rather than stdin,
+/// so that input keeps working when stdin is redirected, e.g. in `cat data | mytool`.
+///
+/// Events are read from `/dev/tty` whenever stdin is not a terminal. On macOS this needs the
+/// library to be built with the `use-dev-tty` feature, as the default event source can't wait
+/// on `/dev/tty` there; enabling fails without it. When enabled, [`crossterm_read_raw_bytes`]
+/// reads from `/dev/tty` as well, even if stdin is a terminal.
+///
+/// Returns `-1` if there is no controlling terminal, if the feature is missing on macOS, and on
+/// Windows, where this is not supported.
+#[no_mangle]
+pub extern "C" fn crossterm_use_dev_tty(enable: bool) -> libc::c_int {
+    catch_panic(|| {
+        let check = || -> anyhow::Result<()> {
+            if !enable {
+                return Ok(());
+            }
+            if cfg!(windows) {
+                anyhow::bail!(
+                    "Unsupported: reading input from /dev/tty is not available on Windows"
+                );
+            }
+            if cfg!(all(target_os = "macos", not(feature = "use-dev-tty"))) {
+                anyhow::bail!(
+                    "Reading events from /dev/tty on macOS needs the `use-dev-tty` feature"
+                );
+            }
+            std::fs::File::open("/dev/tty")
+                .map(drop)
+                .map_err(|e| anyhow::anyhow!("Unable to open /dev/tty: {}", e))
+        };
+        check().c_unwrap();
+        if !crossterm_has_error() {
+            raw::set_use_dev_tty(enable);
+        }
+        r!()
+    })
+}
+
+/// Reads the bytes sent by the terminal into `buf` as they are, without parsing them into events.
+///
+/// This is meant for debugging and for applications that parse escape sequences themselves. It
+/// can be used in turns with the event functions, but bytes that were already read for events
+/// (e.g. by [`crossterm_event_poll`]) are not returned again, so avoid reading events while the
+/// raw bytes are wanted. Enable raw mode to get the keys as they are pressed.
+///
+/// Waits up to `timeout_ms` milliseconds for input, or forever if `timeout_ms` is negative.
+/// Returns the number of bytes read (at most `len`), `0` if no input arrived in time, or `-1` if an
+/// error occurred. Only available on Unix.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `buf` pointer points to memory that is valid for writing `len` bytes.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_read_raw_bytes(
+    buf: *mut u8,
+    len: usize,
+    timeout_ms: i32,
+) -> libc::c_int {
+    catch_panic(|| {
+        if buf.is_null() {
+            set_last_error(anyhow::anyhow!("Received null pointer for buffer"));
+            return -1;
+        }
+        let buf = std::slice::from_raw_parts_mut(buf, len.min(libc::c_int::MAX as usize));
+        let timeout = u64::try_from(timeout_ms)
+            .ok()
+            .map(std::time::Duration::from_millis);
+        let n = raw::read(buf, timeout).c_unwrap();
+        if crossterm_has_error() {
+            r!()
+        } else {
+            n as libc::c_int
+        }
+    })
+}
+
+/// Selects the layout of the event JSON strings returned by [`crossterm_event_read`].
+///
+/// * `1` (default): the original layout, e.g. `{"Resize":[80,24]}` or `"FocusGained"`.
+/// * `2`: every event is an object with a schema version, e.g.
+///   `{"v":2,"type":"Resize","data":[80,24]}`.
+///
+/// Bindings should pin the version they were written against.
+/// Returns `-1` if the version is not supported.
+#[no_mangle]
+pub extern "C" fn crossterm_event_set_schema_version(version: u32) -> libc::c_int {
+    catch_panic(|| {
+        json::set_schema_version(version).c_unwrap();
+        r!()
+    })
+}
+
+/// Returns the current event JSON schema version.
+#[no_mangle]
+pub extern "C" fn crossterm_event_schema_version() -> u32 {
+    catch_panic(json::schema_version)
+}
+
+/// Formats the key and modifiers of a [`KeyEvent`] as a key string such as `"ctrl+shift+f5"`
+/// or `"alt+enter"`, in the format taken by [`crossterm_keybinding_add`].
+///
+/// Modifiers come first, in the order `ctrl`, `alt`, `shift`, `super`, `hyper`, `meta`. Letters are
+/// lowercase, with `shift` for uppercase letters, and the space key is `space`. The kind and state
+/// of the event are ignored.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `event` pointer points to a valid [`KeyEvent`].
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_key_event_to_string(
+    event: *const KeyEvent,
+) -> *const libc::c_char {
+    catch_panic(|| {
+        let Some(event) = event.as_ref() else {
+            set_last_error(anyhow::anyhow!("Received null pointer for key event"));
+            return std::ptr::null();
+        };
+        let key = keys::Key::new(
+            convert::from_key_code(&event.code),
+            convert::from_key_modifiers(event.modifiers),
+        );
+        convert_string_to_c_char(key.to_string())
+    })
+}
+
+/// Parses a key string such as `"ctrl+x"` into `out`, the inverse of
+/// [`crossterm_key_event_to_string`].
+///
+/// The key string has the format taken by [`crossterm_keybinding_add`], for a single key.
+/// Uppercase letters are read as lowercase letters with `shift`, and `backtab` as `shift+tab`.
+/// The event is a press without extra state.
+///
+/// Returns `0` if successful, or `-1` if the key string is invalid, in which case `out` is not
+/// written.
+///
+/// # Safety
+///
+/// This function takes raw pointers as arguments. As such, the caller must ensure that:
+/// - The `text` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `text` won't be deallocated or modified for the duration of the function call.
+/// - The `out` pointer points to memory that is valid for writing a [`KeyEvent`].
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_key_event_parse(
+    text: *const libc::c_char,
+    out: *mut KeyEvent,
+) -> libc::c_int {
+    catch_panic(|| {
+        if out.is_null() {
+            set_last_error(anyhow::anyhow!("Received null pointer for key event"));
+            return -1;
+        }
+        let key = convert_c_char_to_str(text, "key string")
+            .and_then(keys::parse_key)
+            .map(|key| KeyEvent {
+                code: convert::key_code(&key.code),
+                modifiers: convert::key_modifiers(key.modifiers),
+                kind: KeyEventKind::Press,
+                state: KeyEventState::NONE,
+            });
+        match key {
+            Ok(key) => {
+                out.write(key);
+                0
+            }
+            Err(err) => {
+                set_last_error(err);
+                -1
+            }
+        }
+    })
+}
+
+/// Binds a key sequence to an action id.
+///
+/// `sequence` is a space separated list of keys such as `"ctrl+x ctrl+s"`, `"g g"` or `"alt+enter"`.
+/// Each key is a `+` separated list of modifiers (`ctrl`, `shift`, `alt`, `super`, `hyper`, `meta`)
+/// followed by a character or a key name (`enter`, `esc`, `tab`, `space`, `f1`, `pageup`, ...).
+///
+/// Once bound, key presses that could start or continue a sequence are held back by
+/// [`crossterm_event_read`]. When the sequence completes, a single `Action` event is returned in
+/// place of its keys, e.g. `{"Action":{"id":1,"sequence":"ctrl+x ctrl+s"}}`. When a different key
+/// arrives or no key arrives within the timeout (see [`crossterm_keybinding_set_timeout`]), the
+/// held back keys are returned as regular key events.
+///
+/// Binding a sequence that is already bound replaces its action id.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `sequence` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `sequence` won't be deallocated or modified for the duration of the function call.
+/// - The `sequence` pointer is correctly aligned and `sequence` points to an initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_keybinding_add(
+    sequence: *const libc::c_char,
+    action: u32,
+) -> libc::c_int {
+    catch_panic(|| {
+        convert_c_char_to_str(sequence, "key sequence")
+            .and_then(|sequence| events::pipeline().keybindings.add(sequence, action))
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Removes the binding of a key sequence.
+///
+/// Returns `1` if the sequence was bound, `0` if it wasn't, and `-1` if an error occurred.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `sequence` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `sequence` won't be deallocated or modified for the duration of the function call.
+/// - The `sequence` pointer is correctly aligned and `sequence` points to an initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_keybinding_remove(sequence: *const libc::c_char) -> libc::c_int {
+    catch_panic(|| {
+        let removed = convert_c_char_to_str(sequence, "key sequence")
+            .and_then(|sequence| events::pipeline().keybindings.remove(sequence))
+            .c_unwrap();
+        if crossterm_has_error() {
+            r!()
+        } else {
+            removed.into()
+        }
+    })
+}
+
+/// Removes all key sequence bindings.
+#[no_mangle]
+pub extern "C" fn crossterm_keybinding_clear() {
+    catch_panic(|| {
+        events::pipeline().keybindings.clear();
+    })
+}
+
+/// Sets the time allowed between two keys of a bound sequence, in milliseconds (default `1000`).
+#[no_mangle]
+pub extern "C" fn crossterm_keybinding_set_timeout(timeout_ms: u64) {
+    catch_panic(|| {
+        events::pipeline().keybindings.timeout = std::time::Duration::from_millis(timeout_ms);
+    })
+}
+
+/// The outcome of feeding a key to a keymap with [`crossterm_keymap_feed`].
+#[repr(C)]
+pub enum KeymapMatch {
+    /// The key is not part of a bound sequence, and should be handled as a regular key.
+    NoMatch,
+    /// The key may continue into a bound sequence, and should not be handled yet.
+    Pending,
+    /// The key completed a bound sequence.
+    Matched,
+}
+
+/// Creates a keymap that matches key sequences in key events fed by the caller, independently of
+/// the bindings applied to [`crossterm_event_read`] by [`crossterm_keybinding_add`].
+///
+/// Use [`crossterm_keymap_free`] to free the keymap.
+#[no_mangle]
+pub extern "C" fn crossterm_keymap_new() -> *mut Keymap {
+    catch_panic(|| Box::into_raw(Box::default()))
+}
+
+/// Frees a keymap created with [`crossterm_keymap_new`].
+///
+/// # Safety
+///
+/// `keymap` must be null or a pointer returned by [`crossterm_keymap_new`] that was not freed
+/// already.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_keymap_free(keymap: *mut Keymap) {
+    catch_panic(|| {
+        if !keymap.is_null() {
+            drop(Box::from_raw(keymap));
+        }
+    })
+}
+
+/// Binds a key sequence such as `"ctrl+x ctrl+s"` to an action id in a keymap.
+///
+/// Sequences are written as for [`crossterm_keybinding_add`]. Binding a sequence that is already
+/// bound replaces its action id.
+///
+/// # Safety
+///
+/// This function takes raw pointers as arguments. As such, the caller must ensure that:
+/// - The `keymap` pointer was returned by [`crossterm_keymap_new`] and not freed.
+/// - The `sequence` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `sequence` won't be deallocated or modified for the duration of the function call.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_keymap_add(
+    keymap: *mut Keymap,
+    sequence: *const libc::c_char,
+    action: u32,
+) -> libc::c_int {
+    catch_panic(|| {
+        let Some(keymap) = keymap.as_mut() else {
+            set_last_error(anyhow::anyhow!("Received null pointer for keymap"));
+            return -1;
+        };
+        convert_c_char_to_str(sequence, "key sequence")
+            .and_then(|sequence| keymap.add(sequence, action))
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the time allowed between two keys of a sequence in a keymap, in milliseconds (default
+/// `1000`). A key that arrives later starts over.
+///
+/// # Safety
+///
+/// `keymap` must be a pointer returned by [`crossterm_keymap_new`] that was not freed.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_keymap_set_timeout(
+    keymap: *mut Keymap,
+    timeout_ms: u64,
+) -> libc::c_int {
+    catch_panic(|| {
+        let Some(keymap) = keymap.as_mut() else {
+            set_last_error(anyhow::anyhow!("Received null pointer for keymap"));
+            return -1;
+        };
+        keymap.timeout = std::time::Duration::from_millis(timeout_ms);
+        0
+    })
+}
+
+/// Feeds a key event to a keymap, and returns the [`KeymapMatch`] for it.
+///
+/// When the key completes a sequence, `Matched` is returned and the sequence's action id is
+/// stored in `action`. Keys that were `Pending` and turn out not to form a sequence (because a key
+/// that doesn't continue it arrives, or the timeout passes) are not reported again; keep them if
+/// they should be handled as regular keys then. Key releases are never part of a sequence.
+///
+/// Returns `-1` if an error occurred.
+///
+/// # Safety
+///
+/// This function takes raw pointers as arguments. As such, the caller must ensure that:
+/// - The `keymap` pointer was returned by [`crossterm_keymap_new`] and not freed.
+/// - The `event` pointer points to a valid [`KeyEvent`].
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_keymap_feed(
+    keymap: *mut Keymap,
+    event: *const KeyEvent,
+    action: &mut u32,
+) -> libc::c_int {
+    catch_panic(|| {
+        let (Some(keymap), Some(event)) = (keymap.as_mut(), event.as_ref()) else {
+            set_last_error(anyhow::anyhow!(
+                "Received null pointer for keymap or key event"
+            ));
+            return -1;
+        };
+        let event = convert::from_key_event(event);
+        if event.kind == crossterm::event::KeyEventKind::Release {
+            return KeymapMatch::NoMatch as libc::c_int;
+        }
+        let output = keymap.feed(event.into(), std::time::Instant::now());
+        let matched = output.iter().rev().find_map(|output| match output {
+            keymap::Output::Action { id, .. } => Some(*id),
+            keymap::Output::Key(_) => None,
+        });
+        let result = match matched {
+            Some(id) => {
+                *action = id;
+                KeymapMatch::Matched
+            }
+            None if keymap.is_pending() => KeymapMatch::Pending,
+            None => KeymapMatch::NoMatch,
+        };
+        result as libc::c_int
+    })
+}
+
+/// Emits an `Idle` event when no input has arrived for `timeout_ms` milliseconds, and a `Resume`
+/// event before the next input after that.
+///
+/// Only one `Idle` event is emitted per period of inactivity. The events are read with
+/// [`crossterm_event_read`] like any other event, e.g. `"Idle"` and `"Resume"` in schema version 1.
+/// Setting the timeout restarts the idle timer. Pass `0` to disable idle events (the default).
+#[no_mangle]
+pub extern "C" fn crossterm_event_set_idle_timeout_ms(timeout_ms: u64) {
+    catch_panic(|| {
+        let timeout = (timeout_ms > 0).then(|| std::time::Duration::from_millis(timeout_ms));
+        events::pipeline().idle.set_timeout(timeout);
+    })
+}
+
+/// Synthesizes `Repeat` key events for terminals that don't report key event kinds.
+///
+/// Without the kitty keyboard protocol's `ReportEventTypes` flag, a held key arrives as repeated
+/// presses. With this enabled, a key pressed again within `window_ms` milliseconds of its last
+/// press is taken to be held: the first press is read as a `Press` event, and while the terminal
+/// keeps sending the key, `Repeat` events are produced every `interval_ms` milliseconds (or one per
+/// press sent by the terminal if `interval_ms` is `0`).
+///
+/// This is a timing heuristic; a key tapped twice within `window_ms` is read as held. Events from
+/// terminals that report key event kinds themselves are passed on unchanged.
+#[no_mangle]
+pub extern "C" fn crossterm_event_enable_key_repeat(interval_ms: u64, window_ms: u64) {
+    catch_panic(|| {
+        events::pipeline().key_repeat.enable(
+            std::time::Duration::from_millis(interval_ms),
+            std::time::Duration::from_millis(window_ms),
+        );
+    })
+}
+
+/// Disables synthesized key repeat enabled with [`crossterm_event_enable_key_repeat`].
+#[no_mangle]
+pub extern "C" fn crossterm_event_disable_key_repeat() {
+    catch_panic(|| {
+        events::pipeline().key_repeat.disable();
+    })
+}
+
+/// Enables drag gesture events, read with [`crossterm_event_read`] in addition to the raw mouse
+/// events.
+///
+/// * `DragStart` comes right before the first `Drag` event after a button went down, with the
+///   cell where the button went down: `{"DragStart":{"button":"Left","column":3,"row":5}}`.
+/// * `DragEnd` comes right after the `Up` event that ends the drag, with the start and end cells,
+///   the total displacement and the duration:
+///   `{"DragEnd":{"button":"Left","column":9,"delta_column":6,"delta_row":-2,"duration_ms":420,"row":3,"start_column":3,"start_row":5}}`.
+///
+/// Mouse capture must be enabled with [`crossterm_event_enable_mouse_capture`].
+#[no_mangle]
+pub extern "C" fn crossterm_event_enable_drag_gestures() {
+    catch_panic(|| {
+        events::pipeline().gestures.enabled = true;
+    })
+}
+
+/// Disables drag gesture events enabled with [`crossterm_event_enable_drag_gestures`].
+#[no_mangle]
+pub extern "C" fn crossterm_event_disable_drag_gestures() {
+    catch_panic(|| {
+        events::pipeline().gestures = Default::default();
+    })
+}
+
+/// Reads vertical scrolling with Shift held as horizontal scrolling.
+///
+/// Many terminals and mice have no horizontal wheel and send Shift + wheel instead. When enabled,
+/// `ScrollUp` and `ScrollDown` events with the Shift modifier are read as `ScrollLeft` and
+/// `ScrollRight` events without it. Disabled by default.
+#[no_mangle]
+pub extern "C" fn crossterm_event_set_shift_scroll_horizontal(enabled: bool) {
+    catch_panic(|| {
+        events::pipeline().shift_scroll_horizontal = enabled;
+    })
+}
+
+/// Enables best-effort text composition events for combining sequences.
+///
+/// Terminals compose input method and dead key input themselves and only send the result, but
+/// decomposed text (a base character followed by combining marks, e.g. `e` + U+0301) arrives as
+/// separate keys. With this enabled, such a sequence is read as composition events instead of
+/// keys:
+///
+/// * `{"CompositionStart":{"text":"e"}}` with the base character,
+/// * `{"CompositionUpdate":{"text":"é"}}` after each combining mark (preedit text),
+/// * `{"CompositionCommit":{"text":"é"}}` once the sequence ends.
+///
+/// Character keys are held back for a few milliseconds to see whether a combining mark follows.
+#[no_mangle]
+pub extern "C" fn crossterm_event_enable_composition() {
+    catch_panic(|| {
+        events::pipeline().composer.enabled = true;
+    })
+}
+
+/// Disables composition events enabled with [`crossterm_event_enable_composition`].
+#[no_mangle]
+pub extern "C" fn crossterm_event_disable_composition() {
+    catch_panic(|| {
+        let mut pipeline = events::pipeline();
+        let output = pipeline.composer.flush();
+        pipeline.composer.enabled = false;
+        pipeline.compose(output, std::time::Instant::now());
+    })
+}
+
+/// Starts recording a keyboard macro, discarding a recording in progress.
+///
+/// Every key event read with [`crossterm_event_read`] from now on is recorded, except the last one
+/// read before [`crossterm_macro_record_stop`] is called (usually the key that stops recording).
+#[no_mangle]
+pub extern "C" fn crossterm_macro_record_start() {
+    catch_panic(|| {
+        events::pipeline().start_recording();
+    })
+}
+
+/// Stops recording the keyboard macro and keeps it for [`crossterm_macro_play`].
+///
+/// Returns the number of recorded key events, or `-1` if no macro was being recorded.
+#[no_mangle]
+pub extern "C" fn crossterm_macro_record_stop() -> libc::c_int {
+    catch_panic(|| {
+        let recorded = events::pipeline().stop_recording().c_unwrap();
+        if crossterm_has_error() {
+            r!()
+        } else {
+            recorded as libc::c_int
+        }
+    })
+}
+
+/// Tells whether a keyboard macro is being recorded.
+#[no_mangle]
+pub extern "C" fn crossterm_macro_is_recording() -> bool {
+    catch_panic(|| events::pipeline().is_recording())
+}
+
+/// Plays the recorded keyboard macro back `times` times.
+///
+/// The recorded key events are queued as if they were typed again, so they are returned by the
+/// following calls to [`crossterm_event_read`] (after any events that were already queued) and
+/// resolve key sequence bindings as usual.
+///
+/// Returns the number of key events queued.
+#[no_mangle]
+pub extern "C" fn crossterm_macro_play(times: u32) -> libc::c_int {
+    catch_panic(|| events::pipeline().play(times as usize) as libc::c_int)
+}
+
+/// Registers a rectangular screen region for mouse hit testing.
+///
+/// Mouse events read with [`crossterm_event_read`] whose cell lies within a region get a
+/// `"region"` field with the region's `id`, e.g.
+/// `{"Mouse":{"column":3,"kind":"Moved","modifiers":"","region":7,"row":1}}`. If regions overlap,
+/// the most recently added one wins. Adding a region with an existing `id` replaces it.
+///
+/// Regions are matched when the library receives an event from the terminal, against the regions
+/// registered at that time.
+#[no_mangle]
+pub extern "C" fn crossterm_hitmap_add(id: u32, col: u16, row: u16, width: u16, height: u16) {
+    catch_panic(|| {
+        hitmap::hitmap().add(id, col, row, width, height);
+    })
+}
+
+/// Removes the region registered as `id`.
+///
+/// Returns `1` if the region was removed and `0` if there was no such region.
+#[no_mangle]
+pub extern "C" fn crossterm_hitmap_remove(id: u32) -> libc::c_int {
+    catch_panic(|| hitmap::hitmap().remove(id) as libc::c_int)
+}
+
+/// Removes all regions registered with [`crossterm_hitmap_add`].
+#[no_mangle]
+pub extern "C" fn crossterm_hitmap_clear() {
+    catch_panic(|| {
+        hitmap::hitmap().clear();
+    })
+}
+
+/// Use `std::io::stdout()` for all commands
+#[no_mangle]
+pub extern "C" fn crossterm_use_stdout() {
+    catch_panic(|| {
+        output::use_stdout();
+    })
+}
+
+/// Use `std::io::stderr()` for all commands
+#[no_mangle]
+pub extern "C" fn crossterm_use_stderr() {
+    catch_panic(|| {
+        output::use_stderr();
+    })
+}
+
+/// A standard stream to write output to.
+#[repr(C)]
+pub enum OutputTarget {
+    /// Standard output.
+    Stdout,
+    /// Standard error.
+    Stderr,
+}
+
+/// Selects the standard stream that all commands write to (stderr by default, or stdout if the
+/// `LIBCROSSTERM_OUTPUT` environment variable is `stdout`). The stream is shared by all threads.
+///
+/// Rendering to stderr keeps stdout free for data, e.g. when the program is part of a pipeline.
+#[no_mangle]
+pub extern "C" fn crossterm_set_output_target(target: OutputTarget) {
+    catch_panic(|| match target {
+        OutputTarget::Stdout => output::use_stdout(),
+        OutputTarget::Stderr => output::use_stderr(),
+    })
+}
+
+/// Makes all commands on all threads write to the file descriptor `fd`, e.g. of an opened tty.
+/// The descriptor is not closed by the library, and must stay open while it is in use.
+///
+/// Output is written to `fd` without buffering.
+///
+/// Returns `-1` if `fd` is not an open file descriptor.
+#[no_mangle]
+pub extern "C" fn crossterm_set_output_fd(fd: libc::c_int) -> libc::c_int {
+    catch_panic(|| {
+        output::use_fd(fd).c_unwrap();
+        r!()
+    })
+}
+
+/// When output is written to the terminal without calling [`crossterm_flush`].
+#[repr(C)]
+pub enum AutoflushMode {
+    /// Output is written to the stream right away, which buffers it as it does (stdout is flushed
+    /// at newlines, stderr and file descriptors are unbuffered). This is the default.
+    Stream,
+    /// Output is buffered by the library and flushed at the end of every call, so that each
+    /// command is written at once.
+    Command,
+    /// Output is buffered by the library and flushed once the buffer holds a number of bytes.
+    Threshold,
+    /// Output is buffered by the library and only written by [`crossterm_flush`].
+    Manual,
+}
+
+/// Sets when output is flushed, on all threads. `threshold_bytes` is the buffer size at which
+/// [`AutoflushMode::Threshold`] flushes, and is ignored by the other modes.
+///
+/// Output buffered so far is written first. Nothing is flushed automatically while the terminal is
+/// unfocused if [`crossterm_event_enable_focus_tracking`] was asked to hold back flushing.
+///
+/// Returns `-1` if `threshold_bytes` is `0` for [`AutoflushMode::Threshold`], or the buffered
+/// output could not be written.
+#[no_mangle]
+pub extern "C" fn crossterm_set_autoflush(
+    mode: AutoflushMode,
+    threshold_bytes: usize,
+) -> libc::c_int {
+    catch_panic(|| {
+        output::set_autoflush(match mode {
+            AutoflushMode::Stream => output::Autoflush::Stream,
+            AutoflushMode::Command => output::Autoflush::Command,
+            AutoflushMode::Threshold => output::Autoflush::Threshold(threshold_bytes),
+            AutoflushMode::Manual => output::Autoflush::Manual,
+        })
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Makes every command flush its output, as crossterm's `execute!` does, so that calls don't need
+/// to be followed by [`crossterm_flush`]. Disabling returns to queuing. The mode applies to all
+/// threads.
+///
+/// Enabling is the same as calling [`crossterm_set_autoflush`] with [`AutoflushMode::Command`].
+/// Disabling restores the mode that was set before execute mode was enabled, and does nothing if
+/// it isn't enabled. Setting a mode with [`crossterm_set_autoflush`] ends execute mode.
+#[no_mangle]
+pub extern "C" fn crossterm_set_execute_mode(enable: bool) -> libc::c_int {
+    catch_panic(|| {
+        output::set_execute(enable).c_unwrap();
+        r!()
+    })
+}
+
+/// Begins capturing the output of all commands, on all threads, in a buffer instead of writing it
+/// to the terminal, e.g. to test rendering without a terminal. Output queued before is written
+/// first. Captured output is not followed by screen tracking
+/// ([`crossterm_terminal_enable_screen_tracking`]).
+///
+/// Use [`crossterm_capture_take`] to get the captured bytes and [`crossterm_capture_end`] to stop
+/// capturing.
+#[no_mangle]
+pub extern "C" fn crossterm_capture_begin() -> libc::c_int {
+    catch_panic(|| {
+        output::begin_capture().c_unwrap();
+        r!()
+    })
+}
+
+/// Returns the bytes captured since [`crossterm_capture_begin`] or the last call to this function,
+/// and stores their number in `len`. Capturing continues.
+///
+/// The bytes are followed by a null character that is not counted in `len`.
+/// Caller is responsible for memory associated with the returned buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+///
+/// Returns null if output is not being captured.
+#[no_mangle]
+pub extern "C" fn crossterm_capture_take(len: &mut usize) -> *mut u8 {
+    catch_panic(|| {
+        let bytes = match output::take_capture() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                set_last_error(e);
+                return std::ptr::null_mut();
+            }
+        };
+        convert_bytes_to_c(&bytes, len)
+    })
+}
+
+/// Stops capturing output begun with [`crossterm_capture_begin`]. Captured bytes that were not
+/// taken are discarded.
+#[no_mangle]
+pub extern "C" fn crossterm_capture_end() -> libc::c_int {
+    catch_panic(|| {
+        output::end_capture().c_unwrap();
+        r!()
+    })
+}
+
+/// Queues a `crossterm` command on the same writer used by all `crossterm_*` functions.
+///
+/// This is for Rust code embedding this crate, so that its own commands are interleaved with the
+/// library's output instead of being written around it. `crossterm` is re-exported from this crate
+/// so that the command types match the release the library was built against.
+pub fn queue_command(cmd: impl crossterm::Command) -> anyhow::Result<()> {
+    queue!(io!(), cmd)?;
+    Ok(())
+}
+
+/// Flushes the output stream, unless flushing is held back while the terminal is unfocused (see
+/// [`crossterm_event_enable_focus_tracking`]).
+pub fn flush() -> anyhow::Result<()> {
+    if focus::focus().holds_flush() {
+        return Ok(());
+    }
+    io!().flush()?;
+    Ok(())
+}
+
+/// Sleeps for n seconds where n is the argument to this function
+#[no_mangle]
+pub extern "C" fn crossterm_sleep(seconds: f64) {
+    catch_panic(|| {
+        let duration = std::time::Duration::from_nanos((seconds * 1e9).round() as u64);
+        std::thread::sleep(duration);
+    })
+}
+
+/// Set cursor position (col, row)
+///
+/// # Notes
+/// * Top left cell is represented as `0,0`.
+#[no_mangle]
 pub extern "C" fn crossterm_cursor_position_set(col: u16, row: u16) -> libc::c_int {
-    queue!(io!(), crossterm::cursor::MoveTo(col, row)).c_unwrap();
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::MoveTo(col, row)).c_unwrap();
+        r!()
+    })
+}
+
+/// Get cursor position (col, row)
+///
+/// # Notes
+/// * Top left cell is represented as `0,0`.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_position(col: &mut u16, row: &mut u16) -> libc::c_int {
+    catch_panic(|| {
+        let (c, r) = crossterm::cursor::position().c_unwrap();
+        *col = c;
+        *row = r;
+        r!()
+    })
+}
+
+/// Moves the terminal cursor to the given position (col, row).
+///
+/// # Notes
+/// * Top left cell is represented as `0,0`.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_move_to(col: u16, row: u16) -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::MoveTo(col, row)).c_unwrap();
+        r!()
+    })
+}
+
+/// Moves the terminal cursor down the given number of lines and moves it to the first col.
+///
+/// # Notes
+/// * This command is 1 based, meaning `crossterm_cursor_move_to_next_line(1)` moves to the next line.
+/// * Most terminals default 0 argument to 1.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_move_to_next_line(n: u16) -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::MoveToNextLine(n)).c_unwrap();
+        r!()
+    })
+}
+
+/// Moves the terminal cursor up the given number of lines and moves it to the first col.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_move_to_previous_line(n: u16) -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::MoveToPreviousLine(n)).c_unwrap();
+        r!()
+    })
+}
+
+/// Moves the terminal cursor to the given col on the current row.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_move_to_column(col: u16) -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::MoveToColumn(col)).c_unwrap();
+        r!()
+    })
+}
+
+/// Moves the terminal cursor to the given row on the current col.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_move_to_row(row: u16) -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::MoveToRow(row)).c_unwrap();
+        r!()
+    })
+}
+
+/// Moves the terminal cursor a given number of rows up.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_move_up(rows: u16) -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::MoveUp(rows)).c_unwrap();
+        r!()
+    })
+}
+
+/// Moves the terminal cursor a given number of cols to the right.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_move_right(cols: u16) -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::MoveRight(cols)).c_unwrap();
+        r!()
+    })
+}
+
+/// Moves the terminal cursor a given number of rows down.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_move_down(rows: u16) -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::MoveDown(rows)).c_unwrap();
+        r!()
+    })
+}
+
+/// Moves the terminal cursor a given number of cols to the left.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_move_left(cols: u16) -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::MoveLeft(cols)).c_unwrap();
+        r!()
+    })
+}
+
+/// Saves the current terminal cursor position.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_save_position() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::SavePosition).c_unwrap();
+        r!()
+    })
+}
+
+/// Restores the saved terminal cursor position.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_restore_position() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::RestorePosition).c_unwrap();
+        r!()
+    })
+}
+
+/// Hides the terminal cursor.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_hide() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::Hide).c_unwrap();
+        state::record(r!(), |m| m.cursor_hidden = true);
+        r!()
+    })
+}
+
+/// Shows the terminal cursor.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_show() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::Show).c_unwrap();
+        state::record(r!(), |m| m.cursor_hidden = false);
+        r!()
+    })
+}
+
+/// Hides the terminal cursor until every caller that hid it this way has shown it again.
+///
+/// Returns a token to pass to [`crossterm_cursor_show_guarded`], or `0` if an error occurred.
+/// The cursor is only hidden by the first outstanding token and only shown when the last one is
+/// released, so nested components can each hide the cursor without showing it too early.
+/// [`crossterm_cursor_hide`] and [`crossterm_cursor_show`] ignore the tokens.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_hide_guarded() -> u64 {
+    catch_panic(|| {
+        let mut hidden = state::cursor_hidden();
+        let (token, first) = hidden.acquire();
+        if first {
+            if let Err(err) = queue_command(crossterm::cursor::Hide) {
+                hidden.forget(token);
+                set_last_error(err);
+                return 0;
+            }
+            state::modes().cursor_hidden = true;
+        }
+        token
+    })
+}
+
+/// Releases a token returned by [`crossterm_cursor_hide_guarded`], showing the cursor if it was
+/// the last outstanding one.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_show_guarded(token: u64) -> libc::c_int {
+    catch_panic(|| {
+        state::cursor_hidden()
+            .release(token)
+            .and_then(|last| {
+                if last {
+                    queue_command(crossterm::cursor::Show)?;
+                    state::modes().cursor_hidden = false;
+                }
+                Ok(())
+            })
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Enables blinking of the terminal cursor.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_enable_blinking() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::EnableBlinking).c_unwrap();
+        r!()
+    })
+}
+
+/// Disables blinking of the terminal cursor.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_disable_blinking() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::DisableBlinking).c_unwrap();
+        r!()
+    })
+}
+
+/// Style of the cursor.
+/// It uses two types of escape codes, one to control blinking, and the other the shape.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub enum CursorStyle {
+    /// Default cursor shape configured by the user.
+    DefaultUserShape,
+    /// A blinking block cursor shape (■).
+    BlinkingBlock,
+    /// A non blinking block cursor shape (inverse of `BlinkingBlock`).
+    SteadyBlock,
+    /// A blinking underscore cursor shape(_).
+    BlinkingUnderScore,
+    /// A non blinking underscore cursor shape (inverse of `BlinkingUnderScore`).
+    SteadyUnderScore,
+    /// A blinking cursor bar shape (|)
+    BlinkingBar,
+    /// A steady cursor bar shape (inverse of `BlinkingBar`).
+    SteadyBar,
+}
+
+/// Sets the style of the cursor.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_style(cursor_style: CursorStyle) -> libc::c_int {
+    catch_panic(|| {
+        let cs = match cursor_style {
+            CursorStyle::DefaultUserShape => crossterm::cursor::SetCursorStyle::DefaultUserShape,
+            CursorStyle::BlinkingBlock => crossterm::cursor::SetCursorStyle::BlinkingBlock,
+            CursorStyle::SteadyBlock => crossterm::cursor::SetCursorStyle::SteadyBlock,
+            CursorStyle::BlinkingUnderScore => {
+                crossterm::cursor::SetCursorStyle::BlinkingUnderScore
+            }
+            CursorStyle::SteadyUnderScore => crossterm::cursor::SetCursorStyle::SteadyUnderScore,
+            CursorStyle::BlinkingBar => crossterm::cursor::SetCursorStyle::BlinkingBar,
+            CursorStyle::SteadyBar => crossterm::cursor::SetCursorStyle::SteadyBar,
+        };
+        queue!(io!(), cs).c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the style of the cursor to default user shape.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_style_default_user_shape() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::SetCursorStyle::DefaultUserShape).c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the style of the cursor to a blinking block.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_style_blinking_block() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::SetCursorStyle::BlinkingBlock).c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the style of the cursor to a steady block.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_style_steady_block() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::SetCursorStyle::SteadyBlock).c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the style of the cursor to a blinking underscore.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_style_blinking_underscore() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::SetCursorStyle::BlinkingUnderScore).c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the style of the cursor to a steady underscore.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_style_steady_underscore() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::SetCursorStyle::SteadyUnderScore).c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the style of the cursor to a blinking bar.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_style_blinking_bar() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::SetCursorStyle::BlinkingBar).c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the style of the cursor to a steady bar.
+#[no_mangle]
+pub extern "C" fn crossterm_cursor_style_steady_bar() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::cursor::SetCursorStyle::SteadyBar).c_unwrap();
+        r!()
+    })
+}
+
+/// Enable mouse event capturing.
+#[no_mangle]
+pub extern "C" fn crossterm_event_enable_mouse_capture() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::event::EnableMouseCapture).c_unwrap();
+        state::record(r!(), |m| {
+            m.mouse_capture = true;
+            m.mouse_capture_mode = None;
+        });
+        r!()
+    })
+}
+
+/// Which mouse events are reported, from the fewest to the most.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseCaptureMode {
+    /// Presses, releases and scrolling (DECSET 1000).
+    Clicks,
+    /// Also motion while a button is down (DECSET 1002).
+    ClicksAndDrag,
+    /// Also motion without a button down, e.g. for hover effects (DECSET 1003).
+    AnyMotion,
+}
+
+impl MouseCaptureMode {
+    /// The sequence enabling the mode, with events in the SGR format (DECSET 1006), which also
+    /// leaves the modes that report more motion.
+    pub fn enable_sequence(self) -> &'static str {
+        match self {
+            MouseCaptureMode::Clicks => "\x1b[?1003l\x1b[?1002l\x1b[?1000h\x1b[?1006h",
+            MouseCaptureMode::ClicksAndDrag => "\x1b[?1003l\x1b[?1000h\x1b[?1002h\x1b[?1006h",
+            MouseCaptureMode::AnyMotion => "\x1b[?1000h\x1b[?1002h\x1b[?1003h\x1b[?1006h",
+        }
+    }
+}
+
+/// Enable mouse event capturing for only the events of `mode`.
+///
+/// Unlike [`crossterm_event_enable_mouse_capture`], which reports all motion, this avoids a flood
+/// of motion events when the application doesn't use them. Disable it with
+/// [`crossterm_event_disable_mouse_capture`].
+///
+/// On Windows, where mouse events are read from the console rather than as escape sequences, all
+/// mouse events are reported regardless of `mode`.
+#[no_mangle]
+pub extern "C" fn crossterm_event_enable_mouse_capture_mode(mode: MouseCaptureMode) -> libc::c_int {
+    catch_panic(|| {
+        let result = if cfg!(windows) {
+            queue!(io!(), crossterm::event::EnableMouseCapture)
+        } else {
+            queue!(io!(), crossterm::style::Print(mode.enable_sequence()))
+        };
+        result.c_unwrap();
+        state::record(r!(), |m| {
+            m.mouse_capture = true;
+            m.mouse_capture_mode = Some(mode);
+        });
+        r!()
+    })
+}
+
+/// Makes mouse events report pixels (SGR-Pixels).
+const ENABLE_MOUSE_PIXELS: &str = "\x1b[?1016h";
+/// Stops mouse events from reporting pixels.
+const RESET_MOUSE_PIXELS: &str = "\x1b[?1016l";
+
+/// Makes mouse events report their position in pixels as well as in cells, with xterm's SGR-Pixels
+/// mouse mode (`CSI ? 1016 h`), e.g. for sub-cell precision over images.
+///
+/// Enable mouse capture with [`crossterm_event_enable_mouse_capture`] first. The position in
+/// pixels is in the `pixel_x` and `pixel_y` fields of [`MouseEvent`], and the cell is worked out
+/// from the size of the window in pixels (see [`crossterm_terminal_window_size`]), or the size of
+/// a cell if the terminal doesn't report it (see [`crossterm_terminal_cell_size`]). The size is
+/// taken again when the terminal is resized.
+///
+/// Returns -1 with an error starting with `Unsupported` if the size of a cell can't be found. The
+/// mode is left when the process exits.
+#[no_mangle]
+pub extern "C" fn crossterm_event_enable_mouse_pixels() -> libc::c_int {
+    catch_panic(|| {
+        let grid = match pixel_grid() {
+            Ok(grid) => grid,
+            Err(e) => {
+                set_last_error(e);
+                return -1;
+            }
+        };
+        queue!(io!(), crossterm::style::Print(ENABLE_MOUSE_PIXELS)).c_unwrap();
+        state::record(r!(), |m| m.mouse_pixels = Some(grid));
+        r!()
+    })
+}
+
+/// Makes mouse events report cells only again, after [`crossterm_event_enable_mouse_pixels`].
+#[no_mangle]
+pub extern "C" fn crossterm_event_disable_mouse_pixels() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::style::Print(RESET_MOUSE_PIXELS)).c_unwrap();
+        state::record(r!(), |m| m.mouse_pixels = None);
+        r!()
+    })
+}
+
+/// Disable mouse event capturing.
+#[no_mangle]
+pub extern "C" fn crossterm_event_disable_mouse_capture() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::event::DisableMouseCapture).c_unwrap();
+        state::record(r!(), |m| {
+            m.mouse_capture = false;
+            m.mouse_capture_mode = None;
+        });
+        r!()
+    })
+}
+
+/// Represents special flags that tell compatible terminals to add extra information to keyboard events.
+///
+/// See <https://sw.kovidgoyal.net/kitty/keyboard-protocol/#progressive-enhancement> for more information.
+///
+/// Alternate keys and Unicode codepoints are not yet supported by crossterm.
+#[repr(u8)]
+pub enum KeyboardEnhancementFlags {
+    /// Represent Escape and modified keys using CSI-u sequences, so they can be unambiguously
+    /// read.
+    DisambiguateEscapeCodes = 0b0000_0001,
+    /// Add extra events with [`KeyEvent.kind`] set to [`KeyEventKind::Repeat`] or
+    /// [`KeyEventKind::Release`] when keys are autorepeated or released.
+    ReportEventTypes = 0b0000_0010,
+    // Send [alternate keycodes](https://sw.kovidgoyal.net/kitty/keyboard-protocol/#key-codes)
+    // in addition to the base keycode. The alternate keycode overrides the base keycode in
+    // resulting `KeyEvent`s.
+    ReportAlternateKeys = 0b0000_0100,
+    /// Represent all keyboard events as CSI-u sequences. This is required to get repeat/release
+    /// events for plain-text keys.
+    ReportAllKeysAsEscapeCodes = 0b0000_1000,
+}
+
+/// Enables the [kitty keyboard protocol](https://sw.kovidgoyal.net/kitty/keyboard-protocol/),
+/// which adds extra information to keyboard events and removes ambiguity for modifier keys.
+/// It should be paired with [`crossterm_pop_keyboard_enhancement_flags`] at the end of execution.
+///
+/// Returns `-1` if `flags` has bits set that aren't [`KeyboardEnhancementFlags`].
+#[no_mangle]
+pub extern "C" fn crossterm_event_push_keyboard_enhancement_flags(flags: u8) -> libc::c_int {
+    catch_panic(|| {
+        crossterm::event::KeyboardEnhancementFlags::from_bits(flags)
+            .ok_or_else(|| anyhow::anyhow!("Invalid keyboard enhancement flags {:#x}", flags))
+            .and_then(|flags| {
+                queue!(io!(), crossterm::event::PushKeyboardEnhancementFlags(flags))
+                    .map_err(Into::into)
+            })
+            .c_unwrap();
+        state::record(r!(), |m| m.keyboard_enhancement_flags.push(flags));
+        r!()
+    })
+}
+
+/// Disables extra kinds of keyboard events.
+#[no_mangle]
+pub extern "C" fn crossterm_event_pop_keyboard_enhancement_flags() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::event::PopKeyboardEnhancementFlags).c_unwrap();
+        state::record(r!(), |m| {
+            m.keyboard_enhancement_flags.pop();
+        });
+        r!()
+    })
+}
+
+/// Tells whether the terminal supports the
+/// [kitty keyboard protocol](https://sw.kovidgoyal.net/kitty/keyboard-protocol/), to decide
+/// whether to push [`KeyboardEnhancementFlags`] with
+/// [`crossterm_event_push_keyboard_enhancement_flags`] rather than sending them to terminals that
+/// might print them.
+///
+/// The terminal is queried for its flags (`CSI ? u`) followed by its device attributes, which all
+/// terminals answer. Input that arrives meanwhile is kept and read as events later.
+///
+/// Returns 1 if the protocol is supported, 0 if it isn't (always on Windows), and -1 if an error
+/// occurred.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_supports_keyboard_enhancement() -> libc::c_int {
+    catch_panic(|| {
+        let supported = crossterm::terminal::supports_keyboard_enhancement().c_unwrap();
+        if crossterm_has_error() {
+            r!()
+        } else {
+            supported as libc::c_int
+        }
+    })
+}
+
+/// Queries the [`KeyboardEnhancementFlags`] that are active in the terminal (`CSI ? u`), waiting
+/// up to `timeout_ms` milliseconds for the reply.
+///
+/// Unlike the flags tracked by this library, this includes flags pushed by other programs, e.g.
+/// by the parent of an application that spawns a subshell and needs to restore the exact state.
+///
+/// Returns the flag bits, or -1 if an error occurred, with an error starting with `Unsupported`
+/// if the terminal doesn't support the kitty keyboard protocol.
+#[no_mangle]
+pub extern "C" fn crossterm_event_query_keyboard_enhancement_flags(timeout_ms: u32) -> libc::c_int {
+    catch_panic(|| {
+        let timeout = std::time::Duration::from_millis(timeout_ms as u64);
+        match query::keyboard_enhancement_flags(timeout) {
+            Ok(flags) => flags as libc::c_int,
+            Err(err) => {
+                set_last_error(err);
+                -1
+            }
+        }
+    })
+}
+
+/// Enable focus event emission.
+///
+/// It should be paired with [`crossterm_event_disable_focus_change`] at the end of execution.
+///
+/// Focus events can be captured with [`crossterm_event_read`].
+#[no_mangle]
+pub extern "C" fn crossterm_event_enable_focus_change() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::event::EnableFocusChange).c_unwrap();
+        state::record(r!(), |m| m.focus_change = true);
+        r!()
+    })
+}
+
+/// Disable focus event emission.
+#[no_mangle]
+pub extern "C" fn crossterm_event_disable_focus_change() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::event::DisableFocusChange).c_unwrap();
+        state::record(r!(), |m| m.focus_change = false);
+        r!()
+    })
+}
+
+/// Tracks whether the terminal has focus, so that rendering can pause while it doesn't.
+///
+/// Enables focus event emission (see [`crossterm_event_enable_focus_change`]) and starts
+/// following the `FocusGained`/`FocusLost` events read with [`crossterm_event_read`], which are
+/// still delivered. Once the terminal loses focus, [`crossterm_should_render`] returns false until
+/// it gains focus again. If `hold_flush` is true, [`crossterm_flush`] also does nothing in the
+/// meantime, so that output queued by a render loop isn't sent to a terminal in the background.
+/// Output is still written when the standard stream's own buffer fills up or, for stdout, at
+/// each newline; callers should redraw on `FocusGained` either way.
+///
+/// Terminals without focus reporting never send focus events, and rendering never pauses.
+#[no_mangle]
+pub extern "C" fn crossterm_event_enable_focus_tracking(hold_flush: bool) -> libc::c_int {
+    catch_panic(|| {
+        if crossterm_event_enable_focus_change() == 0 {
+            let mut focus = focus::focus();
+            focus.tracking = true;
+            focus.hold_flush = hold_flush;
+        }
+        r!()
+    })
+}
+
+/// Stops tracking focus and disables focus event emission.
+#[no_mangle]
+pub extern "C" fn crossterm_event_disable_focus_tracking() -> libc::c_int {
+    catch_panic(|| {
+        {
+            let mut focus = focus::focus();
+            focus.tracking = false;
+            focus.hold_flush = false;
+        }
+        crossterm_event_disable_focus_change()
+    })
+}
+
+/// Tells whether the caller should render, which is false while the terminal is unfocused and
+/// focus tracking is enabled with [`crossterm_event_enable_focus_tracking`].
+#[no_mangle]
+pub extern "C" fn crossterm_should_render() -> bool {
+    catch_panic(|| focus::focus().should_render())
+}
+
+/// Tells whether the terminal has focus, as of the last `FocusGained` or `FocusLost` event read
+/// with focus events enabled (see [`crossterm_event_enable_focus_change`]).
+///
+/// With `query`, the terminal is asked for its current focus state instead, by enabling focus
+/// reporting, which some terminals (e.g. kitty and foot) answer right away with a focus event.
+/// This lets an application start in the right rendering mode. Focus reporting stays as it was.
+///
+/// Returns 1 if the terminal has focus, 0 if it doesn't, and -1 with an error starting with
+/// `Unsupported` if the state is not known.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_has_focus(query: bool) -> libc::c_int {
+    catch_panic(|| {
+        let focused = if query {
+            let keep_enabled = state::modes().focus_change;
+            query::focus(keep_enabled, query::DEFAULT_TIMEOUT)
+                .inspect(|focused| focus::focus().focused = Some(*focused))
+        } else {
+            focus::focus().focused.ok_or_else(|| {
+                anyhow::anyhow!("Unsupported: the terminal did not report its focus yet")
+            })
+        };
+        match focused {
+            Ok(focused) => focused as libc::c_int,
+            Err(err) => {
+                set_last_error(err);
+                -1
+            }
+        }
+    })
+}
+
+/// Enables [bracketed paste mode](https://en.wikipedia.org/wiki/Bracketed-paste).
+///
+/// It should be paired with [`crossterm_event_disable_bracketed_paste`] at the end of execution.
+///
+/// This is not supported in older Windows terminals without
+/// [virtual terminal sequences](https://docs.microsoft.com/en-us/windows/console/console-virtual-terminal-sequences).
+#[no_mangle]
+pub extern "C" fn crossterm_event_enable_bracketed_paste() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::event::EnableBracketedPaste).c_unwrap();
+        state::record(r!(), |m| m.bracketed_paste = true);
+        r!()
+    })
+}
+
+/// Disables bracketed paste mode.
+#[no_mangle]
+pub extern "C" fn crossterm_event_disable_bracketed_paste() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::event::DisableBracketedPaste).c_unwrap();
+        state::record(r!(), |m| m.bracketed_paste = false);
+        r!()
+    })
+}
+
+#[repr(C)]
+pub enum Attribute {
+    /// Resets all the attributes.
+    Reset,
+    /// Increases the text intensity.
+    Bold,
+    /// Decreases the text intensity.
+    Dim,
+    /// Emphasises the text.
+    Italic,
+    /// Underlines the text.
+    Underlined,
+    /// Double underlines the text.
+    DoubleUnderlined,
+    /// Undercurls the text.
+    Undercurled,
+    /// Underdots the text.
+    Underdotted,
+    /// Underdashes the text.
+    Underdashed,
+    /// Makes the text blinking (< 150 per minute).
+    SlowBlink,
+    /// Makes the text blinking (>= 150 per minute).
+    RapidBlink,
+    /// Swaps foreground and background colors.
+    Reverse,
+    /// Hides the text (also known as Conceal).
+    Hidden,
+    /// Crosses the text.
+    CrossedOut,
+    /// Sets the [Fraktur](https://en.wikipedia.org/wiki/Fraktur) typeface.
+    ///
+    /// Mostly used for [mathematical alphanumeric symbols](https://en.wikipedia.org/wiki/Mathematical_Alphanumeric_Symbols).
+    Fraktur,
+    /// Turns off the `Bold` attribute. - Inconsistent - Prefer to use NormalIntensity
+    NoBold,
+    /// Switches the text back to normal intensity (no bold, italic).
+    NormalIntensity,
+    /// Turns off the `Italic` attribute.
+    NoItalic,
+    /// Turns off the `Underlined` attribute.
+    NoUnderline,
+    /// Turns off the text blinking (`SlowBlink` or `RapidBlink`).
+    NoBlink,
+    /// Turns off the `Reverse` attribute.
+    NoReverse,
+    /// Turns off the `Hidden` attribute.
+    NoHidden,
+    /// Turns off the `CrossedOut` attribute.
+    NotCrossedOut,
+    /// Makes the text framed.
+    Framed,
+    /// Makes the text encircled.
+    Encircled,
+    /// Draws a line at the top of the text.
+    OverLined,
+    /// Turns off the `Frame` and `Encircled` attributes.
+    NotFramedOrEncircled,
+    /// Turns off the `OverLined` attribute.
+    NotOverLined,
+}
+
+impl From<Attribute> for crossterm::style::Attribute {
+    fn from(value: Attribute) -> Self {
+        match value {
+            Attribute::Reset => crossterm::style::Attribute::Reset,
+            Attribute::Bold => crossterm::style::Attribute::Bold,
+            Attribute::Dim => crossterm::style::Attribute::Dim,
+            Attribute::Italic => crossterm::style::Attribute::Italic,
+            Attribute::Underlined => crossterm::style::Attribute::Underlined,
+            Attribute::DoubleUnderlined => crossterm::style::Attribute::DoubleUnderlined,
+            Attribute::Undercurled => crossterm::style::Attribute::Undercurled,
+            Attribute::Underdotted => crossterm::style::Attribute::Underdotted,
+            Attribute::Underdashed => crossterm::style::Attribute::Underdashed,
+            Attribute::SlowBlink => crossterm::style::Attribute::SlowBlink,
+            Attribute::RapidBlink => crossterm::style::Attribute::RapidBlink,
+            Attribute::Reverse => crossterm::style::Attribute::Reverse,
+            Attribute::Hidden => crossterm::style::Attribute::Hidden,
+            Attribute::CrossedOut => crossterm::style::Attribute::CrossedOut,
+            Attribute::Fraktur => crossterm::style::Attribute::Fraktur,
+            Attribute::NoBold => crossterm::style::Attribute::NoBold,
+            Attribute::NormalIntensity => crossterm::style::Attribute::NormalIntensity,
+            Attribute::NoItalic => crossterm::style::Attribute::NoItalic,
+            Attribute::NoUnderline => crossterm::style::Attribute::NoUnderline,
+            Attribute::NoBlink => crossterm::style::Attribute::NoBlink,
+            Attribute::NoReverse => crossterm::style::Attribute::NoReverse,
+            Attribute::NoHidden => crossterm::style::Attribute::NoHidden,
+            Attribute::NotCrossedOut => crossterm::style::Attribute::NotCrossedOut,
+            Attribute::Framed => crossterm::style::Attribute::Framed,
+            Attribute::Encircled => crossterm::style::Attribute::Encircled,
+            Attribute::OverLined => crossterm::style::Attribute::OverLined,
+            Attribute::NotFramedOrEncircled => crossterm::style::Attribute::NotFramedOrEncircled,
+            Attribute::NotOverLined => crossterm::style::Attribute::NotOverLined,
+        }
+    }
+}
+
+/// a bitset for all possible attributes
+///
+/// An [`Attribute`] `attr` is in the set if bit `1 << attr` is set.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Attributes(pub u32);
+
+impl From<Attributes> for crossterm::style::Attributes {
+    fn from(value: Attributes) -> Self {
+        let mut attributes = crossterm::style::Attributes::default();
+        for attr in crossterm::style::Attribute::iterator() {
+            // `crossterm` shifts the bits by one more
+            if value.0 & (attr.bytes() >> 1) != 0 {
+                attributes.set(attr);
+            }
+        }
+        attributes
+    }
+}
+
+/// Sets an attribute.
+///
+/// See [`Attribute`] for more info.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute(attr: Attribute) -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(attr.into()))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `Reset` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_reset() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(Attribute::Reset.into()))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `Bold` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_bold() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(Attribute::Bold.into()))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `Dim` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_dim() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(Attribute::Dim.into()))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `Italic` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_italic() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(Attribute::Italic.into()))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `Underlined` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_underlined() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(Attribute::Underlined.into()))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `DoubleUnderlined` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_double_underlined() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(
+                Attribute::DoubleUnderlined.into()
+            ))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `Undercurled` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_undercurled() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(
+                Attribute::Undercurled.into()
+            ))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `Underdotted` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_underdotted() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(
+                Attribute::Underdotted.into()
+            ))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `Underdashed` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_underdashed() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(
+                Attribute::Underdashed.into()
+            ))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `SlowBlink` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_slow_blink() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(Attribute::SlowBlink.into()))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `RapidBlink` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_rapid_blink() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(Attribute::RapidBlink.into()))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `Reverse` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_reverse() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(Attribute::Reverse.into()))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `Hidden` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_hidden() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(Attribute::Hidden.into()))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `CrossedOut` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_crossed_out() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(Attribute::CrossedOut.into()))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `Fraktur` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_fraktur() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(Attribute::Fraktur.into()))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `NoBold` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_no_bold() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(Attribute::NoBold.into()))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `NormalIntensity` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_normal_intensity() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(
+                Attribute::NormalIntensity.into()
+            ))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `NoItalic` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_no_italic() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(Attribute::NoItalic.into()))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `NoUnderline` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_no_underline() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(
+                Attribute::NoUnderline.into()
+            ))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `NoBlink` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_no_blink() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(Attribute::NoBlink.into()))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `NoReverse` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_no_reverse() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(Attribute::NoReverse.into()))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `NoHidden` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_no_hidden() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(Attribute::NoHidden.into()))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the `NotCrossedOut` attribute.
+#[no_mangle]
+pub extern "C" fn crossterm_style_attribute_not_crossed_out() -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetAttribute(
+                Attribute::NotCrossedOut.into()
+            ))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Print char to stdout
+#[no_mangle]
+pub extern "C" fn crossterm_style_print_char(c: u32) -> libc::c_int {
+    catch_panic(|| {
+        if let Some(ch) = std::char::from_u32(c) {
+            let mut buffer = [0; 4];
+            let text = output::translate_newlines(ch.encode_utf8(&mut buffer));
+            queue!(io!(), crossterm::style::Print(text)).c_unwrap();
+            r!()
+        } else {
+            set_last_error(anyhow::anyhow!("Unable to convert {} to valid char", c));
+            -1
+        }
+    })
+}
+
+/// `ch` repeated `count` times, as the char followed by REP (`CSI n b`) if that is shorter.
+fn repeated(ch: char, count: usize) -> String {
+    if count == 0 {
+        return String::new();
+    }
+    let rep = format!("\x1b[{}b", count - 1);
+    let printable =
+        !ch.is_control() && unicode_width::UnicodeWidthChar::width(ch).is_some_and(|w| w > 0);
+    if printable && rep.len() < (count - 1) * ch.len_utf8() && capabilities::supports_repeat() {
+        format!("{}{}", ch, rep)
+    } else {
+        output::translate_newlines(&ch.to_string().repeat(count)).into_owned()
+    }
+}
+
+/// Print the char `c` `count` times to stdout
+///
+/// The char is printed once and repeated by the terminal with REP (`CSI n b`), which is much
+/// shorter than the repeated text for e.g. horizontal rules. The repeated text is printed instead
+/// when it is as short, when `c` is not a printable char, or when the terminal is not expected to
+/// support REP. Nothing is printed if `count` is 0.
+#[no_mangle]
+pub extern "C" fn crossterm_style_print_char_repeated(c: u32, count: u32) -> libc::c_int {
+    catch_panic(|| {
+        let Some(ch) = std::char::from_u32(c) else {
+            set_last_error(anyhow::anyhow!("Unable to convert {} to valid char", c));
+            return -1;
+        };
+        queue!(io!(), crossterm::style::Print(repeated(ch, count as usize))).c_unwrap();
+        r!()
+    })
+}
+
+/// Switches to the DEC special graphics charset.
+const BEGIN_LINE_DRAWING: &str = "\x1b(0";
+/// Switches back to the ASCII charset.
+const END_LINE_DRAWING: &str = "\x1b(B";
+
+/// Switches to the DEC special graphics charset (`ESC ( 0`), in which e.g. `q` draws `─`, for
+/// drawing lines and boxes in terminals whose fonts lack the Unicode box-drawing chars.
+///
+/// Use [`crossterm_style_line_drawing_char`] for the chars to print, and switch back with
+/// [`crossterm_style_end_line_drawing`] before printing text. The charset is switched back when
+/// the process exits.
+#[no_mangle]
+pub extern "C" fn crossterm_style_begin_line_drawing() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::style::Print(BEGIN_LINE_DRAWING)).c_unwrap();
+        state::record(r!(), |m| m.line_drawing = true);
+        r!()
+    })
+}
+
+/// Switches back to the ASCII charset (`ESC ( B`) after [`crossterm_style_begin_line_drawing`].
+#[no_mangle]
+pub extern "C" fn crossterm_style_end_line_drawing() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::style::Print(END_LINE_DRAWING)).c_unwrap();
+        state::record(r!(), |m| m.line_drawing = false);
+        r!()
+    })
+}
+
+/// Returns the char that draws the box-drawing char `c` in the DEC special graphics charset, e.g.
+/// `q` for `─` (U+2500).
+///
+/// Heavy, double and rounded lines map to the light lines, the only ones in the charset. Returns
+/// 0 and sets the last error if `c` has no equivalent in the charset.
+#[no_mangle]
+pub extern "C" fn crossterm_style_line_drawing_char(c: u32) -> u32 {
+    catch_panic(|| {
+        std::char::from_u32(c)
+            .and_then(charset::dec_special_graphics)
+            .map(u32::from)
+            .ok_or_else(|| anyhow::anyhow!("No DEC special graphics char for {:#x}", c))
+            .c_unwrap()
+    })
+}
+
+/// Print string to stdout
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `title` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `title` won't be deallocated or modified for the duration of the function call..
+/// - The `title` pointer is correctly aligned and `title` points to an initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_style_print_string(s: *const libc::c_char) -> libc::c_int {
+    catch_panic(|| {
+        if s.is_null() {
+            RESULT.with(|r| {
+                *r.borrow_mut() = -1;
+            });
+            set_last_error(anyhow::anyhow!("Received null pointer for print string"));
+            return r!();
+        };
+        let c_str: &std::ffi::CStr = std::ffi::CStr::from_ptr(s);
+        if let Ok(string) = c_str.to_str() {
+            let string = output::translate_newlines(string);
+            queue!(io!(), crossterm::style::Print(string)).c_unwrap();
+            r!()
+        } else {
+            RESULT.with(|r| {
+                *r.borrow_mut() = -1;
+            });
+            set_last_error(anyhow::anyhow!(
+                "Received invalid UTF-8 string for print string"
+            ));
+            r!()
+        }
+    })
+}
+
+/// Print string to stdout
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `title` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `title` won't be deallocated or modified for the duration of the function call..
+/// - The `title` pointer is correctly aligned and `title` points to an initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_style_print(s: *const libc::c_char) -> libc::c_int {
+    catch_panic(|| crossterm_style_print_string(s))
+}
+
+/// Print string to stdout, replacing invalid UTF-8 sequences with U+FFFD, e.g. to display the
+/// output of another process as it is.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `s` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `s` won't be deallocated or modified for the duration of the function call.
+/// - The `s` pointer is correctly aligned and `s` points to an initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_style_print_lossy(s: *const libc::c_char) -> libc::c_int {
+    catch_panic(|| {
+        if s.is_null() {
+            set_last_error(anyhow::anyhow!("Received null pointer for print string"));
+            return -1;
+        }
+        let string = std::ffi::CStr::from_ptr(s).to_string_lossy();
+        let string = output::translate_newlines(&string);
+        queue!(io!(), crossterm::style::Print(string)).c_unwrap();
+        r!()
+    })
+}
+
+/// Print string to stdout followed by a newline: `\r\n` in raw mode, where `\n` alone doesn't
+/// return the cursor to the first column, and `\n` otherwise.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `s` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `s` won't be deallocated or modified for the duration of the function call.
+/// - The `s` pointer is correctly aligned and `s` points to an initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_style_print_line(s: *const libc::c_char) -> libc::c_int {
+    catch_panic(|| {
+        convert_c_char_to_str(s, "print string")
+            .and_then(|string| {
+                let newline = if crossterm::terminal::is_raw_mode_enabled()? {
+                    "\r\n"
+                } else {
+                    "\n"
+                };
+                queue!(
+                    io!(),
+                    crossterm::style::Print(output::translate_newlines(string)),
+                    crossterm::style::Print(newline)
+                )
+                .map_err(Into::into)
+            })
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets whether the print functions translate `\n` to `\r\n`, for all threads (off by default).
+///
+/// In raw mode, `\n` only moves the cursor down, so text with several lines is printed as a
+/// staircase unless the newlines are translated. A `\n` that follows a `\r` is left as it is.
+#[no_mangle]
+pub extern "C" fn crossterm_set_crlf_translation(enabled: bool) {
+    catch_panic(|| output::set_crlf_translation(enabled))
+}
+
+/// The alignment of text in [`crossterm_print_padded`].
+#[repr(C)]
+pub enum Alignment {
+    Left,
+    Right,
+    Center,
+}
+
+/// Print string to stdout in exactly `width` columns: padded with spaces as `align` says, or cut
+/// off at a grapheme boundary and ended with `ellipsis` if it is wider, e.g. for table cells.
+///
+/// Widths are display widths, with wide characters (e.g. CJK) counting as two columns. `ellipsis`
+/// may be null or empty to cut the text off without one. The text should be a single line.
+///
+/// # Safety
+///
+/// This function takes raw pointers as arguments. As such, the caller must ensure that:
+/// - The `s` pointer points to a valid null-terminated string, and the `ellipsis` pointer too
+///   unless it is null.
+/// - This function borrows slices to valid null-terminated strings and the memory referenced by `s` and `ellipsis` won't be deallocated or modified for the duration of the function call.
+/// - The pointers are correctly aligned and point to initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_print_padded(
+    s: *const libc::c_char,
+    width: u16,
+    align: Alignment,
+    ellipsis: *const libc::c_char,
+) -> libc::c_int {
+    catch_panic(|| {
+        let ellipsis = if ellipsis.is_null() {
+            Ok("")
+        } else {
+            convert_c_char_to_str(ellipsis, "ellipsis")
+        };
+        convert_c_char_to_str(s, "print string")
+            .and_then(|string| {
+                let padded = pad::pad(string, width as usize, align, ellipsis?);
+                queue!(io!(), crossterm::style::Print(padded)).map_err(Into::into)
+            })
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Print string to stdout with its foreground color going from `(r1, g1, b1)` at the first
+/// grapheme to `(r2, g2, b2)` at the last one, then reset the foreground color.
+///
+/// Channels are interpolated linearly as in [`crossterm_color_mix`]. Each color is downgraded to
+/// the color depth set with [`crossterm_style_set_color_depth`], and only set when it differs from
+/// the color of the previous grapheme, so runs of graphemes that fall on the same ANSI color cost
+/// a single escape sequence.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `s` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `s` won't be deallocated or modified for the duration of the function call.
+/// - The `s` pointer is correctly aligned and `s` points to an initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_print_gradient(
+    s: *const libc::c_char,
+    r1: u8,
+    g1: u8,
+    b1: u8,
+    r2: u8,
+    g2: u8,
+    b2: u8,
+) -> libc::c_int {
+    catch_panic(|| {
+        convert_c_char_to_str(s, "print string")
+            .and_then(|string| {
+                let string = output::translate_newlines(string);
+                let graphemes: Vec<&str> = string.graphemes(true).collect();
+                let steps = graphemes.len().saturating_sub(1).max(1) as f64;
+                let mut out = io!();
+                let mut current = None;
+                for (i, grapheme) in graphemes.into_iter().enumerate() {
+                    let (r, g, b) = colors::mix((r1, g1, b1), (r2, g2, b2), i as f64 / steps);
+                    let color = colors::downgrade(crossterm::style::Color::Rgb { r, g, b });
+                    if current != Some(color) {
+                        queue!(
+                            out,
+                            colors::Sgr(crossterm::style::SetForegroundColor(color))
+                        )?;
+                        current = Some(color);
+                    }
+                    queue!(out, crossterm::style::Print(grapheme))?;
+                }
+                if current.is_some() {
+                    queue!(
+                        out,
+                        colors::Sgr(crossterm::style::SetForegroundColor(
+                            crossterm::style::Color::Reset
+                        ))
+                    )?;
+                }
+                Ok(())
+            })
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Print `len` UTF-16 code units of `s` to stdout, e.g. a string of Windows, Java or JavaScript.
+///
+/// Returns `-1` if the string has an unpaired surrogate.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `s` pointer points to `len` initialized and correctly aligned code units, or is null if
+///   `len` is `0`.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_style_print_utf16(s: *const u16, len: usize) -> libc::c_int {
+    catch_panic(|| {
+        convert_slice(s, len, "print string")
+            .and_then(|units| {
+                String::from_utf16(units)
+                    .map_err(|_| anyhow::anyhow!("Received invalid UTF-16 string for print string"))
+            })
+            .and_then(|string| {
+                let string = output::translate_newlines(&string);
+                queue!(io!(), crossterm::style::Print(string)).map_err(Into::into)
+            })
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Print `len` UTF-32 code points of `s` to stdout.
+///
+/// Returns `-1` if the string has a value that is not a Unicode scalar value.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `s` pointer points to `len` initialized and correctly aligned code points, or is null if
+///   `len` is `0`.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_style_print_utf32(s: *const u32, len: usize) -> libc::c_int {
+    catch_panic(|| {
+        convert_slice(s, len, "print string")
+            .and_then(|points| {
+                points
+                    .iter()
+                    .map(|&c| {
+                        std::char::from_u32(c)
+                            .ok_or_else(|| anyhow::anyhow!("Unable to convert {} to valid char", c))
+                    })
+                    .collect::<anyhow::Result<String>>()
+            })
+            .and_then(|string| {
+                let string = output::translate_newlines(&string);
+                queue!(io!(), crossterm::style::Print(string)).map_err(Into::into)
+            })
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Print string to stdout at the given position (col, row), moving the cursor there first.
+///
+/// # Notes
+/// * Top left cell is represented as `0,0`.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `s` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `s` won't be deallocated or modified for the duration of the function call.
+/// - The `s` pointer is correctly aligned and `s` points to an initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_print_at(
+    col: u16,
+    row: u16,
+    s: *const libc::c_char,
+) -> libc::c_int {
+    catch_panic(|| {
+        convert_c_char_to_str(s, "print string")
+            .and_then(|string| {
+                queue!(
+                    io!(),
+                    crossterm::cursor::MoveTo(col, row),
+                    crossterm::style::Print(output::translate_newlines(string))
+                )
+                .map_err(Into::into)
+            })
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Fills the rectangle of `width` columns and `height` rows at (col, row) with the char `c` in
+/// `style`, e.g. for the background of a panel.
+///
+/// Each row is filled with the char repeated by REP where that is shorter, see
+/// [`crossterm_style_print_char_repeated`]. A wide char fills two columns, and a trailing odd
+/// column is left as it is. The style is reset afterwards, and the cursor is left after the last
+/// row. `style` may be null to fill with the current style.
+///
+/// # Notes
+/// * Top left cell is represented as `0,0`.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `style` pointer is null or points to an initialized [`ContentStyle`].
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_draw_fill(
+    col: u16,
+    row: u16,
+    width: u16,
+    height: u16,
+    c: u32,
+    style: *const ContentStyle,
+) -> libc::c_int {
+    catch_panic(|| {
+        let fill = || -> anyhow::Result<()> {
+            let ch = std::char::from_u32(c)
+                .ok_or_else(|| anyhow::anyhow!("Unable to convert {} to valid char", c))?;
+            let char_width = unicode_width::UnicodeWidthChar::width(ch)
+                .filter(|w| *w > 0)
+                .ok_or_else(|| {
+                    anyhow::anyhow!("Unable to fill with non-printable char {:#x}", c)
+                })?;
+            if width == 0 || height == 0 {
+                return Ok(());
+            }
+            let line = repeated(ch, width as usize / char_width);
+            let mut out = io!();
+            if !style.is_null() {
+                queue!(
+                    out,
+                    colors::Sgr(crossterm::style::SetStyle((&*style).into()))
+                )?;
+            }
+            for y in row..row.saturating_add(height) {
+                queue!(
+                    out,
+                    crossterm::cursor::MoveTo(col, y),
+                    crossterm::style::Print(&line)
+                )?;
+            }
+            if !style.is_null() {
+                queue!(
+                    out,
+                    colors::Sgr(crossterm::style::SetAttribute(
+                        crossterm::style::Attribute::Reset
+                    ))
+                )?;
+            }
+            Ok(())
+        };
+        fill().c_unwrap();
+        r!()
+    })
+}
+
+/// Print string to stdout in display order for bidirectional text
+///
+/// Each line is reordered with the Unicode Bidirectional Algorithm, so that right-to-left text
+/// (Hebrew, Arabic) appears in the correct visual order on terminals that lay text out left to
+/// right. `base_direction` is the paragraph direction, which decides the order of runs on a line
+/// and where neutral characters go.
+///
+/// Don't use this on terminals that implement bidi themselves, which would reorder the text again.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `text` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `text` won't be deallocated or modified for the duration of the function call.
+/// - The `text` pointer is correctly aligned and `text` points to an initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_print_bidi(
+    text: *const libc::c_char,
+    base_direction: BidiDirection,
+) -> libc::c_int {
+    catch_panic(|| {
+        convert_c_char_to_str(text, "bidi text")
+            .and_then(|text| {
+                let text = bidi::visual(text, base_direction);
+                queue!(
+                    io!(),
+                    crossterm::style::Print(output::translate_newlines(&text))
+                )
+                .map_err(Into::into)
+            })
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Returns `text` reordered for display as [`crossterm_print_bidi`] prints it.
+///
+/// Returns null if an error occurred.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `text` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `text` won't be deallocated or modified for the duration of the function call.
+/// - The `text` pointer is correctly aligned and `text` points to an initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_bidi_visual(
+    text: *const libc::c_char,
+    base_direction: BidiDirection,
+) -> *const libc::c_char {
+    catch_panic(|| match convert_c_char_to_str(text, "bidi text") {
+        Ok(text) => convert_string_to_c_char(bidi::visual(text, base_direction)),
+        Err(err) => {
+            set_last_error(err);
+            std::ptr::null()
+        }
+    })
+}
+
+/// Returns the number of columns `text` takes up when printed, the width of its widest line.
+///
+/// Wide characters (e.g. CJK) count as two columns, and combining marks and other zero width
+/// characters as none. The width doesn't depend on the display order, so it can be used to align
+/// text printed with [`crossterm_print_bidi`].
+///
+/// Returns -1 if an error occurred.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `text` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `text` won't be deallocated or modified for the duration of the function call.
+/// - The `text` pointer is correctly aligned and `text` points to an initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_text_width(text: *const libc::c_char) -> libc::c_int {
+    catch_panic(|| {
+        let width = convert_c_char_to_str(text, "text")
+            .map(bidi::width)
+            .c_unwrap();
+        if crossterm_has_error() {
+            r!()
+        } else {
+            width.min(libc::c_int::MAX as usize) as libc::c_int
+        }
+    })
+}
+
+/// Returns the number of rows `text` takes up when printed from the first column of a terminal
+/// `width` columns wide, which soft-wraps lines longer than the width.
+///
+/// Characters are wrapped as terminals do: a character that doesn't fit on the rest of a row goes
+/// to the next row, and a row that is filled exactly doesn't wrap until the next character. `\n`
+/// starts a new row at the first column, `\r` returns to the first column, and tabs move to the
+/// next multiple of 8 columns. Wide characters take two columns.
+///
+/// Returns -1 if an error occurred.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `text` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `text` won't be deallocated or modified for the duration of the function call.
+/// - The `text` pointer is correctly aligned and `text` points to an initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_visual_lines(
+    text: *const libc::c_char,
+    width: u16,
+) -> libc::c_int {
+    catch_panic(|| {
+        let lines = convert_c_char_to_str(text, "text")
+            .and_then(|text| wrap::lines(text, width))
+            .c_unwrap();
+        if crossterm_has_error() {
+            r!()
+        } else {
+            lines.min(libc::c_int::MAX as usize) as libc::c_int
+        }
+    })
+}
+
+/// Get the position (col, row) of the character at `byte_offset` in `text` once printed from the
+/// first column of a terminal `width` columns wide, relative to where printing started.
+///
+/// For `byte_offset` at the end of `text`, this is where the next character goes, which is the
+/// start of the row after the last one if the last row is full. Wrapping is as in
+/// [`crossterm_visual_lines`]. An inline editor can move the cursor back to the start of its text
+/// and then down and right by the returned amounts after redrawing.
+///
+/// # Notes
+/// * The top left cell of the text is represented as `0,0`.
+/// * `byte_offset` must be at a character boundary.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `text` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `text` won't be deallocated or modified for the duration of the function call.
+/// - The `text` pointer is correctly aligned and `text` points to an initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_visual_cursor_position(
+    text: *const libc::c_char,
+    byte_offset: usize,
+    width: u16,
+    col: &mut u16,
+    row: &mut u16,
+) -> libc::c_int {
+    catch_panic(|| {
+        let (c, r) = convert_c_char_to_str(text, "text")
+            .and_then(|text| wrap::cursor_position(text, byte_offset, width))
+            .c_unwrap();
+        *col = c.min(u16::MAX as usize) as u16;
+        *row = r.min(u16::MAX as usize) as u16;
+        r!()
+    })
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash)]
+pub enum Color {
+    /// Resets the terminal color.
+    Reset,
+    /// Black color.
+    Black,
+    /// Dark grey color.
+    DarkGrey,
+    /// Light red color.
+    Red,
+    /// Dark red color.
+    DarkRed,
+    /// Light green color.
+    Green,
+    /// Dark green color.
+    DarkGreen,
+    /// Light yellow color.
+    Yellow,
+    /// Dark yellow color.
+    DarkYellow,
+    /// Light blue color.
+    Blue,
+    /// Dark blue color.
+    DarkBlue,
+    /// Light magenta color.
+    Magenta,
+    /// Dark magenta color.
+    DarkMagenta,
+    /// Light cyan color.
+    Cyan,
+    /// Dark cyan color.
+    DarkCyan,
+    /// White color.
+    White,
+    /// Grey color.
+    Grey,
+    /// An RGB color. See [RGB color model](https://en.wikipedia.org/wiki/RGB_color_model) for more info.
+    ///
+    /// Most UNIX terminals and Windows 10 supported only.
+    Rgb { r: u8, g: u8, b: u8 },
+    /// An ANSI color. See [256 colors - cheat sheet](https://jonasjacek.github.io/colors/) for more info.
+    ///
+    /// Most UNIX terminals and Windows 10 supported only.
+    AnsiValue(u8),
+}
+
+impl From<Color> for crossterm::style::Color {
+    fn from(color: Color) -> Self {
+        match color {
+            Color::Reset => crossterm::style::Color::Reset,
+            Color::Black => crossterm::style::Color::Black,
+            Color::DarkGrey => crossterm::style::Color::DarkGrey,
+            Color::Red => crossterm::style::Color::Red,
+            Color::DarkRed => crossterm::style::Color::DarkRed,
+            Color::Green => crossterm::style::Color::Green,
+            Color::DarkGreen => crossterm::style::Color::DarkGreen,
+            Color::Yellow => crossterm::style::Color::Yellow,
+            Color::DarkYellow => crossterm::style::Color::DarkYellow,
+            Color::Blue => crossterm::style::Color::Blue,
+            Color::DarkBlue => crossterm::style::Color::DarkBlue,
+            Color::Magenta => crossterm::style::Color::Magenta,
+            Color::DarkMagenta => crossterm::style::Color::DarkMagenta,
+            Color::Cyan => crossterm::style::Color::Cyan,
+            Color::DarkCyan => crossterm::style::Color::DarkCyan,
+            Color::White => crossterm::style::Color::White,
+            Color::Grey => crossterm::style::Color::Grey,
+            Color::Rgb { r, g, b } => crossterm::style::Color::Rgb { r, g, b },
+            Color::AnsiValue(v) => crossterm::style::Color::AnsiValue(v),
+        }
+    }
+}
+
+/// Makes the functions that set colors and attributes follow the `NO_COLOR`, `CLICOLOR` and
+/// `CLICOLOR_FORCE` conventions, or write their escape sequences unconditionally again (the
+/// default) if `respect` is false.
+///
+/// Colors are then disabled if `NO_COLOR` is set or `CLICOLOR` is `0`, and enabled if
+/// `CLICOLOR_FORCE` is set to anything but `0`. Otherwise they are only used if the output goes
+/// to a terminal, e.g. not when it is piped into a file. The decision is made once, when this is
+/// called, and applies to all threads. Check it with [`crossterm_colors_enabled`].
+///
+/// While colors are disabled, setting colors and attributes writes nothing, and the
+/// `crossterm_ansi_*` functions for them return empty strings.
+#[no_mangle]
+pub extern "C" fn crossterm_style_respect_color_env(respect: bool) {
+    catch_panic(|| colors::respect_color_env(respect))
+}
+
+/// Returns whether colors and attributes are written, as decided by
+/// [`crossterm_style_respect_color_env`].
+#[no_mangle]
+pub extern "C" fn crossterm_colors_enabled() -> bool {
+    catch_panic(colors::enabled)
+}
+
+/// The color depth that colors are downgraded to, see [`crossterm_style_set_color_depth`].
+#[repr(C)]
+pub enum ColorDepthMode {
+    /// The color depth detected from the environment.
+    Auto,
+    Ansi16,
+    Ansi256,
+    /// Colors are set as they are. This is the default.
+    TrueColor,
+}
+
+/// Sets the color depth that colors are downgraded to, for terminals without true color support.
+///
+/// Below [`ColorDepthMode::TrueColor`], RGB colors passed to the functions that set foreground,
+/// background and underline colors are replaced by the nearest ANSI color, and so are 256 colors
+/// for [`ColorDepthMode::Ansi16`]. [`ColorDepthMode::Auto`] detects the depth as
+/// [`crossterm_terminal_color_support`] does without querying, once, when it is set.
+#[no_mangle]
+pub extern "C" fn crossterm_style_set_color_depth(mode: ColorDepthMode) {
+    catch_panic(|| {
+        colors::set_depth(match mode {
+            ColorDepthMode::Auto => capabilities::color_support(false),
+            ColorDepthMode::Ansi16 => ColorDepth::Ansi16,
+            ColorDepthMode::Ansi256 => ColorDepth::Ansi256,
+            ColorDepthMode::TrueColor => ColorDepth::TrueColor,
+        })
+    })
+}
+
+/// Sets the the background color.
+///
+/// See [`Color`] for more info.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color(color: Color) -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetBackgroundColor(colors::downgrade(
+                color.into()
+            )))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the the background color in RGB.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color_rgb(r: u8, g: u8, b: u8) -> libc::c_int {
+    catch_panic(|| crossterm_style_background_color(Color::Rgb { r, g, b }))
+}
+
+/// Sets the the background color to an ANSI value.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color_ansi(value: u8) -> libc::c_int {
+    catch_panic(|| crossterm_style_background_color(Color::AnsiValue(value)))
+}
+
+/// Sets the the background color to Reset.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color_reset() -> libc::c_int {
+    catch_panic(|| crossterm_style_background_color(Color::Reset))
+}
+
+/// Sets the the background color to Black.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color_black() -> libc::c_int {
+    catch_panic(|| crossterm_style_background_color(Color::Black))
+}
+
+/// Sets the the background color to DarkGrey.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color_dark_grey() -> libc::c_int {
+    catch_panic(|| crossterm_style_background_color(Color::DarkGrey))
+}
+
+/// Sets the the background color to Red.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color_red() -> libc::c_int {
+    catch_panic(|| crossterm_style_background_color(Color::Red))
+}
+
+/// Sets the the background color to DarkRed.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color_dark_red() -> libc::c_int {
+    catch_panic(|| crossterm_style_background_color(Color::DarkRed))
+}
+
+/// Sets the the background color to Green.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color_green() -> libc::c_int {
+    catch_panic(|| crossterm_style_background_color(Color::Green))
+}
+
+/// Sets the the background color to DarkGreen.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color_dark_green() -> libc::c_int {
+    catch_panic(|| crossterm_style_background_color(Color::DarkGreen))
+}
+
+/// Sets the the background color to Yellow.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color_yellow() -> libc::c_int {
+    catch_panic(|| crossterm_style_background_color(Color::Yellow))
+}
+
+/// Sets the the background color to DarkYellow.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color_dark_yellow() -> libc::c_int {
+    catch_panic(|| crossterm_style_background_color(Color::DarkYellow))
+}
+
+/// Sets the the background color to Blue.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color_blue() -> libc::c_int {
+    catch_panic(|| crossterm_style_background_color(Color::Blue))
+}
+
+/// Sets the the background color to DarkBlue.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color_dark_blue() -> libc::c_int {
+    catch_panic(|| crossterm_style_background_color(Color::DarkBlue))
+}
+
+/// Sets the the background color to Magenta.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color_magenta() -> libc::c_int {
+    catch_panic(|| crossterm_style_background_color(Color::Magenta))
+}
+
+/// Sets the the background color to DarkMagenta.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color_dark_magenta() -> libc::c_int {
+    catch_panic(|| crossterm_style_background_color(Color::DarkMagenta))
+}
+
+/// Sets the the background color to Cyan.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color_cyan() -> libc::c_int {
+    catch_panic(|| crossterm_style_background_color(Color::Cyan))
+}
+
+/// Sets the the background color to DarkCyan.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color_dark_cyan() -> libc::c_int {
+    catch_panic(|| crossterm_style_background_color(Color::DarkCyan))
+}
+
+/// Sets the the background color to White.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color_white() -> libc::c_int {
+    catch_panic(|| crossterm_style_background_color(Color::White))
+}
+
+/// Sets the the background color to Grey.
+#[no_mangle]
+pub extern "C" fn crossterm_style_background_color_grey() -> libc::c_int {
+    catch_panic(|| crossterm_style_background_color(Color::Grey))
+}
+
+/// Sets the the foreground color.
+///
+/// See [`Color`] for more info.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color(color: Color) -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetForegroundColor(colors::downgrade(
+                color.into()
+            )))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the the foreground color in RGB.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color_rgb(r: u8, g: u8, b: u8) -> libc::c_int {
+    catch_panic(|| crossterm_style_foreground_color(Color::Rgb { r, g, b }))
+}
+
+/// Sets the the foreground color to an ANSI value.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color_ansi(value: u8) -> libc::c_int {
+    catch_panic(|| crossterm_style_foreground_color(Color::AnsiValue(value)))
+}
+
+/// Sets the the foreground color to Reset.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color_reset() -> libc::c_int {
+    catch_panic(|| crossterm_style_foreground_color(Color::Reset))
+}
+
+/// Sets the the foreground color to Black.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color_black() -> libc::c_int {
+    catch_panic(|| crossterm_style_foreground_color(Color::Black))
+}
+
+/// Sets the the foreground color to DarkGrey.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color_dark_grey() -> libc::c_int {
+    catch_panic(|| crossterm_style_foreground_color(Color::DarkGrey))
+}
+
+/// Sets the the foreground color to Red.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color_red() -> libc::c_int {
+    catch_panic(|| crossterm_style_foreground_color(Color::Red))
+}
+
+/// Sets the the foreground color to DarkRed.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color_dark_red() -> libc::c_int {
+    catch_panic(|| crossterm_style_foreground_color(Color::DarkRed))
+}
+
+/// Sets the the foreground color to Green.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color_green() -> libc::c_int {
+    catch_panic(|| crossterm_style_foreground_color(Color::Green))
+}
+
+/// Sets the the foreground color to DarkGreen.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color_dark_green() -> libc::c_int {
+    catch_panic(|| crossterm_style_foreground_color(Color::DarkGreen))
+}
+
+/// Sets the the foreground color to Yellow.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color_yellow() -> libc::c_int {
+    catch_panic(|| crossterm_style_foreground_color(Color::Yellow))
+}
+
+/// Sets the the foreground color to DarkYellow.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color_dark_yellow() -> libc::c_int {
+    catch_panic(|| crossterm_style_foreground_color(Color::DarkYellow))
+}
+
+/// Sets the the foreground color to Blue.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color_blue() -> libc::c_int {
+    catch_panic(|| crossterm_style_foreground_color(Color::Blue))
+}
+
+/// Sets the the foreground color to DarkBlue.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color_dark_blue() -> libc::c_int {
+    catch_panic(|| crossterm_style_foreground_color(Color::DarkBlue))
+}
+
+/// Sets the the foreground color to Magenta.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color_magenta() -> libc::c_int {
+    catch_panic(|| crossterm_style_foreground_color(Color::Magenta))
+}
+
+/// Sets the the foreground color to DarkMagenta.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color_dark_magenta() -> libc::c_int {
+    catch_panic(|| crossterm_style_foreground_color(Color::DarkMagenta))
+}
+
+/// Sets the the foreground color to Cyan.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color_cyan() -> libc::c_int {
+    catch_panic(|| crossterm_style_foreground_color(Color::Cyan))
+}
+
+/// Sets the the foreground color to DarkCyan.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color_dark_cyan() -> libc::c_int {
+    catch_panic(|| crossterm_style_foreground_color(Color::DarkCyan))
+}
+
+/// Sets the the foreground color to White.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color_white() -> libc::c_int {
+    catch_panic(|| crossterm_style_foreground_color(Color::White))
+}
+
+/// Sets the the foreground color to Grey.
+#[no_mangle]
+pub extern "C" fn crossterm_style_foreground_color_grey() -> libc::c_int {
+    catch_panic(|| crossterm_style_foreground_color(Color::Grey))
+}
+
+/// Sets the the underline color.
+///
+/// See [`Color`] for more info.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color(color: Color) -> libc::c_int {
+    catch_panic(|| {
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetUnderlineColor(colors::downgrade(
+                color.into()
+            )))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the the underline color in RGB.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color_rgb(r: u8, g: u8, b: u8) -> libc::c_int {
+    catch_panic(|| crossterm_style_underline_color(Color::Rgb { r, g, b }))
+}
+
+/// Sets the the underline color to an ANSI value.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color_ansi(value: u8) -> libc::c_int {
+    catch_panic(|| crossterm_style_underline_color(Color::AnsiValue(value)))
+}
+
+/// Sets the the underline color to Reset.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color_reset() -> libc::c_int {
+    catch_panic(|| crossterm_style_underline_color(Color::Reset))
+}
+
+/// Sets the the underline color to Black.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color_black() -> libc::c_int {
+    catch_panic(|| crossterm_style_underline_color(Color::Black))
+}
+
+/// Sets the the underline color to DarkGrey.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color_dark_grey() -> libc::c_int {
+    catch_panic(|| crossterm_style_underline_color(Color::DarkGrey))
+}
+
+/// Sets the the underline color to Red.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color_red() -> libc::c_int {
+    catch_panic(|| crossterm_style_underline_color(Color::Red))
+}
+
+/// Sets the the underline color to DarkRed.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color_dark_red() -> libc::c_int {
+    catch_panic(|| crossterm_style_underline_color(Color::DarkRed))
+}
+
+/// Sets the the underline color to Green.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color_green() -> libc::c_int {
+    catch_panic(|| crossterm_style_underline_color(Color::Green))
+}
+
+/// Sets the the underline color to DarkGreen.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color_dark_green() -> libc::c_int {
+    catch_panic(|| crossterm_style_underline_color(Color::DarkGreen))
+}
+
+/// Sets the the underline color to Yellow.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color_yellow() -> libc::c_int {
+    catch_panic(|| crossterm_style_underline_color(Color::Yellow))
+}
+
+/// Sets the the underline color to DarkYellow.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color_dark_yellow() -> libc::c_int {
+    catch_panic(|| crossterm_style_underline_color(Color::DarkYellow))
+}
+
+/// Sets the the underline color to Blue.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color_blue() -> libc::c_int {
+    catch_panic(|| crossterm_style_underline_color(Color::Blue))
+}
+
+/// Sets the the underline color to DarkBlue.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color_dark_blue() -> libc::c_int {
+    catch_panic(|| crossterm_style_underline_color(Color::DarkBlue))
+}
+
+/// Sets the the underline color to Magenta.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color_magenta() -> libc::c_int {
+    catch_panic(|| crossterm_style_underline_color(Color::Magenta))
+}
+
+/// Sets the the underline color to DarkMagenta.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color_dark_magenta() -> libc::c_int {
+    catch_panic(|| crossterm_style_underline_color(Color::DarkMagenta))
+}
+
+/// Sets the the underline color to Cyan.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color_cyan() -> libc::c_int {
+    catch_panic(|| crossterm_style_underline_color(Color::Cyan))
+}
+
+/// Sets the the underline color to DarkCyan.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color_dark_cyan() -> libc::c_int {
+    catch_panic(|| crossterm_style_underline_color(Color::DarkCyan))
+}
+
+/// Sets the the underline color to White.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color_white() -> libc::c_int {
+    catch_panic(|| crossterm_style_underline_color(Color::White))
+}
+
+/// Sets the the underline color to Grey.
+#[no_mangle]
+pub extern "C" fn crossterm_style_underline_color_grey() -> libc::c_int {
+    catch_panic(|| crossterm_style_underline_color(Color::Grey))
+}
+
+/// Resets the colors back to default.
+#[no_mangle]
+pub extern "C" fn crossterm_style_reset_color() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), colors::Sgr(crossterm::style::ResetColor)).c_unwrap();
+        r!()
+    })
+}
+
+/// A style as a value: colors and attributes, set together with [`crossterm_style_set`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct ContentStyle {
+    /// Whether `fg` is set; the foreground color is left as it is otherwise.
+    pub has_fg: bool,
+    /// The foreground color.
+    pub fg: Color,
+    /// Whether `bg` is set; the background color is left as it is otherwise.
+    pub has_bg: bool,
+    /// The background color.
+    pub bg: Color,
+    /// Whether `underline_color` is set; the underline color is left as it is otherwise.
+    pub has_underline_color: bool,
+    /// The underline color.
+    pub underline_color: Color,
+    /// The attributes to set. Attributes that are not in the set are left as they are.
+    pub attributes: Attributes,
+}
+
+impl From<&ContentStyle> for crossterm::style::ContentStyle {
+    fn from(style: &ContentStyle) -> Self {
+        crossterm::style::ContentStyle {
+            foreground_color: style.has_fg.then(|| colors::downgrade(style.fg.into())),
+            background_color: style.has_bg.then(|| colors::downgrade(style.bg.into())),
+            underline_color: style
+                .has_underline_color
+                .then(|| colors::downgrade(style.underline_color.into())),
+            attributes: style.attributes.into(),
+        }
+    }
+}
+
+/// Sets the colors and attributes of `style` in one command.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `style` pointer points to an initialized [`ContentStyle`].
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_style_set(style: *const ContentStyle) -> libc::c_int {
+    catch_panic(|| {
+        if style.is_null() {
+            set_last_error(anyhow::anyhow!("Received null pointer for style"));
+            return -1;
+        }
+        queue!(
+            io!(),
+            colors::Sgr(crossterm::style::SetStyle((&*style).into()))
+        )
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Loads a theme of named styles from a UTF-8 JSON object, replacing the current theme.
+///
+/// Each name maps to a style object in which every field is optional, e.g.
+/// `{"error": {"fg": "red", "attributes": ["bold"]}, "highlight": {"bg": "#3a3a3a"}}`:
+///
+/// * `"fg"`, `"bg"` and `"underline_color"`: a color name (`"dark_red"`, or a name from the color
+///   database such as `"DodgerBlue1"`), a hex string `"#0087ff"`, an ANSI index `33` or an object
+///   `{"r": 0, "g": 135, "b": 255}`.
+/// * `"attributes"`: an array of [`Attribute`] names, e.g. `"bold"` or `"Underlined"`.
+///
+/// The theme is left unchanged if the JSON or any style is invalid. Apply a style with
+/// [`crossterm_style_apply_named`].
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `json` pointer points to a valid null-terminated string.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_theme_load_json(json: *const libc::c_char) -> libc::c_int {
+    catch_panic(|| {
+        convert_c_char_to_str(json, "json")
+            .and_then(theme::load)
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Sets the colors and attributes of the style named `name` in the theme loaded with
+/// [`crossterm_theme_load_json`], as [`crossterm_style_set`] does.
+///
+/// Returns -1 if the theme has no such style.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `name` pointer points to a valid null-terminated string.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_style_apply_named(name: *const libc::c_char) -> libc::c_int {
+    catch_panic(|| {
+        convert_c_char_to_str(name, "name")
+            .and_then(theme::get)
+            .and_then(|style| {
+                queue!(io!(), colors::Sgr(crossterm::style::SetStyle(style))).map_err(Into::into)
+            })
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Returns the escape sequence of `command` as a string, instead of writing it.
+fn ansi_string(command: impl crossterm::Command) -> *const libc::c_char {
+    let mut ansi = String::new();
+    match command.write_ansi(&mut ansi) {
+        Ok(()) => convert_string_to_c_char(ansi),
+        Err(_) => {
+            set_last_error(anyhow::anyhow!("Unable to write escape sequence"));
+            std::ptr::null()
+        }
+    }
+}
+
+/// Returns the escape sequence that moves the cursor to the given position (col, row).
+///
+/// # Notes
+/// * Top left cell is represented as `0,0`.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_ansi_move_to(col: u16, row: u16) -> *const libc::c_char {
+    catch_panic(|| ansi_string(crossterm::cursor::MoveTo(col, row)))
+}
+
+/// Returns the escape sequence that moves the cursor up `n` rows.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_ansi_move_up(n: u16) -> *const libc::c_char {
+    catch_panic(|| ansi_string(crossterm::cursor::MoveUp(n)))
+}
+
+/// Returns the escape sequence that moves the cursor down `n` rows.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_ansi_move_down(n: u16) -> *const libc::c_char {
+    catch_panic(|| ansi_string(crossterm::cursor::MoveDown(n)))
+}
+
+/// Returns the escape sequence that moves the cursor left `n` columns.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_ansi_move_left(n: u16) -> *const libc::c_char {
+    catch_panic(|| ansi_string(crossterm::cursor::MoveLeft(n)))
+}
+
+/// Returns the escape sequence that moves the cursor right `n` columns.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_ansi_move_right(n: u16) -> *const libc::c_char {
+    catch_panic(|| ansi_string(crossterm::cursor::MoveRight(n)))
+}
+
+/// Returns the escape sequence that hides the cursor.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_ansi_cursor_hide() -> *const libc::c_char {
+    catch_panic(|| ansi_string(crossterm::cursor::Hide))
+}
+
+/// Returns the escape sequence that shows the cursor.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_ansi_cursor_show() -> *const libc::c_char {
+    catch_panic(|| ansi_string(crossterm::cursor::Show))
+}
+
+/// Returns the escape sequence that sets the foreground color.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_ansi_set_fg(color: Color) -> *const libc::c_char {
+    catch_panic(|| {
+        ansi_string(crossterm::style::SetForegroundColor(colors::downgrade(
+            color.into(),
+        )))
+    })
+}
+
+/// Returns the escape sequence that sets the foreground color in RGB.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_ansi_set_fg_rgb(r: u8, g: u8, b: u8) -> *const libc::c_char {
+    catch_panic(|| crossterm_ansi_set_fg(Color::Rgb { r, g, b }))
+}
+
+/// Returns the escape sequence that sets the background color.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_ansi_set_bg(color: Color) -> *const libc::c_char {
+    catch_panic(|| {
+        ansi_string(crossterm::style::SetBackgroundColor(colors::downgrade(
+            color.into(),
+        )))
+    })
+}
+
+/// Returns the escape sequence that sets the background color in RGB.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_ansi_set_bg_rgb(r: u8, g: u8, b: u8) -> *const libc::c_char {
+    catch_panic(|| crossterm_ansi_set_bg(Color::Rgb { r, g, b }))
+}
+
+/// Returns the escape sequence that sets the underline color.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_ansi_set_underline_color(color: Color) -> *const libc::c_char {
+    catch_panic(|| {
+        ansi_string(crossterm::style::SetUnderlineColor(colors::downgrade(
+            color.into(),
+        )))
+    })
+}
+
+/// Returns the escape sequence that sets an attribute.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_ansi_attribute(attr: Attribute) -> *const libc::c_char {
+    catch_panic(|| ansi_string(colors::Sgr(crossterm::style::SetAttribute(attr.into()))))
+}
+
+/// Returns the escape sequence that resets the colors back to default.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_ansi_reset_color() -> *const libc::c_char {
+    catch_panic(|| ansi_string(colors::Sgr(crossterm::style::ResetColor)))
+}
+
+/// Returns the escape sequence that clears the terminal as `ct` says.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_ansi_clear(ct: ClearType) -> *const libc::c_char {
+    catch_panic(|| ansi_string(crossterm::terminal::Clear(ct.into())))
+}
+
+/// Returns the escape sequence that sets the colors and attributes of `style`, as
+/// [`crossterm_style_set`] writes it.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `style` pointer points to an initialized [`ContentStyle`].
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_ansi_style(style: *const ContentStyle) -> *const libc::c_char {
+    catch_panic(|| {
+        if style.is_null() {
+            set_last_error(anyhow::anyhow!("Received null pointer for style"));
+            return std::ptr::null();
+        }
+        ansi_string(colors::Sgr(crossterm::style::SetStyle((&*style).into())))
+    })
+}
+
+/// Parses text styled with SGR escape sequences, e.g. captured colored output of another process,
+/// and returns its spans of text with the same style as a UTF-8 JSON array, e.g.
+/// `[{"text": "error", "fg": {"ansi": 1}, "bg": null, "attrs": ["Bold"]}, ...]`.
+///
+/// Colors are `null` for the default color, `{"ansi": 33}` for indexed colors, or
+/// `{"r": 0, "g": 135, "b": 255}` for RGB colors. `attrs` are the names of the [`Attribute`]s that
+/// are set. Other escape sequences are removed from the text.
+///
+/// Returns null if an error occurred.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `s` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `s` won't be deallocated or modified for the duration of the function call.
+/// - The `s` pointer is correctly aligned and `s` points to an initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_ansi_parse(s: *const libc::c_char) -> *const libc::c_char {
+    catch_panic(|| match convert_c_char_to_str(s, "styled text") {
+        Ok(text) => convert_string_to_c_char(spans::parse(text).to_string()),
+        Err(err) => {
+            set_last_error(err);
+            std::ptr::null()
+        }
+    })
+}
+
+/// Returns colors as a UTF-8 JSON string.
+///
+/// Null character is stored in the last location of buffer.
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_colors() -> *const libc::c_char {
+    catch_panic(|| convert_string_to_c_char(COLORS.to_string()))
+}
+
+/// Returns colors as a UTF-8 JSON string in a [`Str`].
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_str_free`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_colors_s() -> Str {
+    catch_panic(|| convert_string_to_str(COLORS.to_string()))
+}
+
+/// Returns the colors matching `query` as a UTF-8 JSON array, in the same format as [`crossterm_colors`].
+///
+/// * An empty query matches every color.
+/// * A number matches the color with that ANSI index, e.g. `"33"`.
+/// * A query starting with `#` matches hex strings by prefix, e.g. `"#0087"`.
+/// * Anything else matches color names by case-insensitive substring, e.g. `"blue"`.
+///
+/// Returns null if an error occurred.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `query` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `query` won't be deallocated or modified for the duration of the function call.
+/// - The `query` pointer is correctly aligned and `query` points to an initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_colors_find(query: *const libc::c_char) -> *const libc::c_char {
+    catch_panic(|| {
+        convert_c_char_to_str(query, "colors query")
+            .and_then(colors::find)
+            .map(|entries| {
+                let array: Vec<&serde_json::Value> = entries.iter().map(|e| &e.json).collect();
+                Some(serde_json::json!(array).to_string())
+            })
+            .c_unwrap()
+            .map_or(std::ptr::null_mut(), convert_string_to_c_char)
+    })
+}
+
+/// Looks up a color of the color database by name, e.g. `"DodgerBlue1"`, ignoring case.
+///
+/// Its ANSI index is written to `out_index` and its RGB value to `out_r`, `out_g` and `out_b`.
+/// Names are not unique, and the color with the lowest index is used.
+/// Returns `-1` if there is no color with that name.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `name` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `name` won't be deallocated or modified for the duration of the function call.
+/// - The `name` pointer is correctly aligned and `name` points to an initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_color_by_name(
+    name: *const libc::c_char,
+    out_index: &mut u8,
+    out_r: &mut u8,
+    out_g: &mut u8,
+    out_b: &mut u8,
+) -> libc::c_int {
+    catch_panic(|| {
+        let entry = convert_c_char_to_str(name, "color name").and_then(colors::by_name);
+        match entry {
+            Ok(entry) => {
+                *out_index = entry.id;
+                (*out_r, *out_g, *out_b) = entry.rgb;
+                0
+            }
+            Err(err) => {
+                set_last_error(err);
+                -1
+            }
+        }
+    })
+}
+
+/// Looks up the color with the ANSI index `index` in the color database, and returns its name,
+/// e.g. `"DodgerBlue1"` for `33`.
+///
+/// Its RGB value is written to `out_r`, `out_g` and `out_b`.
+/// Returns null if an error occurred.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_color_by_index(
+    index: u8,
+    out_r: &mut u8,
+    out_g: &mut u8,
+    out_b: &mut u8,
+) -> *const libc::c_char {
+    catch_panic(|| match colors::by_index(index) {
+        Ok(entry) => {
+            (*out_r, *out_g, *out_b) = entry.rgb;
+            convert_string_to_c_char(entry.name.clone())
+        }
+        Err(err) => {
+            set_last_error(err);
+            std::ptr::null()
+        }
+    })
+}
+
+/// Returns the ANSI index of the 256 color palette color closest to an RGB color, for terminals
+/// without true color support, or `-1` if an error occurred.
+///
+/// Colors are compared by a perceptual distance ("redmean" weighted euclidean distance), with the
+/// palette's default RGB values.
+#[no_mangle]
+pub extern "C" fn crossterm_color_nearest_ansi256(r: u8, g: u8, b: u8) -> libc::c_int {
+    catch_panic(|| nearest_ansi((r, g, b), 256))
+}
+
+/// Returns the ANSI index of the 16 color palette color closest to an RGB color, for terminals
+/// with only the basic colors, or `-1` if an error occurred.
+///
+/// Colors are compared as for [`crossterm_color_nearest_ansi256`]. The 16 colors are themed by
+/// most terminals, so the result is only as good as the default palette matches the theme.
+#[no_mangle]
+pub extern "C" fn crossterm_color_nearest_ansi16(r: u8, g: u8, b: u8) -> libc::c_int {
+    catch_panic(|| nearest_ansi((r, g, b), 16))
+}
+
+fn nearest_ansi(rgb: colors::Rgb, limit: usize) -> libc::c_int {
+    match colors::nearest(rgb, limit) {
+        Ok(entry) => entry.id as libc::c_int,
+        Err(err) => {
+            set_last_error(err);
+            -1
+        }
+    }
+}
+
+/// Lightens an RGB color by adding `amount` (in `[0, 1]`) to its HSL lightness.
+///
+/// The result is written to `out_r`, `out_g` and `out_b`.
+#[no_mangle]
+pub extern "C" fn crossterm_color_lighten(
+    r: u8,
+    g: u8,
+    b: u8,
+    amount: f64,
+    out_r: &mut u8,
+    out_g: &mut u8,
+    out_b: &mut u8,
+) -> libc::c_int {
+    catch_panic(|| {
+        (*out_r, *out_g, *out_b) = colors::finite(amount, "amount")
+            .map(|amount| colors::lighten(r, g, b, amount))
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Darkens an RGB color by subtracting `amount` (in `[0, 1]`) from its HSL lightness.
+///
+/// The result is written to `out_r`, `out_g` and `out_b`.
+#[no_mangle]
+pub extern "C" fn crossterm_color_darken(
+    r: u8,
+    g: u8,
+    b: u8,
+    amount: f64,
+    out_r: &mut u8,
+    out_g: &mut u8,
+    out_b: &mut u8,
+) -> libc::c_int {
+    catch_panic(|| {
+        (*out_r, *out_g, *out_b) = colors::finite(amount, "amount")
+            .map(|amount| colors::lighten(r, g, b, -amount))
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Mixes two RGB colors, `t = 0` giving the first color and `t = 1` giving the second.
+///
+/// Channels are interpolated linearly. The result is written to `out_r`, `out_g` and `out_b`.
+#[no_mangle]
+pub extern "C" fn crossterm_color_mix(
+    r1: u8,
+    g1: u8,
+    b1: u8,
+    r2: u8,
+    g2: u8,
+    b2: u8,
+    t: f64,
+    out_r: &mut u8,
+    out_g: &mut u8,
+    out_b: &mut u8,
+) -> libc::c_int {
+    catch_panic(|| {
+        (*out_r, *out_g, *out_b) = colors::finite(t, "t")
+            .map(|t| colors::mix((r1, g1, b1), (r2, g2, b2), t))
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Converts an RGB color to HSL.
+///
+/// Hue is written to `h` in degrees `[0, 360)`, saturation and lightness to `s` and `l` in `[0, 1]`.
+#[no_mangle]
+pub extern "C" fn crossterm_color_rgb_to_hsl(
+    r: u8,
+    g: u8,
+    b: u8,
+    h: &mut f64,
+    s: &mut f64,
+    l: &mut f64,
+) -> libc::c_int {
+    catch_panic(|| {
+        (*h, *s, *l) = anyhow::Ok(colors::rgb_to_hsl(r, g, b)).c_unwrap();
+        r!()
+    })
+}
+
+/// Converts an HSL color to RGB.
+///
+/// Hue is in degrees and taken modulo 360, saturation and lightness are clamped to `[0, 1]`.
+/// The result is written to `r`, `g` and `b`.
+#[no_mangle]
+pub extern "C" fn crossterm_color_hsl_to_rgb(
+    h: f64,
+    s: f64,
+    l: f64,
+    r: &mut u8,
+    g: &mut u8,
+    b: &mut u8,
+) -> libc::c_int {
+    catch_panic(|| {
+        (*r, *g, *b) = colors::finite(h, "hue")
+            .and_then(|_| colors::finite(s, "saturation"))
+            .and_then(|_| colors::finite(l, "lightness"))
+            .map(|_| colors::hsl_to_rgb(h, s, l))
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Creates an RGB [`Color`] from HSL, e.g. to rotate the hue of a color from
+/// [`crossterm_color_to_hsl`].
+///
+/// Hue is in degrees and taken modulo 360, saturation and lightness are clamped to `[0, 1]`.
+/// The result is written to `color`.
+#[no_mangle]
+pub extern "C" fn crossterm_color_from_hsl(
+    h: f64,
+    s: f64,
+    l: f64,
+    color: &mut Color,
+) -> libc::c_int {
+    catch_panic(|| {
+        let (r, g, b) = colors::finite(h, "hue")
+            .and_then(|_| colors::finite(s, "saturation"))
+            .and_then(|_| colors::finite(l, "lightness"))
+            .map(|_| colors::hsl_to_rgb(h, s, l))
+            .c_unwrap();
+        *color = Color::Rgb { r, g, b };
+        r!()
+    })
+}
+
+/// Converts a [`Color`] to HSL.
+///
+/// Named and ANSI colors are converted from the default palette, which the terminal's theme may
+/// differ from. [`Color::Reset`] has no HSL value and is an error. Hue is written to `h` in
+/// degrees `[0, 360)`, saturation and lightness to `s` and `l` in `[0, 1]`.
+#[no_mangle]
+pub extern "C" fn crossterm_color_to_hsl(
+    color: Color,
+    h: &mut f64,
+    s: &mut f64,
+    l: &mut f64,
+) -> libc::c_int {
+    catch_panic(|| {
+        (*h, *s, *l) = colors::to_rgb(color.into())
+            .map(|(r, g, b)| colors::rgb_to_hsl(r, g, b))
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Creates an RGB [`Color`] from HSV.
+///
+/// Hue is in degrees and taken modulo 360, saturation and value are clamped to `[0, 1]`.
+/// The result is written to `color`.
+#[no_mangle]
+pub extern "C" fn crossterm_color_from_hsv(
+    h: f64,
+    s: f64,
+    v: f64,
+    color: &mut Color,
+) -> libc::c_int {
+    catch_panic(|| {
+        let (r, g, b) = colors::finite(h, "hue")
+            .and_then(|_| colors::finite(s, "saturation"))
+            .and_then(|_| colors::finite(v, "value"))
+            .map(|_| colors::hsv_to_rgb(h, s, v))
+            .c_unwrap();
+        *color = Color::Rgb { r, g, b };
+        r!()
+    })
+}
+
+/// Converts a [`Color`] to HSV.
+///
+/// Colors are converted as for [`crossterm_color_to_hsl`]. Hue is written to `h` in degrees
+/// `[0, 360)`, saturation and value to `s` and `v` in `[0, 1]`.
+#[no_mangle]
+pub extern "C" fn crossterm_color_to_hsv(
+    color: Color,
+    h: &mut f64,
+    s: &mut f64,
+    v: &mut f64,
+) -> libc::c_int {
+    catch_panic(|| {
+        (*h, *s, *v) = colors::to_rgb(color.into())
+            .map(|(r, g, b)| colors::rgb_to_hsv(r, g, b))
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Generates `count` colors derived from a base RGB color and returns them as a UTF-8 JSON array.
+///
+/// Each color is an object `{"r": 0, "g": 135, "b": 255}`. Colors are quantized to the color depth
+/// detected from the `COLORTERM` and `TERM` environment variables; quantized colors also have an
+/// `"ansi"` field with their ANSI color index.
+///
+/// See [`PaletteScheme`] for the available schemes. Returns null if an error occurred.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_palette_generate(
+    r: u8,
+    g: u8,
+    b: u8,
+    count: u32,
+    scheme: PaletteScheme,
+) -> *const libc::c_char {
+    catch_panic(|| {
+        let depth = capabilities::color_support(false);
+        let palette: anyhow::Result<Vec<serde_json::Value>> =
+            colors::palette((r, g, b), count as usize, scheme)
+                .into_iter()
+                .map(|rgb| {
+                    let ((r, g, b), ansi) = colors::quantize(rgb, depth)?;
+                    let mut color = serde_json::json!({ "r": r, "g": g, "b": b });
+                    if let Some(ansi) = ansi {
+                        color["ansi"] = ansi.into();
+                    }
+                    Ok(color)
+                })
+                .collect();
+        match palette {
+            Ok(palette) => convert_string_to_c_char(serde_json::json!(palette).to_string()),
+            Err(err) => {
+                set_last_error(err);
+                std::ptr::null()
+            }
+        }
+    })
+}
+
+/// Queries the terminal's 16 color theme and returns it as a UTF-8 JSON array.
+///
+/// Each entry is an object `{"index": 1, "r": 205, "g": 0, "b": 0, "source": "terminal"}`.
+/// Palette entries are queried with OSC 4; entries the terminal doesn't report (or all of them, if
+/// the terminal doesn't answer in time or the platform doesn't support queries) fall back to the
+/// default ANSI colors and have `"source": "default"`.
+///
+/// Returns null if an error occurred.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_theme() -> *const libc::c_char {
+    catch_panic(|| {
+        let indices: Vec<u8> = (0..16).collect();
+        let reported =
+            query::palette_colors(&indices, query::DEFAULT_TIMEOUT).unwrap_or_else(|e| {
+                trace!("Unable to query terminal theme: {:#}", e);
+                Vec::new()
+            });
+        let theme: anyhow::Result<Vec<serde_json::Value>> = indices
+            .iter()
+            .map(|&index| {
+                let (rgb, source) = match reported.iter().find(|(i, _)| *i == index) {
+                    Some((_, rgb)) => (*rgb, "terminal"),
+                    None => {
+                        let entry = colors::database()?
+                            .iter()
+                            .find(|e| e.id == index)
+                            .ok_or_else(|| {
+                                anyhow::anyhow!("Color {} missing from database", index)
+                            })?;
+                        (entry.rgb, "default")
+                    }
+                };
+                Ok(serde_json::json!({
+                    "index": index,
+                    "r": rgb.0,
+                    "g": rgb.1,
+                    "b": rgb.2,
+                    "source": source,
+                }))
+            })
+            .collect();
+        match theme {
+            Ok(theme) => convert_string_to_c_char(serde_json::json!(theme).to_string()),
+            Err(err) => {
+                set_last_error(err);
+                std::ptr::null()
+            }
+        }
+    })
+}
+
+/// Tells whether the terminal supports [OSC 8 hyperlinks](https://gist.github.com/egmontkob/eb114294efbcd5adb1944c9f3cb5feaf).
+///
+/// The decision is based on `FORCE_HYPERLINK`, `TERM_PROGRAM`, `VTE_VERSION` and similar
+/// environment variables, and, if those are not conclusive, on the terminal name reported in
+/// reply to an XTVERSION query. When this returns `false`, print the URL inline instead.
+///
+/// Set `FORCE_HYPERLINK=1` (or `0`) to override the detection.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_supports_hyperlinks() -> bool {
+    catch_panic(capabilities::supports_hyperlinks)
+}
+
+/// Tells how many colors the terminal can display.
+///
+/// The decision is based on the `COLORTERM`, `TERM`, `TERM_PROGRAM` and `WT_SESSION` environment
+/// variables and the `colors` capability of the terminfo entry for `TERM`. With `query`, the
+/// terminal is also asked for its `RGB` and `colors` capabilities with XTGETTCAP, which costs a
+/// round-trip (up to 200ms if the terminal doesn't answer) and is only done on Unix.
+///
+/// `NO_COLOR` is not considered; see [`crossterm_colors_enabled`].
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_color_support(query: bool) -> ColorDepth {
+    catch_panic(|| capabilities::color_support(query))
+}
+
+/// Queries the terminal's background color with OSC 11, waiting up to `timeout_ms` milliseconds
+/// for the reply.
+///
+/// The color is written to `r`, `g` and `b`. Returns -1 with an error starting with
+/// `Unsupported` if the terminal did not report it.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_background_color(
+    timeout_ms: u32,
+    r: &mut u8,
+    g: &mut u8,
+    b: &mut u8,
+) -> libc::c_int {
+    catch_panic(|| {
+        (*r, *g, *b) =
+            query::dynamic_color(11, std::time::Duration::from_millis(timeout_ms as u64))
+                .c_unwrap();
+        r!()
+    })
+}
+
+/// Tells whether the terminal has a dark background, to pick a default theme.
+///
+/// The background color is queried as with [`crossterm_terminal_background_color`], and is dark
+/// if white text has more contrast on it than black text, by their WCAG relative luminance. If the
+/// terminal doesn't report it, the `COLORFGBG` environment variable that some terminals set is
+/// used instead.
+///
+/// Returns 1 for a dark background, 0 for a light one, and -1 if it couldn't be determined.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_is_dark() -> libc::c_int {
+    catch_panic(|| match capabilities::is_dark() {
+        Ok(dark) => dark as libc::c_int,
+        Err(err) => {
+            set_last_error(err);
+            -1
+        }
+    })
+}
+
+/// Resets the default foreground, background and cursor colors (OSC 110, 111 and 112).
+const RESET_DEFAULT_COLORS: &str = "\x1b]110\x1b\\\x1b]111\x1b\\\x1b]112\x1b\\";
+
+/// Sets the dynamic color `code` (OSC 10, 11 or 12).
+fn set_default_color(code: u8, r: u8, g: u8, b: u8) -> libc::c_int {
+    let sequence = format!("\x1b]{};rgb:{:02x}/{:02x}/{:02x}\x1b\\", code, r, g, b);
+    queue!(io!(), crossterm::style::Print(sequence)).c_unwrap();
+    state::record(r!(), |m| m.default_colors_changed = true);
+    r!()
+}
+
+/// Sets the terminal's default foreground color, used for text without a foreground color, with
+/// OSC 10.
+///
+/// Unlike [`crossterm_style_foreground_color_rgb`], this changes the color of all text on the
+/// screen that has the default color, including text written before. Restore the terminal's own
+/// colors with [`crossterm_terminal_reset_default_colors`]; they are also restored when the
+/// process exits after [`crossterm_register_atexit_cleanup`].
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_set_default_fg_color(r: u8, g: u8, b: u8) -> libc::c_int {
+    catch_panic(|| set_default_color(10, r, g, b))
+}
+
+/// Sets the terminal's default background color with OSC 11. See
+/// [`crossterm_terminal_set_default_fg_color`].
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_set_default_bg_color(r: u8, g: u8, b: u8) -> libc::c_int {
+    catch_panic(|| set_default_color(11, r, g, b))
+}
+
+/// Sets the terminal's cursor color with OSC 12. See [`crossterm_terminal_set_default_fg_color`].
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_set_default_cursor_color(r: u8, g: u8, b: u8) -> libc::c_int {
+    catch_panic(|| set_default_color(12, r, g, b))
+}
+
+/// Restores the terminal's own default foreground, background and cursor colors with OSC 110, 111
+/// and 112, after they were changed with [`crossterm_terminal_set_default_fg_color`],
+/// [`crossterm_terminal_set_default_bg_color`] or [`crossterm_terminal_set_default_cursor_color`].
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_reset_default_colors() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::style::Print(RESET_DEFAULT_COLORS)).c_unwrap();
+        state::record(r!(), |m| m.default_colors_changed = false);
+        r!()
+    })
+}
+
+/// Resets all palette entries (OSC 104).
+const RESET_PALETTE: &str = "\x1b]104\x1b\\";
+
+/// Sets entry `index` of the terminal's 256 color palette with OSC 4, e.g. to remap the 16 ANSI
+/// colors to a theme for the duration of the session.
+///
+/// Text on the screen that uses the entry changes color too. Restore the terminal's own palette
+/// with [`crossterm_terminal_reset_palette`]; it is also restored when the process exits after
+/// [`crossterm_register_atexit_cleanup`].
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_set_palette_color(
+    index: u8,
+    r: u8,
+    g: u8,
+    b: u8,
+) -> libc::c_int {
+    catch_panic(|| {
+        let sequence = format!("\x1b]4;{};rgb:{:02x}/{:02x}/{:02x}\x1b\\", index, r, g, b);
+        queue!(io!(), crossterm::style::Print(sequence)).c_unwrap();
+        state::record(r!(), |m| m.palette_changed = true);
+        r!()
+    })
+}
+
+/// Restores the terminal's own palette with OSC 104, after entries were changed with
+/// [`crossterm_terminal_set_palette_color`].
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_reset_palette() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::style::Print(RESET_PALETTE)).c_unwrap();
+        state::record(r!(), |m| m.palette_changed = false);
+        r!()
+    })
+}
+
+/// Queries entry `index` of the terminal's 256 color palette with OSC 4, waiting up to
+/// `timeout_ms` milliseconds for the reply, e.g. to blend colors against the user's actual theme.
+///
+/// The color is written to `r`, `g` and `b`. Returns -1 with an error starting with
+/// `Unsupported` if the terminal did not report it. See [`crossterm_terminal_theme`] to query the
+/// 16 ANSI colors at once.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_get_palette_color(
+    index: u8,
+    timeout_ms: u32,
+    r: &mut u8,
+    g: &mut u8,
+    b: &mut u8,
+) -> libc::c_int {
+    catch_panic(|| {
+        (*r, *g, *b) = query::palette_colors(
+            &[index],
+            std::time::Duration::from_millis(timeout_ms as u64),
+        )
+        .and_then(|reported| {
+            reported
+                .into_iter()
+                .find_map(|(i, rgb)| (i == index).then_some(rgb))
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Unsupported: terminal did not report palette color {}",
+                        index
+                    )
+                })
+        })
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Marks the start of a prompt for shell integration
+///
+/// Shell integration marks are the FinalTerm `OSC 133` sequences that shells emit around prompts
+/// and commands. Terminals that understand them (e.g. iTerm2, kitty, WezTerm, VS Code, Windows
+/// Terminal) offer features like jumping between prompts, selecting a command's output and timing
+/// commands. REPLs emit them in this order:
+///
+/// 1. [`crossterm_shell_mark_prompt_start`] before printing the prompt,
+/// 2. [`crossterm_shell_mark_command_start`] after the prompt, where the user's input begins,
+/// 3. [`crossterm_shell_mark_command_executed`] once the input is accepted, before any output,
+/// 4. [`crossterm_shell_mark_command_end`] with the exit code once the command finished.
+#[no_mangle]
+pub extern "C" fn crossterm_shell_mark_prompt_start() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::style::Print("\x1b]133;A\x1b\\")).c_unwrap();
+        r!()
+    })
+}
+
+/// Marks the end of the prompt and the start of the command input for shell integration. See
+/// [`crossterm_shell_mark_prompt_start`].
+#[no_mangle]
+pub extern "C" fn crossterm_shell_mark_command_start() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::style::Print("\x1b]133;B\x1b\\")).c_unwrap();
+        r!()
+    })
+}
+
+/// Marks the start of the command output for shell integration. See
+/// [`crossterm_shell_mark_prompt_start`].
+#[no_mangle]
+pub extern "C" fn crossterm_shell_mark_command_executed() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::style::Print("\x1b]133;C\x1b\\")).c_unwrap();
+        r!()
+    })
+}
+
+/// Marks the end of a command and its exit code for shell integration. A negative `exit_code`
+/// leaves the exit code out. See [`crossterm_shell_mark_prompt_start`].
+#[no_mangle]
+pub extern "C" fn crossterm_shell_mark_command_end(exit_code: libc::c_int) -> libc::c_int {
+    catch_panic(|| {
+        let sequence = if exit_code < 0 {
+            "\x1b]133;D\x1b\\".to_string()
+        } else {
+            format!("\x1b]133;D;{}\x1b\\", exit_code)
+        };
+        queue!(io!(), crossterm::style::Print(sequence)).c_unwrap();
+        r!()
+    })
+}
+
+/// Same as [`crossterm_shell_mark_prompt_start`].
+#[no_mangle]
+pub extern "C" fn crossterm_mark_prompt_start() -> libc::c_int {
+    catch_panic(|| crossterm_shell_mark_prompt_start())
+}
+
+/// Same as [`crossterm_shell_mark_command_start`].
+#[no_mangle]
+pub extern "C" fn crossterm_mark_command_start() -> libc::c_int {
+    catch_panic(|| crossterm_shell_mark_command_start())
+}
+
+/// Same as [`crossterm_shell_mark_command_executed`].
+#[no_mangle]
+pub extern "C" fn crossterm_mark_command_executed() -> libc::c_int {
+    catch_panic(|| crossterm_shell_mark_command_executed())
+}
+
+/// Same as [`crossterm_shell_mark_command_end`].
+#[no_mangle]
+pub extern "C" fn crossterm_mark_command_end(exit_code: libc::c_int) -> libc::c_int {
+    catch_panic(|| crossterm_shell_mark_command_end(exit_code))
+}
+
+/// Print a hyperlink to a file, such as the location in a compiler diagnostic
+///
+/// The link is an [OSC 8 hyperlink](https://gist.github.com/egmontkob/eb114294efbcd5adb1944c9f3cb5feaf)
+/// to `file://host/path`, with `path` made absolute against the current directory and
+/// percent-encoded. A non-zero `line` is added as the fragment (`#line`, or `#line:column` with a
+/// non-zero `column`), which terminals that open files in an editor pass on to it.
+///
+/// `text` is the text shown for the link. If it is null, `path:line:column` is shown instead
+/// (leaving out a zero `line` or `column`). Terminals without hyperlink support show the text only.
+///
+/// # Safety
+///
+/// This function takes raw pointers as arguments. As such, the caller must ensure that:
+/// - The `path` pointer points to a valid null-terminated string, and `text` is null or points to a valid null-terminated string.
+/// - This function borrows slices to valid null-terminated strings and the memory referenced by `path` and `text` won't be deallocated or modified for the duration of the function call.
+/// - The pointers are correctly aligned and point to initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_style_print_file_link(
+    path: *const libc::c_char,
+    line: u32,
+    column: u32,
+    text: *const libc::c_char,
+) -> libc::c_int {
+    catch_panic(|| {
+        let text = if text.is_null() {
+            Ok(None)
+        } else {
+            convert_c_char_to_str(text, "file link text").map(Some)
+        };
+        text.and_then(|text| {
+            let path = convert_c_char_to_str(path, "file link path")?;
+            let link = links::file_link(path, line, column, text)?;
+            Ok(queue!(io!(), crossterm::style::Print(link))?)
+        })
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Copies a UTF-8 string to the system clipboard.
+///
+/// The native clipboard is used when available (Win32 on Windows, `pbcopy` on macOS, `wl-copy`,
+/// `xclip` or `xsel` on other platforms). In SSH sessions, or if there is no native clipboard, the
+/// text is sent to the terminal's clipboard with OSC 52 instead, which is written and flushed
+/// immediately.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `text` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `text` won't be deallocated or modified for the duration of the function call.
+/// - The `text` pointer is correctly aligned and `text` points to an initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_clipboard_copy(text: *const libc::c_char) -> libc::c_int {
+    catch_panic(|| {
+        convert_c_char_to_str(text, "clipboard text")
+            .and_then(|text| clipboard::copy(text, &mut io!()))
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Returns the text on the system clipboard as a UTF-8 string.
+///
+/// The clipboard is selected as in [`crossterm_clipboard_copy`]. Reading the terminal's clipboard
+/// with OSC 52 requires a terminal that allows it; many don't, or ask the user first.
+///
+/// Returns null if an error occurred.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_clipboard_paste() -> *const libc::c_char {
+    catch_panic(|| match clipboard::paste() {
+        Ok(text) => convert_string_to_c_char(text),
+        Err(err) => {
+            set_last_error(err);
+            std::ptr::null()
+        }
+    })
+}
+
+/// Returns the bytes on the terminal's clipboard, read with OSC 52, and stores their number in
+/// `len`.
+///
+/// Unlike [`crossterm_clipboard_paste`], the native clipboard is never used, and the bytes are
+/// returned as the terminal sent them, which need not be UTF-8. The function waits up to
+/// `timeout_ms` milliseconds for the reply, and terminals that ask the user before sharing the
+/// clipboard need a generous timeout. The wait only ends early when the reply arrives, so a
+/// terminal that doesn't support reading the clipboard costs the full timeout. A reply that
+/// arrives after the timeout is read as key events.
+///
+/// The bytes are followed by a null character that is not counted in `len`.
+/// Caller is responsible for memory associated with the returned buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+///
+/// Returns null if an error occurred, with an error starting with `Unsupported` if the terminal
+/// did not return the clipboard.
+#[no_mangle]
+pub extern "C" fn crossterm_clipboard_get(timeout_ms: u32, len: &mut usize) -> *mut u8 {
+    catch_panic(|| {
+        match clipboard::terminal_paste(std::time::Duration::from_millis(timeout_ms as u64)) {
+            Ok(bytes) => convert_bytes_to_c(&bytes, len),
+            Err(err) => {
+                set_last_error(err);
+                std::ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Returns everything the library believes it has enabled as a UTF-8 JSON object, e.g.
+///
+/// ```json
+/// {"raw_mode": true, "raw_mode_references": 1, "alternate_screen": true, "mouse_capture": false,
+///  "bracketed_paste": false, "focus_change": false, "keyboard_enhancement_flags": 1,
+///  "keyboard_enhancement_stack": [1], "cursor_hidden": true, "cursor_hide_tokens": 0,
+///  "line_wrap": true, "synchronized_update": false, "scroll_region": null,
+///  "mouse_capture_mode": null, "mouse_pixels": false, "line_drawing": false, "tui": null, "default_colors_changed": false, "palette_changed": false}
+/// ```
+///
+/// `mouse_capture_mode` is the name of the [`MouseCaptureMode`] if mouse capture was enabled with
+/// one, and `tui` the [`TuiFlags`] of the modes entered by [`crossterm_tui_enter`].
+///
+/// The state is tracked from the commands queued through this library (other than `raw_mode`,
+/// which is read from the terminal), so it doesn't reflect sequences written by other means.
+///
+/// Returns null if an error occurred.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+#[no_mangle]
+pub extern "C" fn crossterm_state() -> *const libc::c_char {
+    catch_panic(|| match state::to_json() {
+        Ok(state) => convert_string_to_c_char(state.to_string()),
+        Err(err) => {
+            set_last_error(err);
+            std::ptr::null()
+        }
+    })
+}
+
+/// Tells whether the raw mode is enabled.
+///
+/// Check error message to see if this function failed
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_is_raw_mode_enabled() -> bool {
+    catch_panic(|| crossterm::terminal::is_raw_mode_enabled().c_unwrap())
+}
+
+/// Disables raw mode.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_disable_raw_mode() -> libc::c_int {
+    catch_panic(|| {
+        crossterm::terminal::disable_raw_mode().c_unwrap();
+        r!()
+    })
+}
+
+/// Enables raw mode.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_enable_raw_mode() -> libc::c_int {
+    catch_panic(|| {
+        crossterm::terminal::enable_raw_mode().c_unwrap();
+        r!()
+    })
+}
+
+/// Acquires a reference to raw mode, enabling it if this is the first reference.
+///
+/// Raw mode stays enabled until every reference is released with
+/// [`crossterm_terminal_raw_mode_release`], so a component and its host application can both
+/// require raw mode. If raw mode was already enabled (e.g. with
+/// [`crossterm_terminal_enable_raw_mode`]) when the first reference was acquired, releasing the
+/// last reference leaves it enabled.
+///
+/// Returns the number of references after acquiring, or `-1` if an error occurred.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_raw_mode_acquire() -> libc::c_int {
+    catch_panic(|| {
+        let count = state::raw_mode().acquire().c_unwrap();
+        if crossterm_has_error() {
+            r!()
+        } else {
+            count as libc::c_int
+        }
+    })
+}
+
+/// Releases a reference acquired with [`crossterm_terminal_raw_mode_acquire`], disabling raw mode
+/// if it was the last one.
+///
+/// Returns the number of references left, or `-1` if an error occurred.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_raw_mode_release() -> libc::c_int {
+    catch_panic(|| {
+        let count = state::raw_mode().release().c_unwrap();
+        if crossterm_has_error() {
+            r!()
+        } else {
+            count as libc::c_int
+        }
+    })
+}
+
+/// Get terminal size
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_size(width: &mut u16, height: &mut u16) -> libc::c_int {
+    catch_panic(|| {
+        let (w, h) = crossterm::terminal::size().c_unwrap();
+        *width = w;
+        *height = h;
+        r!()
+    })
+}
+
+/// Get terminal size in cells and in pixels, e.g. to work out the pixel size of a cell for
+/// rendering images.
+///
+/// Terminals that don't report their pixel size have a `width_px` and `height_px` of 0. Returns -1
+/// if an error occurred, and on Windows.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_window_size(
+    cols: &mut u16,
+    rows: &mut u16,
+    width_px: &mut u16,
+    height_px: &mut u16,
+) -> libc::c_int {
+    catch_panic(|| {
+        (*cols, *rows, *width_px, *height_px) = crossterm::terminal::window_size()
+            .map(|size| (size.columns, size.rows, size.width, size.height))
+            .c_unwrap();
+        r!()
+    })
+}
+
+/// Get the size of a cell in pixels, e.g. to scale images to whole cells with a graphics protocol.
+///
+/// The size is worked out from [`crossterm_terminal_window_size`], or if the terminal doesn't
+/// report its size in pixels that way, queried with XTWINOPS 16 (`CSI 16 t`). Returns -1 with an
+/// error starting with `Unsupported` if neither works.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_cell_size(
+    width_px: &mut u16,
+    height_px: &mut u16,
+) -> libc::c_int {
+    catch_panic(|| {
+        (*width_px, *height_px) = cell_size().c_unwrap();
+        r!()
+    })
+}
+
+/// The size of the window in pixels and cells, if the terminal reports it in pixels.
+fn window_pixel_grid() -> Option<events::PixelGrid> {
+    crossterm::terminal::window_size()
+        .ok()
+        .filter(|size| size.columns > 0 && size.rows > 0 && size.width > 0 && size.height > 0)
+        .map(|size| events::PixelGrid {
+            width: size.width,
+            height: size.height,
+            columns: size.columns,
+            rows: size.rows,
+        })
+}
+
+/// The size of the window in pixels and cells, from the window size or else from the size of a
+/// cell queried with XTWINOPS 16.
+fn pixel_grid() -> anyhow::Result<events::PixelGrid> {
+    if let Some(grid) = window_pixel_grid() {
+        return Ok(grid);
+    }
+    let (cell_width, cell_height) = query::cell_size(query::DEFAULT_TIMEOUT)?;
+    let (columns, rows) = crossterm::terminal::size()?;
+    Ok(events::PixelGrid {
+        width: cell_width.saturating_mul(columns),
+        height: cell_height.saturating_mul(rows),
+        columns,
+        rows,
+    })
+}
+
+/// The size of a cell in pixels, from the window size or else queried with XTWINOPS 16.
+fn cell_size() -> anyhow::Result<(u16, u16)> {
+    match window_pixel_grid() {
+        Some(grid) => Ok((grid.width / grid.columns, grid.height / grid.rows)),
+        None => query::cell_size(query::DEFAULT_TIMEOUT),
+    }
+}
+
+/// Sets the terminal buffer size `(cols, rows)`.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_size_set(cols: u16, rows: u16) -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::terminal::SetSize(cols, rows)).c_unwrap();
+        r!()
+    })
+}
+
+/// Disables line wrapping.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_disable_line_wrap() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::terminal::DisableLineWrap).c_unwrap();
+        state::record(r!(), |m| m.line_wrap_disabled = true);
+        r!()
+    })
+}
+
+/// Enables line wrapping.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_enable_line_wrap() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::terminal::EnableLineWrap).c_unwrap();
+        state::record(r!(), |m| m.line_wrap_disabled = false);
+        r!()
+    })
+}
+
+/// Enters alternate screen.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_enter_alternate_screen() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::terminal::EnterAlternateScreen).c_unwrap();
+        state::record(r!(), |m| m.alternate_screen = true);
+        r!()
+    })
+}
+
+/// Leaves alternate screen.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_leave_alternate_screen() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::terminal::LeaveAlternateScreen).c_unwrap();
+        state::record(r!(), |m| m.alternate_screen = false);
+        r!()
+    })
+}
+
+/// Suspends the process, as Ctrl-Z does in a shell, e.g. after reading a `Signal` event for
+/// `SIGTSTP` (see [`crossterm_event_enable_signals`]) or Ctrl-Z in raw mode.
+///
+/// The terminal is restored first: the modes set through this library (keyboard enhancement,
+/// focus change, bracketed paste, mouse pixels, mouse capture, line drawing, hidden cursor,
+/// disabled line wrap, alternate screen) are left, and raw mode is disabled. Then `SIGTSTP` is
+/// sent to the process group, which stops it.
+/// Once the process continues, the modes are set again and a `Resumed` event is queued, e.g.
+/// `"Resumed"` in schema version 1, as a cue to redraw the screen.
+///
+/// Returns `-1` if an error occurred, and on Windows.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_suspend() -> libc::c_int {
+    catch_panic(|| {
+        suspend::suspend().c_unwrap();
+        r!()
+    })
+}
+
+/// Restores the terminal when the process exits, even if the application exits without tearing
+/// down, e.g. on an uncaught exception in the host language.
+///
+/// An `atexit` handler, and on Unix handlers for `SIGHUP`, `SIGINT`, `SIGQUIT` and `SIGTERM` if
+/// they have their default action, leave the modes set through this library (alternate screen,
+/// hidden cursor, mouse capture, default colors, palette, ...) and disable raw mode. The signal
+/// handlers then terminate the process as the signal would have. Calling this function again has
+/// no effect.
+///
+/// Returns `-1` if an error occurred, e.g. there is no terminal to restore.
+#[no_mangle]
+pub extern "C" fn crossterm_register_atexit_cleanup() -> libc::c_int {
+    catch_panic(|| {
+        cleanup::register().c_unwrap();
+        r!()
+    })
+}
+
+/// Terminal modes set up by [`crossterm_tui_enter`].
+#[repr(C)]
+pub enum TuiFlags {
+    /// Raw mode, taken as a reference with [`crossterm_terminal_raw_mode_acquire`].
+    RawMode = 0b0000_0001,
+    /// The alternate screen.
+    AlternateScreen = 0b0000_0010,
+    /// A hidden cursor.
+    HideCursor = 0b0000_0100,
+    /// Mouse capture.
+    MouseCapture = 0b0000_1000,
+    /// Bracketed paste.
+    BracketedPaste = 0b0001_0000,
+    /// Focus change events.
+    FocusChange = 0b0010_0000,
+}
+
+type TuiStep = (
+    u8,
+    extern "C" fn() -> libc::c_int,
+    extern "C" fn() -> libc::c_int,
+);
+
+/// How each mode of [`TuiFlags`] is entered and exited, in the order they are entered.
+const TUI_STEPS: [TuiStep; 6] = [
+    (
+        TuiFlags::RawMode as u8,
+        crossterm_terminal_raw_mode_acquire,
+        crossterm_terminal_raw_mode_release,
+    ),
+    (
+        TuiFlags::AlternateScreen as u8,
+        crossterm_terminal_enter_alternate_screen,
+        crossterm_terminal_leave_alternate_screen,
+    ),
+    (
+        TuiFlags::HideCursor as u8,
+        crossterm_cursor_hide,
+        crossterm_cursor_show,
+    ),
+    (
+        TuiFlags::MouseCapture as u8,
+        crossterm_event_enable_mouse_capture,
+        crossterm_event_disable_mouse_capture,
+    ),
+    (
+        TuiFlags::BracketedPaste as u8,
+        crossterm_event_enable_bracketed_paste,
+        crossterm_event_disable_bracketed_paste,
+    ),
+    (
+        TuiFlags::FocusChange as u8,
+        crossterm_event_enable_focus_change,
+        crossterm_event_disable_focus_change,
+    ),
+];
+
+/// Turns the return code of an exported function into its error, for a negative code.
+fn check(rc: libc::c_int) -> anyhow::Result<()> {
+    if rc >= 0 {
+        return Ok(());
+    }
+    Err(take_last_error().unwrap_or_else(|| anyhow::anyhow!("Failed with return code {}", rc)))
+}
+
+/// Exits the modes in `entered` in reverse order, returning the first error.
+fn tui_exit(entered: u8) -> anyhow::Result<()> {
+    let mut result = Ok(());
+    for (flag, _, exit) in TUI_STEPS.iter().rev() {
+        if entered & flag != 0 {
+            result = result.and(check(exit()));
+        }
+    }
+    result.and(flush())
+}
+
+/// Sets up the terminal for a full-screen application: enters the modes in `flags`, a combination
+/// of [`TuiFlags`] values, in the order raw mode, alternate screen, hidden cursor, mouse capture,
+/// bracketed paste and focus change, and flushes the output.
+///
+/// If a mode fails to be entered, the modes entered before it are exited again. Use
+/// [`crossterm_tui_exit`] to exit exactly the modes that were entered.
+///
+/// Returns `-1` if an error occurred, or the terminal was already set up with this function.
+#[no_mangle]
+pub extern "C" fn crossterm_tui_enter(flags: u8) -> libc::c_int {
+    catch_panic(|| {
+        let known = TUI_STEPS.iter().fold(0, |known, (flag, ..)| known | flag);
+        let result = if flags & !known != 0 {
+            Err(anyhow::anyhow!(
+                "Received unknown TUI flag bits {:#010b}",
+                flags
+            ))
+        } else if state::modes().tui.is_some() {
+            Err(anyhow::anyhow!("The TUI was already entered"))
+        } else {
+            let mut entered = 0;
+            TUI_STEPS
+                .iter()
+                .filter(|(flag, ..)| flags & flag != 0)
+                .try_for_each(|(flag, enter, _)| {
+                    check(enter())?;
+                    entered |= flag;
+                    Ok(())
+                })
+                .and_then(|_| flush())
+                .map(|_| state::modes().tui = Some(entered))
+                .inspect_err(|_| {
+                    let _ = tui_exit(entered);
+                })
+        };
+        result.c_unwrap();
+        r!()
+    })
+}
+
+/// Restores the terminal set up with [`crossterm_tui_enter`], exiting the modes it entered in
+/// reverse order, and flushes the output.
+///
+/// Returns `-1` if an error occurred, or the terminal was not set up with
+/// [`crossterm_tui_enter`].
+#[no_mangle]
+pub extern "C" fn crossterm_tui_exit() -> libc::c_int {
+    catch_panic(|| {
+        let entered = state::modes().tui.take();
+        match entered {
+            Some(entered) => tui_exit(entered),
+            None => Err(anyhow::anyhow!("The TUI was not entered")),
+        }
+        .c_unwrap();
+        r!()
+    })
+}
+
+/// Leaves every mode that could have been set, whether or not this library set it, and forgets
+/// the modes it tracked.
+fn reset_all() -> anyhow::Result<()> {
+    use crossterm::{cursor, event, style, terminal};
+
+    let modes = std::mem::take(&mut *state::modes());
+    state::cursor_hidden().clear();
+    state::raw_mode().clear();
+    let pops = modes.keyboard_enhancement_flags.len().max(1);
+    let mut out = io!();
+    queue!(out, style::Print(format!("\x1b[<{}u", pops)))?;
+    queue!(
+        out,
+        event::DisableFocusChange,
+        event::DisableBracketedPaste,
+        style::Print(RESET_MOUSE_PIXELS),
+        event::DisableMouseCapture,
+        terminal::EndSynchronizedUpdate,
+        style::SetAttribute(style::Attribute::Reset),
+        style::Print(END_LINE_DRAWING),
+        cursor::Show,
+        terminal::EnableLineWrap,
+        terminal::LeaveAlternateScreen,
+    )?;
+    if modes.scroll_region.is_some() {
+        queue!(out, style::Print("\x1b[r"))?;
+    }
+    if modes.default_colors_changed {
+        queue!(out, style::Print(RESET_DEFAULT_COLORS))?;
+    }
+    if modes.palette_changed {
+        queue!(out, style::Print(RESET_PALETTE))?;
+    }
+    // flushed even while flushing is held back for an unfocused terminal
+    let flushed = out.flush();
+    drop(out);
+    let disabled = terminal::disable_raw_mode();
+    flushed?;
+    disabled?;
+    Ok(())
+}
+
+/// Best-effort restoring of the terminal in one call, e.g. from a `finally` block or a signal
+/// handler of the host language.
+///
+/// Disables raw mode, leaves the alternate screen, disables mouse capture, bracketed paste and
+/// focus change events, pops keyboard enhancement flags, resets colors and attributes, shows the
+/// cursor, and flushes the output. These modes are left even if they were not set through this
+/// library, and what the library tracked (raw mode references, cursor hide tokens, the state of
+/// [`crossterm_tui_enter`], ...) is forgotten. Modes only this library sets, such as the default
+/// colors and palette, are reset if they were set.
+///
+/// Returns `-1` if an error occurred, e.g. there is no terminal.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_reset_all() -> libc::c_int {
+    catch_panic(|| {
+        reset_all().c_unwrap();
+        r!()
+    })
+}
+
+/// Different ways to clear the terminal buffer.
+#[repr(C)]
+pub enum ClearType {
+    /// All cells.
+    All,
+    /// All plus history
+    Purge,
+    /// All cells from the cursor position downwards.
+    FromCursorDown,
+    /// All cells from the cursor position upwards.
+    FromCursorUp,
+    /// All cells at the cursor row.
+    CurrentLine,
+    /// All cells from the cursor position until the new line.
+    UntilNewLine,
+}
+
+impl From<ClearType> for crossterm::terminal::ClearType {
+    fn from(value: ClearType) -> Self {
+        match value {
+            ClearType::All => crossterm::terminal::ClearType::All,
+            ClearType::Purge => crossterm::terminal::ClearType::Purge,
+            ClearType::FromCursorDown => crossterm::terminal::ClearType::FromCursorDown,
+            ClearType::FromCursorUp => crossterm::terminal::ClearType::FromCursorUp,
+            ClearType::CurrentLine => crossterm::terminal::ClearType::CurrentLine,
+            ClearType::UntilNewLine => crossterm::terminal::ClearType::UntilNewLine,
+        }
+    }
+}
+
+/// Scroll up command.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_scroll_up(n: libc::c_ushort) -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::terminal::ScrollUp(n)).c_unwrap();
+        r!()
+    })
+}
+
+/// Scroll down command.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_scroll_down(n: libc::c_ushort) -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::terminal::ScrollDown(n)).c_unwrap();
+        r!()
+    })
+}
+
+/// Queues `CSI n final`, or nothing if `n` is 0 (which the terminal would take as 1).
+fn queue_csi_count(n: libc::c_ushort, final_byte: char) -> libc::c_int {
+    if n > 0 {
+        let sequence = format!("\x1b[{}{}", n, final_byte);
+        queue!(io!(), crossterm::style::Print(sequence)).c_unwrap();
+    }
     r!()
 }
 
-/// Get cursor position (col, row)
-///
-/// # Notes
-/// * Top left cell is represented as `0,0`.
+/// Inserts `n` blank characters at the cursor (ICH), shifting the rest of the line to the right.
+/// Characters shifted past the right margin are lost. The cursor doesn't move.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_position(col: &mut u16, row: &mut u16) -> libc::c_int {
-    let (c, r) = crossterm::cursor::position().c_unwrap();
-    *col = c;
-    *row = r;
-    r!()
+pub extern "C" fn crossterm_terminal_insert_chars(n: libc::c_ushort) -> libc::c_int {
+    catch_panic(|| queue_csi_count(n, '@'))
 }
 
-/// Moves the terminal cursor to the given position (col, row).
+/// Deletes `n` characters at the cursor (DCH), shifting the rest of the line to the left and
+/// filling the end of the line with blanks. The cursor doesn't move.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_delete_chars(n: libc::c_ushort) -> libc::c_int {
+    catch_panic(|| queue_csi_count(n, 'P'))
+}
+
+/// Erases `n` characters from the cursor on (ECH), replacing them with blanks without shifting
+/// the rest of the line. The cursor doesn't move.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_erase_chars(n: libc::c_ushort) -> libc::c_int {
+    catch_panic(|| queue_csi_count(n, 'X'))
+}
+
+/// Makes the line of the cursor double-width (DECDWL, `ESC # 6`), e.g. for banner-style headers.
 ///
-/// # Notes
-/// * Top left cell is represented as `0,0`.
+/// Only the left half of the line is shown, and the line stays double-width until it is reset
+/// with [`crossterm_terminal_single_width_line`]. Terminals that don't support it ignore it.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_move_to(col: u16, row: u16) -> libc::c_int {
-    queue!(io!(), crossterm::cursor::MoveTo(col, row)).c_unwrap();
-    r!()
+pub extern "C" fn crossterm_terminal_double_width_line() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::style::Print("\x1b#6")).c_unwrap();
+        r!()
+    })
 }
 
-/// Moves the terminal cursor down the given number of lines and moves it to the first col.
+/// Half of a double-height line.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub enum LineHalf {
+    /// The top half.
+    Top,
+    /// The bottom half.
+    Bottom,
+}
+
+/// Makes the line of the cursor the `half` of a double-height line (DECDHL, `ESC # 3` or
+/// `ESC # 4`).
 ///
-/// # Notes
-/// * This command is 1 based, meaning `crossterm_cursor_move_to_next_line(1)` moves to the next line.
-/// * Most terminals default 0 argument to 1.
+/// The same text is printed on two consecutive lines, the top half above the bottom half. The
+/// lines are also double-width, until reset with [`crossterm_terminal_single_width_line`].
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_double_height_line(half: LineHalf) -> libc::c_int {
+    catch_panic(|| {
+        let sequence = match half {
+            LineHalf::Top => "\x1b#3",
+            LineHalf::Bottom => "\x1b#4",
+        };
+        queue!(io!(), crossterm::style::Print(sequence)).c_unwrap();
+        r!()
+    })
+}
+
+/// Resets the line of the cursor to single-width and single-height (DECSWL, `ESC # 5`).
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_move_to_next_line(n: u16) -> libc::c_int {
-    queue!(io!(), crossterm::cursor::MoveToNextLine(n)).c_unwrap();
-    r!()
+pub extern "C" fn crossterm_terminal_single_width_line() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::style::Print("\x1b#5")).c_unwrap();
+        r!()
+    })
 }
 
-/// Moves the terminal cursor up the given number of lines and moves it to the first col.
+/// Clear screen command.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_move_to_previous_line(n: u16) -> libc::c_int {
-    queue!(io!(), crossterm::cursor::MoveToPreviousLine(n)).c_unwrap();
-    r!()
+pub extern "C" fn crossterm_terminal_clear(ct: ClearType) -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::terminal::Clear(ct.into())).c_unwrap();
+        r!()
+    })
 }
 
-/// Moves the terminal cursor to the given col on the current row.
+/// Sets terminal title.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `title` pointer points to a valid null-terminated string.
+/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `title` won't be deallocated or modified for the duration of the function call..
+/// - The `title` pointer is correctly aligned and `title` points to an initialized memory.
+///
+/// If these conditions are not met, the behavior is undefined.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_move_to_column(col: u16) -> libc::c_int {
-    queue!(io!(), crossterm::cursor::MoveToColumn(col)).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_terminal_title(title: *const libc::c_char) -> libc::c_int {
+    catch_panic(|| {
+        if title.is_null() {
+            RESULT.with(|r| {
+                *r.borrow_mut() = -1;
+            });
+            set_last_error(anyhow::anyhow!("Received null pointer for title string"));
+            return r!();
+        };
+        let c_str: &std::ffi::CStr = std::ffi::CStr::from_ptr(title);
+        if let Ok(string) = c_str.to_str() {
+            queue!(io!(), crossterm::terminal::SetTitle(string)).c_unwrap();
+            r!()
+        } else {
+            RESULT.with(|r| {
+                *r.borrow_mut() = -1;
+            });
+            set_last_error(anyhow::anyhow!("Received invalid UTF-8 string for title"));
+            r!()
+        }
+    })
 }
 
-/// Moves the terminal cursor to the given row on the current col.
+/// Returns the terminal title as a UTF-8 string, queried with XTWINOPS 21 (`CSI 21 t`), waiting up
+/// to `timeout_ms` milliseconds for the reply, e.g. to restore it after setting a title with
+/// [`crossterm_terminal_title`].
+///
+/// Many terminals don't report the title, as it could be used to inject input, or report an empty
+/// one. Returns null if an error occurred, with an error starting with `Unsupported` if the
+/// terminal did not report it.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_move_to_row(row: u16) -> libc::c_int {
-    queue!(io!(), crossterm::cursor::MoveToRow(row)).c_unwrap();
-    r!()
+pub extern "C" fn crossterm_terminal_title_get(timeout_ms: u32) -> *const libc::c_char {
+    catch_panic(|| {
+        let timeout = std::time::Duration::from_millis(timeout_ms as u64);
+        match query::window_title(timeout) {
+            Ok(title) => convert_string_to_c_char(title),
+            Err(err) => {
+                set_last_error(err);
+                std::ptr::null()
+            }
+        }
+    })
 }
 
-/// Moves the terminal cursor a given number of rows up.
+/// Instructs the terminal emulator to begin a synchronized frame.
+///
+/// # Notes
+///
+/// * Commands must be executed/queued for execution otherwise they do nothing.
+/// * Use [`crossterm_terminal_end_synchronized_update`] command to leave the entered alternate screen.
+///
+/// When rendering the screen of the terminal, the Emulator usually iterates through each visible grid cell and
+/// renders its current state. With applications updating the screen a at higher frequency this can cause tearing.
+///
+/// This mode attempts to mitigate that.
+///
+/// When the synchronization mode is enabled following render calls will keep rendering the last rendered state.
+/// The terminal Emulator keeps processing incoming text and sequences. When the synchronized update mode is disabled
+/// again the renderer may fetch the latest screen buffer state again, effectively avoiding the tearing effect
+/// by unintentionally rendering in the middle a of an application screen update.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_move_up(rows: u16) -> libc::c_int {
-    queue!(io!(), crossterm::cursor::MoveUp(rows)).c_unwrap();
-    r!()
+pub extern "C" fn crossterm_terminal_begin_synchronized_update() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::terminal::BeginSynchronizedUpdate).c_unwrap();
+        state::record(r!(), |m| m.synchronized_update = true);
+        r!()
+    })
 }
 
-/// Moves the terminal cursor a given number of cols to the right.
+/// Instructs the terminal to end a synchronized frame.
+///
+/// # Notes
+///
+/// * Commands must be executed/queued for execution otherwise they do nothing.
+/// * Use [`crossterm_terminal_begin_synchronized_update`] to enter the alternate screen.
+///
+/// When rendering the screen of the terminal, the Emulator usually iterates through each visible grid cell and
+/// renders its current state. With applications updating the screen a at higher frequency this can cause tearing.
+///
+/// This mode attempts to mitigate that.
+///
+/// When the synchronization mode is enabled following render calls will keep rendering the last rendered state.
+/// The terminal Emulator keeps processing incoming text and sequences. When the synchronized update mode is disabled
+/// again the renderer may fetch the latest screen buffer state again, effectively avoiding the tearing effect
+/// by unintentionally rendering in the middle a of an application screen update.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_move_right(cols: u16) -> libc::c_int {
-    queue!(io!(), crossterm::cursor::MoveRight(cols)).c_unwrap();
-    r!()
+pub extern "C" fn crossterm_terminal_end_synchronized_update() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::terminal::EndSynchronizedUpdate).c_unwrap();
+        state::record(r!(), |m| m.synchronized_update = false);
+        r!()
+    })
 }
 
-/// Moves the terminal cursor a given number of rows down.
+/// Runs `callback(user_data)` inside a synchronized update.
+///
+/// The update is ended (and the output flushed) whatever the callback returns, so the terminal is
+/// never left showing a frozen frame. Synchronized updates nest: if one is already in progress,
+/// on any thread, only the outermost update writes the begin and end markers.
+///
+/// Returns the callback's return value, or `-1` if the synchronized update could not be begun or
+/// ended. If the callback returns non-zero, the update is still ended.
+///
+/// # Safety
+///
+/// `callback` must be a valid function pointer (or null, which is reported as an error), and
+/// `user_data` must be valid for whatever the callback does with it.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_with_synchronized_update(
+    callback: Option<unsafe extern "C" fn(user_data: *mut libc::c_void) -> libc::c_int>,
+    user_data: *mut libc::c_void,
+) -> libc::c_int {
+    catch_panic(|| {
+        let Some(callback) = callback else {
+            set_last_error(anyhow::anyhow!("Received null pointer for callback"));
+            return -1;
+        };
+        let update = match SynchronizedUpdate::begin() {
+            Ok(update) => update,
+            Err(err) => {
+                set_last_error(err);
+                return -1;
+            }
+        };
+        let rc = callback(user_data);
+        match update.end() {
+            Ok(()) => rc,
+            Err(err) => {
+                set_last_error(err);
+                -1
+            }
+        }
+    })
+}
+
+/// Begins a synchronized update and returns a handle to end it with
+/// [`crossterm_synchronized_update_guard_end`].
+///
+/// Unlike [`crossterm_terminal_begin_synchronized_update`], guards nest: only the outermost guard
+/// writes the begin and end markers.
+///
+/// Returns null if an error occurred.
+#[no_mangle]
+pub extern "C" fn crossterm_synchronized_update_guard() -> *mut SynchronizedUpdate {
+    catch_panic(|| match SynchronizedUpdate::begin() {
+        Ok(update) => Box::into_raw(Box::new(update)),
+        Err(err) => {
+            set_last_error(err);
+            std::ptr::null_mut()
+        }
+    })
+}
+
+/// Ends the synchronized update of a guard returned by [`crossterm_synchronized_update_guard`]
+/// and frees the guard. The output is flushed when the outermost guard ends.
+///
+/// # Safety
+///
+/// `guard` must be null or a pointer returned by [`crossterm_synchronized_update_guard`] that was
+/// not ended already.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_synchronized_update_guard_end(
+    guard: *mut SynchronizedUpdate,
+) -> libc::c_int {
+    catch_panic(|| {
+        if guard.is_null() {
+            set_last_error(anyhow::anyhow!("Received null pointer for guard"));
+            return -1;
+        }
+        Box::from_raw(guard).end().c_unwrap();
+        r!()
+    })
+}
+
+/// Begins a frame paced to `target_fps` frames per second.
+///
+/// Queues a [`crossterm_terminal_begin_synchronized_update`] and holds back all output on the
+/// calling thread until [`crossterm_frame_end`], which writes the whole frame at once. Calls to
+/// [`crossterm_flush`] during the frame don't write anything.
+///
+/// The `_ctx` functions still queue their output in their [`Context`], and
+/// [`crossterm_flush_ctx`] during the frame adds it to the frame.
+///
+/// Pass `0` as `target_fps` to batch output without pacing.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_move_down(rows: u16) -> libc::c_int {
-    queue!(io!(), crossterm::cursor::MoveDown(rows)).c_unwrap();
-    r!()
+pub extern "C" fn crossterm_frame_begin(target_fps: f64) -> libc::c_int {
+    catch_panic(|| {
+        frame::begin(target_fps)
+            .and_then(|_| queue_command(crossterm::terminal::BeginSynchronizedUpdate))
+            .c_unwrap();
+        r!()
+    })
 }
 
-/// Moves the terminal cursor a given number of cols to the left.
+/// Ends the frame begun with [`crossterm_frame_begin`].
+///
+/// Queues a [`crossterm_terminal_end_synchronized_update`], writes and flushes the frame's output,
+/// then sleeps for the rest of the frame interval to maintain the target frame rate.
+///
+/// Returns the measured frame time in seconds, from the start of the frame until its output was
+/// flushed (not including the sleep), or a negative value if an error occurred.
+#[no_mangle]
+pub extern "C" fn crossterm_frame_end() -> f64 {
+    catch_panic(|| {
+        let result = frame::end().and_then(|mut frame| {
+            queue!(frame.buffer, crossterm::terminal::EndSynchronizedUpdate)?;
+            let mut out = io!();
+            out.write_all(&frame.buffer)?;
+            out.flush()?;
+            Ok(frame)
+        });
+        match result {
+            Ok(frame) => {
+                let frame_time = frame.elapsed().as_secs_f64();
+                frame.sleep_remaining();
+                frame_time
+            }
+            Err(err) => {
+                set_last_error(err);
+                -1.0
+            }
+        }
+    })
+}
+
+/// Returns the text currently visible on the terminal screen as a UTF-8 string, a line per row
+/// without trailing spaces.
+///
+/// Terminals can't be asked for their contents with escape sequences, so the screen is read
+/// where the platform or terminal offers a way: the console screen buffer on Windows, and
+/// `tmux capture-pane`, `wezterm cli get-text` or `kitten @ get-text` (which needs remote control
+/// to be allowed) when running in tmux, WezTerm or kitty. Otherwise the screen is reconstructed
+/// from the library's output if [`crossterm_terminal_enable_screen_tracking`] was called, and an
+/// error is reported if not.
+///
+/// Returns null if an error occurred.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_move_left(cols: u16) -> libc::c_int {
-    queue!(io!(), crossterm::cursor::MoveLeft(cols)).c_unwrap();
-    r!()
+pub extern "C" fn crossterm_terminal_capture_screen() -> *const libc::c_char {
+    catch_panic(|| match screen::capture() {
+        Ok(text) => convert_string_to_c_char(text),
+        Err(err) => {
+            set_last_error(err);
+            std::ptr::null()
+        }
+    })
 }
 
-/// Saves the current terminal cursor position.
+/// Starts keeping a copy of the screen for [`crossterm_terminal_capture_screen`], built from the
+/// output written through the library.
+///
+/// The copy starts blank with the cursor at the top left, and follows printing, cursor movement,
+/// erasing, scrolling and the alternate screen. Enable it before clearing the screen or entering
+/// the alternate screen, since neither what was on the screen before nor output written by other
+/// means is seen.
+#[no_mangle]
+pub extern "C" fn crossterm_terminal_enable_screen_tracking() -> libc::c_int {
+    catch_panic(|| {
+        screen::start_tracking().c_unwrap();
+        r!()
+    })
+}
+
+/// Stops keeping the copy of the screen enabled with [`crossterm_terminal_enable_screen_tracking`].
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_save_position() -> libc::c_int {
-    queue!(io!(), crossterm::cursor::SavePosition).c_unwrap();
-    r!()
+pub extern "C" fn crossterm_terminal_disable_screen_tracking() {
+    catch_panic(|| {
+        screen::stop_tracking();
+    })
 }
 
-/// Restores the saved terminal cursor position.
+/// Instructs the terminal to send a bell.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_restore_position() -> libc::c_int {
-    queue!(io!(), crossterm::cursor::RestorePosition).c_unwrap();
-    r!()
+pub extern "C" fn crossterm_terminal_ring_bell() -> libc::c_int {
+    catch_panic(|| {
+        queue!(io!(), crossterm::style::Print("\x07")).c_unwrap();
+        r!()
+    })
 }
 
-/// Hides the terminal cursor.
+/// Queues `len` bytes of `bytes` as they are, e.g. precomputed escape sequences or sixel images,
+/// into the same output as the commands, so that they are written in order with them.
+///
+/// # Safety
+///
+/// This function takes a raw pointer as argument. As such, the caller must ensure that:
+/// - The `bytes` pointer points to `len` initialized bytes, or is null if `len` is `0`.
+///
+/// If these conditions are not met, the behavior is undefined.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_hide() -> libc::c_int {
-    queue!(io!(), crossterm::cursor::Hide).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_write_bytes(bytes: *const u8, len: usize) -> libc::c_int {
+    catch_panic(|| {
+        convert_slice(bytes, len, "bytes")
+            .and_then(|bytes| io!().write_all(bytes).map_err(Into::into))
+            .c_unwrap();
+        r!()
+    })
 }
 
-/// Shows the terminal cursor.
+/// Flush the stdout stream, ensuring that all intermediately buffered contents reach their destination.
+///
+/// It is considered an error if not all bytes could be written due to I/O errors or EOF being reached.
+///
+/// Nothing is flushed while the terminal is unfocused if
+/// [`crossterm_event_enable_focus_tracking`] was asked to hold back flushing.
+///
+/// The output target, the autoflush policy and the buffer are shared by all threads, so this
+/// writes the output queued on any thread. The output lock keeps the output of calls on
+/// different threads from interleaving.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_show() -> libc::c_int {
-    queue!(io!(), crossterm::cursor::Show).c_unwrap();
-    r!()
+pub extern "C" fn crossterm_flush() -> libc::c_int {
+    catch_panic(|| {
+        if focus::focus().holds_flush() {
+            return 0;
+        }
+        if let Err(err) = io!().flush() {
+            set_last_error(anyhow::anyhow!(err))
+        }
+        r!()
+    })
 }
 
-/// Enables blinking of the terminal cursor.
+/// Creates a context with its own output buffer and error state, for calling the `_ctx` variants
+/// of the cursor, style and terminal functions from threads that change between calls.
+///
+/// Output of the `_ctx` functions is queued to the context and written to the terminal by
+/// [`crossterm_flush_ctx`], or added to the frame if one is in progress on the flushing thread
+/// (see [`crossterm_frame_begin`]). Their errors are kept in the context, read with
+/// [`crossterm_context_has_error`] and [`crossterm_context_last_error_message`], instead of the
+/// calling thread's error state. A context may be used from any thread, but from one thread at a
+/// time.
+///
+/// Use [`crossterm_context_free`] to free the context.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_enable_blinking() -> libc::c_int {
-    queue!(io!(), crossterm::cursor::EnableBlinking).c_unwrap();
-    r!()
+pub extern "C" fn crossterm_context_new() -> *mut Context {
+    catch_panic(|| Box::into_raw(Box::default()))
 }
 
-/// Disables blinking of the terminal cursor.
+/// Frees a context created with [`crossterm_context_new`], discarding output that was not flushed.
+///
+/// # Safety
+///
+/// `ctx` must be null or a pointer returned by [`crossterm_context_new`] that was not freed
+/// already, and not in use on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_disable_blinking() -> libc::c_int {
-    queue!(io!(), crossterm::cursor::DisableBlinking).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_context_free(ctx: *mut Context) {
+    catch_panic(|| {
+        if !ctx.is_null() {
+            drop(Box::from_raw(ctx));
+        }
+    })
 }
 
-/// Style of the cursor.
-/// It uses two types of escape codes, one to control blinking, and the other the shape.
-#[repr(C)]
-#[derive(Clone, Copy)]
-pub enum CursorStyle {
-    /// Default cursor shape configured by the user.
-    DefaultUserShape,
-    /// A blinking block cursor shape (■).
-    BlinkingBlock,
-    /// A non blinking block cursor shape (inverse of `BlinkingBlock`).
-    SteadyBlock,
-    /// A blinking underscore cursor shape(_).
-    BlinkingUnderScore,
-    /// A non blinking underscore cursor shape (inverse of `BlinkingUnderScore`).
-    SteadyUnderScore,
-    /// A blinking cursor bar shape (|)
-    BlinkingBar,
-    /// A steady cursor bar shape (inverse of `BlinkingBar`).
-    SteadyBar,
+/// Check whether the last call in a context set an error.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_context_has_error(ctx: *const Context) -> bool {
+    catch_panic(|| !ctx.is_null() && (*ctx).error.borrow().is_some())
+}
+
+/// Clears the error of a context.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_context_clear_last_error(ctx: *mut Context) {
+    catch_panic(|| {
+        if !ctx.is_null() {
+            *(*ctx).error.borrow_mut() = None;
+        }
+    })
 }
 
-/// Sets the style of the cursor.
-#[no_mangle]
-pub extern "C" fn crossterm_cursor_style(cursor_style: CursorStyle) -> libc::c_int {
-    let cs = match cursor_style {
-        CursorStyle::DefaultUserShape => crossterm::cursor::SetCursorStyle::DefaultUserShape,
-        CursorStyle::BlinkingBlock => crossterm::cursor::SetCursorStyle::BlinkingBlock,
-        CursorStyle::SteadyBlock => crossterm::cursor::SetCursorStyle::SteadyBlock,
-        CursorStyle::BlinkingUnderScore => crossterm::cursor::SetCursorStyle::BlinkingUnderScore,
-        CursorStyle::SteadyUnderScore => crossterm::cursor::SetCursorStyle::SteadyUnderScore,
-        CursorStyle::BlinkingBar => crossterm::cursor::SetCursorStyle::BlinkingBar,
-        CursorStyle::SteadyBar => crossterm::cursor::SetCursorStyle::SteadyBar,
-    };
-    queue!(io!(), cs).c_unwrap();
-    r!()
+/// Return the error message of the last call in a context, as [`crossterm_last_error_message`]
+/// does for the calling thread, and clear it.
+///
+/// Caller is responsible for memory associated with string buffer.
+/// Use [`crossterm_free_c_char`] to free data.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_context_last_error_message(
+    ctx: *mut Context,
+) -> *const libc::c_char {
+    catch_panic(|| {
+        let last_error = match ctx.as_mut() {
+            Some(ctx) => ctx.error.get_mut().take(),
+            None => Some(anyhow::anyhow!("Received null pointer for context")),
+        }
+        .unwrap_or(anyhow::anyhow!(
+            "No error message found. Check library documentation for more information."
+        ));
+        convert_string_to_c_char(format!("{:#}", last_error))
+    })
 }
 
-/// Sets the style of the cursor to default user shape.
+/// Like [`crossterm_flush`], writing the output queued in the context `ctx` to the terminal.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_style_default_user_shape() -> libc::c_int {
-    queue!(io!(), crossterm::cursor::SetCursorStyle::DefaultUserShape).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_flush_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_flush()))
 }
 
-/// Sets the style of the cursor to a blinking block.
+/// Like [`crossterm_cursor_position_set`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_style_blinking_block() -> libc::c_int {
-    queue!(io!(), crossterm::cursor::SetCursorStyle::BlinkingBlock).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_position_set_ctx(
+    ctx: *mut Context,
+    col: u16,
+    row: u16,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_position_set(col, row)))
 }
 
-/// Sets the style of the cursor to a steady block.
+/// Like [`crossterm_cursor_position`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_style_steady_block() -> libc::c_int {
-    queue!(io!(), crossterm::cursor::SetCursorStyle::SteadyBlock).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_position_ctx(
+    ctx: *mut Context,
+    col: &mut u16,
+    row: &mut u16,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_position(col, row)))
 }
 
-/// Sets the style of the cursor to a blinking underscore.
+/// Like [`crossterm_cursor_move_to`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_style_blinking_underscore() -> libc::c_int {
-    queue!(io!(), crossterm::cursor::SetCursorStyle::BlinkingUnderScore).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_move_to_ctx(
+    ctx: *mut Context,
+    col: u16,
+    row: u16,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_move_to(col, row)))
 }
 
-/// Sets the style of the cursor to a steady underscore.
+/// Like [`crossterm_cursor_move_to_next_line`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_style_steady_underscore() -> libc::c_int {
-    queue!(io!(), crossterm::cursor::SetCursorStyle::SteadyUnderScore).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_move_to_next_line_ctx(
+    ctx: *mut Context,
+    n: u16,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_move_to_next_line(n)))
 }
 
-/// Sets the style of the cursor to a blinking bar.
+/// Like [`crossterm_cursor_move_to_previous_line`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_style_blinking_bar() -> libc::c_int {
-    queue!(io!(), crossterm::cursor::SetCursorStyle::BlinkingBar).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_move_to_previous_line_ctx(
+    ctx: *mut Context,
+    n: u16,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_move_to_previous_line(n)))
 }
 
-/// Sets the style of the cursor to a steady bar.
+/// Like [`crossterm_cursor_move_to_column`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_cursor_style_steady_bar() -> libc::c_int {
-    queue!(io!(), crossterm::cursor::SetCursorStyle::SteadyBar).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_move_to_column_ctx(
+    ctx: *mut Context,
+    col: u16,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_move_to_column(col)))
 }
 
-/// Enable mouse event capturing.
+/// Like [`crossterm_cursor_move_to_row`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_event_enable_mouse_capture() -> libc::c_int {
-    queue!(io!(), crossterm::event::EnableMouseCapture).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_move_to_row_ctx(
+    ctx: *mut Context,
+    row: u16,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_move_to_row(row)))
 }
 
-/// Disable mouse event capturing.
+/// Like [`crossterm_cursor_move_up`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_event_disable_mouse_capture() -> libc::c_int {
-    queue!(io!(), crossterm::event::DisableMouseCapture).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_move_up_ctx(ctx: *mut Context, rows: u16) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_move_up(rows)))
 }
 
-/// Represents special flags that tell compatible terminals to add extra information to keyboard events.
+/// Like [`crossterm_cursor_move_right`], in the context `ctx`.
 ///
-/// See <https://sw.kovidgoyal.net/kitty/keyboard-protocol/#progressive-enhancement> for more information.
+/// # Safety
 ///
-/// Alternate keys and Unicode codepoints are not yet supported by crossterm.
-#[repr(u8)]
-pub enum KeyboardEnhancementFlags {
-    /// Represent Escape and modified keys using CSI-u sequences, so they can be unambiguously
-    /// read.
-    DisambiguateEscapeCodes = 0b0000_0001,
-    /// Add extra events with [`KeyEvent.kind`] set to [`KeyEventKind::Repeat`] or
-    /// [`KeyEventKind::Release`] when keys are autorepeated or released.
-    ReportEventTypes = 0b0000_0010,
-    // Send [alternate keycodes](https://sw.kovidgoyal.net/kitty/keyboard-protocol/#key-codes)
-    // in addition to the base keycode. The alternate keycode overrides the base keycode in
-    // resulting `KeyEvent`s.
-    ReportAlternateKeys = 0b0000_0100,
-    /// Represent all keyboard events as CSI-u sequences. This is required to get repeat/release
-    /// events for plain-text keys.
-    ReportAllKeysAsEscapeCodes = 0b0000_1000,
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_cursor_move_right_ctx(
+    ctx: *mut Context,
+    cols: u16,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_move_right(cols)))
 }
 
-/// Enables the [kitty keyboard protocol](https://sw.kovidgoyal.net/kitty/keyboard-protocol/),
-/// which adds extra information to keyboard events and removes ambiguity for modifier keys.
-/// It should be paired with [`crossterm_pop_keyboard_enhancement_flags`] at the end of execution.
+/// Like [`crossterm_cursor_move_down`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_event_push_keyboard_enhancement_flags(flags: u8) -> libc::c_int {
-    let flags = crossterm::event::KeyboardEnhancementFlags::from_bits(flags).unwrap();
-    queue!(io!(), crossterm::event::PushKeyboardEnhancementFlags(flags)).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_move_down_ctx(
+    ctx: *mut Context,
+    rows: u16,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_move_down(rows)))
 }
 
-/// Disables extra kinds of keyboard events.
+/// Like [`crossterm_cursor_move_left`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_event_pop_keyboard_enhancement_flags() -> libc::c_int {
-    queue!(io!(), crossterm::event::PopKeyboardEnhancementFlags).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_move_left_ctx(
+    ctx: *mut Context,
+    cols: u16,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_move_left(cols)))
 }
 
-/// Enable focus event emission.
+/// Like [`crossterm_cursor_save_position`], in the context `ctx`.
 ///
-/// It should be paired with [`crossterm_event_disable_focus_change`] at the end of execution.
+/// # Safety
 ///
-/// Focus events can be captured with [`crossterm_event_read`].
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_event_enable_focus_change() -> libc::c_int {
-    queue!(io!(), crossterm::event::EnableFocusChange).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_save_position_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_save_position()))
 }
 
-/// Disable focus event emission.
+/// Like [`crossterm_cursor_restore_position`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_event_disable_focus_change() -> libc::c_int {
-    queue!(io!(), crossterm::event::DisableFocusChange).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_restore_position_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_restore_position()))
 }
 
-/// Enables [bracketed paste mode](https://en.wikipedia.org/wiki/Bracketed-paste).
+/// Like [`crossterm_cursor_hide`], in the context `ctx`.
 ///
-/// It should be paired with [`crossterm_event_disable_bracketed_paste`] at the end of execution.
+/// # Safety
 ///
-/// This is not supported in older Windows terminals without
-/// [virtual terminal sequences](https://docs.microsoft.com/en-us/windows/console/console-virtual-terminal-sequences).
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_event_enable_bracketed_paste() -> libc::c_int {
-    queue!(io!(), crossterm::event::EnableBracketedPaste).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_hide_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_hide()))
 }
 
-/// Disables bracketed paste mode.
+/// Like [`crossterm_cursor_show`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_event_disable_bracketed_paste() -> libc::c_int {
-    queue!(io!(), crossterm::event::DisableBracketedPaste).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_show_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_show()))
 }
 
-#[repr(C)]
-pub enum Attribute {
-    /// Resets all the attributes.
-    Reset,
-    /// Increases the text intensity.
-    Bold,
-    /// Decreases the text intensity.
-    Dim,
-    /// Emphasises the text.
-    Italic,
-    /// Underlines the text.
-    Underlined,
-    /// Double underlines the text.
-    DoubleUnderlined,
-    /// Undercurls the text.
-    Undercurled,
-    /// Underdots the text.
-    Underdotted,
-    /// Underdashes the text.
-    Underdashed,
-    /// Makes the text blinking (< 150 per minute).
-    SlowBlink,
-    /// Makes the text blinking (>= 150 per minute).
-    RapidBlink,
-    /// Swaps foreground and background colors.
-    Reverse,
-    /// Hides the text (also known as Conceal).
-    Hidden,
-    /// Crosses the text.
-    CrossedOut,
-    /// Sets the [Fraktur](https://en.wikipedia.org/wiki/Fraktur) typeface.
-    ///
-    /// Mostly used for [mathematical alphanumeric symbols](https://en.wikipedia.org/wiki/Mathematical_Alphanumeric_Symbols).
-    Fraktur,
-    /// Turns off the `Bold` attribute. - Inconsistent - Prefer to use NormalIntensity
-    NoBold,
-    /// Switches the text back to normal intensity (no bold, italic).
-    NormalIntensity,
-    /// Turns off the `Italic` attribute.
-    NoItalic,
-    /// Turns off the `Underlined` attribute.
-    NoUnderline,
-    /// Turns off the text blinking (`SlowBlink` or `RapidBlink`).
-    NoBlink,
-    /// Turns off the `Reverse` attribute.
-    NoReverse,
-    /// Turns off the `Hidden` attribute.
-    NoHidden,
-    /// Turns off the `CrossedOut` attribute.
-    NotCrossedOut,
-    /// Makes the text framed.
-    Framed,
-    /// Makes the text encircled.
-    Encircled,
-    /// Draws a line at the top of the text.
-    OverLined,
-    /// Turns off the `Frame` and `Encircled` attributes.
-    NotFramedOrEncircled,
-    /// Turns off the `OverLined` attribute.
-    NotOverLined,
+/// Like [`crossterm_cursor_show_guarded`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_cursor_show_guarded_ctx(
+    ctx: *mut Context,
+    token: u64,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_show_guarded(token)))
 }
 
-impl From<Attribute> for crossterm::style::Attribute {
-    fn from(value: Attribute) -> Self {
-        match value {
-            Attribute::Reset => crossterm::style::Attribute::Reset,
-            Attribute::Bold => crossterm::style::Attribute::Bold,
-            Attribute::Dim => crossterm::style::Attribute::Dim,
-            Attribute::Italic => crossterm::style::Attribute::Italic,
-            Attribute::Underlined => crossterm::style::Attribute::Underlined,
-            Attribute::DoubleUnderlined => crossterm::style::Attribute::DoubleUnderlined,
-            Attribute::Undercurled => crossterm::style::Attribute::Undercurled,
-            Attribute::Underdotted => crossterm::style::Attribute::Underdotted,
-            Attribute::Underdashed => crossterm::style::Attribute::Underdashed,
-            Attribute::SlowBlink => crossterm::style::Attribute::SlowBlink,
-            Attribute::RapidBlink => crossterm::style::Attribute::RapidBlink,
-            Attribute::Reverse => crossterm::style::Attribute::Reverse,
-            Attribute::Hidden => crossterm::style::Attribute::Hidden,
-            Attribute::CrossedOut => crossterm::style::Attribute::CrossedOut,
-            Attribute::Fraktur => crossterm::style::Attribute::Fraktur,
-            Attribute::NoBold => crossterm::style::Attribute::NoBold,
-            Attribute::NormalIntensity => crossterm::style::Attribute::NormalIntensity,
-            Attribute::NoItalic => crossterm::style::Attribute::NoItalic,
-            Attribute::NoUnderline => crossterm::style::Attribute::NoUnderline,
-            Attribute::NoBlink => crossterm::style::Attribute::NoBlink,
-            Attribute::NoReverse => crossterm::style::Attribute::NoReverse,
-            Attribute::NoHidden => crossterm::style::Attribute::NoHidden,
-            Attribute::NotCrossedOut => crossterm::style::Attribute::NotCrossedOut,
-            Attribute::Framed => crossterm::style::Attribute::Framed,
-            Attribute::Encircled => crossterm::style::Attribute::Encircled,
-            Attribute::OverLined => crossterm::style::Attribute::OverLined,
-            Attribute::NotFramedOrEncircled => crossterm::style::Attribute::NotFramedOrEncircled,
-            Attribute::NotOverLined => crossterm::style::Attribute::NotOverLined,
-        }
-    }
+/// Like [`crossterm_cursor_enable_blinking`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_cursor_enable_blinking_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_enable_blinking()))
 }
 
-/// a bitset for all possible attributes
-#[repr(C)]
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
-pub struct Attributes(u32);
-
-/// Sets an attribute.
+/// Like [`crossterm_cursor_disable_blinking`], in the context `ctx`.
 ///
-/// See [`Attribute`] for more info.
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute(attr: Attribute) -> libc::c_int {
-    queue!(io!(), crossterm::style::SetAttribute(attr.into())).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_disable_blinking_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_disable_blinking()))
 }
 
-/// Sets the `Reset` attribute.
+/// Like [`crossterm_cursor_style`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_reset() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::Reset.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_style_ctx(
+    ctx: *mut Context,
+    cursor_style: CursorStyle,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_style(cursor_style)))
 }
 
-/// Sets the `Bold` attribute.
+/// Like [`crossterm_cursor_style_default_user_shape`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_bold() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::Bold.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_style_default_user_shape_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_style_default_user_shape()))
 }
 
-/// Sets the `Dim` attribute.
+/// Like [`crossterm_cursor_style_blinking_block`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_dim() -> libc::c_int {
-    queue!(io!(), crossterm::style::SetAttribute(Attribute::Dim.into())).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_style_blinking_block_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_style_blinking_block()))
 }
 
-/// Sets the `Italic` attribute.
+/// Like [`crossterm_cursor_style_steady_block`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_italic() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::Italic.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_style_steady_block_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_style_steady_block()))
 }
 
-/// Sets the `Underlined` attribute.
+/// Like [`crossterm_cursor_style_blinking_underscore`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_underlined() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::Underlined.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_style_blinking_underscore_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_style_blinking_underscore()))
 }
 
-/// Sets the `DoubleUnderlined` attribute.
+/// Like [`crossterm_cursor_style_steady_underscore`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_double_underlined() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::DoubleUnderlined.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_style_steady_underscore_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_style_steady_underscore()))
 }
 
-/// Sets the `Undercurled` attribute.
+/// Like [`crossterm_cursor_style_blinking_bar`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_undercurled() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::Undercurled.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_style_blinking_bar_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_style_blinking_bar()))
 }
 
-/// Sets the `Underdotted` attribute.
+/// Like [`crossterm_cursor_style_steady_bar`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_underdotted() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::Underdotted.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_cursor_style_steady_bar_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_cursor_style_steady_bar()))
 }
 
-/// Sets the `Underdashed` attribute.
+/// Like [`crossterm_style_attribute`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_underdashed() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::Underdashed.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_attribute_ctx(
+    ctx: *mut Context,
+    attr: Attribute,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute(attr)))
 }
 
-/// Sets the `SlowBlink` attribute.
+/// Like [`crossterm_style_attribute_reset`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_slow_blink() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::SlowBlink.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_attribute_reset_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_reset()))
 }
 
-/// Sets the `RapidBlink` attribute.
+/// Like [`crossterm_style_attribute_bold`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_rapid_blink() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::RapidBlink.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_attribute_bold_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_bold()))
 }
 
-/// Sets the `Reverse` attribute.
+/// Like [`crossterm_style_attribute_dim`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_reverse() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::Reverse.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_attribute_dim_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_dim()))
 }
 
-/// Sets the `Hidden` attribute.
+/// Like [`crossterm_style_attribute_italic`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_hidden() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::Hidden.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_attribute_italic_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_italic()))
 }
 
-/// Sets the `CrossedOut` attribute.
+/// Like [`crossterm_style_attribute_underlined`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_crossed_out() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::CrossedOut.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_attribute_underlined_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_underlined()))
 }
 
-/// Sets the `Fraktur` attribute.
+/// Like [`crossterm_style_attribute_double_underlined`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_fraktur() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::Fraktur.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_attribute_double_underlined_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_double_underlined()))
 }
 
-/// Sets the `NoBold` attribute.
+/// Like [`crossterm_style_attribute_undercurled`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_no_bold() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::NoBold.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_attribute_undercurled_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_undercurled()))
 }
 
-/// Sets the `NormalIntensity` attribute.
+/// Like [`crossterm_style_attribute_underdotted`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_normal_intensity() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::NormalIntensity.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_attribute_underdotted_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_underdotted()))
 }
 
-/// Sets the `NoItalic` attribute.
+/// Like [`crossterm_style_attribute_underdashed`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_no_italic() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::NoItalic.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_attribute_underdashed_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_underdashed()))
 }
 
-/// Sets the `NoUnderline` attribute.
+/// Like [`crossterm_style_attribute_slow_blink`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_no_underline() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::NoUnderline.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_attribute_slow_blink_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_slow_blink()))
 }
 
-/// Sets the `NoBlink` attribute.
+/// Like [`crossterm_style_attribute_rapid_blink`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_no_blink() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::NoBlink.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_attribute_rapid_blink_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_rapid_blink()))
 }
 
-/// Sets the `NoReverse` attribute.
+/// Like [`crossterm_style_attribute_reverse`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_no_reverse() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::NoReverse.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_attribute_reverse_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_reverse()))
 }
 
-/// Sets the `NoHidden` attribute.
+/// Like [`crossterm_style_attribute_hidden`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_no_hidden() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::NoHidden.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_attribute_hidden_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_hidden()))
 }
 
-/// Sets the `NotCrossedOut` attribute.
+/// Like [`crossterm_style_attribute_crossed_out`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_attribute_not_crossed_out() -> libc::c_int {
-    queue!(
-        io!(),
-        crossterm::style::SetAttribute(Attribute::NotCrossedOut.into())
-    )
-    .c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_attribute_crossed_out_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_crossed_out()))
 }
 
-/// Print char to stdout
+/// Like [`crossterm_style_attribute_fraktur`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_print_char(c: u32) -> libc::c_int {
-    if let Some(ch) = std::char::from_u32(c) {
-        queue!(io!(), crossterm::style::Print(ch)).c_unwrap();
-        r!()
-    } else {
-        set_last_error(anyhow::anyhow!("Unable to convert {} to valid char", c));
-        -1
-    }
+pub unsafe extern "C" fn crossterm_style_attribute_fraktur_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_fraktur()))
 }
 
-/// Print string to stdout
+/// Like [`crossterm_style_attribute_no_bold`], in the context `ctx`.
 ///
 /// # Safety
 ///
-/// This function takes a raw pointer as argument. As such, the caller must ensure that:
-/// - The `title` pointer points to a valid null-terminated string.
-/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `title` won't be deallocated or modified for the duration of the function call..
-/// - The `title` pointer is correctly aligned and `title` points to an initialized memory.
-///
-/// If these conditions are not met, the behavior is undefined.
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub unsafe extern "C" fn crossterm_style_print_string(s: *const libc::c_char) -> libc::c_int {
-    if s.is_null() {
-        RESULT.with(|r| {
-            *r.borrow_mut() = -1;
-        });
-        set_last_error(anyhow::anyhow!("Received null pointer for print string"));
-        return r!();
-    };
-    let c_str: &std::ffi::CStr = std::ffi::CStr::from_ptr(s);
-    if let Ok(string) = c_str.to_str() {
-        queue!(io!(), crossterm::style::Print(string)).c_unwrap();
-        r!()
-    } else {
-        RESULT.with(|r| {
-            *r.borrow_mut() = -1;
-        });
-        set_last_error(anyhow::anyhow!(
-            "Received invalid UTF-8 string for print string"
-        ));
-        r!()
-    }
+pub unsafe extern "C" fn crossterm_style_attribute_no_bold_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_no_bold()))
 }
 
-/// Print string to stdout
+/// Like [`crossterm_style_attribute_normal_intensity`], in the context `ctx`.
 ///
 /// # Safety
 ///
-/// This function takes a raw pointer as argument. As such, the caller must ensure that:
-/// - The `title` pointer points to a valid null-terminated string.
-/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `title` won't be deallocated or modified for the duration of the function call..
-/// - The `title` pointer is correctly aligned and `title` points to an initialized memory.
-///
-/// If these conditions are not met, the behavior is undefined.
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub unsafe extern "C" fn crossterm_style_print(s: *const libc::c_char) -> libc::c_int {
-    crossterm_style_print_string(s)
+pub unsafe extern "C" fn crossterm_style_attribute_normal_intensity_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_normal_intensity()))
 }
 
-#[repr(C)]
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash)]
-pub enum Color {
-    /// Resets the terminal color.
-    Reset,
-    /// Black color.
-    Black,
-    /// Dark grey color.
-    DarkGrey,
-    /// Light red color.
-    Red,
-    /// Dark red color.
-    DarkRed,
-    /// Light green color.
-    Green,
-    /// Dark green color.
-    DarkGreen,
-    /// Light yellow color.
-    Yellow,
-    /// Dark yellow color.
-    DarkYellow,
-    /// Light blue color.
-    Blue,
-    /// Dark blue color.
-    DarkBlue,
-    /// Light magenta color.
-    Magenta,
-    /// Dark magenta color.
-    DarkMagenta,
-    /// Light cyan color.
-    Cyan,
-    /// Dark cyan color.
-    DarkCyan,
-    /// White color.
-    White,
-    /// Grey color.
-    Grey,
-    /// An RGB color. See [RGB color model](https://en.wikipedia.org/wiki/RGB_color_model) for more info.
-    ///
-    /// Most UNIX terminals and Windows 10 supported only.
-    Rgb { r: u8, g: u8, b: u8 },
-    /// An ANSI color. See [256 colors - cheat sheet](https://jonasjacek.github.io/colors/) for more info.
-    ///
-    /// Most UNIX terminals and Windows 10 supported only.
-    AnsiValue(u8),
+/// Like [`crossterm_style_attribute_no_italic`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_style_attribute_no_italic_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_no_italic()))
 }
-
-impl From<Color> for crossterm::style::Color {
-    fn from(color: Color) -> Self {
-        match color {
-            Color::Reset => crossterm::style::Color::Reset,
-            Color::Black => crossterm::style::Color::Black,
-            Color::DarkGrey => crossterm::style::Color::DarkGrey,
-            Color::Red => crossterm::style::Color::Red,
-            Color::DarkRed => crossterm::style::Color::DarkRed,
-            Color::Green => crossterm::style::Color::Green,
-            Color::DarkGreen => crossterm::style::Color::DarkGreen,
-            Color::Yellow => crossterm::style::Color::Yellow,
-            Color::DarkYellow => crossterm::style::Color::DarkYellow,
-            Color::Blue => crossterm::style::Color::Blue,
-            Color::DarkBlue => crossterm::style::Color::DarkBlue,
-            Color::Magenta => crossterm::style::Color::Magenta,
-            Color::DarkMagenta => crossterm::style::Color::DarkMagenta,
-            Color::Cyan => crossterm::style::Color::Cyan,
-            Color::DarkCyan => crossterm::style::Color::DarkCyan,
-            Color::White => crossterm::style::Color::White,
-            Color::Grey => crossterm::style::Color::Grey,
-            Color::Rgb { r, g, b } => crossterm::style::Color::Rgb { r, g, b },
-            Color::AnsiValue(v) => crossterm::style::Color::AnsiValue(v),
-        }
-    }
+
+/// Like [`crossterm_style_attribute_no_underline`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_style_attribute_no_underline_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_no_underline()))
 }
 
-/// Sets the the background color.
+/// Like [`crossterm_style_attribute_no_blink`], in the context `ctx`.
 ///
-/// See [`Color`] for more info.
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color(color: Color) -> libc::c_int {
-    queue!(io!(), crossterm::style::SetBackgroundColor(color.into())).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_attribute_no_blink_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_no_blink()))
 }
 
-/// Sets the the background color in RGB.
+/// Like [`crossterm_style_attribute_no_reverse`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color_rgb(r: u8, g: u8, b: u8) -> libc::c_int {
-    crossterm_style_background_color(Color::Rgb { r, g, b })
+pub unsafe extern "C" fn crossterm_style_attribute_no_reverse_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_no_reverse()))
 }
 
-/// Sets the the background color to an ANSI value.
+/// Like [`crossterm_style_attribute_no_hidden`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color_ansi(value: u8) -> libc::c_int {
-    crossterm_style_background_color(Color::AnsiValue(value))
+pub unsafe extern "C" fn crossterm_style_attribute_no_hidden_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_no_hidden()))
 }
 
-/// Sets the the background color to Reset.
+/// Like [`crossterm_style_attribute_not_crossed_out`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color_reset() -> libc::c_int {
-    crossterm_style_background_color(Color::Reset)
+pub unsafe extern "C" fn crossterm_style_attribute_not_crossed_out_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_attribute_not_crossed_out()))
 }
 
-/// Sets the the background color to Black.
+/// Like [`crossterm_style_print_char`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color_black() -> libc::c_int {
-    crossterm_style_background_color(Color::Black)
+pub unsafe extern "C" fn crossterm_style_print_char_ctx(ctx: *mut Context, c: u32) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_print_char(c)))
 }
 
-/// Sets the the background color to DarkGrey.
+/// Like [`crossterm_style_print_string`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
+/// The other arguments must be valid as for [`crossterm_style_print_string`].
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color_dark_grey() -> libc::c_int {
-    crossterm_style_background_color(Color::DarkGrey)
+pub unsafe extern "C" fn crossterm_style_print_string_ctx(
+    ctx: *mut Context,
+    s: *const libc::c_char,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_print_string(s)))
 }
 
-/// Sets the the background color to Red.
+/// Like [`crossterm_style_print`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
+/// The other arguments must be valid as for [`crossterm_style_print`].
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color_red() -> libc::c_int {
-    crossterm_style_background_color(Color::Red)
+pub unsafe extern "C" fn crossterm_style_print_ctx(
+    ctx: *mut Context,
+    s: *const libc::c_char,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_print(s)))
 }
 
-/// Sets the the background color to DarkRed.
+/// Like [`crossterm_style_background_color`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color_dark_red() -> libc::c_int {
-    crossterm_style_background_color(Color::DarkRed)
+pub unsafe extern "C" fn crossterm_style_background_color_ctx(
+    ctx: *mut Context,
+    color: Color,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color(color)))
 }
 
-/// Sets the the background color to Green.
+/// Like [`crossterm_style_background_color_rgb`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color_green() -> libc::c_int {
-    crossterm_style_background_color(Color::Green)
+pub unsafe extern "C" fn crossterm_style_background_color_rgb_ctx(
+    ctx: *mut Context,
+    r: u8,
+    g: u8,
+    b: u8,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color_rgb(r, g, b)))
 }
 
-/// Sets the the background color to DarkGreen.
+/// Like [`crossterm_style_background_color_ansi`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color_dark_green() -> libc::c_int {
-    crossterm_style_background_color(Color::DarkGreen)
+pub unsafe extern "C" fn crossterm_style_background_color_ansi_ctx(
+    ctx: *mut Context,
+    value: u8,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color_ansi(value)))
 }
 
-/// Sets the the background color to Yellow.
+/// Like [`crossterm_style_background_color_reset`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color_yellow() -> libc::c_int {
-    crossterm_style_background_color(Color::Yellow)
+pub unsafe extern "C" fn crossterm_style_background_color_reset_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color_reset()))
 }
 
-/// Sets the the background color to DarkYellow.
+/// Like [`crossterm_style_background_color_black`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color_dark_yellow() -> libc::c_int {
-    crossterm_style_background_color(Color::DarkYellow)
+pub unsafe extern "C" fn crossterm_style_background_color_black_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color_black()))
 }
 
-/// Sets the the background color to Blue.
+/// Like [`crossterm_style_background_color_dark_grey`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color_blue() -> libc::c_int {
-    crossterm_style_background_color(Color::Blue)
+pub unsafe extern "C" fn crossterm_style_background_color_dark_grey_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color_dark_grey()))
 }
 
-/// Sets the the background color to DarkBlue.
+/// Like [`crossterm_style_background_color_red`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color_dark_blue() -> libc::c_int {
-    crossterm_style_background_color(Color::DarkBlue)
+pub unsafe extern "C" fn crossterm_style_background_color_red_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color_red()))
 }
 
-/// Sets the the background color to Magenta.
+/// Like [`crossterm_style_background_color_dark_red`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color_magenta() -> libc::c_int {
-    crossterm_style_background_color(Color::Magenta)
+pub unsafe extern "C" fn crossterm_style_background_color_dark_red_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color_dark_red()))
 }
 
-/// Sets the the background color to DarkMagenta.
+/// Like [`crossterm_style_background_color_green`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color_dark_magenta() -> libc::c_int {
-    crossterm_style_background_color(Color::DarkMagenta)
+pub unsafe extern "C" fn crossterm_style_background_color_green_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color_green()))
 }
 
-/// Sets the the background color to Cyan.
+/// Like [`crossterm_style_background_color_dark_green`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color_cyan() -> libc::c_int {
-    crossterm_style_background_color(Color::Cyan)
+pub unsafe extern "C" fn crossterm_style_background_color_dark_green_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color_dark_green()))
 }
 
-/// Sets the the background color to DarkCyan.
+/// Like [`crossterm_style_background_color_yellow`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color_dark_cyan() -> libc::c_int {
-    crossterm_style_background_color(Color::DarkCyan)
+pub unsafe extern "C" fn crossterm_style_background_color_yellow_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color_yellow()))
 }
 
-/// Sets the the background color to White.
+/// Like [`crossterm_style_background_color_dark_yellow`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color_white() -> libc::c_int {
-    crossterm_style_background_color(Color::White)
+pub unsafe extern "C" fn crossterm_style_background_color_dark_yellow_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color_dark_yellow()))
 }
 
-/// Sets the the background color to Grey.
+/// Like [`crossterm_style_background_color_blue`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_background_color_grey() -> libc::c_int {
-    crossterm_style_background_color(Color::Grey)
+pub unsafe extern "C" fn crossterm_style_background_color_blue_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color_blue()))
 }
 
-/// Sets the the foreground color.
+/// Like [`crossterm_style_background_color_dark_blue`], in the context `ctx`.
 ///
-/// See [`Color`] for more info.
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color(color: Color) -> libc::c_int {
-    queue!(io!(), crossterm::style::SetForegroundColor(color.into())).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_background_color_dark_blue_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color_dark_blue()))
 }
 
-/// Sets the the foreground color in RGB.
+/// Like [`crossterm_style_background_color_magenta`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color_rgb(r: u8, g: u8, b: u8) -> libc::c_int {
-    crossterm_style_foreground_color(Color::Rgb { r, g, b })
+pub unsafe extern "C" fn crossterm_style_background_color_magenta_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color_magenta()))
 }
 
-/// Sets the the foreground color to an ANSI value.
+/// Like [`crossterm_style_background_color_dark_magenta`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color_ansi(value: u8) -> libc::c_int {
-    crossterm_style_foreground_color(Color::AnsiValue(value))
+pub unsafe extern "C" fn crossterm_style_background_color_dark_magenta_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color_dark_magenta()))
 }
 
-/// Sets the the foreground color to Reset.
+/// Like [`crossterm_style_background_color_cyan`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color_reset() -> libc::c_int {
-    crossterm_style_foreground_color(Color::Reset)
+pub unsafe extern "C" fn crossterm_style_background_color_cyan_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color_cyan()))
 }
 
-/// Sets the the foreground color to Black.
+/// Like [`crossterm_style_background_color_dark_cyan`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color_black() -> libc::c_int {
-    crossterm_style_foreground_color(Color::Black)
+pub unsafe extern "C" fn crossterm_style_background_color_dark_cyan_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color_dark_cyan()))
 }
 
-/// Sets the the foreground color to DarkGrey.
+/// Like [`crossterm_style_background_color_white`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color_dark_grey() -> libc::c_int {
-    crossterm_style_foreground_color(Color::DarkGrey)
+pub unsafe extern "C" fn crossterm_style_background_color_white_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color_white()))
 }
 
-/// Sets the the foreground color to Red.
+/// Like [`crossterm_style_background_color_grey`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color_red() -> libc::c_int {
-    crossterm_style_foreground_color(Color::Red)
+pub unsafe extern "C" fn crossterm_style_background_color_grey_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_background_color_grey()))
 }
 
-/// Sets the the foreground color to DarkRed.
+/// Like [`crossterm_style_foreground_color`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color_dark_red() -> libc::c_int {
-    crossterm_style_foreground_color(Color::DarkRed)
+pub unsafe extern "C" fn crossterm_style_foreground_color_ctx(
+    ctx: *mut Context,
+    color: Color,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color(color)))
 }
 
-/// Sets the the foreground color to Green.
+/// Like [`crossterm_style_foreground_color_rgb`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color_green() -> libc::c_int {
-    crossterm_style_foreground_color(Color::Green)
+pub unsafe extern "C" fn crossterm_style_foreground_color_rgb_ctx(
+    ctx: *mut Context,
+    r: u8,
+    g: u8,
+    b: u8,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color_rgb(r, g, b)))
 }
 
-/// Sets the the foreground color to DarkGreen.
+/// Like [`crossterm_style_foreground_color_ansi`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color_dark_green() -> libc::c_int {
-    crossterm_style_foreground_color(Color::DarkGreen)
+pub unsafe extern "C" fn crossterm_style_foreground_color_ansi_ctx(
+    ctx: *mut Context,
+    value: u8,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color_ansi(value)))
 }
 
-/// Sets the the foreground color to Yellow.
+/// Like [`crossterm_style_foreground_color_reset`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color_yellow() -> libc::c_int {
-    crossterm_style_foreground_color(Color::Yellow)
+pub unsafe extern "C" fn crossterm_style_foreground_color_reset_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color_reset()))
 }
 
-/// Sets the the foreground color to DarkYellow.
+/// Like [`crossterm_style_foreground_color_black`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color_dark_yellow() -> libc::c_int {
-    crossterm_style_foreground_color(Color::DarkYellow)
+pub unsafe extern "C" fn crossterm_style_foreground_color_black_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color_black()))
 }
 
-/// Sets the the foreground color to Blue.
+/// Like [`crossterm_style_foreground_color_dark_grey`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color_blue() -> libc::c_int {
-    crossterm_style_foreground_color(Color::Blue)
+pub unsafe extern "C" fn crossterm_style_foreground_color_dark_grey_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color_dark_grey()))
 }
 
-/// Sets the the foreground color to DarkBlue.
+/// Like [`crossterm_style_foreground_color_red`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color_dark_blue() -> libc::c_int {
-    crossterm_style_foreground_color(Color::DarkBlue)
+pub unsafe extern "C" fn crossterm_style_foreground_color_red_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color_red()))
 }
 
-/// Sets the the foreground color to Magenta.
+/// Like [`crossterm_style_foreground_color_dark_red`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color_magenta() -> libc::c_int {
-    crossterm_style_foreground_color(Color::Magenta)
+pub unsafe extern "C" fn crossterm_style_foreground_color_dark_red_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color_dark_red()))
 }
 
-/// Sets the the foreground color to DarkMagenta.
+/// Like [`crossterm_style_foreground_color_green`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color_dark_magenta() -> libc::c_int {
-    crossterm_style_foreground_color(Color::DarkMagenta)
+pub unsafe extern "C" fn crossterm_style_foreground_color_green_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color_green()))
 }
 
-/// Sets the the foreground color to Cyan.
+/// Like [`crossterm_style_foreground_color_dark_green`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color_cyan() -> libc::c_int {
-    crossterm_style_foreground_color(Color::Cyan)
+pub unsafe extern "C" fn crossterm_style_foreground_color_dark_green_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color_dark_green()))
 }
 
-/// Sets the the foreground color to DarkCyan.
+/// Like [`crossterm_style_foreground_color_yellow`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color_dark_cyan() -> libc::c_int {
-    crossterm_style_foreground_color(Color::DarkCyan)
+pub unsafe extern "C" fn crossterm_style_foreground_color_yellow_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color_yellow()))
 }
 
-/// Sets the the foreground color to White.
+/// Like [`crossterm_style_foreground_color_dark_yellow`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color_white() -> libc::c_int {
-    crossterm_style_foreground_color(Color::White)
+pub unsafe extern "C" fn crossterm_style_foreground_color_dark_yellow_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color_dark_yellow()))
 }
 
-/// Sets the the foreground color to Grey.
+/// Like [`crossterm_style_foreground_color_blue`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_foreground_color_grey() -> libc::c_int {
-    crossterm_style_foreground_color(Color::Grey)
+pub unsafe extern "C" fn crossterm_style_foreground_color_blue_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color_blue()))
 }
 
-/// Sets the the underline color.
+/// Like [`crossterm_style_foreground_color_dark_blue`], in the context `ctx`.
 ///
-/// See [`Color`] for more info.
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color(color: Color) -> libc::c_int {
-    queue!(io!(), crossterm::style::SetUnderlineColor(color.into())).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_foreground_color_dark_blue_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color_dark_blue()))
 }
 
-/// Sets the the underline color in RGB.
+/// Like [`crossterm_style_foreground_color_magenta`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color_rgb(r: u8, g: u8, b: u8) -> libc::c_int {
-    crossterm_style_underline_color(Color::Rgb { r, g, b })
+pub unsafe extern "C" fn crossterm_style_foreground_color_magenta_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color_magenta()))
 }
 
-/// Sets the the underline color to an ANSI value.
+/// Like [`crossterm_style_foreground_color_dark_magenta`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color_ansi(value: u8) -> libc::c_int {
-    crossterm_style_underline_color(Color::AnsiValue(value))
+pub unsafe extern "C" fn crossterm_style_foreground_color_dark_magenta_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color_dark_magenta()))
 }
 
-/// Sets the the underline color to Reset.
+/// Like [`crossterm_style_foreground_color_cyan`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color_reset() -> libc::c_int {
-    crossterm_style_underline_color(Color::Reset)
+pub unsafe extern "C" fn crossterm_style_foreground_color_cyan_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color_cyan()))
 }
 
-/// Sets the the underline color to Black.
+/// Like [`crossterm_style_foreground_color_dark_cyan`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color_black() -> libc::c_int {
-    crossterm_style_underline_color(Color::Black)
+pub unsafe extern "C" fn crossterm_style_foreground_color_dark_cyan_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color_dark_cyan()))
 }
 
-/// Sets the the underline color to DarkGrey.
+/// Like [`crossterm_style_foreground_color_white`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color_dark_grey() -> libc::c_int {
-    crossterm_style_underline_color(Color::DarkGrey)
+pub unsafe extern "C" fn crossterm_style_foreground_color_white_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color_white()))
+}
+
+/// Like [`crossterm_style_foreground_color_grey`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_style_foreground_color_grey_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_foreground_color_grey()))
+}
+
+/// Like [`crossterm_style_underline_color`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_style_underline_color_ctx(
+    ctx: *mut Context,
+    color: Color,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color(color)))
+}
+
+/// Like [`crossterm_style_underline_color_rgb`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_style_underline_color_rgb_ctx(
+    ctx: *mut Context,
+    r: u8,
+    g: u8,
+    b: u8,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color_rgb(r, g, b)))
 }
 
-/// Sets the the underline color to Red.
+/// Like [`crossterm_style_underline_color_ansi`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color_red() -> libc::c_int {
-    crossterm_style_underline_color(Color::Red)
+pub unsafe extern "C" fn crossterm_style_underline_color_ansi_ctx(
+    ctx: *mut Context,
+    value: u8,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color_ansi(value)))
 }
 
-/// Sets the the underline color to DarkRed.
+/// Like [`crossterm_style_underline_color_reset`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color_dark_red() -> libc::c_int {
-    crossterm_style_underline_color(Color::DarkRed)
+pub unsafe extern "C" fn crossterm_style_underline_color_reset_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color_reset()))
 }
 
-/// Sets the the underline color to Green.
+/// Like [`crossterm_style_underline_color_black`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color_green() -> libc::c_int {
-    crossterm_style_underline_color(Color::Green)
+pub unsafe extern "C" fn crossterm_style_underline_color_black_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color_black()))
 }
 
-/// Sets the the underline color to DarkGreen.
+/// Like [`crossterm_style_underline_color_dark_grey`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color_dark_green() -> libc::c_int {
-    crossterm_style_underline_color(Color::DarkGreen)
+pub unsafe extern "C" fn crossterm_style_underline_color_dark_grey_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color_dark_grey()))
 }
 
-/// Sets the the underline color to Yellow.
+/// Like [`crossterm_style_underline_color_red`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color_yellow() -> libc::c_int {
-    crossterm_style_underline_color(Color::Yellow)
+pub unsafe extern "C" fn crossterm_style_underline_color_red_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color_red()))
 }
 
-/// Sets the the underline color to DarkYellow.
+/// Like [`crossterm_style_underline_color_dark_red`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color_dark_yellow() -> libc::c_int {
-    crossterm_style_underline_color(Color::DarkYellow)
+pub unsafe extern "C" fn crossterm_style_underline_color_dark_red_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color_dark_red()))
 }
 
-/// Sets the the underline color to Blue.
+/// Like [`crossterm_style_underline_color_green`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color_blue() -> libc::c_int {
-    crossterm_style_underline_color(Color::Blue)
+pub unsafe extern "C" fn crossterm_style_underline_color_green_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color_green()))
 }
 
-/// Sets the the underline color to DarkBlue.
+/// Like [`crossterm_style_underline_color_dark_green`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color_dark_blue() -> libc::c_int {
-    crossterm_style_underline_color(Color::DarkBlue)
+pub unsafe extern "C" fn crossterm_style_underline_color_dark_green_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color_dark_green()))
 }
 
-/// Sets the the underline color to Magenta.
+/// Like [`crossterm_style_underline_color_yellow`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color_magenta() -> libc::c_int {
-    crossterm_style_underline_color(Color::Magenta)
+pub unsafe extern "C" fn crossterm_style_underline_color_yellow_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color_yellow()))
 }
 
-/// Sets the the underline color to DarkMagenta.
+/// Like [`crossterm_style_underline_color_dark_yellow`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color_dark_magenta() -> libc::c_int {
-    crossterm_style_underline_color(Color::DarkMagenta)
+pub unsafe extern "C" fn crossterm_style_underline_color_dark_yellow_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color_dark_yellow()))
 }
 
-/// Sets the the underline color to Cyan.
+/// Like [`crossterm_style_underline_color_blue`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color_cyan() -> libc::c_int {
-    crossterm_style_underline_color(Color::Cyan)
+pub unsafe extern "C" fn crossterm_style_underline_color_blue_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color_blue()))
 }
 
-/// Sets the the underline color to DarkCyan.
+/// Like [`crossterm_style_underline_color_dark_blue`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color_dark_cyan() -> libc::c_int {
-    crossterm_style_underline_color(Color::DarkCyan)
+pub unsafe extern "C" fn crossterm_style_underline_color_dark_blue_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color_dark_blue()))
 }
 
-/// Sets the the underline color to White.
+/// Like [`crossterm_style_underline_color_magenta`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color_white() -> libc::c_int {
-    crossterm_style_underline_color(Color::White)
+pub unsafe extern "C" fn crossterm_style_underline_color_magenta_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color_magenta()))
 }
 
-/// Sets the the underline color to Grey.
+/// Like [`crossterm_style_underline_color_dark_magenta`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_underline_color_grey() -> libc::c_int {
-    crossterm_style_underline_color(Color::Grey)
+pub unsafe extern "C" fn crossterm_style_underline_color_dark_magenta_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color_dark_magenta()))
 }
 
-/// Resets the colors back to default.
+/// Like [`crossterm_style_underline_color_cyan`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_style_reset_color() -> libc::c_int {
-    queue!(io!(), crossterm::style::ResetColor).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_underline_color_cyan_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color_cyan()))
 }
 
-/// Returns colors as a UTF-8 JSON string.
+/// Like [`crossterm_style_underline_color_dark_cyan`], in the context `ctx`.
 ///
-/// Null character is stored in the last location of buffer.
-/// Caller is responsible for memory associated with string buffer.
-/// Use [`crossterm_free_c_char`] to free data.
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_colors() -> *const libc::c_char {
-    convert_string_to_c_char(COLORS.to_string())
+pub unsafe extern "C" fn crossterm_style_underline_color_dark_cyan_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color_dark_cyan()))
 }
 
-/// Tells whether the raw mode is enabled.
+/// Like [`crossterm_style_underline_color_white`], in the context `ctx`.
 ///
-/// Check error message to see if this function failed
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub fn crossterm_terminal_is_raw_mode_enabled() -> bool {
-    crossterm::terminal::is_raw_mode_enabled().c_unwrap()
+pub unsafe extern "C" fn crossterm_style_underline_color_white_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color_white()))
 }
 
-/// Disables raw mode.
+/// Like [`crossterm_style_underline_color_grey`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_terminal_disable_raw_mode() -> libc::c_int {
-    crossterm::terminal::disable_raw_mode().c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_underline_color_grey_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_underline_color_grey()))
 }
 
-/// Enables raw mode.
+/// Like [`crossterm_style_reset_color`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_terminal_enable_raw_mode() -> libc::c_int {
-    crossterm::terminal::enable_raw_mode().c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_style_reset_color_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_style_reset_color()))
 }
 
-/// Get terminal size
-#[no_mangle]
-pub extern "C" fn crossterm_terminal_size(width: &mut u16, height: &mut u16) -> libc::c_int {
-    let (w, h) = crossterm::terminal::size().c_unwrap();
-    *width = w;
-    *height = h;
-    r!()
+/// Like [`crossterm_style_print_file_link`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
+/// The other arguments must be valid as for [`crossterm_style_print_file_link`].
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_style_print_file_link_ctx(
+    ctx: *mut Context,
+    path: *const libc::c_char,
+    line: u32,
+    column: u32,
+    text: *const libc::c_char,
+) -> libc::c_int {
+    catch_panic(|| {
+        context::with(ctx, || {
+            crossterm_style_print_file_link(path, line, column, text)
+        })
+    })
 }
 
-/// Sets the terminal buffer size `(cols, rows)`.
+/// Like [`crossterm_terminal_disable_raw_mode`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_terminal_size_set(cols: u16, rows: u16) -> libc::c_int {
-    queue!(io!(), crossterm::terminal::SetSize(cols, rows)).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_terminal_disable_raw_mode_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_terminal_disable_raw_mode()))
 }
 
-/// Disables line wrapping.
+/// Like [`crossterm_terminal_enable_raw_mode`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_terminal_disable_line_wrap() -> libc::c_int {
-    queue!(io!(), crossterm::terminal::DisableLineWrap).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_terminal_enable_raw_mode_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_terminal_enable_raw_mode()))
 }
 
-/// Enables line wrapping.
+/// Like [`crossterm_terminal_raw_mode_acquire`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_terminal_enable_line_wrap() -> libc::c_int {
-    queue!(io!(), crossterm::terminal::EnableLineWrap).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_terminal_raw_mode_acquire_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_terminal_raw_mode_acquire()))
 }
 
-/// Enters alternate screen.
+/// Like [`crossterm_terminal_raw_mode_release`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_terminal_enter_alternate_screen() -> libc::c_int {
-    queue!(io!(), crossterm::terminal::EnterAlternateScreen).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_terminal_raw_mode_release_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_terminal_raw_mode_release()))
 }
 
-/// Leaves alternate screen.
+/// Like [`crossterm_terminal_size`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_terminal_leave_alternate_screen() -> libc::c_int {
-    queue!(io!(), crossterm::terminal::LeaveAlternateScreen).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_terminal_size_ctx(
+    ctx: *mut Context,
+    width: &mut u16,
+    height: &mut u16,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_terminal_size(width, height)))
 }
 
-/// Different ways to clear the terminal buffer.
-#[repr(C)]
-pub enum ClearType {
-    /// All cells.
-    All,
-    /// All plus history
-    Purge,
-    /// All cells from the cursor position downwards.
-    FromCursorDown,
-    /// All cells from the cursor position upwards.
-    FromCursorUp,
-    /// All cells at the cursor row.
-    CurrentLine,
-    /// All cells from the cursor position until the new line.
-    UntilNewLine,
+/// Like [`crossterm_terminal_size_set`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_terminal_size_set_ctx(
+    ctx: *mut Context,
+    cols: u16,
+    rows: u16,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_terminal_size_set(cols, rows)))
 }
 
-impl From<ClearType> for crossterm::terminal::ClearType {
-    fn from(value: ClearType) -> Self {
-        match value {
-            ClearType::All => crossterm::terminal::ClearType::All,
-            ClearType::Purge => crossterm::terminal::ClearType::Purge,
-            ClearType::FromCursorDown => crossterm::terminal::ClearType::FromCursorDown,
-            ClearType::FromCursorUp => crossterm::terminal::ClearType::FromCursorUp,
-            ClearType::CurrentLine => crossterm::terminal::ClearType::CurrentLine,
-            ClearType::UntilNewLine => crossterm::terminal::ClearType::UntilNewLine,
-        }
-    }
+/// Like [`crossterm_terminal_disable_line_wrap`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_terminal_disable_line_wrap_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_terminal_disable_line_wrap()))
 }
 
-/// Scroll up command.
+/// Like [`crossterm_terminal_enable_line_wrap`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_terminal_scroll_up(n: libc::c_ushort) -> libc::c_int {
-    queue!(io!(), crossterm::terminal::ScrollUp(n)).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_terminal_enable_line_wrap_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_terminal_enable_line_wrap()))
 }
 
-/// Scroll down command.
+/// Like [`crossterm_terminal_enter_alternate_screen`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_terminal_scroll_down(n: libc::c_ushort) -> libc::c_int {
-    queue!(io!(), crossterm::terminal::ScrollDown(n)).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_terminal_enter_alternate_screen_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_terminal_enter_alternate_screen()))
 }
 
-/// Clear screen command.
+/// Like [`crossterm_terminal_leave_alternate_screen`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_terminal_clear(ct: ClearType) -> libc::c_int {
-    queue!(io!(), crossterm::terminal::Clear(ct.into())).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_terminal_leave_alternate_screen_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_terminal_leave_alternate_screen()))
 }
 
-/// Sets terminal title.
+/// Like [`crossterm_terminal_scroll_up`], in the context `ctx`.
 ///
 /// # Safety
 ///
-/// This function takes a raw pointer as argument. As such, the caller must ensure that:
-/// - The `title` pointer points to a valid null-terminated string.
-/// - This function borrows a slice to a valid null-terminated string and the memory referenced by `title` won't be deallocated or modified for the duration of the function call..
-/// - The `title` pointer is correctly aligned and `title` points to an initialized memory.
-///
-/// If these conditions are not met, the behavior is undefined.
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub unsafe extern "C" fn crossterm_terminal_title(title: *const libc::c_char) -> libc::c_int {
-    if title.is_null() {
-        RESULT.with(|r| {
-            *r.borrow_mut() = -1;
-        });
-        set_last_error(anyhow::anyhow!("Received null pointer for title string"));
-        return r!();
-    };
-    let c_str: &std::ffi::CStr = std::ffi::CStr::from_ptr(title);
-    if let Ok(string) = c_str.to_str() {
-        queue!(io!(), crossterm::terminal::SetTitle(string)).c_unwrap();
-        r!()
-    } else {
-        RESULT.with(|r| {
-            *r.borrow_mut() = -1;
-        });
-        set_last_error(anyhow::anyhow!("Received invalid UTF-8 string for title"));
-        r!()
-    }
+pub unsafe extern "C" fn crossterm_terminal_scroll_up_ctx(
+    ctx: *mut Context,
+    n: libc::c_ushort,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_terminal_scroll_up(n)))
 }
 
-/// Instructs the terminal emulator to begin a synchronized frame.
-///
-/// # Notes
+/// Like [`crossterm_terminal_scroll_down`], in the context `ctx`.
 ///
-/// * Commands must be executed/queued for execution otherwise they do nothing.
-/// * Use [`crossterm_terminal_end_synchronized_update`] command to leave the entered alternate screen.
+/// # Safety
 ///
-/// When rendering the screen of the terminal, the Emulator usually iterates through each visible grid cell and
-/// renders its current state. With applications updating the screen a at higher frequency this can cause tearing.
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_terminal_scroll_down_ctx(
+    ctx: *mut Context,
+    n: libc::c_ushort,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_terminal_scroll_down(n)))
+}
+
+/// Like [`crossterm_terminal_clear`], in the context `ctx`.
 ///
-/// This mode attempts to mitigate that.
+/// # Safety
 ///
-/// When the synchronization mode is enabled following render calls will keep rendering the last rendered state.
-/// The terminal Emulator keeps processing incoming text and sequences. When the synchronized update mode is disabled
-/// again the renderer may fetch the latest screen buffer state again, effectively avoiding the tearing effect
-/// by unintentionally rendering in the middle a of an application screen update.
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_terminal_begin_synchronized_update() -> libc::c_int {
-    queue!(io!(), crossterm::terminal::BeginSynchronizedUpdate).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_terminal_clear_ctx(
+    ctx: *mut Context,
+    ct: ClearType,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_terminal_clear(ct)))
 }
 
-/// Instructs the terminal to end a synchronized frame.
-///
-/// # Notes
+/// Like [`crossterm_terminal_title`], in the context `ctx`.
 ///
-/// * Commands must be executed/queued for execution otherwise they do nothing.
-/// * Use [`crossterm_terminal_begin_synchronized_update`] to enter the alternate screen.
+/// # Safety
 ///
-/// When rendering the screen of the terminal, the Emulator usually iterates through each visible grid cell and
-/// renders its current state. With applications updating the screen a at higher frequency this can cause tearing.
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
+/// The other arguments must be valid as for [`crossterm_terminal_title`].
+#[no_mangle]
+pub unsafe extern "C" fn crossterm_terminal_title_ctx(
+    ctx: *mut Context,
+    title: *const libc::c_char,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_terminal_title(title)))
+}
+
+/// Like [`crossterm_terminal_begin_synchronized_update`], in the context `ctx`.
 ///
-/// This mode attempts to mitigate that.
+/// # Safety
 ///
-/// When the synchronization mode is enabled following render calls will keep rendering the last rendered state.
-/// The terminal Emulator keeps processing incoming text and sequences. When the synchronized update mode is disabled
-/// again the renderer may fetch the latest screen buffer state again, effectively avoiding the tearing effect
-/// by unintentionally rendering in the middle a of an application screen update.
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_terminal_end_synchronized_update() -> libc::c_int {
-    queue!(io!(), crossterm::terminal::EndSynchronizedUpdate).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_terminal_begin_synchronized_update_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_terminal_begin_synchronized_update()))
 }
 
-/// Instructs the terminal to send a bell.
+/// Like [`crossterm_terminal_end_synchronized_update`], in the context `ctx`.
+///
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_terminal_ring_bell() -> libc::c_int {
-    queue!(io!(), crossterm::style::Print("\x07")).c_unwrap();
-    r!()
+pub unsafe extern "C" fn crossterm_terminal_end_synchronized_update_ctx(
+    ctx: *mut Context,
+) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_terminal_end_synchronized_update()))
 }
 
-/// Flush the stdout stream, ensuring that all intermediately buffered contents reach their destination.
+/// Like [`crossterm_terminal_ring_bell`], in the context `ctx`.
 ///
-/// It is considered an error if not all bytes could be written due to I/O errors or EOF being reached.
+/// # Safety
+///
+/// `ctx` must be a pointer returned by [`crossterm_context_new`] that was not freed, and not in use
+/// on another thread.
 #[no_mangle]
-pub extern "C" fn crossterm_flush() -> libc::c_int {
-    if let Err(err) = io!().flush() {
-        set_last_error(anyhow::anyhow!(err))
-    }
-    r!()
+pub unsafe extern "C" fn crossterm_terminal_ring_bell_ctx(ctx: *mut Context) -> libc::c_int {
+    catch_panic(|| context::with(ctx, || crossterm_terminal_ring_bell()))
 }
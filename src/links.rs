@@ -0,0 +1,83 @@
+//! [OSC 8 hyperlinks](https://gist.github.com/egmontkob/eb114294efbcd5adb1944c9f3cb5feaf) to
+//! files.
+
+use std::path::{Path, PathBuf};
+
+/// Wraps `text` in an OSC 8 hyperlink to `url`.
+fn hyperlink(url: &str, text: &str) -> String {
+    format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+}
+
+/// The name of this host, which terminals compare with their own to tell local files from files
+/// on a remote host.
+fn hostname() -> String {
+    #[cfg(unix)]
+    {
+        let mut buffer = [0u8; 256];
+        let rc =
+            unsafe { libc::gethostname(buffer.as_mut_ptr() as *mut libc::c_char, buffer.len()) };
+        if rc == 0 {
+            let len = buffer.iter().position(|b| *b == 0).unwrap_or(buffer.len());
+            return String::from_utf8_lossy(&buffer[..len]).into_owned();
+        }
+        String::new()
+    }
+    #[cfg(windows)]
+    {
+        std::env::var("COMPUTERNAME").unwrap_or_default()
+    }
+}
+
+/// Percent-encodes everything but unreserved characters and the given separators.
+fn percent_encode(s: &str, keep: &[u8]) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) || keep.contains(&b) {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+        }
+    }
+    encoded
+}
+
+/// Builds a `file://host/path` URL for `path`, made absolute against the current directory.
+///
+/// A non-zero `line` is added as the fragment, `#line` or `#line:column` with a non-zero `column`,
+/// which terminals such as kitty and WezTerm pass on to the editor that opens the file.
+fn file_url(path: &str, line: u32, column: u32) -> anyhow::Result<String> {
+    if path.is_empty() {
+        anyhow::bail!("Empty file path");
+    }
+    let absolute: PathBuf = std::path::absolute(Path::new(path))?;
+    let mut path = absolute.to_string_lossy().into_owned();
+    if cfg!(windows) {
+        // `C:\dir\file` is written `/C:/dir/file`
+        path = format!("/{}", path.replace('\\', "/"));
+    }
+    let mut url = format!(
+        "file://{}{}",
+        percent_encode(&hostname(), &[]),
+        percent_encode(&path, b"/:")
+    );
+    if line > 0 {
+        url.push_str(&format!("#{}", line));
+        if column > 0 {
+            url.push_str(&format!(":{}", column));
+        }
+    }
+    Ok(url)
+}
+
+/// Builds the hyperlink to a file location, showing `text` or else `path`, `path:line` or
+/// `path:line:column` as in compiler diagnostics.
+pub fn file_link(path: &str, line: u32, column: u32, text: Option<&str>) -> anyhow::Result<String> {
+    let url = file_url(path, line, column)?;
+    let label = match (text, line, column) {
+        (Some(text), _, _) => text.to_string(),
+        (None, 0, _) => path.to_string(),
+        (None, line, 0) => format!("{}:{}", path, line),
+        (None, line, column) => format!("{}:{}:{}", path, line, column),
+    };
+    Ok(hyperlink(&url, &label))
+}
@@ -0,0 +1,139 @@
+//! A file descriptor (a Windows event handle on Windows) that is signaled while events are ready,
+//! for applications that wait for input in their own event loop (epoll, kqueue, libuv, ...).
+//!
+//! The event pipeline signals the handle when it is unlocked with events ready, and clears it
+//! once they have been read, so it can be waited on level-triggered: wait, then read events until
+//! none is available. A watcher thread reads terminal input into the pipeline meanwhile.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use crate::events;
+
+static SIGNAL: OnceLock<native::Signal> = OnceLock::new();
+/// Whether the signal is set. Only changed with the pipeline locked.
+static SIGNALED: AtomicBool = AtomicBool::new(false);
+
+/// The signal, created together with its watcher thread on first use.
+pub fn signal() -> anyhow::Result<native::Signal> {
+    static INIT: Mutex<()> = Mutex::new(());
+    let _init = INIT.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(signal) = SIGNAL.get() {
+        return Ok(*signal);
+    }
+    let created = native::Signal::new()?;
+    std::thread::Builder::new()
+        .name("crossterm-notify".to_string())
+        .spawn(watch)?;
+    let signal = *SIGNAL.get_or_init(|| created);
+    // unlocking the pipeline signals the events that are ready already
+    drop(events::pipeline());
+    Ok(signal)
+}
+
+/// Sets the signal if `ready`, and clears it otherwise. Called with the pipeline locked whenever
+/// it is unlocked.
+pub fn update(ready: bool) {
+    let Some(signal) = SIGNAL.get() else {
+        return;
+    };
+    if SIGNALED.swap(ready, Ordering::Relaxed) != ready {
+        if ready {
+            signal.set();
+        } else {
+            signal.clear();
+        }
+    }
+}
+
+/// Reads terminal input into the pipeline until reading fails, e.g. without a terminal. Events
+/// queued by other means, like wakeups and signals, are still signaled after that.
+fn watch() {
+    while events::pump(None, None, || true).is_ok() {}
+}
+
+#[cfg(unix)]
+pub mod native {
+    /// The two ends of a pipe, which has a byte in it while signaled.
+    #[derive(Clone, Copy)]
+    pub struct Signal {
+        read: libc::c_int,
+        write: libc::c_int,
+    }
+
+    impl Signal {
+        pub fn new() -> anyhow::Result<Self> {
+            let mut fds = [0; 2];
+            if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            for fd in fds {
+                unsafe {
+                    let flags = libc::fcntl(fd, libc::F_GETFL);
+                    libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+                    libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+                }
+            }
+            Ok(Signal {
+                read: fds[0],
+                write: fds[1],
+            })
+        }
+
+        pub fn fd(&self) -> libc::c_int {
+            self.read
+        }
+
+        pub fn set(&self) {
+            unsafe { libc::write(self.write, [1u8].as_ptr() as *const libc::c_void, 1) };
+        }
+
+        pub fn clear(&self) {
+            let mut buffer = [0u8; 64];
+            while unsafe {
+                libc::read(
+                    self.read,
+                    buffer.as_mut_ptr() as *mut libc::c_void,
+                    buffer.len(),
+                )
+            } > 0
+            {}
+        }
+    }
+}
+
+#[cfg(windows)]
+pub mod native {
+    use winapi::um::synchapi::{CreateEventW, ResetEvent, SetEvent};
+
+    /// A manual-reset event. The handle is kept as an integer so the signal can be shared with the
+    /// watcher thread.
+    #[derive(Clone, Copy)]
+    pub struct Signal {
+        event: usize,
+    }
+
+    impl Signal {
+        pub fn new() -> anyhow::Result<Self> {
+            let event = unsafe { CreateEventW(std::ptr::null_mut(), 1, 0, std::ptr::null()) };
+            if event.is_null() {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            Ok(Signal {
+                event: event as usize,
+            })
+        }
+
+        pub fn handle(&self) -> *mut libc::c_void {
+            self.event as *mut libc::c_void
+        }
+
+        pub fn set(&self) {
+            unsafe { SetEvent(self.handle() as _) };
+        }
+
+        pub fn clear(&self) {
+            unsafe { ResetEvent(self.handle() as _) };
+        }
+    }
+}
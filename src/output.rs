@@ -0,0 +1,404 @@
+//! Where the output goes: stdout, stderr or a file descriptor, or a buffer while output is
+//! captured.
+//!
+//! Unless the stream's own buffering is used, output is collected in a buffer and written to the
+//! target when it is flushed, either explicitly or by the autoflush policy.
+//!
+//! A thread holds the output lock while it has a writer, so that the output of a call isn't
+//! interleaved with that of calls on other threads. The target, the policy, the buffer, the
+//! capture and the nesting of synchronized updates are shared by all threads, and only changed with the output lock held, so they don't
+//! change during a call on another thread.
+
+use std::borrow::Cow;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, LazyLock, Mutex, MutexGuard};
+use std::thread::ThreadId;
+
+use crate::{focus, screen, CUnwrapper};
+
+#[derive(Clone, Copy)]
+enum Target {
+    Stdout,
+    Stderr,
+    Fd(libc::c_int),
+}
+
+/// When buffered output is written to the target without an explicit flush.
+#[derive(Clone, Copy)]
+pub enum Autoflush {
+    /// Output isn't buffered by the library, only by the stream (stdout is line-buffered).
+    Stream,
+    /// After each call, when its writer is dropped.
+    Command,
+    /// Once this many bytes are buffered.
+    Threshold(usize),
+    /// Only when flushed.
+    Manual,
+}
+
+/// The output state shared by all threads.
+struct State {
+    target: Target,
+    autoflush: Autoflush,
+    /// The policy to go back to when execute mode is disabled.
+    before_execute: Option<Autoflush>,
+    /// Output buffered for the target.
+    pending: Vec<u8>,
+    /// Output captured since capturing began, instead of being written to the target.
+    capture: Option<Vec<u8>>,
+    /// Number of synchronized updates in progress.
+    synchronized: usize,
+}
+
+static STATE: LazyLock<Mutex<State>> = LazyLock::new(|| {
+    Mutex::new(State {
+        target: if std::env::var("LIBCROSSTERM_OUTPUT").unwrap_or_default() == "stdout" {
+            Target::Stdout
+        } else {
+            Target::Stderr
+        },
+        autoflush: Autoflush::Stream,
+        before_execute: None,
+        pending: Vec::new(),
+        capture: None,
+        synchronized: 0,
+    })
+});
+
+/// The output state. It is only locked for a moment, never while writing to the target.
+fn state() -> MutexGuard<'static, State> {
+    STATE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Whether printed text has its newlines translated to `\r\n`.
+static CRLF: AtomicBool = AtomicBool::new(false);
+
+/// The thread holding the output lock, and how many times it acquired it.
+static OWNER: Mutex<Option<(ThreadId, usize)>> = Mutex::new(None);
+static RELEASED: Condvar = Condvar::new();
+
+/// Holds the output lock, which a thread can acquire again while holding it.
+pub struct Lock;
+
+impl Lock {
+    pub fn acquire() -> Self {
+        let id = std::thread::current().id();
+        let mut owner = OWNER.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            match owner.as_mut() {
+                None => *owner = Some((id, 1)),
+                Some((thread, count)) if *thread == id => *count += 1,
+                Some(_) => {
+                    owner = RELEASED.wait(owner).unwrap_or_else(|e| e.into_inner());
+                    continue;
+                }
+            }
+            return Lock;
+        }
+    }
+
+    /// Whether the thread holds the lock only once, through this guard.
+    fn is_outermost(&self) -> bool {
+        let owner = OWNER.lock().unwrap_or_else(|e| e.into_inner());
+        matches!(*owner, Some((_, 1)))
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let mut owner = OWNER.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((_, count)) = owner.as_mut() {
+            *count -= 1;
+            if *count == 0 {
+                *owner = None;
+                RELEASED.notify_one();
+            }
+        }
+    }
+}
+
+/// A writer that holds the output lock.
+struct Locked {
+    out: Box<dyn Write>,
+    lock: Lock,
+}
+
+impl Write for Locked {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.out.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.out.flush()
+    }
+}
+
+impl Drop for Locked {
+    /// Flushes the output of the call in [`Autoflush::Command`] mode, so that each command is
+    /// written in one piece rather than as the fragments it is formatted in.
+    fn drop(&mut self) {
+        let due = matches!(state().autoflush, Autoflush::Command);
+        if due && self.lock.is_outermost() && !focus::focus().holds_flush() {
+            // a successful flush must not clear an error of the call
+            if let Err(e) = flush_pending() {
+                Err::<(), _>(e).c_unwrap();
+            }
+        }
+    }
+}
+
+/// Switches to `new` target, writing output buffered for the previous one first.
+fn set_target(new: Target) {
+    let _lock = Lock::acquire();
+    // a failed write only loses output meant for the previous target
+    let _ = flush_pending();
+    state().target = new;
+}
+
+pub fn use_stdout() {
+    set_target(Target::Stdout);
+}
+
+pub fn use_stderr() {
+    set_target(Target::Stderr);
+}
+
+/// Writes the output to `fd`, which stays owned by the caller.
+pub fn use_fd(fd: libc::c_int) -> anyhow::Result<()> {
+    if !is_open(fd) {
+        anyhow::bail!("Received invalid file descriptor {}", fd);
+    }
+    set_target(Target::Fd(fd));
+    Ok(())
+}
+
+#[cfg(unix)]
+fn is_open(fd: libc::c_int) -> bool {
+    fd >= 0 && unsafe { libc::fcntl(fd, libc::F_GETFD) } != -1
+}
+
+#[cfg(windows)]
+fn is_open(fd: libc::c_int) -> bool {
+    fd >= 0 && unsafe { libc::get_osfhandle(fd) } != -1
+}
+
+/// Sets the autoflush policy, writing output buffered so far. This ends execute mode.
+pub fn set_autoflush(autoflush: Autoflush) -> anyhow::Result<()> {
+    if let Autoflush::Threshold(0) = autoflush {
+        anyhow::bail!("Received zero threshold for flushing output");
+    }
+    let _lock = Lock::acquire();
+    flush_pending()?;
+    let mut state = state();
+    state.autoflush = autoflush;
+    state.before_execute = None;
+    Ok(())
+}
+
+/// Flushes after each call, or goes back to the policy that was used before that was enabled.
+pub fn set_execute(enable: bool) -> anyhow::Result<()> {
+    let _lock = Lock::acquire();
+    if enable {
+        let before = {
+            let state = state();
+            state.before_execute.unwrap_or(state.autoflush)
+        };
+        set_autoflush(Autoflush::Command)?;
+        state().before_execute = Some(before);
+        Ok(())
+    } else {
+        let before = state().before_execute.take();
+        match before {
+            Some(before) => set_autoflush(before),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Writes the buffered output to the target.
+fn flush_pending() -> std::io::Result<()> {
+    let _lock = Lock::acquire();
+    let pending = std::mem::take(&mut state().pending);
+    let mut out = target();
+    out.write_all(&pending)?;
+    out.flush()
+}
+
+pub fn set_crlf_translation(enabled: bool) {
+    CRLF.store(enabled, Ordering::SeqCst);
+}
+
+/// Translates the newlines of printed `text` that don't follow a `\r` to `\r\n` if that is
+/// enabled, so that lines start at the first column in raw mode.
+pub fn translate_newlines(text: &str) -> Cow<'_, str> {
+    if !CRLF.load(Ordering::SeqCst) || !text.contains('\n') {
+        return Cow::Borrowed(text);
+    }
+    let mut translated = String::with_capacity(text.len() + 8);
+    let mut previous = None;
+    for c in text.chars() {
+        if c == '\n' && previous != Some('\r') {
+            translated.push('\r');
+        }
+        translated.push(c);
+        previous = Some(c);
+    }
+    Cow::Owned(translated)
+}
+
+/// Begins capturing, writing the output buffered so far to the target first.
+pub fn begin_capture() -> anyhow::Result<()> {
+    let _lock = Lock::acquire();
+    if state().capture.is_some() {
+        anyhow::bail!("Output is already being captured");
+    }
+    flush_pending()?;
+    state().capture = Some(Vec::new());
+    Ok(())
+}
+
+/// Takes the output captured so far, and keeps capturing.
+pub fn take_capture() -> anyhow::Result<Vec<u8>> {
+    let _lock = Lock::acquire();
+    match state().capture.as_mut() {
+        Some(buffer) => Ok(std::mem::take(buffer)),
+        None => anyhow::bail!("Output is not being captured"),
+    }
+}
+
+/// Stops capturing, discarding the output that was not taken.
+pub fn end_capture() -> anyhow::Result<()> {
+    let _lock = Lock::acquire();
+    state()
+        .capture
+        .take()
+        .map(drop)
+        .ok_or_else(|| anyhow::anyhow!("Output is not being captured"))
+}
+
+/// The output, stderr unless `LIBCROSSTERM_OUTPUT` is `stdout`. Captured output never reaches the
+/// screen, so it isn't followed by the screen tracker.
+pub fn writer() -> Box<dyn Write> {
+    let lock = Lock::acquire();
+    let (autoflush, capturing) = {
+        let state = state();
+        (state.autoflush, state.capture.is_some())
+    };
+    let out: Box<dyn Write> = if capturing {
+        Box::new(CaptureWriter)
+    } else if let Autoflush::Stream = autoflush {
+        screen::tee(target())
+    } else {
+        screen::tee(Box::new(Buffered))
+    };
+    Box::new(Locked { out, lock })
+}
+
+/// Counts a synchronized update beginning, and returns whether it is the outermost one. Called
+/// with the output lock held.
+pub fn enter_synchronized() -> bool {
+    let mut state = state();
+    state.synchronized += 1;
+    state.synchronized == 1
+}
+
+/// Counts a synchronized update ending, and returns whether it was the outermost one. Called with
+/// the output lock held.
+pub fn leave_synchronized() -> bool {
+    let mut state = state();
+    let outermost = state.synchronized == 1;
+    state.synchronized = state.synchronized.saturating_sub(1);
+    outermost
+}
+
+/// Number of synchronized updates in progress.
+pub fn synchronized_depth() -> usize {
+    state().synchronized
+}
+
+/// Whether the output goes to a terminal.
+pub fn is_terminal() -> bool {
+    use std::io::IsTerminal;
+    match state().target {
+        Target::Stdout => std::io::stdout().is_terminal(),
+        Target::Stderr => std::io::stderr().is_terminal(),
+        Target::Fd(fd) => unsafe { libc::isatty(fd) == 1 },
+    }
+}
+
+fn target() -> Box<dyn Write> {
+    match state().target {
+        Target::Stdout => Box::new(std::io::stdout()),
+        Target::Stderr => Box::new(std::io::stderr()),
+        Target::Fd(fd) => Box::new(FdWriter(fd)),
+    }
+}
+
+/// Appends to the buffered output, and writes it to the target as the autoflush policy says.
+struct Buffered;
+
+impl Write for Buffered {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let (len, autoflush) = {
+            let mut state = state();
+            state.pending.extend_from_slice(buf);
+            (state.pending.len(), state.autoflush)
+        };
+        let due = match autoflush {
+            Autoflush::Stream => true,
+            Autoflush::Command | Autoflush::Manual => false,
+            Autoflush::Threshold(threshold) => len >= threshold,
+        };
+        if due && !focus::focus().holds_flush() {
+            flush_pending()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        flush_pending()
+    }
+}
+
+/// Writes to a file descriptor without buffering or closing it.
+struct FdWriter(libc::c_int);
+
+impl Write for FdWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        #[cfg(unix)]
+        let n = unsafe { libc::write(self.0, buf.as_ptr() as *const libc::c_void, buf.len()) };
+        #[cfg(windows)]
+        let n = unsafe {
+            let len = buf.len().min(libc::c_uint::MAX as usize) as libc::c_uint;
+            libc::write(self.0, buf.as_ptr() as *const libc::c_void, len)
+        };
+        if n < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(n as usize)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Appends to the captured output.
+struct CaptureWriter;
+
+impl Write for CaptureWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match state().capture.as_mut() {
+            Some(buffer) => {
+                buffer.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            None => Err(std::io::Error::other("Output is not being captured")),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+//! Fitting text into a number of columns: cut off at a grapheme boundary, with an ellipsis, and
+//! padded with spaces to the full width.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::Alignment;
+
+/// `text` cut off and padded to take up exactly `width` columns when printed on one line.
+///
+/// Text that is too wide ends in `ellipsis`, or as much of it as fits.
+pub fn pad(text: &str, width: usize, align: Alignment, ellipsis: &str) -> String {
+    let mut fitted = String::with_capacity(text.len().max(width));
+    let mut used = 0;
+    if text.width() <= width {
+        fitted.push_str(text);
+        used = text.width();
+    } else {
+        let ellipsis = fit(ellipsis, width);
+        let available = width - ellipsis.width();
+        for grapheme in text.graphemes(true) {
+            let w = grapheme.width();
+            if used + w > available {
+                break;
+            }
+            fitted.push_str(grapheme);
+            used += w;
+        }
+        fitted.push_str(ellipsis);
+        used += ellipsis.width();
+    }
+    let space = width - used;
+    let (left, right) = match align {
+        Alignment::Left => (0, space),
+        Alignment::Right => (space, 0),
+        Alignment::Center => (space / 2, space - space / 2),
+    };
+    format!("{}{}{}", " ".repeat(left), fitted, " ".repeat(right))
+}
+
+/// The longest start of `text` that fits in `width` columns.
+fn fit(text: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (i, grapheme) in text.grapheme_indices(true) {
+        used += grapheme.width();
+        if used > width {
+            return &text[..i];
+        }
+    }
+    text
+}
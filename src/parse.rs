@@ -0,0 +1,724 @@
+//! A port of `crossterm`'s parser of Unix terminal input, for the input a terminal query reads.
+//!
+//! While a query waits for its reply, it reads the terminal itself, and the user's input arrives
+//! mixed with the reply. `crossterm` doesn't expose its parser, so that input is parsed here the
+//! same way `crossterm` would have parsed it, and passed on to the event pipeline. Replies (OSC,
+//! DCS and APC strings, and the reports terminals send as CSI sequences) are returned as they are.
+//!
+//! The buffered bytes are parsed again after each byte, until they make an event or a reply,
+//! which is returned, or can't be the start of one, in which case they are dropped.
+#![cfg(unix)]
+
+use crate::crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers, MediaKeyCode,
+    ModifierKeyCode, MouseButton, MouseEvent, MouseEventKind,
+};
+
+/// What a complete sequence was read as.
+#[derive(Debug, PartialEq)]
+pub enum Parsed {
+    Event(Event),
+    /// A reply to a query, with the bytes of its sequence.
+    Reply(Vec<u8>),
+}
+
+/// What a complete sequence was read as, before its bytes are taken from the buffer.
+enum Sequence {
+    Event(Event),
+    Reply,
+}
+
+/// The buffered bytes can't be the start of a sequence.
+#[derive(Debug)]
+struct Invalid;
+
+/// `Ok(None)` if more bytes are needed.
+type Parse = Result<Option<Sequence>, Invalid>;
+
+#[derive(Default)]
+pub struct Parser {
+    buffer: Vec<u8>,
+}
+
+impl Parser {
+    /// Parses `bytes` after the bytes buffered by earlier calls. `more` is whether more input
+    /// may follow, in which case a lone ESC at the end is kept as the start of a sequence.
+    pub fn advance(&mut self, bytes: &[u8], more: bool) -> Vec<Parsed> {
+        let mut parsed = Vec::new();
+        for (i, byte) in bytes.iter().enumerate() {
+            self.buffer.push(*byte);
+            match parse_event(&self.buffer, i + 1 < bytes.len() || more) {
+                Ok(None) => {}
+                Ok(Some(Sequence::Event(event))) => {
+                    parsed.push(Parsed::Event(event));
+                    self.buffer.clear();
+                }
+                Ok(Some(Sequence::Reply)) => {
+                    parsed.push(Parsed::Reply(std::mem::take(&mut self.buffer)));
+                }
+                Err(Invalid) => self.buffer.clear(),
+            }
+        }
+        parsed
+    }
+
+    /// Parses the buffered bytes knowing that no more input follows. A lone ESC is the Esc key,
+    /// and a sequence that was cut off is dropped.
+    pub fn finish(&mut self) -> Option<Parsed> {
+        match parse_event(&std::mem::take(&mut self.buffer), false) {
+            Ok(Some(Sequence::Event(event))) => Some(Parsed::Event(event)),
+            _ => None,
+        }
+    }
+}
+
+fn event(event: Event) -> Parse {
+    Ok(Some(Sequence::Event(event)))
+}
+
+fn reply() -> Parse {
+    Ok(Some(Sequence::Reply))
+}
+
+fn key(key: impl Into<KeyEvent>) -> Parse {
+    event(Event::Key(key.into()))
+}
+
+fn raw_mode() -> bool {
+    crate::crossterm::terminal::is_raw_mode_enabled().unwrap_or(false)
+}
+
+fn parse_event(buffer: &[u8], more: bool) -> Parse {
+    match buffer {
+        [] => Ok(None),
+        [b'\x1b'] if more => Ok(None),
+        [b'\x1b'] => key(KeyCode::Esc),
+        [b'\x1b', b'O'] => Ok(None),
+        [b'\x1b', b'O', last, ..] => match last {
+            b'D' => key(KeyCode::Left),
+            b'C' => key(KeyCode::Right),
+            b'A' => key(KeyCode::Up),
+            b'B' => key(KeyCode::Down),
+            b'H' => key(KeyCode::Home),
+            b'F' => key(KeyCode::End),
+            b'P'..=b'S' => key(KeyCode::F(1 + last - b'P')),
+            _ => Err(Invalid),
+        },
+        [b'\x1b', b'[', ..] => parse_csi(buffer),
+        [b'\x1b', b']' | b'P' | b'_', ..] => parse_string(buffer, more),
+        [b'\x1b', b'\x1b', ..] => key(KeyCode::Esc),
+        [b'\x1b', rest @ ..] => Ok(parse_event(rest, more)?.map(|parsed| match parsed {
+            Sequence::Event(Event::Key(mut key)) => {
+                key.modifiers |= KeyModifiers::ALT;
+                Sequence::Event(Event::Key(key))
+            }
+            parsed => parsed,
+        })),
+        [b'\r', ..] => key(KeyCode::Enter),
+        // in raw mode, the terminal doesn't turn Enter into `\n`, so it is Ctrl+J
+        [b'\n', ..] if !raw_mode() => key(KeyCode::Enter),
+        [b'\t', ..] => key(KeyCode::Tab),
+        [b'\x7f', ..] => key(KeyCode::Backspace),
+        [c @ b'\x01'..=b'\x1a', ..] => key(KeyEvent::new(
+            KeyCode::Char((c - 0x01 + b'a') as char),
+            KeyModifiers::CONTROL,
+        )),
+        [c @ b'\x1c'..=b'\x1f', ..] => key(KeyEvent::new(
+            KeyCode::Char((c - 0x1c + b'4') as char),
+            KeyModifiers::CONTROL,
+        )),
+        [b'\0', ..] => key(KeyEvent::new(KeyCode::Char(' '), KeyModifiers::CONTROL)),
+        _ => parse_utf8_char(buffer),
+    }
+}
+
+fn parse_utf8_char(buffer: &[u8]) -> Parse {
+    match std::str::from_utf8(buffer) {
+        Ok(s) => {
+            let c = s.chars().next().ok_or(Invalid)?;
+            let modifiers = if c.is_uppercase() {
+                KeyModifiers::SHIFT
+            } else {
+                KeyModifiers::NONE
+            };
+            key(KeyEvent::new(KeyCode::Char(c), modifiers))
+        }
+        // the bytes so far are the start of a character
+        Err(e) if e.error_len().is_none() => Ok(None),
+        Err(_) => Err(Invalid),
+    }
+}
+
+fn parse_csi(buffer: &[u8]) -> Parse {
+    let Some(&first) = buffer.get(2) else {
+        return Ok(None);
+    };
+    match first {
+        b'[' => match buffer.get(3) {
+            None => Ok(None),
+            Some(c @ b'A'..=b'E') => key(KeyCode::F(1 + c - b'A')),
+            Some(_) => Err(Invalid),
+        },
+        b'D' => key(KeyCode::Left),
+        b'C' => key(KeyCode::Right),
+        b'A' => key(KeyCode::Up),
+        b'B' => key(KeyCode::Down),
+        b'H' => key(KeyCode::Home),
+        b'F' => key(KeyCode::End),
+        b'Z' => key(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT)),
+        b'M' => parse_normal_mouse(buffer),
+        b'<' => parse_sgr_mouse(buffer),
+        b'I' => event(Event::FocusGained),
+        b'O' => event(Event::FocusLost),
+        b';' => parse_modifier_key_code(buffer),
+        // the 1 of `CSI 1 P` is left out without modifiers under the kitty keyboard protocol
+        b'P' => key(KeyCode::F(1)),
+        b'Q' => key(KeyCode::F(2)),
+        b'S' => key(KeyCode::F(4)),
+        // private reports, like keyboard enhancement flags, device attributes and mode reports
+        b'?' | b'>' | b'=' => match buffer[buffer.len() - 1] {
+            64..=126 if buffer.len() > 3 => reply(),
+            _ => Ok(None),
+        },
+        b'0'..=b'9' => {
+            let last = buffer[buffer.len() - 1];
+            if buffer.len() == 3 || !(64..=126).contains(&last) {
+                return Ok(None);
+            }
+            if buffer.starts_with(b"\x1b[200~") {
+                return parse_bracketed_paste(buffer);
+            }
+            match last {
+                b'M' => parse_rxvt_mouse(buffer),
+                b'~' => parse_special_key_code(buffer),
+                b'u' => parse_csi_u_encoded_key_code(buffer),
+                // cursor position, window and status reports
+                b'R' | b't' | b'n' | b'y' => reply(),
+                _ => parse_modifier_key_code(buffer),
+            }
+        }
+        _ => Err(Invalid),
+    }
+}
+
+/// An OSC (`ESC ]`), DCS (`ESC P`) or APC (`ESC _`) string, ended by `ESC \` or, for OSC, BEL.
+/// Only strings that start like a reply are read as one, so that e.g. Alt+] typed while waiting is
+/// still a key.
+fn parse_string(buffer: &[u8], more: bool) -> Parse {
+    let reply_starts: &[u8] = match buffer[1] {
+        b']' => b"0123456789lL",
+        b'P' => b"0123456789>!$+=",
+        _ => b"G",
+    };
+    match buffer.get(2) {
+        None if more => return Ok(None),
+        None => {
+            let c = buffer[1] as char;
+            let modifiers = if c.is_uppercase() {
+                KeyModifiers::ALT | KeyModifiers::SHIFT
+            } else {
+                KeyModifiers::ALT
+            };
+            return key(KeyEvent::new(KeyCode::Char(c), modifiers));
+        }
+        Some(first) if !reply_starts.contains(first) => return Err(Invalid),
+        Some(_) => {}
+    }
+    match &buffer[buffer.len() - 2..] {
+        [b'\x1b', b'\\'] => reply(),
+        [_, b'\x07'] if buffer[1] == b']' => reply(),
+        // an ESC in the string must start its terminator
+        [b'\x1b', _] => Err(Invalid),
+        _ => Ok(None),
+    }
+}
+
+/// The parameters of a CSI sequence, between the introducer of `start` bytes and the final
+/// byte.
+fn params(buffer: &[u8], start: usize) -> Result<&str, Invalid> {
+    std::str::from_utf8(&buffer[start..buffer.len() - 1]).map_err(|_| Invalid)
+}
+
+fn next_parsed<'a, T: std::str::FromStr>(
+    params: &mut impl Iterator<Item = &'a str>,
+) -> Result<T, Invalid> {
+    params.next().ok_or(Invalid)?.parse().map_err(|_| Invalid)
+}
+
+/// Parses a `modifiers:kind` parameter.
+fn modifiers_and_kind<'a>(params: &mut impl Iterator<Item = &'a str>) -> Option<(u8, u8)> {
+    let mut parts = params.next()?.split(':');
+    let mask = parts.next()?.parse().ok()?;
+    let kind = parts.next().and_then(|kind| kind.parse().ok()).unwrap_or(1);
+    Some((mask, kind))
+}
+
+fn parse_modifiers(mask: u8) -> KeyModifiers {
+    let mask = mask.saturating_sub(1);
+    [
+        (1, KeyModifiers::SHIFT),
+        (2, KeyModifiers::ALT),
+        (4, KeyModifiers::CONTROL),
+        (8, KeyModifiers::SUPER),
+        (16, KeyModifiers::HYPER),
+        (32, KeyModifiers::META),
+    ]
+    .into_iter()
+    .filter(|(bit, _)| mask & bit != 0)
+    .fold(KeyModifiers::NONE, |modifiers, (_, modifier)| {
+        modifiers | modifier
+    })
+}
+
+fn parse_modifiers_to_state(mask: u8) -> KeyEventState {
+    let mask = mask.saturating_sub(1);
+    let mut state = KeyEventState::NONE;
+    if mask & 64 != 0 {
+        state |= KeyEventState::CAPS_LOCK;
+    }
+    if mask & 128 != 0 {
+        state |= KeyEventState::NUM_LOCK;
+    }
+    state
+}
+
+fn parse_key_event_kind(kind: u8) -> KeyEventKind {
+    match kind {
+        2 => KeyEventKind::Repeat,
+        3 => KeyEventKind::Release,
+        _ => KeyEventKind::Press,
+    }
+}
+
+/// The modifiers, kind and lock state of the `modifiers:kind` parameter, if there is one.
+fn key_modifiers<'a>(
+    params: &mut impl Iterator<Item = &'a str>,
+) -> (KeyModifiers, KeyEventKind, KeyEventState) {
+    match modifiers_and_kind(params) {
+        Some((mask, kind)) => (
+            parse_modifiers(mask),
+            parse_key_event_kind(kind),
+            parse_modifiers_to_state(mask),
+        ),
+        None => (KeyModifiers::NONE, KeyEventKind::Press, KeyEventState::NONE),
+    }
+}
+
+/// `CSI 1 ; modifiers X`, with `X` one of the final bytes of the cursor and F1-F4 keys.
+fn parse_modifier_key_code(buffer: &[u8]) -> Parse {
+    let mut params = params(buffer, 2)?.split(';');
+    params.next();
+    let (modifiers, kind) = match modifiers_and_kind(&mut params) {
+        Some((mask, kind)) => (parse_modifiers(mask), parse_key_event_kind(kind)),
+        None if buffer.len() > 3 => {
+            let mask = (buffer[buffer.len() - 2] as char)
+                .to_digit(10)
+                .ok_or(Invalid)?;
+            (parse_modifiers(mask as u8), KeyEventKind::Press)
+        }
+        None => (KeyModifiers::NONE, KeyEventKind::Press),
+    };
+    let code = match buffer[buffer.len() - 1] {
+        b'A' => KeyCode::Up,
+        b'B' => KeyCode::Down,
+        b'C' => KeyCode::Right,
+        b'D' => KeyCode::Left,
+        b'F' => KeyCode::End,
+        b'H' => KeyCode::Home,
+        b'P' => KeyCode::F(1),
+        b'Q' => KeyCode::F(2),
+        b'R' => KeyCode::F(3),
+        b'S' => KeyCode::F(4),
+        _ => return Err(Invalid),
+    };
+    key(KeyEvent::new_with_kind(code, modifiers, kind))
+}
+
+/// The kitty keyboard protocol's codes for keys that have no character.
+fn translate_functional_key_code(codepoint: u32) -> Option<(KeyCode, KeyEventState)> {
+    let keypad = match codepoint {
+        57399..=57408 => Some(KeyCode::Char(
+            char::from_digit(codepoint - 57399, 10).unwrap(),
+        )),
+        57409 => Some(KeyCode::Char('.')),
+        57410 => Some(KeyCode::Char('/')),
+        57411 => Some(KeyCode::Char('*')),
+        57412 => Some(KeyCode::Char('-')),
+        57413 => Some(KeyCode::Char('+')),
+        57414 => Some(KeyCode::Enter),
+        57415 => Some(KeyCode::Char('=')),
+        57416 => Some(KeyCode::Char(',')),
+        57417 => Some(KeyCode::Left),
+        57418 => Some(KeyCode::Right),
+        57419 => Some(KeyCode::Up),
+        57420 => Some(KeyCode::Down),
+        57421 => Some(KeyCode::PageUp),
+        57422 => Some(KeyCode::PageDown),
+        57423 => Some(KeyCode::Home),
+        57424 => Some(KeyCode::End),
+        57425 => Some(KeyCode::Insert),
+        57426 => Some(KeyCode::Delete),
+        57427 => Some(KeyCode::KeypadBegin),
+        _ => None,
+    };
+    if let Some(code) = keypad {
+        return Some((code, KeyEventState::KEYPAD));
+    }
+    let code = match codepoint {
+        57358 => KeyCode::CapsLock,
+        57359 => KeyCode::ScrollLock,
+        57360 => KeyCode::NumLock,
+        57361 => KeyCode::PrintScreen,
+        57362 => KeyCode::Pause,
+        57363 => KeyCode::Menu,
+        57376..=57398 => KeyCode::F((codepoint - 57376 + 13) as u8),
+        57428 => KeyCode::Media(MediaKeyCode::Play),
+        57429 => KeyCode::Media(MediaKeyCode::Pause),
+        57430 => KeyCode::Media(MediaKeyCode::PlayPause),
+        57431 => KeyCode::Media(MediaKeyCode::Reverse),
+        57432 => KeyCode::Media(MediaKeyCode::Stop),
+        57433 => KeyCode::Media(MediaKeyCode::FastForward),
+        57434 => KeyCode::Media(MediaKeyCode::Rewind),
+        57435 => KeyCode::Media(MediaKeyCode::TrackNext),
+        57436 => KeyCode::Media(MediaKeyCode::TrackPrevious),
+        57437 => KeyCode::Media(MediaKeyCode::Record),
+        57438 => KeyCode::Media(MediaKeyCode::LowerVolume),
+        57439 => KeyCode::Media(MediaKeyCode::RaiseVolume),
+        57440 => KeyCode::Media(MediaKeyCode::MuteVolume),
+        57441 => KeyCode::Modifier(ModifierKeyCode::LeftShift),
+        57442 => KeyCode::Modifier(ModifierKeyCode::LeftControl),
+        57443 => KeyCode::Modifier(ModifierKeyCode::LeftAlt),
+        57444 => KeyCode::Modifier(ModifierKeyCode::LeftSuper),
+        57445 => KeyCode::Modifier(ModifierKeyCode::LeftHyper),
+        57446 => KeyCode::Modifier(ModifierKeyCode::LeftMeta),
+        57447 => KeyCode::Modifier(ModifierKeyCode::RightShift),
+        57448 => KeyCode::Modifier(ModifierKeyCode::RightControl),
+        57449 => KeyCode::Modifier(ModifierKeyCode::RightAlt),
+        57450 => KeyCode::Modifier(ModifierKeyCode::RightSuper),
+        57451 => KeyCode::Modifier(ModifierKeyCode::RightHyper),
+        57452 => KeyCode::Modifier(ModifierKeyCode::RightMeta),
+        57453 => KeyCode::Modifier(ModifierKeyCode::IsoLevel3Shift),
+        57454 => KeyCode::Modifier(ModifierKeyCode::IsoLevel5Shift),
+        _ => return None,
+    };
+    Some((code, KeyEventState::NONE))
+}
+
+/// The key of a codepoint reported for a key in a `CSI u` sequence.
+fn char_key_code(codepoint: u32, modifiers: KeyModifiers) -> Result<KeyCode, Invalid> {
+    Ok(match char::from_u32(codepoint).ok_or(Invalid)? {
+        '\x1b' => KeyCode::Esc,
+        '\r' => KeyCode::Enter,
+        '\n' if !raw_mode() => KeyCode::Enter,
+        '\t' if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+        '\t' => KeyCode::Tab,
+        '\x7f' => KeyCode::Backspace,
+        c => KeyCode::Char(c),
+    })
+}
+
+/// `CSI code:shifted ; modifiers:kind u`, from the kitty keyboard protocol or fixterms. The
+/// shifted key is reported with the alternate keys enhancement.
+fn parse_csi_u_encoded_key_code(buffer: &[u8]) -> Parse {
+    let mut params = params(buffer, 2)?.split(';');
+    let mut codepoints = params.next().ok_or(Invalid)?.split(':');
+    let codepoint: u32 = next_parsed(&mut codepoints)?;
+    let (mut modifiers, kind, state) = key_modifiers(&mut params);
+    let (mut code, keypad) = match translate_functional_key_code(codepoint) {
+        Some(functional) => functional,
+        None => (char_key_code(codepoint, modifiers)?, KeyEventState::NONE),
+    };
+    if let KeyCode::Modifier(modifier) = code {
+        modifiers |= match modifier {
+            ModifierKeyCode::LeftAlt | ModifierKeyCode::RightAlt => KeyModifiers::ALT,
+            ModifierKeyCode::LeftControl | ModifierKeyCode::RightControl => KeyModifiers::CONTROL,
+            ModifierKeyCode::LeftShift | ModifierKeyCode::RightShift => KeyModifiers::SHIFT,
+            ModifierKeyCode::LeftSuper | ModifierKeyCode::RightSuper => KeyModifiers::SUPER,
+            ModifierKeyCode::LeftHyper | ModifierKeyCode::RightHyper => KeyModifiers::HYPER,
+            ModifierKeyCode::LeftMeta | ModifierKeyCode::RightMeta => KeyModifiers::META,
+            _ => KeyModifiers::NONE,
+        };
+    }
+    // with alternate keys reported, Shift comes with the shifted key, which replaces it
+    let shifted = codepoints
+        .next()
+        .and_then(|codepoint| codepoint.parse().ok())
+        .and_then(char::from_u32);
+    if let (true, Some(shifted)) = (modifiers.contains(KeyModifiers::SHIFT), shifted) {
+        code = KeyCode::Char(shifted);
+        modifiers -= KeyModifiers::SHIFT;
+    }
+    key(KeyEvent::new_with_kind_and_state(
+        code,
+        modifiers,
+        kind,
+        keypad | state,
+    ))
+}
+
+/// `CSI number ; modifiers:kind ~`.
+fn parse_special_key_code(buffer: &[u8]) -> Parse {
+    let mut params = params(buffer, 2)?.split(';');
+    let number: u8 = next_parsed(&mut params)?;
+    let (modifiers, kind, state) = key_modifiers(&mut params);
+    let code = match number {
+        1 | 7 => KeyCode::Home,
+        2 => KeyCode::Insert,
+        3 => KeyCode::Delete,
+        4 | 8 => KeyCode::End,
+        5 => KeyCode::PageUp,
+        6 => KeyCode::PageDown,
+        n @ 11..=15 => KeyCode::F(n - 10),
+        n @ 17..=21 => KeyCode::F(n - 11),
+        n @ 23..=26 => KeyCode::F(n - 12),
+        n @ 28..=29 => KeyCode::F(n - 15),
+        n @ 31..=34 => KeyCode::F(n - 17),
+        _ => return Err(Invalid),
+    };
+    key(KeyEvent::new_with_kind_and_state(
+        code, modifiers, kind, state,
+    ))
+}
+
+fn mouse(kind: MouseEventKind, column: u16, row: u16, modifiers: KeyModifiers) -> Parse {
+    event(Event::Mouse(MouseEvent {
+        kind,
+        column,
+        row,
+        modifiers,
+    }))
+}
+
+/// `CSI cb ; x ; y M`.
+fn parse_rxvt_mouse(buffer: &[u8]) -> Parse {
+    let mut params = params(buffer, 2)?.split(';');
+    let cb = next_parsed::<u8>(&mut params)?
+        .checked_sub(32)
+        .ok_or(Invalid)?;
+    let (kind, modifiers) = parse_cb(cb)?;
+    let column = next_parsed::<u16>(&mut params)?.saturating_sub(1);
+    let row = next_parsed::<u16>(&mut params)?.saturating_sub(1);
+    mouse(kind, column, row, modifiers)
+}
+
+/// `CSI M cb x y`, with each value a byte offset by 32.
+fn parse_normal_mouse(buffer: &[u8]) -> Parse {
+    if buffer.len() < 6 {
+        return Ok(None);
+    }
+    let cb = buffer[3].checked_sub(32).ok_or(Invalid)?;
+    let (kind, modifiers) = parse_cb(cb)?;
+    let column = u16::from(buffer[4].saturating_sub(32)).saturating_sub(1);
+    let row = u16::from(buffer[5].saturating_sub(32)).saturating_sub(1);
+    mouse(kind, column, row, modifiers)
+}
+
+/// `CSI < cb ; x ; y M`, or `m` for a release.
+fn parse_sgr_mouse(buffer: &[u8]) -> Parse {
+    let last = buffer[buffer.len() - 1];
+    if last != b'M' && last != b'm' {
+        return Ok(None);
+    }
+    let mut params = params(buffer, 3)?.split(';');
+    let (kind, modifiers) = parse_cb(next_parsed(&mut params)?)?;
+    let column = next_parsed::<u16>(&mut params)?.saturating_sub(1);
+    let row = next_parsed::<u16>(&mut params)?.saturating_sub(1);
+    let kind = match kind {
+        MouseEventKind::Down(button) if last == b'm' => MouseEventKind::Up(button),
+        kind => kind,
+    };
+    mouse(kind, column, row, modifiers)
+}
+
+/// The button, drag bit and modifiers of a mouse report. Buttons are in bits 0-1 and 6-7, and
+/// drags have bit 5 set.
+fn parse_cb(cb: u8) -> Result<(MouseEventKind, KeyModifiers), Invalid> {
+    let button = (cb & 0b0000_0011) | ((cb & 0b1100_0000) >> 4);
+    let dragging = cb & 0b0010_0000 != 0;
+    let kind = match (button, dragging) {
+        (0, false) => MouseEventKind::Down(MouseButton::Left),
+        (1, false) => MouseEventKind::Down(MouseButton::Middle),
+        (2, false) => MouseEventKind::Down(MouseButton::Right),
+        (0, true) => MouseEventKind::Drag(MouseButton::Left),
+        (1, true) => MouseEventKind::Drag(MouseButton::Middle),
+        (2, true) => MouseEventKind::Drag(MouseButton::Right),
+        (3, false) => MouseEventKind::Up(MouseButton::Left),
+        (3..=5, true) => MouseEventKind::Moved,
+        (4, false) => MouseEventKind::ScrollUp,
+        (5, false) => MouseEventKind::ScrollDown,
+        (6, false) => MouseEventKind::ScrollLeft,
+        (7, false) => MouseEventKind::ScrollRight,
+        _ => return Err(Invalid),
+    };
+    let mut modifiers = KeyModifiers::NONE;
+    if cb & 0b0000_0100 != 0 {
+        modifiers |= KeyModifiers::SHIFT;
+    }
+    if cb & 0b0000_1000 != 0 {
+        modifiers |= KeyModifiers::ALT;
+    }
+    if cb & 0b0001_0000 != 0 {
+        modifiers |= KeyModifiers::CONTROL;
+    }
+    Ok((kind, modifiers))
+}
+
+/// `CSI 200 ~ text CSI 201 ~`.
+fn parse_bracketed_paste(buffer: &[u8]) -> Parse {
+    if !buffer.ends_with(b"\x1b[201~") {
+        return Ok(None);
+    }
+    let text = String::from_utf8_lossy(&buffer[6..buffer.len() - 6]).into_owned();
+    event(Event::Paste(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Parsed {
+        Parsed::Event(Event::Key(KeyEvent::new(code, modifiers)))
+    }
+
+    fn parse(bytes: &[u8]) -> Vec<Parsed> {
+        let mut parser = Parser::default();
+        let mut parsed = parser.advance(bytes, true);
+        parsed.extend(parser.finish());
+        parsed
+    }
+
+    #[test]
+    fn parses_characters_and_control_keys() {
+        assert_eq!(
+            parse("aéA".as_bytes()),
+            vec![
+                key(KeyCode::Char('a'), KeyModifiers::NONE),
+                key(KeyCode::Char('é'), KeyModifiers::NONE),
+                key(KeyCode::Char('A'), KeyModifiers::SHIFT),
+            ]
+        );
+        assert_eq!(
+            parse(b"\x01\r\t\x7f\x1bx\x1b"),
+            vec![
+                key(KeyCode::Char('a'), KeyModifiers::CONTROL),
+                key(KeyCode::Enter, KeyModifiers::NONE),
+                key(KeyCode::Tab, KeyModifiers::NONE),
+                key(KeyCode::Backspace, KeyModifiers::NONE),
+                key(KeyCode::Char('x'), KeyModifiers::ALT),
+                key(KeyCode::Esc, KeyModifiers::NONE),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_csi_keys() {
+        assert_eq!(
+            parse(b"\x1b[A\x1b[1;5C\x1b[3~\x1b[15;2~\x1bOP\x1b[Z"),
+            vec![
+                key(KeyCode::Up, KeyModifiers::NONE),
+                key(KeyCode::Right, KeyModifiers::CONTROL),
+                key(KeyCode::Delete, KeyModifiers::NONE),
+                key(KeyCode::F(5), KeyModifiers::SHIFT),
+                key(KeyCode::F(1), KeyModifiers::NONE),
+                key(KeyCode::BackTab, KeyModifiers::SHIFT),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_kitty_keys() {
+        assert_eq!(
+            parse(b"\x1b[49:33;2u\x1b[97;1:3u\x1b[57441;2u"),
+            vec![
+                key(KeyCode::Char('!'), KeyModifiers::NONE),
+                Parsed::Event(Event::Key(KeyEvent::new_with_kind(
+                    KeyCode::Char('a'),
+                    KeyModifiers::NONE,
+                    KeyEventKind::Release
+                ))),
+                key(
+                    KeyCode::Modifier(ModifierKeyCode::LeftShift),
+                    KeyModifiers::SHIFT
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_mouse_paste_and_focus() {
+        assert_eq!(
+            parse(b"\x1b[<0;3;4M\x1b[<35;10;20M\x1b[I\x1b[200~a\x1b[Ab\x1b[201~"),
+            vec![
+                Parsed::Event(Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::Down(MouseButton::Left),
+                    column: 2,
+                    row: 3,
+                    modifiers: KeyModifiers::NONE,
+                })),
+                Parsed::Event(Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::Moved,
+                    column: 9,
+                    row: 19,
+                    modifiers: KeyModifiers::NONE,
+                })),
+                Parsed::Event(Event::FocusGained),
+                Parsed::Event(Event::Paste("a\x1b[Ab".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn tells_replies_from_input() {
+        assert_eq!(
+            parse(b"x\x1b]11;rgb:0000/0000/0000\x1b\\y\x1b[5;7R\x1bP>|kitty\x1b\\\x1b[?62c"),
+            vec![
+                key(KeyCode::Char('x'), KeyModifiers::NONE),
+                Parsed::Reply(b"\x1b]11;rgb:0000/0000/0000\x1b\\".to_vec()),
+                key(KeyCode::Char('y'), KeyModifiers::NONE),
+                Parsed::Reply(b"\x1b[5;7R".to_vec()),
+                Parsed::Reply(b"\x1bP>|kitty\x1b\\".to_vec()),
+                Parsed::Reply(b"\x1b[?62c".to_vec()),
+            ]
+        );
+        assert_eq!(
+            parse(b"\x1b]4;1;rgb:ff/00/00\x07\x1b[6;16;8t\x1b[?2026;2$y"),
+            vec![
+                Parsed::Reply(b"\x1b]4;1;rgb:ff/00/00\x07".to_vec()),
+                Parsed::Reply(b"\x1b[6;16;8t".to_vec()),
+                Parsed::Reply(b"\x1b[?2026;2$y".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_alt_keys_that_start_strings() {
+        assert_eq!(
+            parse(b"\x1b]"),
+            vec![key(KeyCode::Char(']'), KeyModifiers::ALT)]
+        );
+    }
+
+    #[test]
+    fn waits_for_the_rest_of_a_sequence() {
+        let mut parser = Parser::default();
+        assert_eq!(parser.advance(b"\x1b", true), vec![]);
+        assert_eq!(parser.advance(b"[1;", true), vec![]);
+        assert_eq!(
+            parser.advance(b"5D", true),
+            vec![key(KeyCode::Left, KeyModifiers::CONTROL)]
+        );
+        assert_eq!(parser.advance(b"\x1b]52;c;YQ", true), vec![]);
+        assert_eq!(
+            parser.advance(b"==\x07", true),
+            vec![Parsed::Reply(b"\x1b]52;c;YQ==\x07".to_vec())]
+        );
+    }
+
+    #[test]
+    fn drops_invalid_sequences() {
+        assert_eq!(
+            parse(b"\x1b[999;q\xffz\x1b[1;"),
+            vec![key(KeyCode::Char('z'), KeyModifiers::NONE)]
+        );
+    }
+}
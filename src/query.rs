@@ -0,0 +1,342 @@
+//! Terminal queries: writing a request to the terminal and reading back its reply.
+//!
+//! Every request is followed by a primary device attributes request (`CSI c`), which all
+//! terminals answer. Its reply marks the end of the answers to the actual request, so a terminal
+//! that ignores the request costs one round-trip instead of the full timeout.
+//!
+//! Other threads don't read terminal input while a query waits for its reply. Input the user
+//! typed meanwhile is told apart from the replies with [`crate::parse`] and passed on to the
+//! event pipeline.
+
+use std::time::Duration;
+
+use crate::colors::Rgb;
+
+/// Timeout used by queries that don't take one from the caller.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_millis(200);
+
+const DEVICE_ATTRIBUTES: &[u8] = b"\x1b[c";
+
+/// Sends `request` to the terminal and returns the replies received until the device attributes
+/// reply, which is not included.
+///
+/// Raw mode is enabled for the duration of the query if it isn't already.
+pub fn query(request: &[u8], timeout: Duration) -> anyhow::Result<Vec<u8>> {
+    query_replies(request, timeout, false)
+}
+
+/// [`query`], with focus reports read as replies if `focus_replies`.
+fn query_replies(
+    request: &[u8],
+    timeout: Duration,
+    focus_replies: bool,
+) -> anyhow::Result<Vec<u8>> {
+    let request = [request, DEVICE_ATTRIBUTES].concat();
+    let replies = exchange(&request, timeout, focus_replies, is_device_attributes)?;
+    Ok(replies
+        .into_iter()
+        .filter(|reply| !is_device_attributes(reply))
+        .collect::<Vec<_>>()
+        .concat())
+}
+
+/// Whether `reply` is a primary device attributes reply (`CSI ? ... c`).
+fn is_device_attributes(reply: &[u8]) -> bool {
+    reply.starts_with(b"\x1b[?") && reply.ends_with(b"c")
+}
+
+/// Sends `request` to the terminal and reads its input until `done` returns true for a reply,
+/// returning the replies up to and including that one. Focus reports are replies if
+/// `focus_replies`, and input otherwise. Other input is passed on to the event pipeline as it is
+/// read, also if the terminal doesn't reply within `timeout`.
+///
+/// Raw mode is enabled for the duration of the exchange if it isn't already.
+#[cfg(unix)]
+pub fn exchange(
+    request: &[u8],
+    timeout: Duration,
+    focus_replies: bool,
+    done: impl Fn(&[u8]) -> bool,
+) -> anyhow::Result<Vec<Vec<u8>>> {
+    use std::io::Write;
+    use std::os::unix::io::AsRawFd;
+
+    use crate::crossterm::terminal;
+
+    let _input = crate::events::lock_input();
+    let mut tty = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")?;
+    let was_raw = terminal::is_raw_mode_enabled()?;
+    if !was_raw {
+        terminal::enable_raw_mode()?;
+    }
+    let result = (|| {
+        tty.write_all(request)?;
+        tty.flush()?;
+        read_replies(tty.as_raw_fd(), timeout, focus_replies, done)
+    })();
+    if !was_raw {
+        terminal::disable_raw_mode()?;
+    }
+    result
+}
+
+#[cfg(not(unix))]
+pub fn exchange(
+    _request: &[u8],
+    _timeout: Duration,
+    _focus_replies: bool,
+    _done: impl Fn(&[u8]) -> bool,
+) -> anyhow::Result<Vec<Vec<u8>>> {
+    anyhow::bail!("Unsupported: terminal queries are only available on Unix")
+}
+
+#[cfg(unix)]
+fn read_replies(
+    fd: libc::c_int,
+    timeout: Duration,
+    focus_replies: bool,
+    done: impl Fn(&[u8]) -> bool,
+) -> anyhow::Result<Vec<Vec<u8>>> {
+    use crate::crossterm::event::Event;
+    use crate::parse::{Parsed, Parser};
+
+    let deadline = std::time::Instant::now() + timeout;
+    let mut parser = Parser::default();
+    let mut replies = Vec::new();
+    // passes on the input, and keeps the replies, returning whether `done` is among them
+    let mut sort = |parsed: Vec<Parsed>| {
+        let mut input = Vec::new();
+        let mut finished = false;
+        for parsed in parsed {
+            match parsed {
+                Parsed::Event(Event::FocusGained) if focus_replies => {
+                    replies.push(b"\x1b[I".to_vec())
+                }
+                Parsed::Event(Event::FocusLost) if focus_replies => {
+                    replies.push(b"\x1b[O".to_vec())
+                }
+                Parsed::Event(event) => input.push((event, Default::default())),
+                Parsed::Reply(reply) => {
+                    finished |= done(&reply);
+                    replies.push(reply);
+                }
+            }
+        }
+        crate::events::keep_input(input);
+        finished
+    };
+    loop {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        if remaining.is_zero() {
+            sort(parser.finish().into_iter().collect());
+            anyhow::bail!("Timed out waiting for the terminal to reply");
+        }
+        let mut pollfd = [libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        }];
+        let ready = crate::raw::poll(&mut pollfd, remaining.as_millis() as libc::c_int);
+        if ready < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(err.into());
+        }
+        if ready == 0 {
+            continue;
+        }
+        let mut buf = [0u8; 1024];
+        let n = unsafe { libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+        if n < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(err.into());
+        }
+        if sort(parser.advance(&buf[..n as usize], true)) {
+            sort(parser.finish().into_iter().collect());
+            return Ok(replies);
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Extracts the payloads of all OSC sequences (`ESC ] ... BEL` or `ESC ] ... ESC \`) in `bytes`.
+pub fn osc_payloads(bytes: &[u8]) -> Vec<String> {
+    let mut payloads = Vec::new();
+    let mut rest = bytes;
+    while let Some(start) = find(rest, b"\x1b]") {
+        let body = &rest[start + 2..];
+        let bel = body.iter().position(|b| *b == 0x07);
+        let st = find(body, b"\x1b\\");
+        let (end, terminator_len) = match (bel, st) {
+            (Some(b), Some(s)) if b < s => (b, 1),
+            (_, Some(s)) => (s, 2),
+            (Some(b), None) => (b, 1),
+            (None, None) => break,
+        };
+        payloads.push(String::from_utf8_lossy(&body[..end]).into_owned());
+        rest = &body[end + terminator_len..];
+    }
+    payloads
+}
+
+/// Parses an X11 color specification as used in OSC color replies, e.g. `rgb:ffff/8787/0000`.
+pub fn parse_x11_color(spec: &str) -> Option<Rgb> {
+    let spec = spec.trim();
+    if let Some(hex) = spec.strip_prefix('#') {
+        if hex.len() == 6 {
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            return Some((channel(0)?, channel(2)?, channel(4)?));
+        }
+        return None;
+    }
+    let body = spec.strip_prefix("rgb:")?;
+    let mut channels = body.split('/').map(|c| {
+        if c.is_empty() || c.len() > 4 {
+            return None;
+        }
+        let value = u32::from_str_radix(c, 16).ok()?;
+        let max = (1u32 << (4 * c.len())) - 1;
+        Some(((value * 255 + max / 2) / max) as u8)
+    });
+    let color = (channels.next()??, channels.next()??, channels.next()??);
+    if channels.next().is_some() {
+        return None;
+    }
+    Some(color)
+}
+
+/// Queries palette entries with OSC 4, returning the entries the terminal answered for.
+pub fn palette_colors(indices: &[u8], timeout: Duration) -> anyhow::Result<Vec<(u8, Rgb)>> {
+    let request: String = indices
+        .iter()
+        .map(|i| format!("\x1b]4;{};?\x1b\\", i))
+        .collect();
+    let reply = query(request.as_bytes(), timeout)?;
+    Ok(osc_payloads(&reply)
+        .iter()
+        .filter_map(|payload| {
+            let mut parts = payload.splitn(3, ';');
+            if parts.next()? != "4" {
+                return None;
+            }
+            let index = parts.next()?.parse().ok()?;
+            Some((index, parse_x11_color(parts.next()?)?))
+        })
+        .collect())
+}
+
+/// Queries a dynamic color with OSC 10 (foreground), 11 (background) or 12 (cursor).
+pub fn dynamic_color(code: u8, timeout: Duration) -> anyhow::Result<Rgb> {
+    let reply = query(format!("\x1b]{};?\x1b\\", code).as_bytes(), timeout)?;
+    osc_payloads(&reply)
+        .iter()
+        .find_map(|payload| {
+            let (number, spec) = payload.split_once(';')?;
+            if number.parse::<u8>().ok()? != code {
+                return None;
+            }
+            parse_x11_color(spec)
+        })
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unsupported: terminal did not reply to the OSC {} query",
+                code
+            )
+        })
+}
+
+/// Queries the window title with XTWINOPS 21 (`CSI 21 t`), which is answered with `OSC l title ST`.
+pub fn window_title(timeout: Duration) -> anyhow::Result<String> {
+    let reply = query(b"\x1b[21t", timeout)?;
+    osc_payloads(&reply)
+        .into_iter()
+        .find_map(|payload| payload.strip_prefix('l').map(str::to_string))
+        .ok_or_else(|| anyhow::anyhow!("Unsupported: terminal did not report its title"))
+}
+
+/// Queries the size of a cell in pixels with XTWINOPS 16 (`CSI 16 t`), which is answered with
+/// `CSI 6 ; height ; width t`. Returns `(width, height)`.
+pub fn cell_size(timeout: Duration) -> anyhow::Result<(u16, u16)> {
+    let reply = query(b"\x1b[16t", timeout)?;
+    let reply = String::from_utf8_lossy(&reply);
+    reply
+        .split("\x1b[6;")
+        .skip(1)
+        .find_map(|rest| {
+            let (params, _) = rest.split_once('t')?;
+            let (height, width) = params.split_once(';')?;
+            Some((width.parse().ok()?, height.parse().ok()?))
+        })
+        .ok_or_else(|| anyhow::anyhow!("Unsupported: terminal did not report its cell size"))
+}
+
+/// Asks for the focus state by enabling focus reporting (`CSI ? 1004 h`), which some terminals
+/// answer right away with `CSI I` when focused or `CSI O` when not. Focus reporting is disabled
+/// again unless `keep_enabled`.
+pub fn focus(keep_enabled: bool, timeout: Duration) -> anyhow::Result<bool> {
+    let request: &[u8] = if keep_enabled {
+        b"\x1b[?1004h"
+    } else {
+        b"\x1b[?1004h\x1b[?1004l"
+    };
+    let reply = query_replies(request, timeout, true)?;
+    // the last report is the current state
+    reply
+        .windows(3)
+        .rev()
+        .find_map(|report| match report {
+            b"\x1b[I" => Some(true),
+            b"\x1b[O" => Some(false),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow::anyhow!("Unsupported: terminal did not report its focus"))
+}
+
+/// Queries the active kitty keyboard protocol flags (`CSI ? u`), which are answered with
+/// `CSI ? flags u`.
+pub fn keyboard_enhancement_flags(timeout: Duration) -> anyhow::Result<u8> {
+    let reply = query(b"\x1b[?u", timeout)?;
+    let reply = String::from_utf8_lossy(&reply);
+    reply
+        .split("\x1b[?")
+        .skip(1)
+        .find_map(|rest| rest.split_once('u')?.0.parse().ok())
+        .ok_or_else(|| {
+            anyhow::anyhow!("Unsupported: terminal did not report keyboard enhancement flags")
+        })
+}
+
+/// Extracts the payloads of all DCS sequences (`ESC P ... ESC \`) in `bytes`.
+pub fn dcs_payloads(bytes: &[u8]) -> Vec<String> {
+    let mut payloads = Vec::new();
+    let mut rest = bytes;
+    while let Some(start) = find(rest, b"\x1bP") {
+        let body = &rest[start + 2..];
+        let Some(end) = find(body, b"\x1b\\") else {
+            break;
+        };
+        payloads.push(String::from_utf8_lossy(&body[..end]).into_owned());
+        rest = &body[end + 2..];
+    }
+    payloads
+}
+
+/// Queries the terminal name and version with XTVERSION (`CSI > q`), e.g. `kitty(0.31.0)`.
+pub fn terminal_version(timeout: Duration) -> anyhow::Result<String> {
+    let reply = query(b"\x1b[>q", timeout)?;
+    dcs_payloads(&reply)
+        .into_iter()
+        .find_map(|payload| payload.strip_prefix(">|").map(str::to_string))
+        .ok_or_else(|| anyhow::anyhow!("Unsupported: terminal did not report its version"))
+}
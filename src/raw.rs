@@ -0,0 +1,142 @@
+//! Reading the bytes the terminal sends as they are, without parsing them into events.
+//!
+//! Input is read from stdin if it is a terminal, and from the controlling terminal otherwise, like
+//! `crossterm` does, or always from the controlling terminal after [`set_use_dev_tty`]. Bytes that
+//! `crossterm` already read for events are not returned again.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+static USE_DEV_TTY: AtomicBool = AtomicBool::new(false);
+
+/// Reads input from `/dev/tty` even if stdin is a terminal.
+pub fn set_use_dev_tty(enable: bool) {
+    USE_DEV_TTY.store(enable, Ordering::Relaxed);
+}
+
+/// The controlling terminal, opened once.
+#[cfg(unix)]
+fn dev_tty() -> anyhow::Result<libc::c_int> {
+    use std::os::unix::io::AsRawFd;
+    use std::sync::OnceLock;
+
+    static TTY: OnceLock<std::fs::File> = OnceLock::new();
+
+    match TTY.get() {
+        Some(tty) => Ok(tty.as_raw_fd()),
+        None => {
+            let tty = std::fs::File::open("/dev/tty")?;
+            Ok(TTY.get_or_init(|| tty).as_raw_fd())
+        }
+    }
+}
+
+#[cfg(unix)]
+fn stdin_is_tty() -> bool {
+    unsafe { libc::isatty(libc::STDIN_FILENO) == 1 }
+}
+
+/// The file descriptor input is read from.
+#[cfg(unix)]
+fn input_fd() -> anyhow::Result<libc::c_int> {
+    if stdin_is_tty() && !USE_DEV_TTY.load(Ordering::Relaxed) {
+        return Ok(libc::STDIN_FILENO);
+    }
+    dev_tty()
+}
+
+/// A file descriptor of the terminal `crossterm` reads events from, for [`crate::input`] to wait
+/// on.
+#[cfg(unix)]
+pub fn event_fd() -> anyhow::Result<libc::c_int> {
+    if stdin_is_tty() {
+        return Ok(libc::STDIN_FILENO);
+    }
+    dev_tty()
+}
+
+/// `poll(2)` for input on `fds`, with a timeout in milliseconds (`-1` for none).
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn poll(fds: &mut [libc::pollfd], timeout: libc::c_int) -> libc::c_int {
+    unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, timeout) }
+}
+
+/// `poll(2)` for input on `fds`, with a timeout in milliseconds (`-1` for none). macOS can't
+/// `poll` terminal devices, so `select` is used there instead.
+#[cfg(target_os = "macos")]
+pub fn poll(fds: &mut [libc::pollfd], timeout: libc::c_int) -> libc::c_int {
+    unsafe {
+        let mut set: libc::fd_set = std::mem::zeroed();
+        libc::FD_ZERO(&mut set);
+        for pollfd in fds.iter() {
+            libc::FD_SET(pollfd.fd, &mut set);
+        }
+        let nfds = fds.iter().map(|pollfd| pollfd.fd).max().unwrap_or(-1) + 1;
+        let mut timeval = libc::timeval {
+            tv_sec: (timeout / 1000) as libc::time_t,
+            tv_usec: (timeout % 1000 * 1000) as libc::suseconds_t,
+        };
+        let timeval = if timeout < 0 {
+            std::ptr::null_mut()
+        } else {
+            &mut timeval as *mut libc::timeval
+        };
+        let ready = libc::select(
+            nfds,
+            &mut set,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            timeval,
+        );
+        for pollfd in fds.iter_mut() {
+            pollfd.revents = if ready > 0 && libc::FD_ISSET(pollfd.fd, &set) {
+                libc::POLLIN
+            } else {
+                0
+            };
+        }
+        ready
+    }
+}
+
+/// Reads up to `buf.len()` bytes, waiting up to `timeout` (forever if `None`) for input to
+/// arrive. Returns the number of bytes read, which is `0` if the timeout passed.
+#[cfg(unix)]
+pub fn read(buf: &mut [u8], timeout: Option<Duration>) -> anyhow::Result<usize> {
+    let fd = input_fd()?;
+    let timeout = timeout.map_or(-1, |t| {
+        t.as_millis().min(libc::c_int::MAX as u128) as libc::c_int
+    });
+    loop {
+        let mut pollfd = [libc::pollfd {
+            fd,
+            events: libc::POLLIN,
+            revents: 0,
+        }];
+        let ready = poll(&mut pollfd, timeout);
+        if ready < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(err.into());
+        }
+        if ready == 0 {
+            return Ok(0);
+        }
+        let n = unsafe { libc::read(fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+        if n < 0 {
+            let err = std::io::Error::last_os_error();
+            if err.kind() == std::io::ErrorKind::Interrupted {
+                continue;
+            }
+            return Err(err.into());
+        }
+        return Ok(n as usize);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn read(_buf: &mut [u8], _timeout: Option<Duration>) -> anyhow::Result<usize> {
+    anyhow::bail!("Unsupported: reading raw input bytes is only available on Unix")
+}
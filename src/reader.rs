@@ -0,0 +1,278 @@
+//! A background thread that keeps reading terminal input, so that input is parsed as it arrives
+//! even while the caller is busy.
+//!
+//! The reader passes input through the pipeline's stages as usual, and moves the finished events
+//! to a lock-free bounded queue, from which callers take them without waiting for the reader.
+//! When the queue is full, the [`OverflowPolicy`] decides whether the oldest or the newest events
+//! are dropped, or whether reading pauses until the caller catches up.
+//!
+//! Control events ([`Event::Wakeup`], [`Event::Signal`] and [`Event::Resumed`]) don't go through
+//! the queue, so they are never dropped. They wait in the pipeline and are read after the events
+//! in the queue.
+//!
+//! An error reading input is kept and returned by the next read of the caller, while the reader
+//! keeps trying.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crossbeam_queue::ArrayQueue;
+
+use crate::events::{self, Event, Pipeline};
+use crate::input;
+
+/// How long the reader waits before trying again after reading input failed.
+const RETRY_INTERVAL: Duration = Duration::from_millis(20);
+
+/// What the background reader does with new events when the queue is full.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drop the oldest waiting events to make room.
+    DropOldest,
+    /// Drop the new events.
+    DropNewest,
+    /// Stop reading input until events have been read. Input waits in the terminal's buffer
+    /// meanwhile.
+    Block,
+}
+
+struct Reader {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+static READER: Mutex<Option<Reader>> = Mutex::new(None);
+
+/// The queue of the running reader. The lock only guards replacing it when the reader starts or
+/// stops; the reader and its callers share it with read locks, which don't wait for each other.
+static QUEUE: RwLock<Option<Arc<ArrayQueue<Event>>>> = RwLock::new(None);
+
+/// Locked by the reader to wait for room in the queue with [`ROOM`].
+static WAIT: Mutex<()> = Mutex::new(());
+/// Notified when an event was taken from the queue while the reader waits for room.
+static ROOM: Condvar = Condvar::new();
+/// Whether the reader waits for room in the queue.
+static WAITING: AtomicBool = AtomicBool::new(false);
+
+/// Events dropped since the reader was started.
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// The last error reading input that wasn't returned to the caller yet.
+static ERROR: Mutex<Option<anyhow::Error>> = Mutex::new(None);
+
+pub fn start(capacity: usize, policy: OverflowPolicy) -> anyhow::Result<()> {
+    if capacity == 0 {
+        anyhow::bail!("Received zero capacity for the event reader");
+    }
+    let mut reader = READER.lock().unwrap_or_else(|e| e.into_inner());
+    if reader.is_some() {
+        anyhow::bail!("The event reader is already running");
+    }
+    DROPPED.store(0, Ordering::Relaxed);
+    *ERROR.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    let queue = Arc::new(ArrayQueue::new(capacity));
+    *QUEUE.write().unwrap_or_else(|e| e.into_inner()) = Some(queue.clone());
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread = std::thread::Builder::new()
+        .name("crossterm-reader".to_string())
+        .spawn({
+            let stop = stop.clone();
+            move || run(&queue, policy, &stop)
+        });
+    let thread = match thread {
+        Ok(thread) => thread,
+        Err(e) => {
+            *QUEUE.write().unwrap_or_else(|e| e.into_inner()) = None;
+            return Err(e.into());
+        }
+    };
+    *reader = Some(Reader { stop, thread });
+    Ok(())
+}
+
+pub fn stop() -> anyhow::Result<()> {
+    let reader = READER.lock().unwrap_or_else(|e| e.into_inner()).take();
+    let Some(reader) = reader else {
+        anyhow::bail!("The event reader is not running");
+    };
+    reader.stop.store(true, Ordering::SeqCst);
+    notify_room();
+    input::wake();
+    let joined = reader
+        .thread
+        .join()
+        .map_err(|_| anyhow::anyhow!("The event reader thread panicked"));
+    // the events left in the queue are older than the ones waiting in the pipeline
+    let mut pipeline = events::pipeline();
+    if let Some(queue) = QUEUE.write().unwrap_or_else(|e| e.into_inner()).take() {
+        pipeline.restore(std::iter::from_fn(|| queue.pop()).collect());
+    }
+    joined
+}
+
+pub fn dropped() -> u64 {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+/// Returns the error the reader ran into since the last call, if any.
+pub fn take_error() -> anyhow::Result<()> {
+    match ERROR.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Takes the oldest event from the queue, if the reader runs and the queue isn't empty.
+pub fn pop() -> Option<Event> {
+    let event = QUEUE
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()?
+        .pop()?;
+    if WAITING.load(Ordering::SeqCst) {
+        notify_room();
+    }
+    Some(event)
+}
+
+/// Whether the queue has events.
+pub fn has_queued() -> bool {
+    QUEUE
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .is_some_and(|queue| !queue.is_empty())
+}
+
+/// Wakes the reader if it waits for room in the queue.
+fn notify_room() {
+    // taking the lock orders this after the reader's check for room
+    drop(WAIT.lock().unwrap_or_else(|e| e.into_inner()));
+    ROOM.notify_one();
+}
+
+fn run(queue: &ArrayQueue<Event>, policy: OverflowPolicy, stop: &AtomicBool) {
+    while !stop.load(Ordering::SeqCst) {
+        let mut bound = None;
+        if policy == OverflowPolicy::Block {
+            let mut wait = WAIT.lock().unwrap_or_else(|e| e.into_inner());
+            WAITING.store(true, Ordering::SeqCst);
+            while queue.is_full() && !stop.load(Ordering::SeqCst) {
+                wait = ROOM.wait(wait).unwrap_or_else(|e| e.into_inner());
+            }
+            WAITING.store(false, Ordering::SeqCst);
+            bound = Some(queue.capacity() - queue.len());
+        }
+        if let Err(e) = events::pump(None, bound, || !stop.load(Ordering::SeqCst)) {
+            *ERROR.lock().unwrap_or_else(|e| e.into_inner()) = Some(e);
+            // e.g. no terminal to read from: retry rather than spinning
+            std::thread::sleep(RETRY_INTERVAL);
+        }
+        let dropped = hand_over(&mut events::pipeline(), queue, policy);
+        DROPPED.fetch_add(dropped, Ordering::Relaxed);
+    }
+}
+
+/// Moves the events ready in the pipeline to the queue, other than control events, and returns
+/// the number of events dropped because the queue was full.
+fn hand_over(pipeline: &mut Pipeline, queue: &ArrayQueue<Event>, policy: OverflowPolicy) -> u64 {
+    let mut dropped = 0;
+    let mut full = false;
+    pipeline.hand_over(|event| {
+        if event.is_control() || full {
+            return Some(event);
+        }
+        match policy {
+            OverflowPolicy::DropOldest => {
+                if queue.force_push(event).is_some() {
+                    dropped += 1;
+                }
+                None
+            }
+            OverflowPolicy::DropNewest => {
+                if queue.push(event).is_err() {
+                    dropped += 1;
+                }
+                None
+            }
+            // the rest waits in the pipeline, in order, until there is room
+            OverflowPolicy::Block => match queue.push(event) {
+                Ok(()) => None,
+                Err(event) => {
+                    full = true;
+                    Some(event)
+                }
+            },
+        }
+    });
+    dropped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crossterm;
+    use crate::events::Details;
+
+    fn resize(n: u16) -> Event {
+        Event::Terminal(crossterm::event::Event::Resize(n, n), Details::default())
+    }
+
+    fn sizes(events: impl IntoIterator<Item = Event>) -> Vec<Option<u16>> {
+        events
+            .into_iter()
+            .map(|event| match event {
+                Event::Terminal(crossterm::event::Event::Resize(n, _), _) => Some(n),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn waiting(pipeline: &mut Pipeline) -> Vec<Event> {
+        let mut events = Vec::new();
+        pipeline.hand_over(|event| {
+            events.push(event);
+            None
+        });
+        events
+    }
+
+    #[test]
+    fn keeps_control_events_when_the_queue_is_full() {
+        for (policy, queued) in [
+            (OverflowPolicy::DropOldest, vec![Some(2), Some(3)]),
+            (OverflowPolicy::DropNewest, vec![Some(0), Some(1)]),
+        ] {
+            let mut pipeline = Pipeline::default();
+            pipeline.restore(vec![
+                resize(0),
+                resize(1),
+                Event::Wakeup,
+                resize(2),
+                Event::Resumed,
+                resize(3),
+            ]);
+            let queue = ArrayQueue::new(2);
+            assert_eq!(hand_over(&mut pipeline, &queue, policy), 2);
+            assert_eq!(sizes(std::iter::from_fn(|| queue.pop())), queued);
+            let kept = waiting(&mut pipeline);
+            assert!(matches!(kept[..], [Event::Wakeup, Event::Resumed]));
+        }
+    }
+
+    #[test]
+    fn blocks_without_dropping_events() {
+        let mut pipeline = Pipeline::default();
+        pipeline.restore((0..4).map(resize).collect());
+        let queue = ArrayQueue::new(2);
+        assert_eq!(hand_over(&mut pipeline, &queue, OverflowPolicy::Block), 0);
+        assert_eq!(
+            sizes(std::iter::from_fn(|| queue.pop())),
+            [Some(0), Some(1)]
+        );
+        assert_eq!(sizes(waiting(&mut pipeline)), [Some(2), Some(3)]);
+    }
+}
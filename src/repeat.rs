@@ -0,0 +1,198 @@
+//! Synthesized key auto-repeat for terminals that don't report key event kinds.
+//!
+//! Without the kitty keyboard protocol, a held key arrives as a stream of identical presses. Once
+//! the same key is pressed again within the repeat window, the key is taken to be held: the
+//! terminal's presses are swallowed and `Repeat` events are produced at a steady interval instead,
+//! until the terminal stops sending the key for longer than the window.
+//!
+//! This is a timing heuristic: a key tapped twice within the window is reported as held.
+
+use std::time::{Duration, Instant};
+
+use crate::crossterm::event::{KeyEvent, KeyEventKind};
+use crate::events::KeyInput;
+use crate::keys::Key;
+
+struct Held {
+    event: KeyInput,
+    /// When the terminal last sent the key.
+    last_seen: Instant,
+    /// When the next `Repeat` is due, once the key is known to be held.
+    next_repeat: Option<Instant>,
+}
+
+#[derive(Default)]
+pub struct KeyRepeat {
+    /// Interval between synthesized repeats (`None` if synthesizing is disabled). A zero interval
+    /// produces one `Repeat` per press sent by the terminal.
+    interval: Option<Duration>,
+    window: Duration,
+    held: Option<Held>,
+}
+
+impl KeyRepeat {
+    /// Enables synthesizing repeats every `interval` for keys pressed again within `window`.
+    pub fn enable(&mut self, interval: Duration, window: Duration) {
+        self.interval = Some(interval);
+        self.window = window;
+        self.held = None;
+    }
+
+    pub fn disable(&mut self) {
+        self.interval = None;
+        self.held = None;
+    }
+
+    /// Feeds a key event and returns the events to pass on in its place.
+    pub fn feed(&mut self, event: KeyInput, now: Instant) -> Vec<KeyInput> {
+        let Some(interval) = self.interval else {
+            return vec![event];
+        };
+        if event.event.kind != KeyEventKind::Press {
+            // the terminal reports event kinds itself
+            self.held = None;
+            return vec![event];
+        }
+        let mut output = self.expire(now);
+        match self.held.as_mut() {
+            Some(held)
+                if Key::from(&held.event.event) == Key::from(&event.event)
+                    && now.saturating_duration_since(held.last_seen) <= self.window =>
+            {
+                held.last_seen = now;
+                if interval.is_zero() {
+                    output.push(repeat(&held.event));
+                } else if held.next_repeat.is_none() {
+                    output.push(repeat(&held.event));
+                    held.next_repeat = now.checked_add(interval);
+                }
+            }
+            _ => {
+                self.held = Some(Held {
+                    event: event.clone(),
+                    last_seen: now,
+                    next_repeat: None,
+                });
+                output.push(event);
+            }
+        }
+        output
+    }
+
+    /// Anything other than a key press means no key is held anymore.
+    pub fn reset(&mut self) {
+        self.held = None;
+    }
+
+    /// Produces the repeats that are due, and forgets the held key once the terminal stopped
+    /// sending it.
+    pub fn expire(&mut self, now: Instant) -> Vec<KeyInput> {
+        let mut output = Vec::new();
+        let (Some(interval), Some(held)) = (self.interval, self.held.as_mut()) else {
+            return output;
+        };
+        let released = held.last_seen.checked_add(self.window);
+        while let Some(due) = held.next_repeat {
+            if due > now || released.is_some_and(|released| due > released) {
+                break;
+            }
+            output.push(repeat(&held.event));
+            held.next_repeat = due.checked_add(interval);
+        }
+        if released.is_some_and(|released| now > released) {
+            self.held = None;
+        }
+        output
+    }
+
+    /// When the next repeat is due or the held key is forgotten.
+    pub fn deadline(&self) -> Option<Instant> {
+        let held = self.held.as_ref()?;
+        let released = held.last_seen.checked_add(self.window);
+        match (held.next_repeat, released) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+fn repeat(key: &KeyInput) -> KeyInput {
+    KeyInput {
+        event: KeyEvent {
+            kind: KeyEventKind::Repeat,
+            ..key.event
+        },
+        details: key.details.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crossterm::event::{KeyCode, KeyModifiers};
+
+    const INTERVAL: Duration = Duration::from_millis(30);
+    const WINDOW: Duration = Duration::from_millis(100);
+
+    fn press(c: char) -> KeyInput {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE).into()
+    }
+
+    fn kinds(events: &[KeyInput]) -> Vec<KeyEventKind> {
+        events.iter().map(|key| key.event.kind).collect()
+    }
+
+    #[test]
+    fn repeats_a_held_key_at_the_interval() {
+        let mut repeat = KeyRepeat::default();
+        repeat.enable(INTERVAL, WINDOW);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        assert_eq!(
+            kinds(&repeat.feed(press('a'), at(0))),
+            [KeyEventKind::Press]
+        );
+        assert_eq!(
+            kinds(&repeat.feed(press('a'), at(50))),
+            [KeyEventKind::Repeat]
+        );
+        // the terminal's own repeats are swallowed
+        assert_eq!(kinds(&repeat.feed(press('a'), at(70))), []);
+        assert_eq!(
+            kinds(&repeat.expire(at(110))),
+            [KeyEventKind::Repeat, KeyEventKind::Repeat]
+        );
+        // the terminal stopped sending the key: repeats are due up to the end of the window
+        assert_eq!(
+            kinds(&repeat.expire(at(300))),
+            [KeyEventKind::Repeat, KeyEventKind::Repeat]
+        );
+        assert_eq!(repeat.deadline(), None);
+    }
+
+    #[test]
+    fn presses_other_keys_and_late_presses() {
+        let mut repeat = KeyRepeat::default();
+        repeat.enable(INTERVAL, WINDOW);
+        let start = Instant::now();
+        repeat.feed(press('a'), start);
+        assert_eq!(
+            kinds(&repeat.feed(press('b'), start)),
+            [KeyEventKind::Press]
+        );
+        let late = start + WINDOW * 2;
+        assert_eq!(kinds(&repeat.feed(press('b'), late)), [KeyEventKind::Press]);
+    }
+
+    #[test]
+    fn passes_on_reported_kinds() {
+        let mut repeat = KeyRepeat::default();
+        repeat.enable(INTERVAL, WINDOW);
+        let release = KeyInput::from(KeyEvent::new_with_kind(
+            KeyCode::Char('a'),
+            KeyModifiers::NONE,
+            KeyEventKind::Release,
+        ));
+        assert_eq!(repeat.feed(release.clone(), Instant::now()), vec![release]);
+    }
+}
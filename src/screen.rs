@@ -0,0 +1,537 @@
+//! Capturing the text on the terminal screen.
+//!
+//! Terminals can't be asked for their contents with escape sequences, so the screen is read
+//! through the platform or the multiplexer where possible: the console buffer on Windows, and the
+//! command line tools of tmux, WezTerm and kitty. Otherwise the library's own copy of the screen
+//! is used if tracking is enabled: a small VT emulator that follows everything written through
+//! the library. It understands the cursor movement, erasing, scrolling and alternate screen
+//! sequences applications use, and ignores styles.
+
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex, MutexGuard};
+
+use unicode_width::UnicodeWidthChar;
+
+const TAB_STOP: usize = 8;
+
+/// Cells hold a character with any combining marks. The cell after a wide character is empty.
+type Grid = Vec<Vec<String>>;
+
+fn blank(cols: usize, rows: usize) -> Grid {
+    vec![vec![" ".to_string(); cols]; rows]
+}
+
+#[derive(Clone, Copy, Default)]
+struct Cursor {
+    x: usize,
+    y: usize,
+    /// The last column was written, and the next character goes to the next row.
+    pending_wrap: bool,
+}
+
+#[derive(Default)]
+enum Parser {
+    #[default]
+    Ground,
+    Escape,
+    /// An escape sequence with intermediate bytes, e.g. `ESC ( B`.
+    EscapeIntermediate,
+    Csi(String),
+    /// OSC, DCS, APC, PM and SOS strings, ignored up to their terminator.
+    String {
+        escape: bool,
+    },
+}
+
+#[derive(Default)]
+pub struct Screen {
+    cols: usize,
+    rows: usize,
+    grid: Grid,
+    cursor: Cursor,
+    saved: Cursor,
+    /// The main screen and its cursor while the alternate screen is shown.
+    main: Option<(Grid, Cursor)>,
+    /// The scrolling region as the first and last row.
+    region: (usize, usize),
+    autowrap: bool,
+    parser: Parser,
+    /// Bytes of an incomplete UTF-8 character.
+    partial: Vec<u8>,
+    last: Option<char>,
+}
+
+impl Screen {
+    pub fn new(cols: u16, rows: u16) -> Self {
+        let (cols, rows) = (cols.max(1) as usize, rows.max(1) as usize);
+        Screen {
+            cols,
+            rows,
+            grid: blank(cols, rows),
+            region: (0, rows - 1),
+            autowrap: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn resize(&mut self, cols: u16, rows: u16) {
+        let (cols, rows) = (cols.max(1) as usize, rows.max(1) as usize);
+        if (cols, rows) == (self.cols, self.rows) {
+            return;
+        }
+        for grid in std::iter::once(&mut self.grid).chain(self.main.as_mut().map(|(g, _)| g)) {
+            grid.resize(rows, vec![" ".to_string(); cols]);
+            for row in grid.iter_mut() {
+                row.resize(cols, " ".to_string());
+            }
+        }
+        self.cols = cols;
+        self.rows = rows;
+        self.region = (0, rows - 1);
+        self.cursor.x = self.cursor.x.min(cols - 1);
+        self.cursor.y = self.cursor.y.min(rows - 1);
+    }
+
+    /// The text on the screen, a line per row without trailing spaces.
+    pub fn text(&self) -> String {
+        self.grid
+            .iter()
+            .map(|row| row.concat().trim_end().to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            if !self.partial.is_empty() || b >= 0x80 {
+                self.partial.push(b);
+                match std::str::from_utf8(&self.partial) {
+                    Ok(s) => {
+                        let c = s.chars().next().unwrap_or(char::REPLACEMENT_CHARACTER);
+                        self.partial.clear();
+                        self.byte_or_char(c);
+                    }
+                    Err(err) if err.error_len().is_some() || self.partial.len() >= 4 => {
+                        self.partial.clear();
+                        self.byte_or_char(char::REPLACEMENT_CHARACTER);
+                    }
+                    Err(_) => {}
+                }
+            } else {
+                self.byte_or_char(b as char);
+            }
+        }
+    }
+
+    fn byte_or_char(&mut self, c: char) {
+        match std::mem::take(&mut self.parser) {
+            Parser::Ground => match c {
+                '\x1b' => self.parser = Parser::Escape,
+                c if (c as u32) < 0x20 || c == '\x7f' => self.control(c),
+                c => self.print(c),
+            },
+            Parser::Escape => match c {
+                '[' => self.parser = Parser::Csi(String::new()),
+                ']' | 'P' | '_' | '^' | 'X' => self.parser = Parser::String { escape: false },
+                ' '..='/' => self.parser = Parser::EscapeIntermediate,
+                '7' => self.saved = self.cursor,
+                '8' => self.cursor = self.saved,
+                'D' => self.index(),
+                'E' => {
+                    self.cursor.x = 0;
+                    self.index();
+                }
+                'M' => self.reverse_index(),
+                'c' => *self = Screen::new(self.cols as u16, self.rows as u16),
+                _ => {}
+            },
+            Parser::EscapeIntermediate => {}
+            Parser::Csi(mut params) => match c {
+                '0'..='?' | ' '..='/' => {
+                    params.push(c);
+                    self.parser = Parser::Csi(params);
+                }
+                '@'..='~' => self.csi(&params, c),
+                '\x1b' => self.parser = Parser::Escape,
+                // anything else aborts the sequence
+                _ => {}
+            },
+            Parser::String { escape } => match c {
+                '\x07' => {}
+                '\\' if escape => {}
+                c => {
+                    self.parser = Parser::String {
+                        escape: c == '\x1b',
+                    }
+                }
+            },
+        }
+    }
+
+    fn control(&mut self, c: char) {
+        match c {
+            '\r' => self.carriage_return(),
+            '\n' | '\x0b' | '\x0c' => {
+                // the terminal driver turns newlines into CRLF unless in raw mode
+                if !crate::crossterm::terminal::is_raw_mode_enabled().unwrap_or(false) {
+                    self.carriage_return();
+                }
+                self.index();
+            }
+            '\x08' => {
+                self.cursor.x = self.cursor.x.saturating_sub(1);
+                self.cursor.pending_wrap = false;
+            }
+            '\t' => {
+                self.cursor.x = ((self.cursor.x / TAB_STOP + 1) * TAB_STOP).min(self.cols - 1);
+                self.cursor.pending_wrap = false;
+            }
+            _ => {}
+        }
+    }
+
+    fn carriage_return(&mut self) {
+        self.cursor.x = 0;
+        self.cursor.pending_wrap = false;
+    }
+
+    fn print(&mut self, c: char) {
+        let w = c.width().unwrap_or(0);
+        if w == 0 {
+            // a combining mark joins the character before the cursor
+            let x = if self.cursor.pending_wrap {
+                self.cursor.x
+            } else {
+                self.cursor.x.saturating_sub(1)
+            };
+            self.grid[self.cursor.y][x].push(c);
+            return;
+        }
+        if self.cursor.pending_wrap && self.autowrap {
+            self.carriage_return();
+            self.index();
+        }
+        if self.cursor.x + w > self.cols {
+            if self.autowrap && self.cursor.x > 0 {
+                self.carriage_return();
+                self.index();
+            } else {
+                self.cursor.x = self.cols.saturating_sub(w);
+            }
+        }
+        let (x, y) = (self.cursor.x, self.cursor.y);
+        self.grid[y][x] = c.to_string();
+        if w == 2 && x + 1 < self.cols {
+            self.grid[y][x + 1] = String::new();
+        }
+        self.last = Some(c);
+        self.cursor.pending_wrap = false;
+        if x + w >= self.cols {
+            self.cursor.x = self.cols - 1;
+            self.cursor.pending_wrap = true;
+        } else {
+            self.cursor.x = x + w;
+        }
+    }
+
+    /// Moves down a row, scrolling at the bottom of the scrolling region.
+    fn index(&mut self) {
+        self.cursor.pending_wrap = false;
+        if self.cursor.y == self.region.1 {
+            self.scroll_up(1);
+        } else if self.cursor.y + 1 < self.rows {
+            self.cursor.y += 1;
+        }
+    }
+
+    fn reverse_index(&mut self) {
+        self.cursor.pending_wrap = false;
+        if self.cursor.y == self.region.0 {
+            self.scroll_down(1);
+        } else {
+            self.cursor.y = self.cursor.y.saturating_sub(1);
+        }
+    }
+
+    fn scroll_up(&mut self, n: usize) {
+        let (top, bottom) = self.region;
+        for _ in 0..n.min(bottom + 1 - top) {
+            self.grid.remove(top);
+            self.grid.insert(bottom, vec![" ".to_string(); self.cols]);
+        }
+    }
+
+    fn scroll_down(&mut self, n: usize) {
+        let (top, bottom) = self.region;
+        for _ in 0..n.min(bottom + 1 - top) {
+            self.grid.remove(bottom);
+            self.grid.insert(top, vec![" ".to_string(); self.cols]);
+        }
+    }
+
+    fn erase(&mut self, y: usize, from: usize, to: usize) {
+        for cell in &mut self.grid[y][from.min(self.cols)..to.min(self.cols)] {
+            *cell = " ".to_string();
+        }
+    }
+
+    fn csi(&mut self, params: &str, action: char) {
+        let private = params.starts_with(['?', '>', '<', '=']);
+        let numbers: Vec<usize> = params
+            .trim_start_matches(['?', '>', '<', '='])
+            .split(';')
+            .map(|p| p.parse().unwrap_or(0))
+            .collect();
+        let arg = |i: usize, default: usize| match numbers.get(i) {
+            Some(0) | None => default,
+            Some(n) => *n,
+        };
+        let n = arg(0, 1);
+        let (cols, rows) = (self.cols, self.rows);
+        let (x, y) = (self.cursor.x, self.cursor.y);
+        match action {
+            'h' | 'l' if private => {
+                for mode in &numbers {
+                    match mode {
+                        7 => self.autowrap = action == 'h',
+                        47 | 1047 | 1049 => self.alternate_screen(action == 'h'),
+                        _ => {}
+                    }
+                }
+                return;
+            }
+            _ if private => return,
+            'A' => self.cursor.y = y.saturating_sub(n),
+            'B' | 'e' => self.cursor.y = (y + n).min(rows - 1),
+            'C' | 'a' => self.cursor.x = (x + n).min(cols - 1),
+            'D' => self.cursor.x = x.saturating_sub(n),
+            'E' => (self.cursor.x, self.cursor.y) = (0, (y + n).min(rows - 1)),
+            'F' => (self.cursor.x, self.cursor.y) = (0, y.saturating_sub(n)),
+            'G' | '`' => self.cursor.x = (n - 1).min(cols - 1),
+            'd' => self.cursor.y = (n - 1).min(rows - 1),
+            'H' | 'f' => {
+                self.cursor.y = (arg(0, 1) - 1).min(rows - 1);
+                self.cursor.x = (arg(1, 1) - 1).min(cols - 1);
+            }
+            'J' => match numbers[0] {
+                0 => {
+                    self.erase(y, x, cols);
+                    (y + 1..rows).for_each(|row| self.erase(row, 0, cols));
+                }
+                1 => {
+                    (0..y).for_each(|row| self.erase(row, 0, cols));
+                    self.erase(y, 0, x + 1);
+                }
+                _ => (0..rows).for_each(|row| self.erase(row, 0, cols)),
+            },
+            'K' => match numbers[0] {
+                0 => self.erase(y, x, cols),
+                1 => self.erase(y, 0, x + 1),
+                _ => self.erase(y, 0, cols),
+            },
+            'X' => self.erase(y, x, x + n),
+            '@' => {
+                let row = &mut self.grid[y];
+                for _ in 0..n.min(cols - x) {
+                    row.insert(x, " ".to_string());
+                    row.pop();
+                }
+            }
+            'P' => {
+                let row = &mut self.grid[y];
+                for _ in 0..n.min(cols - x) {
+                    row.remove(x);
+                    row.push(" ".to_string());
+                }
+            }
+            // lines are inserted and deleted by scrolling the region below the cursor
+            'L' | 'M' if (self.region.0..=self.region.1).contains(&y) => {
+                let region = self.region;
+                self.region.0 = y;
+                if action == 'L' {
+                    self.scroll_down(n);
+                } else {
+                    self.scroll_up(n);
+                }
+                self.region = region;
+                self.cursor.x = 0;
+            }
+            'S' => self.scroll_up(n),
+            'T' => self.scroll_down(n),
+            'b' => {
+                if let Some(c) = self.last {
+                    for _ in 0..n.min(cols * rows) {
+                        self.print(c);
+                    }
+                }
+            }
+            'r' => {
+                let top = arg(0, 1) - 1;
+                let bottom = arg(1, rows).min(rows) - 1;
+                if top < bottom {
+                    self.region = (top, bottom);
+                    (self.cursor.x, self.cursor.y) = (0, 0);
+                }
+            }
+            's' => self.saved = self.cursor,
+            'u' => self.cursor = self.saved,
+            _ => {}
+        }
+        self.cursor.pending_wrap = false;
+    }
+
+    fn alternate_screen(&mut self, enter: bool) {
+        match (enter, self.main.take()) {
+            (true, None) => {
+                let grid = std::mem::replace(&mut self.grid, blank(self.cols, self.rows));
+                self.main = Some((grid, self.cursor));
+            }
+            (false, Some((grid, cursor))) => {
+                self.grid = grid;
+                self.cursor = cursor;
+            }
+            (_, main) => self.main = main,
+        }
+    }
+}
+
+static TRACKING: AtomicBool = AtomicBool::new(false);
+static SCREEN: LazyLock<Mutex<Screen>> = LazyLock::new(Default::default);
+
+pub fn screen() -> MutexGuard<'static, Screen> {
+    SCREEN.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Starts following the output with a blank screen of the terminal's size.
+pub fn start_tracking() -> anyhow::Result<()> {
+    let (cols, rows) = crate::crossterm::terminal::size()?;
+    *screen() = Screen::new(cols, rows);
+    TRACKING.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+pub fn stop_tracking() {
+    TRACKING.store(false, Ordering::Relaxed);
+}
+
+/// Passes output on to `out`, following it on the screen while tracking.
+pub fn tee(out: Box<dyn Write>) -> Box<dyn Write> {
+    if TRACKING.load(Ordering::Relaxed) {
+        Box::new(Tee(out))
+    } else {
+        out
+    }
+}
+
+struct Tee(Box<dyn Write>);
+
+impl Write for Tee {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.0.write(buf)?;
+        screen().feed(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+pub fn resize(cols: u16, rows: u16) {
+    if TRACKING.load(Ordering::Relaxed) {
+        screen().resize(cols, rows);
+    }
+}
+
+/// Returns the text on the screen, from the terminal if it can tell, or else from the tracked
+/// output.
+pub fn capture() -> anyhow::Result<String> {
+    match native::capture() {
+        Ok(text) => return Ok(text),
+        Err(err) => log::trace!("Unable to read the screen from the terminal: {:#}", err),
+    }
+    if !TRACKING.load(Ordering::Relaxed) {
+        anyhow::bail!("Unsupported: the terminal can't be read and screen tracking is not enabled");
+    }
+    let mut screen = screen();
+    if let Ok((cols, rows)) = crate::crossterm::terminal::size() {
+        screen.resize(cols, rows);
+    }
+    Ok(screen.text())
+}
+
+#[cfg(not(windows))]
+mod native {
+    use crate::clipboard::run;
+
+    fn env(name: &str) -> String {
+        std::env::var(name).unwrap_or_default()
+    }
+
+    /// Asks the multiplexer or terminal emulator the process runs in.
+    pub fn capture() -> anyhow::Result<String> {
+        let text = if !env("TMUX").is_empty() {
+            let pane = env("TMUX_PANE");
+            let mut args = vec!["capture-pane", "-p"];
+            if !pane.is_empty() {
+                args.extend(["-t", &pane]);
+            }
+            run("tmux", &args, None)?
+        } else if !env("WEZTERM_PANE").is_empty() {
+            let pane = env("WEZTERM_PANE");
+            run("wezterm", &["cli", "get-text", "--pane-id", &pane], None)?
+        } else if !env("KITTY_WINDOW_ID").is_empty() {
+            // requires remote control to be allowed
+            let window = format!("id:{}", env("KITTY_WINDOW_ID"));
+            run("kitten", &["@", "get-text", "--match", &window], None)?
+        } else {
+            anyhow::bail!("No way to read the screen of this terminal");
+        };
+        Ok(text.trim_end_matches('\n').to_string())
+    }
+}
+
+#[cfg(windows)]
+mod native {
+    use winapi::um::processenv::GetStdHandle;
+    use winapi::um::winbase::STD_OUTPUT_HANDLE;
+    use winapi::um::wincon::{
+        GetConsoleScreenBufferInfo, ReadConsoleOutputCharacterW, CONSOLE_SCREEN_BUFFER_INFO, COORD,
+    };
+
+    /// Reads the visible part of the console's screen buffer.
+    pub fn capture() -> anyhow::Result<String> {
+        unsafe {
+            let handle = GetStdHandle(STD_OUTPUT_HANDLE);
+            let mut info: CONSOLE_SCREEN_BUFFER_INFO = std::mem::zeroed();
+            if GetConsoleScreenBufferInfo(handle, &mut info) == 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            let window = info.srWindow;
+            let width = (window.Right - window.Left + 1).max(0) as usize;
+            let mut lines = Vec::new();
+            for y in window.Top..=window.Bottom {
+                let mut buffer = vec![0u16; width];
+                let mut read = 0;
+                let start = COORD {
+                    X: window.Left,
+                    Y: y,
+                };
+                if ReadConsoleOutputCharacterW(
+                    handle,
+                    buffer.as_mut_ptr(),
+                    width as u32,
+                    start,
+                    &mut read,
+                ) == 0
+                {
+                    return Err(std::io::Error::last_os_error().into());
+                }
+                let line = String::from_utf16_lossy(&buffer[..read as usize]);
+                lines.push(line.trim_end().to_string());
+            }
+            Ok(lines.join("\n"))
+        }
+    }
+}
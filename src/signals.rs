@@ -0,0 +1,158 @@
+//! OS signals delivered as events, for languages that can't easily handle signals themselves.
+//!
+//! While a signal is enabled, its handler only marks it as received, and the event pipeline turns
+//! it into an event. Disabling a signal restores the handling it had before, except for
+//! `SIGWINCH`, which is left to the handlers of other components and only stops being counted.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// A signal that can be delivered as an event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Signal {
+    /// The terminal window changed size.
+    Winch,
+    /// Interrupt, usually Ctrl-C.
+    Int,
+    /// Termination request.
+    Term,
+    /// Stop request from the terminal, usually Ctrl-Z.
+    Tstp,
+}
+
+pub const ALL: [Signal; 4] = [Signal::Winch, Signal::Int, Signal::Term, Signal::Tstp];
+
+impl Signal {
+    /// The bit of the signal in a mask, as in `SignalFlags`.
+    pub fn bit(self) -> u8 {
+        1 << self as u8
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Signal::Winch => "SIGWINCH",
+            Signal::Int => "SIGINT",
+            Signal::Term => "SIGTERM",
+            Signal::Tstp => "SIGTSTP",
+        }
+    }
+}
+
+/// Signals delivered as events.
+static ENABLED: AtomicU8 = AtomicU8::new(0);
+/// Signals received and not turned into events yet.
+static PENDING: AtomicU8 = AtomicU8::new(0);
+
+/// Delivers the signals in `mask` as events, and stops delivering the others.
+pub fn enable(mask: u8) -> anyhow::Result<()> {
+    let known = ALL.iter().fold(0, |known, signal| known | signal.bit());
+    if mask & !known != 0 {
+        anyhow::bail!("Received unknown signal bits {:#010b}", mask);
+    }
+    native::apply(mask)?;
+    ENABLED.store(mask, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Takes the signals received since the last call.
+pub fn take_pending() -> impl Iterator<Item = Signal> {
+    let pending = PENDING.swap(0, Ordering::SeqCst);
+    ALL.into_iter()
+        .filter(move |signal| pending & signal.bit() != 0)
+}
+
+#[cfg(unix)]
+mod native {
+    use std::sync::atomic::Ordering;
+    use std::sync::Mutex;
+
+    use super::{Signal, ALL, ENABLED, PENDING};
+
+    /// Whether the `SIGWINCH` handler is registered.
+    static WINCH_REGISTERED: Mutex<bool> = Mutex::new(false);
+    /// The actions replaced by the handler of each other signal, while it is installed.
+    static PREVIOUS: Mutex<[Option<libc::sigaction>; 4]> = Mutex::new([None; 4]);
+
+    fn number(signal: Signal) -> libc::c_int {
+        match signal {
+            Signal::Winch => libc::SIGWINCH,
+            Signal::Int => libc::SIGINT,
+            Signal::Term => libc::SIGTERM,
+            Signal::Tstp => libc::SIGTSTP,
+        }
+    }
+
+    extern "C" fn handle(number: libc::c_int) {
+        for signal in ALL {
+            if self::number(signal) == number {
+                PENDING.fetch_or(signal.bit(), Ordering::SeqCst);
+            }
+        }
+        crate::input::wake_from_signal();
+    }
+
+    /// Handles the signals in `mask`, and returns the others to their previous handling.
+    pub fn apply(mask: u8) -> anyhow::Result<()> {
+        // the handlers wake up a wait for input through its pipe, which they can't create
+        crate::input::init()?;
+        let mut previous = PREVIOUS.lock().unwrap_or_else(|e| e.into_inner());
+        for signal in ALL {
+            let enabled = mask & signal.bit() != 0;
+            match signal {
+                // the input reader has a handler registered with `signal-hook` for resize events,
+                // which must keep running: register another one that only counts while enabled
+                Signal::Winch if enabled => register_winch()?,
+                Signal::Winch => {}
+                _ => {
+                    let previous = &mut previous[signal as usize];
+                    if enabled && previous.is_none() {
+                        let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+                        action.sa_sigaction = handle as extern "C" fn(libc::c_int) as usize;
+                        action.sa_flags = libc::SA_RESTART;
+                        let mut replaced: libc::sigaction = unsafe { std::mem::zeroed() };
+                        if unsafe { libc::sigaction(number(signal), &action, &mut replaced) } != 0 {
+                            return Err(std::io::Error::last_os_error().into());
+                        }
+                        *previous = Some(replaced);
+                    } else if let (false, Some(replaced)) = (enabled, previous.as_ref()) {
+                        let rc = unsafe {
+                            libc::sigaction(number(signal), replaced, std::ptr::null_mut())
+                        };
+                        if rc != 0 {
+                            return Err(std::io::Error::last_os_error().into());
+                        }
+                        *previous = None;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn register_winch() -> anyhow::Result<()> {
+        let mut registered = WINCH_REGISTERED.lock().unwrap_or_else(|e| e.into_inner());
+        if !*registered {
+            let bit = Signal::Winch.bit();
+            // SAFETY: the action only uses atomics and `write`, which are async-signal-safe
+            unsafe {
+                signal_hook::low_level::register(libc::SIGWINCH, move || {
+                    if ENABLED.load(Ordering::SeqCst) & bit != 0 {
+                        PENDING.fetch_or(bit, Ordering::SeqCst);
+                        crate::input::wake_from_signal();
+                    }
+                })?;
+            }
+            *registered = true;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(unix))]
+mod native {
+    pub fn apply(mask: u8) -> anyhow::Result<()> {
+        if mask != 0 {
+            anyhow::bail!("Signals are only delivered as events on Unix");
+        }
+        Ok(())
+    }
+}
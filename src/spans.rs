@@ -0,0 +1,260 @@
+//! Parsing text styled with SGR escape sequences, e.g. the colored output of another process,
+//! into spans of text with the same style.
+//!
+//! Other escape sequences are removed from the text.
+
+use serde_json::{json, Value};
+
+/// Names of the attributes tracked for spans, by bit.
+const ATTRIBUTES: [&str; 17] = [
+    "Bold",
+    "Dim",
+    "Italic",
+    "Underlined",
+    "DoubleUnderlined",
+    "Undercurled",
+    "Underdotted",
+    "Underdashed",
+    "SlowBlink",
+    "RapidBlink",
+    "Reverse",
+    "Hidden",
+    "CrossedOut",
+    "Fraktur",
+    "Framed",
+    "Encircled",
+    "OverLined",
+];
+
+const UNDERLINES: [&str; 5] = [
+    "Underlined",
+    "DoubleUnderlined",
+    "Undercurled",
+    "Underdotted",
+    "Underdashed",
+];
+
+fn bit(name: &str) -> u32 {
+    ATTRIBUTES
+        .iter()
+        .position(|a| *a == name)
+        .map_or(0, |i| 1 << i)
+}
+
+fn bits(names: &[&str]) -> u32 {
+    names.iter().fold(0, |bits, name| bits | bit(name))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Color {
+    Ansi(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    fn json(color: Option<Color>) -> Value {
+        match color {
+            None => Value::Null,
+            Some(Color::Ansi(ansi)) => json!({ "ansi": ansi }),
+            Some(Color::Rgb(r, g, b)) => json!({ "r": r, "g": g, "b": b }),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+struct Style {
+    fg: Option<Color>,
+    bg: Option<Color>,
+    attributes: u32,
+}
+
+impl Style {
+    /// Applies the parameters of an SGR sequence, e.g. `1;38;5;33`.
+    fn apply(&mut self, params: &str) {
+        let params: Vec<Vec<u16>> = params
+            .split(';')
+            .map(|p| p.split(':').map(|n| n.parse().unwrap_or(0)).collect())
+            .collect();
+        let mut i = 0;
+        while i < params.len() {
+            let param = &params[i];
+            i += 1;
+            match param[0] {
+                // extended colors, with their arguments as sub-parameters or following parameters
+                code @ (38 | 48 | 58) => {
+                    let args: Vec<u16> = if param.len() > 1 {
+                        param[1..].to_vec()
+                    } else {
+                        let kind = params.get(i).map_or(0, |p| p[0]);
+                        let count = match kind {
+                            5 => 2,
+                            2 => 4,
+                            _ => 1,
+                        };
+                        let args = params[i..].iter().take(count).map(|p| p[0]).collect();
+                        i += count;
+                        args
+                    };
+                    let color = match args.as_slice() {
+                        [5, n, ..] => Some(Color::Ansi(*n as u8)),
+                        // the color space id is optional in the sub-parameter form
+                        [2, _, r, g, b] | [2, r, g, b, ..] => {
+                            Some(Color::Rgb(*r as u8, *g as u8, *b as u8))
+                        }
+                        _ => None,
+                    };
+                    match code {
+                        38 => self.fg = color.or(self.fg),
+                        48 => self.bg = color.or(self.bg),
+                        // the underline color is not tracked
+                        _ => {}
+                    }
+                }
+                4 if param.len() > 1 => {
+                    self.attributes &= !bits(&UNDERLINES);
+                    self.attributes |= match param[1] {
+                        1 => bit("Underlined"),
+                        2 => bit("DoubleUnderlined"),
+                        3 => bit("Undercurled"),
+                        4 => bit("Underdotted"),
+                        5 => bit("Underdashed"),
+                        _ => 0,
+                    };
+                }
+                code => self.apply_code(code),
+            }
+        }
+    }
+
+    fn apply_code(&mut self, code: u16) {
+        let (set, unset) = match code {
+            0 => {
+                *self = Style::default();
+                return;
+            }
+            1 => (bit("Bold"), 0),
+            2 => (bit("Dim"), 0),
+            3 => (bit("Italic"), 0),
+            4 => (bit("Underlined"), bits(&UNDERLINES)),
+            5 => (bit("SlowBlink"), bit("RapidBlink")),
+            6 => (bit("RapidBlink"), bit("SlowBlink")),
+            7 => (bit("Reverse"), 0),
+            8 => (bit("Hidden"), 0),
+            9 => (bit("CrossedOut"), 0),
+            20 => (bit("Fraktur"), 0),
+            21 => (bit("DoubleUnderlined"), bits(&UNDERLINES)),
+            22 => (0, bits(&["Bold", "Dim"])),
+            23 => (0, bits(&["Italic", "Fraktur"])),
+            24 => (0, bits(&UNDERLINES)),
+            25 => (0, bits(&["SlowBlink", "RapidBlink"])),
+            27 => (0, bit("Reverse")),
+            28 => (0, bit("Hidden")),
+            29 => (0, bit("CrossedOut")),
+            30..=37 => {
+                self.fg = Some(Color::Ansi((code - 30) as u8));
+                return;
+            }
+            39 => {
+                self.fg = None;
+                return;
+            }
+            40..=47 => {
+                self.bg = Some(Color::Ansi((code - 40) as u8));
+                return;
+            }
+            49 => {
+                self.bg = None;
+                return;
+            }
+            51 => (bit("Framed"), bit("Encircled")),
+            52 => (bit("Encircled"), bit("Framed")),
+            53 => (bit("OverLined"), 0),
+            54 => (0, bits(&["Framed", "Encircled"])),
+            55 => (0, bit("OverLined")),
+            90..=97 => {
+                self.fg = Some(Color::Ansi((code - 90 + 8) as u8));
+                return;
+            }
+            100..=107 => {
+                self.bg = Some(Color::Ansi((code - 100 + 8) as u8));
+                return;
+            }
+            _ => return,
+        };
+        self.attributes = (self.attributes & !unset) | set;
+    }
+}
+
+fn span(text: &str, style: &Style) -> Value {
+    let attrs: Vec<&str> = ATTRIBUTES
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| style.attributes & (1 << i) != 0)
+        .map(|(_, name)| *name)
+        .collect();
+    json!({
+        "text": text,
+        "fg": Color::json(style.fg),
+        "bg": Color::json(style.bg),
+        "attrs": attrs,
+    })
+}
+
+/// The spans of `text` as JSON, e.g.
+/// `[{"text": "error", "fg": {"ansi": 1}, "bg": null, "attrs": ["Bold"]}]`.
+///
+/// Colors are `null` for the default color, an object with the `"ansi"` index for indexed colors,
+/// or an object with `"r"`, `"g"` and `"b"` for RGB colors.
+pub fn parse(text: &str) -> Value {
+    let mut spans = Vec::new();
+    let mut style = Style::default();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            current.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('[') => {
+                let mut params = String::new();
+                let mut action = None;
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        action = Some(c);
+                        break;
+                    }
+                    params.push(c);
+                }
+                // private sequences ending in `m` (e.g. `CSI > 4 ; 2 m`) are not SGR
+                if action != Some('m') || params.starts_with(['?', '>', '<', '=']) {
+                    continue;
+                }
+                let mut next = style;
+                next.apply(&params);
+                if next != style && !current.is_empty() {
+                    spans.push(span(&current, &style));
+                    current.clear();
+                }
+                style = next;
+            }
+            // OSC, DCS, APC, PM and SOS strings, up to BEL or ST
+            Some(']' | 'P' | '_' | '^' | 'X') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            // an escape sequence with intermediate bytes, e.g. `ESC ( B`
+            Some(' '..='/') => {
+                chars.next();
+            }
+            _ => {}
+        }
+    }
+    if !current.is_empty() {
+        spans.push(span(&current, &style));
+    }
+    Value::Array(spans)
+}
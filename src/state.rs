@@ -0,0 +1,175 @@
+//! Terminal state shared by independent components of an application.
+
+use std::collections::BTreeSet;
+use std::sync::{LazyLock, Mutex, MutexGuard};
+
+/// Outstanding holds on a terminal setting, identified by tokens. The setting is applied when the
+/// first hold is taken and reverted when the last one is released.
+#[derive(Default)]
+pub struct Holds {
+    next: u64,
+    held: BTreeSet<u64>,
+}
+
+impl Holds {
+    /// Takes a hold, returning its token (never `0`) and whether it is the only one.
+    pub fn acquire(&mut self) -> (u64, bool) {
+        self.next += 1;
+        self.held.insert(self.next);
+        (self.next, self.held.len() == 1)
+    }
+
+    /// Releases the hold `token`, returning whether it was the last one.
+    pub fn release(&mut self, token: u64) -> anyhow::Result<bool> {
+        if !self.held.remove(&token) {
+            anyhow::bail!("Unknown or already released token {}", token);
+        }
+        Ok(self.held.is_empty())
+    }
+
+    /// Undoes [`Holds::acquire`] after the setting failed to apply.
+    pub fn forget(&mut self, token: u64) {
+        self.held.remove(&token);
+    }
+
+    /// Drops all holds, after the setting was reverted regardless of them.
+    pub fn clear(&mut self) {
+        self.held.clear();
+    }
+}
+
+static CURSOR_HIDDEN: LazyLock<Mutex<Holds>> = LazyLock::new(Default::default);
+
+/// Holds on the cursor being hidden.
+pub fn cursor_hidden() -> MutexGuard<'static, Holds> {
+    CURSOR_HIDDEN.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Raw mode shared by reference count.
+#[derive(Default)]
+pub struct RawMode {
+    count: usize,
+    /// Whether raw mode was already enabled when the first reference was taken, in which case it
+    /// is left enabled when the last one is released.
+    was_enabled: bool,
+}
+
+impl RawMode {
+    /// Takes a reference, enabling raw mode if it is the first. Returns the new count.
+    pub fn acquire(&mut self) -> anyhow::Result<usize> {
+        use crate::crossterm::terminal;
+
+        if self.count == 0 {
+            self.was_enabled = terminal::is_raw_mode_enabled()?;
+            if !self.was_enabled {
+                terminal::enable_raw_mode()?;
+            }
+        }
+        self.count += 1;
+        Ok(self.count)
+    }
+
+    /// Releases a reference, disabling raw mode if it was the last one and raw mode was not
+    /// enabled before the first. Returns the new count.
+    pub fn release(&mut self) -> anyhow::Result<usize> {
+        if self.count == 0 {
+            anyhow::bail!("Raw mode released more often than acquired");
+        }
+        if self.count == 1 && !self.was_enabled {
+            crate::crossterm::terminal::disable_raw_mode()?;
+        }
+        self.count -= 1;
+        Ok(self.count)
+    }
+
+    /// Drops all references, after raw mode was disabled regardless of them.
+    pub fn clear(&mut self) {
+        self.count = 0;
+        self.was_enabled = false;
+    }
+}
+
+static RAW_MODE: LazyLock<Mutex<RawMode>> = LazyLock::new(Default::default);
+
+pub fn raw_mode() -> MutexGuard<'static, RawMode> {
+    RAW_MODE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Terminal modes as set through this library.
+#[derive(Default)]
+pub struct Modes {
+    pub alternate_screen: bool,
+    pub mouse_capture: bool,
+    /// The events reported while mouse capture is enabled, if not all of them.
+    pub mouse_capture_mode: Option<crate::MouseCaptureMode>,
+    pub bracketed_paste: bool,
+    pub focus_change: bool,
+    /// Pushed keyboard enhancement flags, innermost last.
+    pub keyboard_enhancement_flags: Vec<u8>,
+    pub cursor_hidden: bool,
+    pub line_wrap_disabled: bool,
+    pub synchronized_update: bool,
+    /// Top and bottom rows of the scrolling region, if one was set.
+    pub scroll_region: Option<(u16, u16)>,
+    /// The `TuiFlags` of the modes entered by `crossterm_tui_enter`, until it is exited.
+    pub tui: Option<u8>,
+    /// Whether the default foreground, background or cursor color was set.
+    pub default_colors_changed: bool,
+    /// Whether palette entries were set.
+    pub palette_changed: bool,
+    /// Whether the DEC special graphics charset is in use.
+    pub line_drawing: bool,
+    /// The size of the window, while mouse events report pixels (SGR-Pixels).
+    pub mouse_pixels: Option<crate::events::PixelGrid>,
+}
+
+static MODES: LazyLock<Mutex<Modes>> = LazyLock::new(Default::default);
+
+pub fn modes() -> MutexGuard<'static, Modes> {
+    MODES.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// The modes if they aren't locked, without blocking.
+pub fn try_modes() -> Option<MutexGuard<'static, Modes>> {
+    match MODES.try_lock() {
+        Ok(modes) => Some(modes),
+        Err(std::sync::TryLockError::Poisoned(e)) => Some(e.into_inner()),
+        Err(std::sync::TryLockError::WouldBlock) => None,
+    }
+}
+
+/// Records a mode change with `f` if the command that made it succeeded (`rc == 0`).
+pub fn record(rc: libc::c_int, f: impl FnOnce(&mut Modes)) {
+    if rc == 0 {
+        f(&mut modes());
+    }
+}
+
+/// Everything the library believes it has enabled, as JSON.
+pub fn to_json() -> anyhow::Result<serde_json::Value> {
+    let raw_mode_enabled = crate::crossterm::terminal::is_raw_mode_enabled()?;
+    let raw_mode_references = raw_mode().count;
+    let cursor_hide_tokens = cursor_hidden().held.len();
+    let modes = modes();
+    Ok(serde_json::json!({
+        "raw_mode": raw_mode_enabled,
+        "raw_mode_references": raw_mode_references,
+        "alternate_screen": modes.alternate_screen,
+        "mouse_capture": modes.mouse_capture,
+        "bracketed_paste": modes.bracketed_paste,
+        "focus_change": modes.focus_change,
+        "keyboard_enhancement_flags": modes.keyboard_enhancement_flags.last().copied().unwrap_or(0),
+        "keyboard_enhancement_stack": modes.keyboard_enhancement_flags,
+        "cursor_hidden": modes.cursor_hidden,
+        "cursor_hide_tokens": cursor_hide_tokens,
+        "line_wrap": !modes.line_wrap_disabled,
+        "synchronized_update": modes.synchronized_update || crate::synchronized::depth() > 0,
+        "scroll_region": modes.scroll_region.map(|(top, bottom)| serde_json::json!([top, bottom])),
+        "mouse_capture_mode": modes.mouse_capture_mode.map(|mode| format!("{:?}", mode)),
+        "mouse_pixels": modes.mouse_pixels.is_some(),
+        "line_drawing": modes.line_drawing,
+        "tui": modes.tui,
+        "default_colors_changed": modes.default_colors_changed,
+        "palette_changed": modes.palette_changed,
+    }))
+}
@@ -0,0 +1,164 @@
+//! Suspending the process from a full-screen application, as Ctrl-Z does in a shell: the terminal
+//! is restored while the process is stopped, and set up again when it continues.
+
+use crate::crossterm::{cursor, event, style, terminal};
+use crate::{
+    events, flush, queue_command, state, MouseCaptureMode, BEGIN_LINE_DRAWING, ENABLE_MOUSE_PIXELS,
+    END_LINE_DRAWING, RESET_MOUSE_PIXELS,
+};
+
+/// The modes that were set when the process was suspended.
+struct Saved {
+    raw_mode: bool,
+    alternate_screen: bool,
+    mouse_capture: bool,
+    mouse_capture_mode: Option<MouseCaptureMode>,
+    mouse_pixels: bool,
+    bracketed_paste: bool,
+    focus_change: bool,
+    keyboard_enhancement_flags: Vec<u8>,
+    cursor_hidden: bool,
+    line_wrap_disabled: bool,
+    line_drawing: bool,
+}
+
+impl Saved {
+    fn take() -> anyhow::Result<Self> {
+        let modes = state::modes();
+        Ok(Saved {
+            raw_mode: terminal::is_raw_mode_enabled()?,
+            alternate_screen: modes.alternate_screen,
+            mouse_capture: modes.mouse_capture,
+            mouse_capture_mode: modes.mouse_capture_mode,
+            mouse_pixels: modes.mouse_pixels.is_some(),
+            bracketed_paste: modes.bracketed_paste,
+            focus_change: modes.focus_change,
+            keyboard_enhancement_flags: modes.keyboard_enhancement_flags.clone(),
+            cursor_hidden: modes.cursor_hidden,
+            line_wrap_disabled: modes.line_wrap_disabled,
+            line_drawing: modes.line_drawing,
+        })
+    }
+
+    /// Restores the terminal, leaving the modes in the reverse order of [`Saved::apply`].
+    fn restore(&self) -> anyhow::Result<()> {
+        for _ in &self.keyboard_enhancement_flags {
+            queue_command(event::PopKeyboardEnhancementFlags)?;
+        }
+        if self.focus_change {
+            queue_command(event::DisableFocusChange)?;
+        }
+        if self.bracketed_paste {
+            queue_command(event::DisableBracketedPaste)?;
+        }
+        if self.mouse_pixels {
+            queue_command(style::Print(RESET_MOUSE_PIXELS))?;
+        }
+        if self.mouse_capture {
+            queue_command(event::DisableMouseCapture)?;
+        }
+        if self.line_drawing {
+            queue_command(style::Print(END_LINE_DRAWING))?;
+        }
+        if self.cursor_hidden {
+            queue_command(cursor::Show)?;
+        }
+        if self.line_wrap_disabled {
+            queue_command(terminal::EnableLineWrap)?;
+        }
+        if self.alternate_screen {
+            queue_command(terminal::LeaveAlternateScreen)?;
+        }
+        flush()?;
+        if self.raw_mode {
+            terminal::disable_raw_mode()?;
+        }
+        Ok(())
+    }
+
+    /// Sets the saved modes again.
+    fn apply(&self) -> anyhow::Result<()> {
+        if self.raw_mode {
+            terminal::enable_raw_mode()?;
+        }
+        if self.alternate_screen {
+            queue_command(terminal::EnterAlternateScreen)?;
+        }
+        if self.line_wrap_disabled {
+            queue_command(terminal::DisableLineWrap)?;
+        }
+        if self.cursor_hidden {
+            queue_command(cursor::Hide)?;
+        }
+        if self.line_drawing {
+            queue_command(style::Print(BEGIN_LINE_DRAWING))?;
+        }
+        match self.mouse_capture_mode {
+            Some(mode) if self.mouse_capture => {
+                queue_command(style::Print(mode.enable_sequence()))?;
+            }
+            _ if self.mouse_capture => queue_command(event::EnableMouseCapture)?,
+            _ => {}
+        }
+        if self.mouse_pixels {
+            queue_command(style::Print(ENABLE_MOUSE_PIXELS))?;
+        }
+        if self.bracketed_paste {
+            queue_command(event::EnableBracketedPaste)?;
+        }
+        if self.focus_change {
+            queue_command(event::EnableFocusChange)?;
+        }
+        for flags in &self.keyboard_enhancement_flags {
+            let flags = event::KeyboardEnhancementFlags::from_bits_retain(*flags);
+            queue_command(event::PushKeyboardEnhancementFlags(flags))?;
+        }
+        flush()
+    }
+}
+
+/// Restores the terminal, stops the process until it is continued, then sets the terminal up
+/// again and queues an [`events::Event::Resumed`].
+pub fn suspend() -> anyhow::Result<()> {
+    if !cfg!(unix) {
+        anyhow::bail!("Suspending the process is only supported on Unix");
+    }
+    let saved = Saved::take()?;
+    saved.restore()?;
+    let stopped = native::stop();
+    saved.apply()?;
+    stopped?;
+    events::resumed();
+    Ok(())
+}
+
+#[cfg(unix)]
+mod native {
+    /// Sends `SIGTSTP` to the process group with its default action, which stops the process, and
+    /// returns once the process is continued.
+    pub fn stop() -> anyhow::Result<()> {
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = libc::SIG_DFL;
+            let mut previous: libc::sigaction = std::mem::zeroed();
+            if libc::sigaction(libc::SIGTSTP, &action, &mut previous) != 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            let result = if libc::kill(0, libc::SIGTSTP) != 0 {
+                Err(std::io::Error::last_os_error().into())
+            } else {
+                Ok(())
+            };
+            // a handler installed for events is only reinstated once the process continued
+            libc::sigaction(libc::SIGTSTP, &previous, std::ptr::null_mut());
+            result
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod native {
+    pub fn stop() -> anyhow::Result<()> {
+        unreachable!("suspending is checked to be supported")
+    }
+}
@@ -0,0 +1,55 @@
+//! Synchronized updates that nest and are always ended.
+//!
+//! Only the outermost update writes the begin and end markers, so a component can wrap its
+//! drawing in a synchronized update without ending one its caller started. The nesting is counted
+//! with the output, which all threads share.
+
+use crate::crossterm::terminal::{BeginSynchronizedUpdate, EndSynchronizedUpdate};
+use crate::output;
+
+/// Number of synchronized updates in progress.
+pub fn depth() -> usize {
+    output::synchronized_depth()
+}
+
+/// A synchronized update in progress, ended when dropped.
+pub struct SynchronizedUpdate {
+    ended: bool,
+}
+
+impl SynchronizedUpdate {
+    pub fn begin() -> anyhow::Result<Self> {
+        // held so that no other thread writes between counting the update and its marker
+        let _lock = output::Lock::acquire();
+        if output::enter_synchronized() {
+            if let Err(err) = crate::queue_command(BeginSynchronizedUpdate) {
+                output::leave_synchronized();
+                return Err(err);
+            }
+        }
+        Ok(SynchronizedUpdate { ended: false })
+    }
+
+    /// Ends the update, writing and flushing the end marker if this is the outermost update.
+    pub fn end(mut self) -> anyhow::Result<()> {
+        self.ended = true;
+        finish()
+    }
+}
+
+impl Drop for SynchronizedUpdate {
+    fn drop(&mut self) {
+        if !self.ended {
+            let _ = finish();
+        }
+    }
+}
+
+fn finish() -> anyhow::Result<()> {
+    let _lock = output::Lock::acquire();
+    if output::leave_synchronized() {
+        crate::queue_command(EndSynchronizedUpdate)?;
+        crate::flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,117 @@
+//! Named styles, e.g. `error` or `highlight`, loaded from a JSON theme so that styling can be
+//! configured instead of hard-coded.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use serde_json::Value;
+
+use crate::colors;
+use crate::crossterm::style::{Attribute, Color, ContentStyle};
+
+static THEME: Mutex<BTreeMap<String, ContentStyle>> = Mutex::new(BTreeMap::new());
+
+/// Parses a color: a name such as `"dark_red"` or `"DodgerBlue1"`, a hex string `"#0087ff"`, an
+/// ANSI index, or an object `{"r": 0, "g": 135, "b": 255}`.
+fn color(value: &Value) -> anyhow::Result<Color> {
+    let invalid = || anyhow::anyhow!("Received invalid color {}", value);
+    match value {
+        Value::Number(n) => Ok(Color::AnsiValue(
+            n.as_u64()
+                .and_then(|n| u8::try_from(n).ok())
+                .ok_or_else(invalid)?,
+        )),
+        Value::String(s) => {
+            if let Some(hex) = s.strip_prefix('#') {
+                let channel = |i: usize| {
+                    hex.get(i..i + 2)
+                        .and_then(|c| u8::from_str_radix(c, 16).ok())
+                };
+                return match (hex.len(), channel(0), channel(2), channel(4)) {
+                    (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb { r, g, b }),
+                    _ => Err(invalid()),
+                };
+            }
+            if let Ok(color) = Color::try_from(s.to_lowercase().as_str()) {
+                return Ok(color);
+            }
+            Ok(Color::AnsiValue(colors::by_name(s)?.id))
+        }
+        Value::Object(rgb) => {
+            let channel = |c: &str| {
+                rgb.get(c)
+                    .and_then(Value::as_u64)
+                    .and_then(|v| u8::try_from(v).ok())
+                    .ok_or_else(invalid)
+            };
+            Ok(Color::Rgb {
+                r: channel("r")?,
+                g: channel("g")?,
+                b: channel("b")?,
+            })
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn attribute(value: &Value) -> anyhow::Result<Attribute> {
+    value
+        .as_str()
+        .and_then(|name| {
+            Attribute::iterator().find(|a| format!("{:?}", a).eq_ignore_ascii_case(name))
+        })
+        .ok_or_else(|| anyhow::anyhow!("Received invalid attribute {}", value))
+}
+
+/// Parses a style `{"fg": ..., "bg": ..., "underline_color": ..., "attributes": ["bold"]}`, in
+/// which every field is optional.
+fn style(name: &str, value: &Value) -> anyhow::Result<ContentStyle> {
+    let fields = value
+        .as_object()
+        .ok_or_else(|| anyhow::anyhow!("Received invalid style {} for {:?}", value, name))?;
+    let mut style = ContentStyle::new();
+    for (field, value) in fields {
+        match field.as_str() {
+            "fg" => style.foreground_color = Some(color(value)?),
+            "bg" => style.background_color = Some(color(value)?),
+            "underline_color" => style.underline_color = Some(color(value)?),
+            "attributes" => {
+                for value in value.as_array().into_iter().flatten() {
+                    style.attributes.set(attribute(value)?);
+                }
+            }
+            _ => anyhow::bail!("Received unknown field {:?} in style {:?}", field, name),
+        }
+    }
+    Ok(style)
+}
+
+/// Replaces the theme with the styles of a JSON object mapping names to styles.
+///
+/// Nothing is changed if any style is invalid.
+pub fn load(json: &str) -> anyhow::Result<()> {
+    let value: Value = serde_json::from_str(json)?;
+    let styles = value
+        .as_object()
+        .ok_or_else(|| anyhow::anyhow!("Received theme that is not a JSON object"))?;
+    let theme = styles
+        .iter()
+        .map(|(name, value)| Ok((name.clone(), style(name, value)?)))
+        .collect::<anyhow::Result<_>>()?;
+    *THEME.lock().unwrap_or_else(|e| e.into_inner()) = theme;
+    Ok(())
+}
+
+/// The style named `name`, with its colors downgraded to the current color depth.
+pub fn get(name: &str) -> anyhow::Result<ContentStyle> {
+    let theme = THEME.lock().unwrap_or_else(|e| e.into_inner());
+    let style = theme
+        .get(name)
+        .ok_or_else(|| anyhow::anyhow!("No style named {:?} in the theme", name))?;
+    Ok(ContentStyle {
+        foreground_color: style.foreground_color.map(colors::downgrade),
+        background_color: style.background_color.map(colors::downgrade),
+        underline_color: style.underline_color.map(colors::downgrade),
+        attributes: style.attributes,
+    })
+}
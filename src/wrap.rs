@@ -0,0 +1,94 @@
+//! Where text ends up when the terminal soft-wraps it at the right margin.
+//!
+//! Terminals wrap a character that doesn't fit on the rest of the row to the start of the next
+//! row, leaving the last column empty for a wide character. A row that is filled exactly does not
+//! wrap until the next character arrives.
+
+use unicode_width::UnicodeWidthChar;
+
+const TAB_STOP: usize = 8;
+
+/// The cursor while laying out text, starting at the top left cell.
+struct Layout {
+    width: usize,
+    row: usize,
+    col: usize,
+}
+
+impl Layout {
+    fn new(width: u16) -> anyhow::Result<Self> {
+        if width == 0 {
+            anyhow::bail!("Received zero width for wrapping text");
+        }
+        Ok(Layout {
+            width: width as usize,
+            row: 0,
+            col: 0,
+        })
+    }
+
+    /// Where a character of width `w` is drawn.
+    fn place(&self, w: usize) -> (usize, usize) {
+        if self.col + w > self.width && self.col > 0 {
+            (0, self.row + 1)
+        } else {
+            (self.col, self.row)
+        }
+    }
+
+    fn advance(&mut self, c: char) {
+        match c {
+            // newlines are taken to start at the first column, as when output is post-processed
+            '\n' => {
+                self.row += 1;
+                self.col = 0;
+            }
+            '\r' => self.col = 0,
+            // tabs move to the next tab stop, or the last column, without wrapping
+            '\t' => {
+                let col = self.col.min(self.width - 1);
+                self.col = ((col / TAB_STOP + 1) * TAB_STOP).min(self.width - 1);
+            }
+            c => {
+                let w = c.width().unwrap_or(0);
+                if w > 0 {
+                    (self.col, self.row) = self.place(w);
+                    self.col += w;
+                }
+            }
+        }
+    }
+}
+
+/// The number of rows `text` takes up when printed from the first column of a terminal `width`
+/// columns wide.
+pub fn lines(text: &str, width: u16) -> anyhow::Result<usize> {
+    let mut layout = Layout::new(width)?;
+    text.chars().for_each(|c| layout.advance(c));
+    Ok(layout.row + 1)
+}
+
+/// The cell, as `(col, row)` relative to where printing started, that the character at
+/// `byte_offset` in `text` is drawn in, or where the next character goes for the end of `text`.
+pub fn cursor_position(
+    text: &str,
+    byte_offset: usize,
+    width: u16,
+) -> anyhow::Result<(usize, usize)> {
+    if !text.is_char_boundary(byte_offset) {
+        anyhow::bail!(
+            "Byte offset {} is not at a character boundary in text of {} bytes",
+            byte_offset,
+            text.len()
+        );
+    }
+    let mut layout = Layout::new(width)?;
+    text[..byte_offset].chars().for_each(|c| layout.advance(c));
+    // the next character, or a typed one at the end, wraps once the row is full
+    let w = text[byte_offset..]
+        .chars()
+        .next()
+        .and_then(|c| c.width())
+        .unwrap_or(0);
+    Ok(layout.place(w.max(1)))
+}